@@ -0,0 +1,21 @@
+/* C interface to session-finder, built with `cargo build --release --features ffi`
+ * (target/release/libsession_finder.so, .dylib, or .dll). */
+#ifndef SESSION_FINDER_H
+#define SESSION_FINDER_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Each call takes its parameters as a JSON object, as the JSON-RPC method of the same name
+ * does, and returns a JSON string: {"result": ...} or {"error": {"code": ..., "message": ...}}.
+ * Free every returned string with session_finder_free. */
+char *session_finder_search(const char *params_json);
+char *session_finder_timeline(const char *params_json);
+void session_finder_free(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
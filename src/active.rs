@@ -0,0 +1,106 @@
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::resume::session_working_dir;
+
+/// A session written to this recently is taken to be active
+const ACTIVE_SECONDS: u64 = 60;
+
+/// How long a session can go unwritten, as when Claude runs a long build, and still count as
+/// active while Claude Code is running in its directory
+const IDLE_SECONDS: u64 = 30 * 60;
+
+/// How long a look at the running processes is reused; the daemon asks again and again
+const PROCESS_SCAN_SECONDS: u64 = 5;
+
+/// Working directories of running Claude Code processes, with when they were listed
+static CLAUDE_DIRS: Mutex<Option<(Instant, Vec<PathBuf>)>> = Mutex::new(None);
+
+/// Whether a session is being written now: its last line is unfinished, it was written in the
+/// last minute, or Claude Code is running in its directory and it's the project's most
+/// recently written session and not idle for long.
+pub fn is_active(path: &Path) -> bool {
+    let Some(age) = fs::metadata(path).and_then(|metadata| metadata.modified()).ok().map(age) else {
+        return false;
+    };
+    if age < Duration::from_secs(ACTIVE_SECONDS) || ends_mid_line(path) {
+        return true;
+    }
+    age < Duration::from_secs(IDLE_SECONDS)
+        && claude_running_in(Path::new(&session_working_dir(path)))
+        && is_latest_in_folder(path)
+}
+
+fn age(modified: SystemTime) -> Duration {
+    SystemTime::now().duration_since(modified).unwrap_or_default()
+}
+
+/// Claude Code writes a line at a time, so a file not ending in a newline is mid-write.
+fn ends_mid_line(path: &Path) -> bool {
+    let Ok(mut file) = fs::File::open(path) else {
+        return false;
+    };
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::End(-1)).is_ok() && file.read_exact(&mut last).is_ok() && last[0] != b'\n'
+}
+
+/// Whether no other session in the same project folder was written more recently.
+fn is_latest_in_folder(path: &Path) -> bool {
+    let (Some(folder), Ok(modified)) = (path.parent(), fs::metadata(path).and_then(|metadata| metadata.modified())) else {
+        return false;
+    };
+    let Ok(entries) = fs::read_dir(folder) else {
+        return false;
+    };
+    !entries.filter_map(|entry| entry.ok()).any(|entry| {
+        entry.path() != path
+            && entry.path().extension().is_some_and(|extension| extension == "jsonl")
+            && entry.metadata().and_then(|metadata| metadata.modified()).is_ok_and(|other| other > modified)
+    })
+}
+
+fn claude_running_in(dir: &Path) -> bool {
+    if dir.as_os_str().is_empty() {
+        return false;
+    }
+    let mut cached = CLAUDE_DIRS.lock().unwrap_or_else(|e| e.into_inner());
+    let fresh = cached.as_ref().is_some_and(|(listed, _)| listed.elapsed() < Duration::from_secs(PROCESS_SCAN_SECONDS));
+    if !fresh {
+        *cached = Some((Instant::now(), claude_working_dirs()));
+    }
+    cached.as_ref().is_some_and(|(_, dirs)| dirs.iter().any(|running| running == dir))
+}
+
+/// Working directories of the `claude` processes in `/proc`. Other systems have no `/proc`, so
+/// there the file's age and last line decide alone.
+fn claude_working_dirs() -> Vec<PathBuf> {
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    processes
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().bytes().all(|byte| byte.is_ascii_digit()))
+        .filter(|entry| fs::read(entry.path().join("cmdline")).is_ok_and(|cmdline| is_claude_command(&cmdline)))
+        .filter_map(|entry| fs::read_link(entry.path().join("cwd")).ok())
+        .collect()
+}
+
+/// `claude ...` itself, or Node running Claude Code's CLI script.
+fn is_claude_command(cmdline: &[u8]) -> bool {
+    let args: Vec<String> = cmdline
+        .split(|&byte| byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    let program = |arg: &String| Path::new(arg).file_name().is_some_and(|name| name == "claude");
+    match args.first() {
+        Some(first) if program(first) => true,
+        Some(first) if Path::new(first).file_name().is_some_and(|name| name == "node") => {
+            args.iter().skip(1).take(2).any(|arg| program(arg) || arg.contains("@anthropic-ai/claude-code"))
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use crate::error::{Result, SessionFinderError};
+
+/// The command line with each `@name` argument replaced by the flags the config's `[aliases]`
+/// table gives for it, e.g. `@errors` for `--types error --sort recent --limit 5`. Arguments
+/// after `--` and `@words` that aren't aliases are left alone, so they can still be searched for.
+pub fn expand_aliases(args: impl IntoIterator<Item = OsString>, aliases: &HashMap<String, String>) -> Result<Vec<OsString>> {
+    let mut expanded = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            expanded.push(arg);
+            expanded.extend(args.by_ref());
+            break;
+        }
+        let alias = arg.to_str().and_then(|arg| arg.strip_prefix('@')).and_then(|name| Some((name, aliases.get(name)?)));
+        match alias {
+            Some((name, flags)) => expanded.extend(
+                split_words(flags)
+                    .map_err(|e| SessionFinderError::Usage(format!("Alias @{} in the config file: {}", name, e)))?
+                    .into_iter()
+                    .map(OsString::from),
+            ),
+            None => expanded.push(arg),
+        }
+    }
+    Ok(expanded)
+}
+
+/// Splits an alias into arguments at whitespace, keeping quoted text together.
+fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some(close) if close == c => break,
+                        Some(inner) => word.push(inner),
+                        None => return Err(SessionFinderError::Usage(format!("unclosed {} quote", c))),
+                    }
+                }
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
@@ -0,0 +1,114 @@
+use crate::error::{Result, SessionFinderError};
+use crate::resolve::{extract_session_id_from_path, resolve_session_path};
+use crate::store::Store;
+use crate::trash::{TrashOperation, TrashedItem};
+
+/// Applies `+tag` / `-tag` edits to a session (a bare `tag` adds it), then prints its tags.
+pub fn run_tag(session_ref: &str, edits: &[&str]) -> Result<()> {
+    let session_id = extract_session_id_from_path(&resolve_session_path(session_ref)?)?;
+    let mut store = Store::load()?;
+    let mut trash = TrashOperation::begin("tag");
+
+    for edit in edits {
+        let (remove, tag) = match edit.strip_prefix('-') {
+            Some(tag) => (true, tag),
+            None => (false, edit.strip_prefix('+').unwrap_or(edit)),
+        };
+        if tag.is_empty() {
+            return Err(SessionFinderError::Usage(format!("Empty tag in '{}'", edit)));
+        }
+        if remove {
+            if let Some(tag) = store.remove_tag(&session_id, tag) {
+                trash.record(TrashedItem::Tag { session: session_id.clone(), tag })?;
+            }
+        } else {
+            store.add_tag(&session_id, tag);
+        }
+    }
+    if !edits.is_empty() {
+        store.save()?;
+    }
+    trash.finish();
+
+    let tags = store.tags(&session_id);
+    if tags.is_empty() {
+        println!("{}: no tags", session_id);
+    } else {
+        println!("{}: {}", session_id, tags.join(", "));
+    }
+    Ok(())
+}
+
+/// Adds, replaces, or removes a bookmark on one message, or lists the session's bookmarks
+/// when no message is given.
+pub fn run_bookmark(session_ref: &str, message: Option<usize>, label: Option<String>, remove: bool) -> Result<()> {
+    let session_id = extract_session_id_from_path(&resolve_session_path(session_ref)?)?;
+    let mut store = Store::load()?;
+
+    match (message, remove) {
+        (Some(message), true) => {
+            let bookmark = store
+                .remove_bookmark(&session_id, message)
+                .ok_or_else(|| SessionFinderError::Usage(format!(
+                    "No bookmark on message {} of {}",
+                    message,
+                    session_id
+                )))?;
+            let mut trash = TrashOperation::begin("bookmark");
+            trash.record(TrashedItem::Bookmark { session: session_id.clone(), message, label: bookmark.label })?;
+            store.save()?;
+            trash.finish();
+        }
+        (Some(message), false) => {
+            store.add_bookmark(&session_id, message, label);
+            store.save()?;
+        }
+        (None, true) => return Err(SessionFinderError::Usage("--remove needs a message index".to_string())),
+        (None, false) => {}
+    }
+
+    let bookmarks = store.bookmarks(&session_id);
+    if bookmarks.is_empty() {
+        println!("{}: no bookmarks", session_id);
+    }
+    for bookmark in bookmarks {
+        match &bookmark.label {
+            Some(label) => println!("{} message {}: {}", session_id, bookmark.message, label),
+            None => println!("{} message {}", session_id, bookmark.message),
+        }
+    }
+    Ok(())
+}
+
+/// Attaches a note to a session or one of its messages, removes one, or lists the session's notes.
+pub fn run_note(session_ref: &str, message: Option<usize>, text: Option<String>, remove: Option<usize>) -> Result<()> {
+    let session_id = extract_session_id_from_path(&resolve_session_path(session_ref)?)?;
+    let mut store = Store::load()?;
+
+    if let Some(position) = remove {
+        let note = store
+            .remove_note(&session_id, position)
+            .ok_or_else(|| SessionFinderError::Usage(format!("No note {} on {}", position, session_id)))?;
+        let mut trash = TrashOperation::begin("note");
+        trash.record(TrashedItem::Note { session: session_id.clone(), position, message: note.message, text: note.text })?;
+        store.save()?;
+        trash.finish();
+    } else if let Some(text) = text {
+        store.add_note(&session_id, message, text);
+        store.save()?;
+    } else if message.is_some() {
+        return Err(SessionFinderError::Usage("--message needs note text".to_string()));
+    }
+
+    let notes = store.notes(&session_id);
+    if notes.is_empty() {
+        println!("{}: no notes", session_id);
+    }
+    for (position, note) in notes.iter().enumerate() {
+        match note.message {
+            Some(message) => println!("{}. [message {}] {}", position + 1, message, note.text),
+            None => println!("{}. {}", position + 1, note.text),
+        }
+    }
+    Ok(())
+}
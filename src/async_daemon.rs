@@ -0,0 +1,93 @@
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedReadHalf;
+use tokio::net::UnixStream;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info};
+
+use crate::daemon::{SessionWatch, WATCH_INTERVAL};
+use crate::error::Result;
+use crate::rpc::{body_text, encode_message, read_header_line, respond_async, Framing, HeaderLine, Reply};
+
+/// Serves the daemon's connections as tasks on a tokio runtime rather than a thread each, so
+/// thousands of idle clients cost no threads. Requests are answered by the async library API,
+/// which reads session files through `tokio::fs`; the watcher is a task too.
+pub fn serve_daemon(listener: std::os::unix::net::UnixListener) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    runtime.block_on(async {
+        listener.set_nonblocking(true)?;
+        let listener = tokio::net::UnixListener::from_std(listener)?;
+        tokio::spawn(watch_sessions());
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    debug!("Connection failed: {}", e);
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                if let Err(e) = serve_connection(stream).await {
+                    info!("Connection closed: {}", e);
+                }
+            });
+        }
+    })
+}
+
+/// Checks for changed session files every `WATCH_INTERVAL`, off the runtime's workers.
+async fn watch_sessions() {
+    let Ok(mut watch) = tokio::task::spawn_blocking(SessionWatch::new).await else {
+        return;
+    };
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first tick completes at once, and the files were only just looked at
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        watch = match tokio::task::spawn_blocking(move || {
+            watch.check();
+            watch
+        })
+        .await
+        {
+            Ok(watch) => watch,
+            Err(_) => return,
+        };
+    }
+}
+
+/// Answers one client's requests, in order, until `exit` or it disconnects.
+async fn serve_connection(stream: UnixStream) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    while let Some((body, framing)) = read_message(&mut reader).await? {
+        match respond_async(&body).await {
+            Reply::Exit => break,
+            Reply::Nothing => {}
+            Reply::Response(response) => writer.write_all(encode_message(&response, framing)?.as_bytes()).await?,
+        }
+    }
+    Ok(())
+}
+
+/// `rpc::read_message`, without blocking the task's thread while waiting for input.
+async fn read_message(reader: &mut BufReader<OwnedReadHalf>) -> Result<Option<(String, Framing)>> {
+    let mut content_length: Option<usize> = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        match read_header_line(&line, &mut content_length)? {
+            HeaderLine::Message(body) => return Ok(Some((body, Framing::Line))),
+            HeaderLine::BodyFollows(length) => {
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).await?;
+                return body_text(body).map(|body| Some((body, Framing::ContentLength)));
+            }
+            HeaderLine::More => {}
+        }
+    }
+}
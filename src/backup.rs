@@ -0,0 +1,264 @@
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::claude_data::history_session_ids;
+use crate::crypto::ENCRYPTED_EXTENSION;
+use crate::error::{Result, SessionFinderError};
+use crate::glyphs::Glyph;
+use crate::render::RenderOptions;
+use crate::resolve::{extract_session_id_from_path, read_session_text, session_roots};
+use crate::store::{store_path, Store};
+
+/// A session file as it was when the manifest was made
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    /// Relative to `~/.claude`, e.g. `projects/-home-me-api/<id>.jsonl`
+    path: String,
+    session_id: String,
+    size: u64,
+    sha256: String,
+}
+
+/// Hashes of every session file, for checking backups against
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    created: DateTime<Utc>,
+    files: Vec<ManifestEntry>,
+}
+
+fn claude_dir() -> Result<PathBuf> {
+    Ok(Path::new(&std::env::var("HOME")?).join(".claude"))
+}
+
+/// Where `backup manifest` stores the manifest.
+pub fn manifest_path() -> Result<PathBuf> {
+    Ok(claude_dir()?.join("session-finder").join("backup-manifest.json"))
+}
+
+/// SHA-256 of the first `limit` bytes (or all) of what `reader` yields, with the count read.
+fn hash_reader(reader: impl Read, limit: Option<u64>) -> io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let size = match limit {
+        Some(limit) => io::copy(&mut reader.take(limit), &mut hasher)?,
+        None => io::copy(&mut { reader }, &mut hasher)?,
+    };
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+/// Hashes a session file, decompressing `.gz` copies like those `sync push --compress` writes
+/// and decrypting `.age` ones.
+fn hash_file(path: &Path, limit: Option<u64>) -> io::Result<(String, u64)> {
+    // Encryption differs every time, so pulled sessions are hashed by their text
+    if path.extension().is_some_and(|extension| extension == ENCRYPTED_EXTENSION) {
+        let text = read_session_text(path).map_err(io::Error::other)?;
+        return hash_reader(text.as_bytes(), limit);
+    }
+    let file = File::open(path)?;
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        hash_reader(GzDecoder::new(file), limit)
+    } else {
+        hash_reader(file, limit)
+    }
+}
+
+/// SHA-256 of a file's content, as hex.
+pub fn content_hash(path: &Path) -> io::Result<String> {
+    hash_file(path, None).map(|(hash, _)| hash)
+}
+
+/// Hashes every session file and stores the manifest, replacing the one before.
+pub fn run_backup_manifest() -> Result<()> {
+    let manifest = build_manifest()?;
+    save_manifest(&manifest)?;
+    println!(
+        "Recorded {} session file(s), {} bytes, in {}",
+        manifest.files.len(),
+        manifest.files.iter().map(|entry| entry.size).sum::<u64>(),
+        manifest_path()?.display()
+    );
+    Ok(())
+}
+
+fn build_manifest() -> Result<BackupManifest> {
+    let claude = claude_dir()?;
+    let mut files = Vec::new();
+    for root in session_roots()? {
+        for entry in walkdir::WalkDir::new(&root) {
+            let entry = entry?;
+            if !entry.file_type().is_file() || entry.path().extension().is_none_or(|extension| extension != "jsonl") {
+                continue;
+            }
+            let Ok(session_id) = extract_session_id_from_path(entry.path()) else {
+                continue;
+            };
+            let (sha256, size) = hash_file(entry.path(), None)?;
+            let path = entry.path().strip_prefix(&claude).unwrap_or(entry.path());
+            files.push(ManifestEntry { path: path.to_string_lossy().into_owned(), session_id, size, sha256 });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(BackupManifest { created: Utc::now(), files })
+}
+
+fn save_manifest(manifest: &BackupManifest) -> Result<()> {
+    let path = manifest_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(manifest)?)?;
+    Ok(())
+}
+
+/// How a manifest entry's copy in the backup compares
+enum CopyState {
+    Intact,
+    /// Longer, with the manifest's content at its start: the session was resumed since
+    Grown,
+    Missing,
+    Corrupted,
+}
+
+/// Checks a backup copy of `~/.claude` (or of just `~/.claude/projects`) against the stored
+/// manifest, made first if there is none: every file present with the recorded content, and
+/// every session named in the history index or annotation store present too. Returns whether
+/// the backup is complete.
+pub fn run_backup_verify(dir: &Path, render: RenderOptions) -> Result<bool> {
+    if !dir.is_dir() {
+        return Err(SessionFinderError::Usage(format!("{} is not a directory", dir.display())));
+    }
+    let path = manifest_path()?;
+    let manifest = match fs::read_to_string(&path) {
+        Ok(content) => {
+            serde_json::from_str::<BackupManifest>(&content).map_err(|e| SessionFinderError::json(&path, e))?
+        }
+        Err(_) => {
+            let manifest = build_manifest()?;
+            save_manifest(&manifest)?;
+            eprintln!("No manifest yet; recorded the current session files in {}", manifest_path()?.display());
+            manifest
+        }
+    };
+    // A copy of the projects directory alone holds the project folders directly
+    let projects_only = !dir.join("projects").is_dir();
+
+    let mut states: Vec<(&ManifestEntry, CopyState)> = Vec::new();
+    let mut not_covered = 0;
+    for entry in &manifest.files {
+        let relative = match (projects_only, entry.path.strip_prefix("projects/")) {
+            (false, _) => entry.path.as_str(),
+            (true, Some(rest)) => rest,
+            (true, None) => {
+                not_covered += 1;
+                continue;
+            }
+        };
+        states.push((entry, copy_state(entry, &dir.join(relative))?));
+    }
+
+    let backup_sessions = backup_session_ids(dir)?;
+    let recorded: HashSet<&str> = manifest.files.iter().map(|entry| entry.session_id.as_str()).collect();
+    let added = backup_sessions.iter().filter(|session_id| !recorded.contains(session_id.as_str())).count();
+    let (index_sources, referenced) = referenced_sessions(dir)?;
+    let unreferenced: Vec<&String> = referenced.iter().filter(|session_id| !backup_sessions.contains(*session_id)).collect();
+
+    println!(
+        "Checked {} against the manifest of {} ({} session file(s))",
+        dir.display(),
+        manifest.created.format("%Y-%m-%d %H:%M UTC"),
+        manifest.files.len()
+    );
+    let count = |wanted: fn(&CopyState) -> bool| states.iter().filter(|(_, state)| wanted(state)).count();
+    println!("  {} {} intact", render.glyph(Glyph::Ok), count(|state| matches!(state, CopyState::Intact)));
+    let grown = count(|state| matches!(state, CopyState::Grown));
+    if grown > 0 {
+        println!("  {} {} appended to since the manifest, recorded content intact", render.glyph(Glyph::Ok), grown);
+    }
+    if added > 0 {
+        println!("  + {} session(s) not in the manifest (newer than it)", added);
+    }
+    if not_covered > 0 {
+        println!("  - {} imported or team session file(s) not covered by a copy of the projects directory", not_covered);
+    }
+    for (entry, state) in &states {
+        match state {
+            CopyState::Missing => println!("  {} missing: {}", render.glyph(Glyph::Failed), entry.path),
+            CopyState::Corrupted => println!("  {} corrupted: {}", render.glyph(Glyph::Failed), entry.path),
+            CopyState::Intact | CopyState::Grown => {}
+        }
+    }
+    for session_id in &unreferenced {
+        println!("  {} in the {} but not the backup: {}", render.glyph(Glyph::Failed), index_sources, session_id);
+    }
+
+    let damaged = count(|state| matches!(state, CopyState::Missing | CopyState::Corrupted));
+    let complete = damaged == 0 && unreferenced.is_empty();
+    if complete {
+        println!("The backup is complete.");
+    } else {
+        println!("The backup is incomplete: {} file(s) missing or corrupted, {} indexed session(s) absent.", damaged, unreferenced.len());
+    }
+    Ok(complete)
+}
+
+fn copy_state(entry: &ManifestEntry, copy: &Path) -> Result<CopyState> {
+    let copy = if copy.exists() {
+        copy.to_path_buf()
+    } else {
+        let compressed = copy.with_extension("jsonl.gz");
+        if !compressed.exists() {
+            return Ok(CopyState::Missing);
+        }
+        compressed
+    };
+    let (sha256, size) = hash_file(&copy, Some(entry.size))?;
+    if sha256 != entry.sha256 || size != entry.size {
+        return Ok(CopyState::Corrupted);
+    }
+    // Only the recorded length was read; anything after it is a later append
+    let total = if copy.extension().is_some_and(|extension| extension == "gz") {
+        io::copy(&mut GzDecoder::new(File::open(&copy)?), &mut io::sink())?
+    } else {
+        fs::metadata(&copy)?.len()
+    };
+    Ok(if total > entry.size { CopyState::Grown } else { CopyState::Intact })
+}
+
+/// Session IDs of the session files (plain or gzipped) anywhere in the backup.
+fn backup_session_ids(dir: &Path) -> Result<HashSet<String>> {
+    let mut sessions = HashSet::new();
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy();
+        let Some(stem) = name.strip_suffix(".jsonl").or_else(|| name.strip_suffix(".jsonl.gz")) else {
+            continue;
+        };
+        if entry.file_type().is_file() && stem != "history" {
+            sessions.insert(stem.to_string());
+        }
+    }
+    Ok(sessions)
+}
+
+/// Sessions the history index and annotation store refer to, read from the backup when it
+/// holds copies of them and from `~/.claude` otherwise, with a description of where from.
+fn referenced_sessions(dir: &Path) -> Result<(String, Vec<String>)> {
+    let (history, store, source) = if dir.join("history.jsonl").exists() {
+        (dir.join("history.jsonl"), dir.join("session-finder").join("store.toml"), "backup's")
+    } else {
+        (claude_dir()?.join("history.jsonl"), store_path()?, "current")
+    };
+    let mut sessions = history_session_ids(&history);
+    let mut seen: HashSet<String> = sessions.iter().cloned().collect();
+    for session_id in Store::load_from(&store)?.session_ids() {
+        if seen.insert(session_id.clone()) {
+            sessions.push(session_id.clone());
+        }
+    }
+    Ok((format!("{} history index or annotations", source), sessions))
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::SessionInfo;
+use crate::error::{Result, SessionFinderError};
+use crate::glyphs::Glyph;
+use crate::render::RenderOptions;
+
+/// The fields of a saved `--format json` result that a comparison needs
+#[derive(Deserialize)]
+struct BaselineSession {
+    session_id: String,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+/// How a result set differs from one saved earlier with `--format json`; ranks count from 1
+#[derive(Debug, Serialize)]
+pub struct BaselineComparison {
+    pub baseline: PathBuf,
+    /// Sessions in the saved results
+    pub sessions_then: usize,
+    pub sessions_now: usize,
+    pub new: Vec<RankedSession>,
+    /// Saved sessions no longer returned, at their old rank
+    pub gone: Vec<RankedSession>,
+    pub moved: Vec<MovedSession>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedSession {
+    pub rank: usize,
+    pub session_id: String,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MovedSession {
+    pub rank: usize,
+    pub was: usize,
+    pub session_id: String,
+    pub title: Option<String>,
+}
+
+/// Compares the results with a result set saved earlier with `--format json`, finding new
+/// sessions, sessions no longer returned, and sessions whose rank changed.
+pub fn compare_with_baseline(baseline_path: &Path, sessions: &[SessionInfo]) -> Result<BaselineComparison> {
+    let content = fs::read_to_string(baseline_path).map_err(|e| {
+        io::Error::new(e.kind(), format!("Could not read baseline {}: {}", baseline_path.display(), e))
+    })?;
+    let baseline: Vec<BaselineSession> = serde_json::from_str(&content).map_err(|e| SessionFinderError::ParseError {
+        path: baseline_path.to_path_buf(),
+        line: Some(e.line()),
+        message: format!("not a saved result set (write one with --format json): {}", e),
+    })?;
+    let baseline_rank = |session_id: &str| baseline.iter().position(|old| old.session_id == session_id);
+
+    let mut new = Vec::new();
+    let mut moved = Vec::new();
+    for (rank, session) in sessions.iter().enumerate() {
+        let (session_id, title) = (session.session_id.clone(), session.title.clone());
+        match baseline_rank(&session.session_id) {
+            None => new.push(RankedSession { rank: rank + 1, session_id, title }),
+            Some(old_rank) if old_rank != rank => {
+                moved.push(MovedSession { rank: rank + 1, was: old_rank + 1, session_id, title })
+            }
+            Some(_) => {}
+        }
+    }
+    let gone = baseline
+        .iter()
+        .enumerate()
+        .filter(|(_, old)| !sessions.iter().any(|session| session.session_id == old.session_id))
+        .map(|(old_rank, old)| RankedSession {
+            rank: old_rank + 1,
+            session_id: old.session_id.clone(),
+            title: old.title.clone(),
+        })
+        .collect();
+    Ok(BaselineComparison {
+        baseline: baseline_path.to_path_buf(),
+        sessions_then: baseline.len(),
+        sessions_now: sessions.len(),
+        new,
+        gone,
+        moved,
+    })
+}
+
+/// Prints a comparison as `--baseline` shows it.
+pub fn display_baseline_comparison(comparison: &BaselineComparison, render: RenderOptions) {
+    println!(
+        "Compared with {} ({} session(s) then, {} now):\n",
+        comparison.baseline.display(),
+        comparison.sessions_then,
+        comparison.sessions_now
+    );
+    if comparison.new.is_empty() && comparison.gone.is_empty() && comparison.moved.is_empty() {
+        println!("No changes.");
+        return;
+    }
+    if !comparison.new.is_empty() {
+        println!("New ({}):", comparison.new.len());
+        for session in &comparison.new {
+            println!("  + #{} {}", session.rank, label(&session.session_id, session.title.as_deref()));
+        }
+        println!();
+    }
+    if !comparison.gone.is_empty() {
+        println!("No longer found ({}):", comparison.gone.len());
+        for old in &comparison.gone {
+            println!("  - was #{} {}", old.rank, label(&old.session_id, old.title.as_deref()));
+        }
+        println!();
+    }
+    if !comparison.moved.is_empty() {
+        println!("Moved ({}):", comparison.moved.len());
+        for session in &comparison.moved {
+            let arrow = if session.rank < session.was { Glyph::Up } else { Glyph::Down };
+            println!(
+                "  {} #{} (was #{}) {}",
+                render.glyph(arrow),
+                session.rank,
+                session.was,
+                label(&session.session_id, session.title.as_deref())
+            );
+        }
+        println!();
+    }
+}
+
+fn label(session_id: &str, title: Option<&str>) -> String {
+    match title {
+        Some(title) => format!("{} ({})", title, session_id),
+        None => session_id.to_string(),
+    }
+}
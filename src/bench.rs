@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, SessionFinderError};
+use crate::matcher::SearchOptions;
+use crate::prefilter::count_matching_lines;
+use crate::resolve::{all_session_files, read_session_text, session_roots};
+use crate::timeline::parse_session_messages_with_diagnostics;
+use crate::{
+    analyze_session_content_enhanced, analyze_session_file, find_sessions, rank_and_limit_sessions,
+};
+
+/// Phases of a search, in the order they run
+const PHASES: [&str; 7] = ["discovery", "search (rg)", "read", "parse", "analysis", "ranking", "end to end"];
+
+/// What one run of the benchmark measured
+struct Run {
+    timings: [Duration; 7],
+    session_files: usize,
+    corpus_bytes: u64,
+    matched_files: usize,
+    matched_bytes: usize,
+    messages: usize,
+}
+
+/// Times each phase of a search for `search_terms` over the real sessions `runs` times and
+/// prints the median and fastest time per phase. Phases are timed separately, so `end to end`
+/// (the search as `session-finder TERMS` runs it) is the figure to compare across versions.
+pub fn run_bench(
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    limit: usize,
+    runs: usize,
+    options: &SearchOptions,
+) -> Result<()> {
+    if runs == 0 {
+        return Err(SessionFinderError::Usage("--runs must be at least 1".to_string()));
+    }
+    let mut results = Vec::with_capacity(runs);
+    for run in 0..runs {
+        eprintln!("Run {}/{}...", run + 1, runs);
+        results.push(bench_once(search_terms, project_filter, recent_days, limit, options)?);
+    }
+
+    let last = &results[results.len() - 1];
+    println!("Benchmark for \"{}\" ({} run(s))\n", search_terms.join(" "), runs);
+    println!(
+        "Corpus: {} session file(s), {}; {} matched, {}; {} message(s) parsed\n",
+        last.session_files,
+        format_bytes(last.corpus_bytes),
+        last.matched_files,
+        format_bytes(last.matched_bytes as u64),
+        last.messages
+    );
+    println!("{:<12} {:>10} {:>10} {:>12}", "phase", "median", "min", "throughput");
+    for (phase, name) in PHASES.iter().enumerate() {
+        let mut timings: Vec<Duration> = results.iter().map(|run| run.timings[phase]).collect();
+        timings.sort();
+        let median = timings[timings.len() / 2];
+        // Reading and parsing scale with the bytes of the matched files
+        let throughput = match *name {
+            "read" | "parse" if !median.is_zero() => {
+                format!("{}/s", format_bytes((last.matched_bytes as f64 / median.as_secs_f64()) as u64))
+            }
+            _ => String::new(),
+        };
+        let row = format!("{:<12} {:>10} {:>10} {:>12}", name, format_ms(median), format_ms(timings[0]), throughput);
+        println!("{}", row.trim_end());
+    }
+    Ok(())
+}
+
+fn bench_once(
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    limit: usize,
+    options: &SearchOptions,
+) -> Result<Run> {
+    let mut timings = [Duration::ZERO; 7];
+
+    let started = Instant::now();
+    let files = all_session_files()?;
+    let corpus_bytes = files.iter().filter_map(|path| fs::metadata(path).ok()).map(|m| m.len()).sum();
+    timings[0] = started.elapsed();
+
+    let started = Instant::now();
+    let mut matched: Vec<PathBuf> = Vec::new();
+    for root in session_roots()? {
+        matched.extend(count_matching_lines(&root, search_terms)?.into_iter().map(|(path, _)| root.join(path)));
+    }
+    timings[1] = started.elapsed();
+
+    let started = Instant::now();
+    let contents: Vec<String> = matched.iter().filter_map(|path| read_session_text(path).ok()).collect();
+    timings[2] = started.elapsed();
+    let matched_bytes = contents.iter().map(|content| content.len()).sum();
+
+    let started = Instant::now();
+    let parsed: Vec<_> = contents
+        .iter()
+        .map(|content| parse_session_messages_with_diagnostics(content).0)
+        .collect();
+    timings[3] = started.elapsed();
+    let messages = parsed.iter().map(|messages| messages.len()).sum();
+
+    let started = Instant::now();
+    for messages in &parsed {
+        analyze_session_content_enhanced(messages, search_terms, options)?;
+    }
+    timings[4] = started.elapsed();
+
+    // Ranking needs full results; building them repeats the work above, so it isn't timed
+    let mut sessions = Vec::new();
+    for path in &matched {
+        sessions.extend(analyze_session_file(path, search_terms, project_filter, recent_days, options)?);
+    }
+    let started = Instant::now();
+    rank_and_limit_sessions(sessions, limit, options.sort);
+    timings[5] = started.elapsed();
+
+    let started = Instant::now();
+    let sessions = find_sessions(search_terms, project_filter, recent_days, limit, None, options)?;
+    rank_and_limit_sessions(sessions, limit, options.sort);
+    timings[6] = started.elapsed();
+
+    Ok(Run {
+        timings,
+        session_files: files.len(),
+        corpus_bytes,
+        matched_files: matched.len(),
+        matched_bytes,
+        messages,
+    })
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GiB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MiB", b as f64 / (1u64 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
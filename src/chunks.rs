@@ -0,0 +1,196 @@
+use serde::Serialize;
+use std::io::Write;
+use tracing::warn;
+
+use crate::error::Result;
+use crate::export::select_sessions;
+use crate::matcher::SearchOptions;
+use crate::render::RenderOptions;
+use crate::resolve::read_session_text;
+use crate::timeline::{extract_content_text, format_tool_content, parse_session_messages};
+use crate::{Content, SessionMessage};
+
+/// How sessions are cut into chunks
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkOptions {
+    /// Most characters of text in one chunk; a longer message is split across several
+    pub max_chars: usize,
+    /// Messages (or pieces of a split message) each chunk repeats from the end of the one
+    /// before, so a passage cut at a boundary is whole in one of them
+    pub overlap: usize,
+    /// How tool calls' markers are drawn in chunk text
+    pub render: RenderOptions,
+}
+
+/// A run of consecutive messages from one session, with what an embedding pipeline needs to
+/// cite and filter it
+#[derive(Debug, Serialize)]
+pub struct Chunk {
+    pub session_id: String,
+    pub project: String,
+    /// Position of the chunk in its session, from 0
+    pub chunk: usize,
+    pub first_message: usize,
+    pub last_message: usize,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    /// Roles speaking in the chunk, in order of first appearance
+    pub roles: Vec<String>,
+    /// Files the chunk's tool calls read or changed
+    pub files: Vec<String>,
+    pub text: String,
+}
+
+/// One message, or one piece of a long one, rendered as text
+struct Piece {
+    message: usize,
+    text: String,
+}
+
+/// Writes every chunk of the selected sessions to stdout as JSON Lines.
+pub fn run_chunks(
+    session_refs: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    options: ChunkOptions,
+) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for chunk in chunk_sessions(session_refs, project_filter, recent_days, options)? {
+        match writeln!(out, "{}", serde_json::to_string(&chunk)?) {
+            // The reader stopped early, as `| head` does
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+/// Every chunk of the selected sessions, in session order. Each session is read only when
+/// its chunks are reached; one that can't be read or parsed is skipped with a warning.
+pub fn chunk_sessions(
+    session_refs: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    options: ChunkOptions,
+) -> Result<impl Iterator<Item = Chunk>> {
+    let sessions = select_sessions(
+        session_refs,
+        &[],
+        project_filter,
+        recent_days,
+        &SearchOptions { render: options.render, ..SearchOptions::default() },
+    )?;
+    Ok(sessions.into_iter().flat_map(move |info| {
+        let messages = read_session_text(&info.path).and_then(|content| parse_session_messages(&content));
+        match messages {
+            Ok(messages) => chunk_session(&messages, &info.session_id, &info.project_path, options),
+            Err(e) => {
+                warn!("Skipped {}: {:#}", info.path.display(), e);
+                Vec::new()
+            }
+        }
+    }))
+}
+
+/// Cuts a session into overlapping chunks of whole messages, splitting only messages longer
+/// than a chunk.
+pub fn chunk_session(messages: &[SessionMessage], session_id: &str, project: &str, options: ChunkOptions) -> Vec<Chunk> {
+    let max_chars = options.max_chars.max(1);
+    let pieces: Vec<Piece> = messages
+        .iter()
+        .enumerate()
+        .flat_map(|(index, msg)| {
+            split_chars(&message_text(msg, options.render), max_chars)
+                .into_iter()
+                .map(move |text| Piece { message: index, text })
+        })
+        .collect();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < pieces.len() {
+        let mut end = start + 1;
+        let mut chars = pieces[start].text.chars().count();
+        while end < pieces.len() && chars + pieces[end].text.chars().count() <= max_chars {
+            chars += pieces[end].text.chars().count();
+            end += 1;
+        }
+        chunks.push(build_chunk(messages, &pieces[start..end], session_id, project, chunks.len()));
+        if end == pieces.len() {
+            break;
+        }
+        // Step back for the overlap, but always move forward
+        start = end.saturating_sub(options.overlap).max(start + 1);
+    }
+    chunks
+}
+
+fn build_chunk(messages: &[SessionMessage], pieces: &[Piece], session_id: &str, project: &str, number: usize) -> Chunk {
+    let first_message = pieces[0].message;
+    let last_message = pieces[pieces.len() - 1].message;
+    let span = &messages[first_message..=last_message];
+
+    let mut roles: Vec<String> = Vec::new();
+    let mut files: Vec<String> = Vec::new();
+    for msg in span {
+        let Some(inner) = msg.message.as_ref() else {
+            continue;
+        };
+        if let Some(role) = inner.role.as_ref().filter(|role| !roles.contains(role)) {
+            roles.push(role.clone());
+        }
+        if let Some(Content::Array(blocks)) = inner.content.as_ref() {
+            let paths = blocks
+                .iter()
+                .filter_map(|block| block.input.as_ref()?.get("file_path")?.as_str())
+                .map(str::to_string);
+            for path in paths {
+                if !files.contains(&path) {
+                    files.push(path);
+                }
+            }
+        }
+    }
+
+    Chunk {
+        session_id: session_id.to_string(),
+        project: project.to_string(),
+        chunk: number,
+        first_message,
+        last_message,
+        start_time: span.iter().find_map(|msg| msg.timestamp.clone()),
+        end_time: span.iter().rev().find_map(|msg| msg.timestamp.clone()),
+        roles,
+        files,
+        text: pieces.iter().map(|piece| piece.text.as_str()).collect::<Vec<_>>().join("\n\n"),
+    }
+}
+
+/// A message as `role: text`, with its tool calls after the text. Tool results are left out:
+/// they are mostly file contents and command output, which say little on their own.
+fn message_text(msg: &SessionMessage, render: RenderOptions) -> String {
+    let Some(inner) = msg.message.as_ref() else {
+        return String::new();
+    };
+    let Some(content) = inner.content.as_ref() else {
+        return String::new();
+    };
+    let mut parts = vec![extract_content_text(content).into_owned()];
+    if let Content::Array(blocks) = content {
+        for block in blocks.iter().filter(|block| block.r#type == "tool_use") {
+            parts.push(format_tool_content(block.name.as_deref().unwrap_or_default(), &block.input, render));
+        }
+    }
+    let text = parts.iter().map(|part| part.trim()).filter(|part| !part.is_empty()).collect::<Vec<_>>().join("\n");
+    if text.is_empty() {
+        return text;
+    }
+    format!("{}: {}", inner.role.as_deref().unwrap_or("unknown"), text)
+}
+
+/// `text` in pieces of at most `max_chars` characters; none for empty text.
+fn split_chars(text: &str, max_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.chunks(max_chars).map(|piece| piece.iter().collect()).collect()
+}
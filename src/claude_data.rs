@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+/// One item of a session's todo list, as Claude Code records it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    pub content: String,
+    /// `pending`, `in_progress`, or `completed`
+    #[serde(default = "pending")]
+    pub status: String,
+}
+
+fn pending() -> String {
+    "pending".to_string()
+}
+
+/// What Claude Code keeps about sessions outside their transcripts: todo lists in
+/// `~/.claude/todos/` and titles in the `~/.claude/history.jsonl` index. Either may be missing.
+#[derive(Debug, Default)]
+pub struct ClaudeData {
+    todos: HashMap<String, Vec<Todo>>,
+    titles: HashMap<String, String>,
+}
+
+/// A line of the history index; only lines naming a session and a title are used
+#[derive(Deserialize)]
+struct HistoryEntry {
+    #[serde(rename = "sessionId", alias = "session_id")]
+    session_id: Option<String>,
+    #[serde(alias = "summary")]
+    title: Option<String>,
+}
+
+static DATA: RwLock<Option<Arc<ClaudeData>>> = RwLock::new(None);
+
+/// The todo lists and titles, read the first time they are needed.
+pub fn claude_data() -> Arc<ClaudeData> {
+    if let Some(data) = DATA.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return data.clone();
+    }
+    let data = Arc::new(match std::env::var("HOME") {
+        Ok(home) => ClaudeData::load(&Path::new(&home).join(".claude")),
+        Err(_) => ClaudeData::default(),
+    });
+    *DATA.write().unwrap_or_else(|e| e.into_inner()) = Some(data.clone());
+    data
+}
+
+/// Drops the loaded todo lists and titles so the next use reads them again, for the daemon
+/// when Claude Code changes them.
+pub fn reload_claude_data() {
+    *DATA.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+impl ClaudeData {
+    fn load(claude_dir: &Path) -> Self {
+        ClaudeData { todos: load_todos(&claude_dir.join("todos")), titles: load_titles(&claude_dir.join("history.jsonl")) }
+    }
+
+    /// The session's recorded todo list, empty if it never made one.
+    pub fn todos(&self, session_id: &str) -> &[Todo] {
+        self.todos.get(session_id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The title Claude Code gave the session in its history index.
+    pub fn title(&self, session_id: &str) -> Option<&str> {
+        self.titles.get(session_id).map(String::as_str)
+    }
+}
+
+/// Todo files are named `<session id>-agent-<agent id>.json`; the main agent's has the
+/// session's own ID as agent ID, and subagents' lists are used only when it is empty.
+fn load_todos(todos_dir: &Path) -> HashMap<String, Vec<Todo>> {
+    let mut todos: HashMap<String, (bool, Vec<Todo>)> = HashMap::new();
+    let Ok(entries) = fs::read_dir(todos_dir) else {
+        return HashMap::new();
+    };
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let Some((session_id, agent_id)) = todo_file_ids(&path) else {
+            continue;
+        };
+        let list: Vec<Todo> = match fs::read_to_string(&path).map(|content| serde_json::from_str(&content)) {
+            Ok(Ok(list)) => list,
+            _ => {
+                debug!(file = %path.display(), "Skipped: not a todo list");
+                continue;
+            }
+        };
+        if list.is_empty() {
+            continue;
+        }
+        let is_main = agent_id == session_id;
+        let known = todos.entry(session_id).or_insert((false, Vec::new()));
+        if is_main || (!known.0 && known.1.is_empty()) {
+            *known = (is_main, list);
+        }
+    }
+    todos.into_iter().map(|(session_id, (_, list))| (session_id, list)).collect()
+}
+
+fn todo_file_ids(path: &Path) -> Option<(String, String)> {
+    let stem = path.file_name()?.to_str()?.strip_suffix(".json")?;
+    let (session_id, agent_id) = stem.split_once("-agent-")?;
+    Some((session_id.to_string(), agent_id.to_string()))
+}
+
+/// The last title recorded for each session in the history index.
+fn load_titles(history_path: &Path) -> HashMap<String, String> {
+    let Ok(content) = fs::read_to_string(history_path) else {
+        return HashMap::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+        .filter_map(|entry| Some((entry.session_id?, entry.title.filter(|title| !title.trim().is_empty())?)))
+        .collect()
+}
+
+/// Every session the history index mentions, titled or not, in order of first mention.
+pub fn history_session_ids(history_path: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(history_path) else {
+        return Vec::new();
+    };
+    let mut seen = HashSet::new();
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok()?.session_id)
+        .filter(|session_id| seen.insert(session_id.clone()))
+        .collect()
+}
+
+/// `3 of 5 done; in progress: Write tests` for search results.
+pub fn todo_summary(todos: &[Todo]) -> String {
+    let done = todos.iter().filter(|todo| todo.status == "completed").count();
+    let mut summary = format!("{} of {} done", done, todos.len());
+    if let Some(current) = todos.iter().find(|todo| todo.status == "in_progress") {
+        summary.push_str(&format!("; in progress: {}", current.content));
+    }
+    summary
+}
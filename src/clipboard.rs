@@ -0,0 +1,31 @@
+use crate::error::{Result, SessionFinderError};
+
+/// How long to keep serving the clipboard on Linux so a clipboard manager can take it over
+#[cfg(target_os = "linux")]
+const CLIPBOARD_HOLD: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Puts `text` on the system clipboard and reports what was copied on stderr.
+pub fn copy_to_clipboard(text: &str, description: &str) -> Result<()> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|e| SessionFinderError::backend("clipboard", format!("Could not access the clipboard: {}", e)))?;
+
+    // X11 and Wayland clipboards are served by the owning process, so the contents would
+    // vanish when we exit unless something else picks them up first
+    #[cfg(target_os = "linux")]
+    {
+        use arboard::SetExtLinux;
+        clipboard
+            .set()
+            .wait_until(std::time::Instant::now() + CLIPBOARD_HOLD)
+            .text(text)
+            .map_err(|e| SessionFinderError::backend("clipboard", format!("Could not copy to the clipboard: {}", e)))?;
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    clipboard
+        .set_text(text)
+        .map_err(|e| SessionFinderError::backend("clipboard", format!("Could not copy to the clipboard: {}", e)))?;
+
+    eprintln!("Copied {} to clipboard", description);
+    Ok(())
+}
@@ -0,0 +1,287 @@
+use crate::fence::CodeBlock;
+use crate::CodeInfo;
+
+/// What session-finder knows about one programming language's code blocks
+pub trait LanguageAnalyzer: Sync {
+    /// Canonical name, shown in timelines
+    fn name(&self) -> &'static str;
+
+    /// Other fence tags that mean this language, such as `py` or `sh`
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// File extensions, without the dot
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Whether untagged code looks like this language
+    fn looks_like(&self, code: &str) -> bool;
+
+    /// Whether the block is a whole unit (a function, statement, or script) rather than a
+    /// fragment
+    fn is_complete(&self, code: &str) -> bool;
+}
+
+struct Rust;
+struct Python;
+struct JavaScript;
+struct TypeScript;
+struct Go;
+struct Sql;
+struct Shell;
+
+/// Every known language, in the order untagged code is tested against them
+pub fn language_analyzers() -> &'static [&'static dyn LanguageAnalyzer] {
+    &[&Rust, &Go, &TypeScript, &JavaScript, &Python, &Sql, &Shell]
+}
+
+/// The analyzer for a fence tag or language name, ignoring case.
+pub fn analyzer_named(name: &str) -> Option<&'static dyn LanguageAnalyzer> {
+    let name = name.to_ascii_lowercase();
+    language_analyzers()
+        .iter()
+        .copied()
+        .find(|analyzer| analyzer.name() == name || analyzer.aliases().contains(&name.as_str()))
+}
+
+/// The analyzer for a file's language, from its extension.
+pub fn analyzer_for_path(path: &str) -> Option<&'static dyn LanguageAnalyzer> {
+    let extension = std::path::Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    language_analyzers()
+        .iter()
+        .copied()
+        .find(|analyzer| analyzer.extensions().contains(&extension.as_str()))
+}
+
+/// Language, completeness, and size of a fenced code block. Known fence tags are normalized
+/// (`py` is `python`), unknown ones kept, and untagged code is recognized when it can be.
+pub fn analyze_code_block(block: &CodeBlock) -> CodeInfo {
+    let analyzer = match block.language {
+        Some(tag) => analyzer_named(tag),
+        None => language_analyzers().iter().copied().find(|analyzer| analyzer.looks_like(block.code)),
+    };
+    let language = analyzer
+        .map(|analyzer| analyzer.name().to_string())
+        .or_else(|| block.language.map(|tag| tag.to_ascii_lowercase()));
+    let is_complete = !block.unterminated
+        && match analyzer {
+            Some(analyzer) => analyzer.is_complete(block.code),
+            None => balanced(block.code) && code_lines(block.code) > 3,
+        };
+    CodeInfo { language, is_complete, line_count: code_lines(block.code) }
+}
+
+/// Lines with something on them
+fn code_lines(code: &str) -> usize {
+    code.lines().filter(|line| !line.trim().is_empty()).count()
+}
+
+/// Whether brackets pair up, ignoring those inside quotes. Good enough to tell a whole
+/// function from one cut off midway.
+fn balanced(code: &str) -> bool {
+    let mut stack = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in code.chars() {
+        if let Some(open) = quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == open => quote = None,
+                // Unclosed quotes don't run past the end of a line
+                '\n' if open != '`' => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' | '`' => quote = Some(c),
+            '(' | '[' | '{' => stack.push(c),
+            ')' | ']' | '}' => {
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if stack.pop() != Some(expected) {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    stack.is_empty()
+}
+
+/// Whether any line starts with one of the keywords, after indentation.
+fn has_line_starting_with(code: &str, keywords: &[&str]) -> bool {
+    code.lines().map(str::trim_start).any(|line| keywords.iter().any(|keyword| line.starts_with(keyword)))
+}
+
+impl LanguageAnalyzer for Rust {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+    fn aliases(&self) -> &'static [&'static str] {
+        &["rs"]
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["rs"]
+    }
+    fn looks_like(&self, code: &str) -> bool {
+        has_line_starting_with(code, &["fn ", "pub fn ", "impl ", "use std::", "let mut ", "#[derive"])
+    }
+    fn is_complete(&self, code: &str) -> bool {
+        balanced(code)
+            && has_line_starting_with(
+                code,
+                &["fn ", "pub ", "impl", "struct ", "enum ", "trait ", "mod ", "async fn ", "#["],
+            )
+    }
+}
+
+impl LanguageAnalyzer for Python {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+    fn aliases(&self) -> &'static [&'static str] {
+        &["py", "python3"]
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["py", "pyi"]
+    }
+    fn looks_like(&self, code: &str) -> bool {
+        (has_line_starting_with(code, &["def ", "import ", "from ", "class "]) && !code.contains('{'))
+            || code.contains("self.")
+    }
+    fn is_complete(&self, code: &str) -> bool {
+        // A def or class needs an indented body after it
+        let lines: Vec<&str> = code.lines().filter(|line| !line.trim().is_empty()).collect();
+        let has_body = lines.windows(2).any(|pair| {
+            let header = pair[0].trim_start();
+            (header.starts_with("def ") || header.starts_with("class ") || header.starts_with("async def "))
+                && header.trim_end().ends_with(':')
+                && indentation(pair[1]) > indentation(pair[0])
+        });
+        balanced(code) && has_body
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// `function`, arrow functions, classes, and exports are whole units in JS and TS
+fn is_complete_js(code: &str) -> bool {
+    balanced(code)
+        && (code.contains("function ")
+            || code.contains("=>")
+            || has_line_starting_with(code, &["class ", "export ", "import ", "const ", "let "]))
+}
+
+impl LanguageAnalyzer for JavaScript {
+    fn name(&self) -> &'static str {
+        "javascript"
+    }
+    fn aliases(&self) -> &'static [&'static str] {
+        &["js", "jsx", "node", "mjs"]
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["js", "jsx", "mjs", "cjs"]
+    }
+    fn looks_like(&self, code: &str) -> bool {
+        has_line_starting_with(code, &["function ", "const ", "export ", "import ", "module.exports"])
+            || code.contains("console.log(")
+    }
+    fn is_complete(&self, code: &str) -> bool {
+        is_complete_js(code)
+    }
+}
+
+impl LanguageAnalyzer for TypeScript {
+    fn name(&self) -> &'static str {
+        "typescript"
+    }
+    fn aliases(&self) -> &'static [&'static str] {
+        &["ts", "tsx"]
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ts", "tsx", "mts", "cts"]
+    }
+    fn looks_like(&self, code: &str) -> bool {
+        has_line_starting_with(code, &["interface ", "type ", "export interface ", "export type "])
+            || (code.contains(": string") && (code.contains("=>") || code.contains("function ")))
+    }
+    fn is_complete(&self, code: &str) -> bool {
+        is_complete_js(code) || (balanced(code) && has_line_starting_with(code, &["interface ", "type "]))
+    }
+}
+
+impl LanguageAnalyzer for Go {
+    fn name(&self) -> &'static str {
+        "go"
+    }
+    fn aliases(&self) -> &'static [&'static str] {
+        &["golang"]
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["go"]
+    }
+    fn looks_like(&self, code: &str) -> bool {
+        has_line_starting_with(code, &["package ", "func "]) || code.contains(":= ")
+    }
+    fn is_complete(&self, code: &str) -> bool {
+        balanced(code) && has_line_starting_with(code, &["package ", "func ", "type "])
+    }
+}
+
+impl LanguageAnalyzer for Sql {
+    fn name(&self) -> &'static str {
+        "sql"
+    }
+    fn aliases(&self) -> &'static [&'static str] {
+        &["postgresql", "postgres", "mysql", "sqlite", "psql", "plpgsql"]
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["sql"]
+    }
+    fn looks_like(&self, code: &str) -> bool {
+        let upper = code.trim_start().to_ascii_uppercase();
+        ["SELECT ", "INSERT INTO ", "UPDATE ", "DELETE FROM ", "CREATE TABLE ", "ALTER TABLE ", "WITH "]
+            .iter()
+            .any(|keyword| upper.starts_with(keyword))
+    }
+    fn is_complete(&self, code: &str) -> bool {
+        // Statements end in a semicolon; a lone query without one is whole if it has its FROM
+        let trimmed = code.trim_end();
+        let upper = trimmed.to_ascii_uppercase();
+        balanced(code)
+            && (trimmed.ends_with(';') || (upper.trim_start().starts_with("SELECT") && upper.contains(" FROM ")))
+    }
+}
+
+impl LanguageAnalyzer for Shell {
+    fn name(&self) -> &'static str {
+        "shell"
+    }
+    fn aliases(&self) -> &'static [&'static str] {
+        &["sh", "bash", "zsh", "console", "shell-session", "fish"]
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["sh", "bash", "zsh", "fish"]
+    }
+    fn looks_like(&self, code: &str) -> bool {
+        code.starts_with("#!/bin/") || code.starts_with("$ ")
+            || has_line_starting_with(code, &["cargo ", "npm ", "git ", "cd ", "export ", "sudo ", "docker "])
+    }
+    fn is_complete(&self, code: &str) -> bool {
+        // No dangling line continuation, and every block keyword is closed
+        let words: Vec<&str> = code.split_whitespace().collect();
+        let count = |word: &str| words.iter().filter(|w| **w == word).count();
+        !code.trim_end().ends_with('\\')
+            && count("if") == count("fi")
+            && count("case") == count("esac")
+            && count("do") == count("done")
+            && balanced(code)
+    }
+}
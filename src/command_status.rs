@@ -0,0 +1,103 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::glyphs::Glyph;
+use crate::render::RenderOptions;
+use crate::show::result_text;
+use crate::{Content, ContentBlock, SessionMessage};
+
+/// How a Bash call ended, judged from its tool result
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum CommandOutcome {
+    Succeeded,
+    Failed { exit_code: Option<i32> },
+    /// The call never got a result, e.g. because the turn was interrupted
+    NoResult,
+}
+
+impl CommandOutcome {
+    pub fn failed(self) -> bool {
+        matches!(self, CommandOutcome::Failed { .. })
+    }
+
+    /// `✗ failed (exit code 2)` for timelines and code diffs.
+    pub fn label(self, render: RenderOptions) -> String {
+        match self {
+            CommandOutcome::Succeeded => format!("{} succeeded", render.glyph(Glyph::Ok)),
+            CommandOutcome::Failed { exit_code: Some(code) } => format!("{} failed (exit code {})", render.glyph(Glyph::Failed), code),
+            CommandOutcome::Failed { exit_code: None } => format!("{} failed", render.glyph(Glyph::Failed)),
+            CommandOutcome::NoResult => "? no result".to_string(),
+        }
+    }
+}
+
+/// Claude Code reports a failing command's status as `Exit code 1` at the start of its result
+fn exit_code_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?i)\bexit (?:code|status):? *(-?\d+)").expect("valid regex"))
+}
+
+/// The outcome of every Bash call in the session, by tool_use ID: failed when its result is
+/// flagged as an error, reports a non-zero exit code, or says the command failed.
+pub fn bash_outcomes(messages: &[SessionMessage]) -> HashMap<String, CommandOutcome> {
+    let blocks = || {
+        messages
+            .iter()
+            .filter_map(|msg| match msg.message.as_ref()?.content.as_ref()? {
+                Content::Array(blocks) => Some(blocks),
+                Content::Text(_) => None,
+            })
+            .flatten()
+    };
+    let mut outcomes: HashMap<String, CommandOutcome> = blocks()
+        .filter(|block| block.r#type == "tool_use" && block.name.as_deref() == Some("Bash"))
+        .filter_map(|block| Some((block.id.clone()?, CommandOutcome::NoResult)))
+        .collect();
+    for block in blocks().filter(|block| block.r#type == "tool_result") {
+        if let Some(outcome) = block.tool_use_id.as_ref().and_then(|id| outcomes.get_mut(id)) {
+            *outcome = result_outcome(block);
+        }
+    }
+    outcomes
+}
+
+fn result_outcome(block: &ContentBlock) -> CommandOutcome {
+    let output = block.content.as_ref().map(result_text).unwrap_or_default();
+    let exit_code = exit_code_regex()
+        .captures(&output)
+        .and_then(|captures| captures[1].parse::<i32>().ok());
+    let failed = block.is_error == Some(true)
+        || exit_code.is_some_and(|code| code != 0)
+        || output.to_lowercase().contains("command failed");
+    if failed {
+        CommandOutcome::Failed { exit_code: exit_code.filter(|&code| code != 0) }
+    } else {
+        CommandOutcome::Succeeded
+    }
+}
+
+/// The outcome of the Bash calls a message makes or reports results for: failed if any
+/// failed, otherwise unknown if any got no result.
+pub fn message_command_outcome(msg: &SessionMessage, outcomes: &HashMap<String, CommandOutcome>) -> Option<CommandOutcome> {
+    let Some(Content::Array(blocks)) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+        return None;
+    };
+    let found: Vec<CommandOutcome> = blocks
+        .iter()
+        .filter_map(|block| match block.r#type.as_str() {
+            "tool_use" => block.id.as_ref(),
+            "tool_result" => block.tool_use_id.as_ref(),
+            _ => None,
+        })
+        .filter_map(|id| outcomes.get(id).copied())
+        .collect();
+    found
+        .iter()
+        .find(|outcome| outcome.failed())
+        .or_else(|| found.iter().find(|outcome| **outcome == CommandOutcome::NoResult))
+        .or(found.first())
+        .copied()
+}
@@ -0,0 +1,65 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::timeline::extract_content_text;
+use crate::SessionMessage;
+
+/// Search terms of the form `command:/review` restrict results to sessions that ran the command
+pub const COMMAND_SCOPE_PREFIX: &str = "command:";
+
+/// Claude Code records a slash command as a user message wrapping the command name in
+/// `<command-name>` tags, next to `<command-message>` and `<command-args>`
+fn command_name_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"<command-name>\s*(/?[^<\s]+)\s*</command-name>").expect("valid regex"))
+}
+
+/// The slash command a user message invoked, such as `/review`.
+pub fn invoked_command(msg: &SessionMessage) -> Option<String> {
+    if msg.msg_type != "user" {
+        return None;
+    }
+    let text = extract_content_text(msg.message.as_ref()?.content.as_ref()?);
+    command_name_regex()
+        .captures(&text)
+        .map(|captures| normalize_command(&captures[1]))
+}
+
+/// Slash commands the session ran, with how often, in order of first use.
+pub fn slash_commands(messages: &[SessionMessage]) -> Vec<(String, usize)> {
+    let mut commands: Vec<(String, usize)> = Vec::new();
+    for command in messages.iter().filter_map(invoked_command) {
+        match commands.iter_mut().find(|(known, _)| *known == command) {
+            Some((_, count)) => *count += 1,
+            None => commands.push((command, 1)),
+        }
+    }
+    commands
+}
+
+/// Splits `command:/review` scopes out of the search terms, returning the commands and the
+/// remaining terms.
+pub fn split_command_scopes<'a>(terms: &[&'a str]) -> (Vec<String>, Vec<&'a str>) {
+    let mut commands = Vec::new();
+    let mut rest = Vec::new();
+    for term in terms {
+        match term.strip_prefix(COMMAND_SCOPE_PREFIX).filter(|command| !command.is_empty()) {
+            Some(command) => commands.push(normalize_command(command)),
+            None => rest.push(*term),
+        }
+    }
+    (commands, rest)
+}
+
+/// The literal text a session file contains when it ran the command, for prefiltering with ripgrep.
+pub fn command_marker(command: &str) -> String {
+    format!("<command-name>{}</command-name>", command)
+}
+
+fn normalize_command(command: &str) -> String {
+    if command.starts_with('/') {
+        command.to_string()
+    } else {
+        format!("/{}", command)
+    }
+}
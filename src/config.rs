@@ -0,0 +1,77 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::digest::DigestConfig;
+use crate::error::{Result, SessionFinderError};
+use crate::error_rules::ErrorRule;
+use crate::ignore::IgnoreRules;
+use crate::notify::NotifyConfig;
+use crate::trash::TrashConfig;
+
+/// Settings from `~/.claude/session-finder/config.toml`, next to the store
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub ignore: IgnoreRules,
+    /// Default for `--max-memory`
+    pub max_memory: Option<String>,
+    /// Extra ways to recognise errors in timelines, tried before the built-in ones
+    #[serde(default)]
+    pub error_rules: Vec<ErrorRule>,
+    /// Messages mentioning any of these are never matches, e.g. `["session-finder"]` to skip
+    /// the searches themselves when run from inside a session
+    #[serde(default)]
+    pub exclude_terms: Vec<String>,
+    /// Tokens the model can hold, for telling whether a session is too long to resume
+    pub context_window: Option<u64>,
+    /// Default for `--preview`: messages shown from each end of a session
+    pub preview: Option<usize>,
+    /// Default for `--preview-width`
+    pub preview_width: Option<usize>,
+    /// Default for `--terms`: common terms listed per session
+    pub terms: Option<usize>,
+    /// Print ASCII markers instead of emoji, as `--ascii` does
+    #[serde(default)]
+    pub ascii: bool,
+    /// Flag bundles invoked as `@name`, e.g. `errors = "--types error --sort recent --limit 5"`
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// Session events the daemon announces
+    #[serde(default)]
+    pub notify: NotifyConfig,
+    /// Where `digest --save` writes
+    #[serde(default)]
+    pub digest: DigestConfig,
+    /// How long and how much the trash keeps
+    #[serde(default)]
+    pub trash: TrashConfig,
+    /// This machine's name for `sync`, so sessions pushed from several machines stay apart
+    pub machine: Option<String>,
+    /// The team's age key file for encrypted sessions, when `SESSION_FINDER_KEY_FILE` isn't set
+    pub key_file: Option<PathBuf>,
+    /// Project roots on other machines with the directory each is on this one, e.g.
+    /// `"/Users/me/code" = "/home/me/src"`, so their sessions count as the same projects
+    #[serde(default)]
+    pub project_roots: HashMap<String, String>,
+}
+
+pub fn config_path() -> Result<PathBuf> {
+    Ok(Path::new(&std::env::var("HOME")?)
+        .join(".claude")
+        .join("session-finder")
+        .join("config.toml"))
+}
+
+impl Config {
+    /// Reads the config file, or the defaults if there is none.
+    pub fn load() -> Result<Self> {
+        let path = config_path()?;
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| SessionFinderError::toml(path.clone(), &content, e))
+    }
+}
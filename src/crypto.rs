@@ -0,0 +1,122 @@
+use age::secrecy::ExposeSecret;
+use age::x25519::Identity;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::config::Config;
+use crate::error::{Result, SessionFinderError};
+
+/// File extension added to encrypted session files
+pub const ENCRYPTED_EXTENSION: &str = "age";
+
+/// Environment variable holding the secret key itself (`AGE-SECRET-KEY-1...`)
+const KEY_ENV: &str = "SESSION_FINDER_KEY";
+/// Environment variable naming a key file, overriding the default location
+const KEY_FILE_ENV: &str = "SESSION_FINDER_KEY_FILE";
+
+/// Default key file, in the format written by `age-keygen`
+pub fn default_key_file() -> Result<PathBuf> {
+    Ok(Path::new(&std::env::var("HOME")?)
+        .join(".claude")
+        .join("session-finder")
+        .join("key.txt"))
+}
+
+/// The team's age key, from `SESSION_FINDER_KEY`, the file named by `SESSION_FINDER_KEY_FILE`,
+/// the config's `key_file`, or the default key file, in that order.
+pub fn load_identity() -> Result<Identity> {
+    if let Ok(key) = std::env::var(KEY_ENV) {
+        return parse_identity(&key)
+            .map_err(|e| SessionFinderError::Environment(format!("{} is not a valid age secret key: {}", KEY_ENV, e)));
+    }
+
+    let path = match std::env::var_os(KEY_FILE_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => match Config::load()?.key_file {
+            Some(path) => path,
+            None => default_key_file()?,
+        },
+    };
+    let content = fs::read_to_string(&path).map_err(|_| {
+        SessionFinderError::Environment(format!(
+            "No encryption key: set {} or {}, or create {} with `session-finder sync keygen`",
+            KEY_ENV,
+            KEY_FILE_ENV,
+            path.display()
+        ))
+    })?;
+    // Key files from age-keygen start with comment lines
+    let key = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .ok_or_else(|| SessionFinderError::Environment(format!("Key file {} contains no key", path.display())))?;
+    parse_identity(key).map_err(|e| SessionFinderError::Environment(format!(
+        "Key file {} does not hold a valid age secret key: {}",
+        path.display(),
+        e
+    )))
+}
+
+/// The team's key for reading pulled sessions, loaded when the first encrypted one is read
+/// and kept for the rest of the run.
+pub fn session_identity() -> Result<&'static Identity> {
+    static IDENTITY: OnceLock<Identity> = OnceLock::new();
+    if let Some(identity) = IDENTITY.get() {
+        return Ok(identity);
+    }
+    let identity = load_identity()?;
+    Ok(IDENTITY.get_or_init(|| identity))
+}
+
+fn parse_identity(key: &str) -> Result<Identity> {
+    key.trim().parse::<Identity>().map_err(|e| SessionFinderError::Environment(e.to_string()))
+}
+
+/// Writes a new key to the default key file, readable only by the current user. Refuses to
+/// replace an existing key, since sessions encrypted with it could no longer be read.
+pub fn generate_key_file() -> Result<()> {
+    let path = default_key_file()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let identity = Identity::generate();
+    let content = format!(
+        "# created: {}\n# public key: {}\n{}\n",
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        identity.to_public(),
+        identity.to_string().expose_secret()
+    );
+    // Created with its final permissions, so the secret is never readable by others
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = match options.open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err(SessionFinderError::Usage(format!("Key file already exists: {}", path.display())));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    file.write_all(content.as_bytes())?;
+
+    eprintln!("Wrote a new key to {}", path.display());
+    eprintln!("Share this file with your team over a secure channel; anyone with it can read synced sessions.");
+    Ok(())
+}
+
+pub fn encrypt(identity: &Identity, plaintext: &[u8]) -> Result<Vec<u8>> {
+    age::encrypt(&identity.to_public(), plaintext)
+        .map_err(|e| SessionFinderError::Environment(format!("Encryption failed: {}", e)))
+}
+
+pub fn decrypt(identity: &Identity, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    age::decrypt(identity, ciphertext).map_err(|e| SessionFinderError::Environment(format!("Decryption failed: {}", e)))
+}
@@ -0,0 +1,274 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::{Result, SessionFinderError};
+use crate::ranking::SortOrder;
+use crate::{SessionAnalysis, SessionAnalyzer};
+#[cfg(unix)]
+use crate::SessionInfo;
+
+/// Analyses the daemon keeps before starting over, so many distinct queries can't grow it
+/// without bound
+const MAX_CACHED_ANALYSES: usize = 20_000;
+
+/// How often the daemon looks for changed session files
+#[cfg(unix)]
+pub const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// A session's analysis for one set of search terms, valid while the file is unchanged
+struct CachedAnalysis {
+    modified: DateTime<Utc>,
+    size: u64,
+    analysis: SessionAnalysis,
+    /// Where the analysis got to, to carry on from once the session is appended to
+    analyzer: SessionAnalyzer,
+}
+
+type AnalysisKey = (PathBuf, Vec<String>);
+
+/// Set only in the daemon; other runs analyse each session once and exit.
+static ANALYSES: OnceLock<Mutex<HashMap<AnalysisKey, CachedAnalysis>>> = OnceLock::new();
+
+/// Where the daemon listens, and how the CLI finds it.
+pub fn socket_path() -> Result<PathBuf> {
+    Ok(Path::new(&std::env::var("HOME")?)
+        .join(".claude")
+        .join("session-finder")
+        .join("daemon.sock"))
+}
+
+/// The analysis made earlier of an unchanged session for the same terms, in the daemon.
+pub fn cached_analysis(path: &Path, search_terms: &[&str], modified: DateTime<Utc>, size: u64) -> Option<SessionAnalysis> {
+    let analyses = ANALYSES.get()?.lock().unwrap_or_else(|e| e.into_inner());
+    let cached = analyses.get(&analysis_key(path, search_terms))?;
+    (cached.modified == modified && cached.size == size).then(|| cached.analysis.clone())
+}
+
+/// Whether this is the daemon, which keeps analyses between searches.
+pub fn caching_analyses() -> bool {
+    ANALYSES.get().is_some()
+}
+
+/// The state of the last analysis of a session for the same terms, in the daemon, whether or
+/// not the session has changed since.
+pub fn cached_analyzer(path: &Path, search_terms: &[&str]) -> Option<SessionAnalyzer> {
+    let analyses = ANALYSES.get()?.lock().unwrap_or_else(|e| e.into_inner());
+    analyses.get(&analysis_key(path, search_terms)).map(|cached| cached.analyzer.clone())
+}
+
+pub fn cache_analysis(
+    path: &Path,
+    search_terms: &[&str],
+    modified: DateTime<Utc>,
+    size: u64,
+    analysis: &SessionAnalysis,
+    analyzer: SessionAnalyzer,
+) {
+    let Some(analyses) = ANALYSES.get() else {
+        return;
+    };
+    let mut analyses = analyses.lock().unwrap_or_else(|e| e.into_inner());
+    if analyses.len() >= MAX_CACHED_ANALYSES {
+        analyses.clear();
+    }
+    analyses.insert(analysis_key(path, search_terms), CachedAnalysis { modified, size, analysis: analysis.clone(), analyzer });
+}
+
+fn analysis_key(path: &Path, search_terms: &[&str]) -> AnalysisKey {
+    (path.to_path_buf(), search_terms.iter().map(|term| term.to_lowercase()).collect())
+}
+
+/// Serves the `lsp-like` JSON-RPC methods on a Unix domain socket, keeping every session
+/// analysis in memory until its file changes, so searches from the CLI answer at
+/// interactive speed.
+#[cfg(unix)]
+pub fn run_daemon() -> Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let path = socket_path()?;
+    if UnixStream::connect(&path).is_ok() {
+        return Err(SessionFinderError::Environment(format!("A daemon is already listening on {}", path.display())));
+    }
+    // Left behind by a daemon that didn't shut down cleanly
+    let _ = std::fs::remove_file(&path);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    let _ = ANALYSES.set(Mutex::default());
+    eprintln!("Listening on {}", path.display());
+    #[cfg(feature = "tokio")]
+    return crate::async_daemon::serve_daemon(listener);
+    #[cfg(not(feature = "tokio"))]
+    serve_with_threads(listener)
+}
+
+/// Serves each connection on a thread of its own, with another watching for changes.
+#[cfg(all(unix, not(feature = "tokio")))]
+fn serve_with_threads(listener: std::os::unix::net::UnixListener) -> Result<()> {
+    use std::io::BufReader;
+    use tracing::{debug, info};
+
+    std::thread::spawn(watch_sessions);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                debug!("Connection failed: {}", e);
+                continue;
+            }
+        };
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(&stream);
+            let mut writer = &stream;
+            if let Err(e) = crate::rpc::serve(&mut reader, &mut writer) {
+                info!("Connection closed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn run_daemon() -> Result<()> {
+    Err(SessionFinderError::Environment("The daemon needs Unix domain sockets, which this platform lacks".to_string()))
+}
+
+/// Drops analyses of sessions that changed or went away, and reloads Claude's todo lists and
+/// titles when they change, checking every `WATCH_INTERVAL`.
+#[cfg(all(unix, not(feature = "tokio")))]
+fn watch_sessions() {
+    let mut watch = SessionWatch::new();
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        watch.check();
+    }
+}
+
+/// Size and modification time of every file the daemon's answers depend on, as last seen
+#[cfg(unix)]
+pub struct SessionWatch {
+    known: HashMap<PathBuf, (std::time::SystemTime, u64)>,
+    notifier: Option<crate::notify::Notifier>,
+}
+
+#[cfg(unix)]
+impl SessionWatch {
+    pub fn new() -> Self {
+        let notify = crate::config::Config::load().map(|config| config.notify).unwrap_or_default();
+        SessionWatch { known: Self::snapshot(), notifier: crate::notify::Notifier::from_config(notify) }
+    }
+
+    fn snapshot() -> HashMap<PathBuf, (std::time::SystemTime, u64)> {
+        let mut files: Vec<PathBuf> = crate::resolve::all_session_files().unwrap_or_default();
+        if let Ok(home) = std::env::var("HOME") {
+            let claude_dir = Path::new(&home).join(".claude");
+            files.push(claude_dir.join("history.jsonl"));
+            if let Ok(todos) = std::fs::read_dir(claude_dir.join("todos")) {
+                files.extend(todos.filter_map(|entry| entry.ok()).map(|entry| entry.path()));
+            }
+        }
+        files
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = std::fs::metadata(&path).ok()?;
+                Some((path, (metadata.modified().ok()?, metadata.len())))
+            })
+            .collect()
+    }
+
+    /// Looks at the files again, dropping analyses of sessions removed since the last look.
+    /// Those of changed sessions stay, so an append is analysed from where they stopped.
+    pub fn check(&mut self) {
+        let current = Self::snapshot();
+        let changed: Vec<&PathBuf> = self
+            .known
+            .iter()
+            .filter(|(path, state)| current.get(*path) != Some(state))
+            .map(|(path, _)| path)
+            .collect();
+        if !changed.is_empty() || current.len() != self.known.len() {
+            tracing::debug!(changed = changed.len(), "Session files changed");
+            if let Some(analyses) = ANALYSES.get() {
+                analyses.lock().unwrap_or_else(|e| e.into_inner()).retain(|(path, _), _| current.contains_key(path));
+            }
+            crate::claude_data::reload_claude_data();
+        }
+        if let Some(notifier) = &mut self.notifier {
+            // Session files that grew, with their size before; not Claude's history or todos
+            let grown: Vec<(PathBuf, u64)> = current
+                .iter()
+                .filter(|(path, _)| path.extension().is_some_and(|ext| ext == "jsonl") && !path.ends_with("history.jsonl"))
+                .filter_map(|(path, (_, size))| {
+                    let previous = self.known.get(path).map_or(0, |(_, previous)| *previous);
+                    (*size != previous).then(|| (path.clone(), previous))
+                })
+                .collect();
+            notifier.observe(&grown);
+        }
+        self.known = current;
+    }
+}
+
+/// Sessions matching a search, found by the daemon if one is running; `None` means search
+/// here instead.
+#[cfg(unix)]
+pub fn query_daemon(
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    limit: usize,
+    group_by: Option<&str>,
+    sort: SortOrder,
+    no_ignore: bool,
+) -> Result<Option<Vec<SessionInfo>>> {
+    use crate::error::SessionFinderError;
+    use serde_json::{json, Value};
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use tracing::debug;
+
+    let Ok(mut stream) = UnixStream::connect(socket_path()?) else {
+        return Ok(None);
+    };
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "find_sessions",
+        "params": {
+            "terms": search_terms,
+            "project": project_filter,
+            "recent_days": recent_days,
+            "limit": limit,
+            "group_by": group_by,
+            "sort": sort.name(),
+            "no_ignore": no_ignore,
+        },
+    });
+    let daemon_error = |e: &dyn std::fmt::Display| SessionFinderError::backend("daemon", format!("The daemon failed to answer: {}", e));
+    writeln!(stream, "{}", request).map_err(|e| daemon_error(&e))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).map_err(|e| daemon_error(&e))?;
+    let mut response: Value = serde_json::from_str(&line).map_err(|e| daemon_error(&e))?;
+    if let Some(error) = response.get("error") {
+        let message = error.get("message").and_then(Value::as_str).unwrap_or("daemon error");
+        return Err(SessionFinderError::backend("daemon", message));
+    }
+    debug!("Answered by the daemon");
+    Ok(Some(serde_json::from_value(response["result"].take()).map_err(|e| daemon_error(&e))?))
+}
+
+#[cfg(not(unix))]
+pub fn query_daemon(
+    _search_terms: &[&str],
+    _project_filter: Option<&String>,
+    _recent_days: Option<i64>,
+    _limit: usize,
+    _group_by: Option<&str>,
+    _sort: SortOrder,
+    _no_ignore: bool,
+) -> Result<Option<Vec<crate::SessionInfo>>> {
+    Ok(None)
+}
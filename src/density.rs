@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+
+use crate::glyphs::Glyph;
+use crate::render::RenderOptions;
+use crate::stats::format_duration;
+use crate::TimelineExtraction;
+
+/// Columns in the density chart
+const DENSITY_COLUMNS: usize = 60;
+
+/// Width of the row labels in front of each chart line
+const LABEL_WIDTH: usize = 9;
+
+/// Where along the session each message falls
+enum Axis {
+    /// Spread by when messages were sent, between the first and last timestamps
+    Time { start: DateTime<Utc>, end: DateTime<Utc> },
+    /// Spread evenly by position, for sessions without usable timestamps
+    Index,
+}
+
+/// Prints sparklines of message volume and of the shown matches across the session, with the
+/// busiest stretch called out, so clusters of activity stand out before reading entries.
+pub fn display_density(timeline: &TimelineExtraction, render: RenderOptions) {
+    let times = &timeline.message_times;
+    if times.is_empty() {
+        return;
+    }
+
+    let axis = match (times.iter().flatten().min(), times.iter().flatten().max()) {
+        (Some(&start), Some(&end)) if end > start => Axis::Time { start, end },
+        _ => Axis::Index,
+    };
+    let columns = match axis {
+        Axis::Time { .. } => DENSITY_COLUMNS,
+        Axis::Index => DENSITY_COLUMNS.min(times.len()),
+    };
+
+    // Messages without a timestamp sit with the message before them
+    let mut last_time = None;
+    let message_columns: Vec<usize> = times
+        .iter()
+        .enumerate()
+        .map(|(index, time)| {
+            last_time = time.or(last_time);
+            let fraction = match (&axis, last_time) {
+                (Axis::Time { start, end }, Some(time)) => {
+                    (time - *start).num_milliseconds() as f64 / (*end - *start).num_milliseconds() as f64
+                }
+                (Axis::Time { .. }, None) => 0.0,
+                (Axis::Index, _) => index as f64 / times.len().max(2).saturating_sub(1) as f64,
+            };
+            ((fraction * columns as f64) as usize).min(columns - 1)
+        })
+        .collect();
+
+    let mut messages = vec![0usize; columns];
+    for &column in &message_columns {
+        messages[column] += 1;
+    }
+    let mut matches = vec![0usize; columns];
+    for entry in &timeline.timeline {
+        if let Some(&column) = message_columns.get(entry.message_index) {
+            matches[column] += 1;
+        }
+    }
+
+    match axis {
+        Axis::Time { start, end } => {
+            let span = (end - start).num_seconds();
+            println!(
+                "Activity over {} ({} {} {}), {} per column:",
+                format_span(span),
+                start.format("%Y-%m-%d %H:%M"),
+                render.glyph(Glyph::Arrow),
+                end.format("%Y-%m-%d %H:%M"),
+                format_span(span / columns as i64)
+            );
+        }
+        Axis::Index => println!(
+            "Activity across {} messages (no timestamps), {:.1} per column:",
+            times.len(),
+            times.len() as f64 / columns as f64
+        ),
+    }
+    let rule = render.glyph(Glyph::Rule);
+    println!("{:<width$}{}{}{} peak {}", "messages", rule, sparkline(&messages, render), rule, max(&messages), width = LABEL_WIDTH);
+    println!("{:<width$}{}{}{} peak {}", "matches", rule, sparkline(&matches, render), rule, max(&matches), width = LABEL_WIDTH);
+
+    let last_index = times.len() - 1;
+    let first_label = "#0".to_string();
+    let last_label = format!("#{}", last_index);
+    println!(
+        "{:<width$} {}{:>pad$}",
+        "",
+        first_label,
+        last_label,
+        width = LABEL_WIDTH,
+        pad = columns.saturating_sub(first_label.chars().count())
+    );
+
+    let busiest = (0..columns).max_by_key(|&column| (matches[column], messages[column]));
+    if let Some(column) = busiest.filter(|&column| messages[column] > 0) {
+        let indices: Vec<usize> = (0..message_columns.len())
+            .filter(|&index| message_columns[index] == column)
+            .collect();
+        let label = if matches[column] > 0 { "Most matches" } else { "Busiest" };
+        println!(
+            "{}: messages #{}{}#{} ({} message(s), {} match(es))",
+            label,
+            indices[0],
+            render.glyph(Glyph::Range),
+            indices[indices.len() - 1],
+            messages[column],
+            matches[column]
+        );
+    }
+    println!();
+}
+
+pub fn sparkline(counts: &[usize], render: RenderOptions) -> String {
+    let peak = max(counts);
+    let levels = render.sparkline_levels();
+    counts
+        .iter()
+        .map(|&count| match count {
+            0 => levels[0],
+            // Any activity gets at least the lowest bar
+            _ => levels[(count * (levels.len() - 1)).div_ceil(peak).max(1)],
+        })
+        .collect()
+}
+
+fn max(counts: &[usize]) -> usize {
+    counts.iter().copied().max().unwrap_or_default()
+}
+
+fn format_span(secs: i64) -> String {
+    if secs < 60 {
+        format!("{}s", secs.max(1))
+    } else {
+        format_duration(secs)
+    }
+}
@@ -0,0 +1,197 @@
+use chrono::{DateTime, Days, Local, NaiveDate, TimeZone};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::{Result, SessionFinderError};
+use crate::export::{session_title, short_id};
+use crate::file_ops::files_touched;
+use crate::glyphs::Glyph;
+use crate::matcher::SearchOptions;
+use crate::render::RenderOptions;
+use crate::resolve::read_session_text;
+use crate::resume::session_working_dir;
+use crate::stats::{estimate_cost, format_duration, selected_session_files};
+use crate::timeline::{message_time, parse_session_messages};
+use crate::trash::TrashOperation;
+use crate::{analyze_session_file, SessionMessage};
+
+/// Edited files listed per session before the rest are counted
+const DIGEST_FILE_LIMIT: usize = 8;
+
+/// Settings for `digest --save`, from `[digest]` in the config file
+#[derive(Debug, Default, Deserialize)]
+pub struct DigestConfig {
+    /// Directory digests are saved into; `~/` is the home directory
+    pub dir: Option<String>,
+}
+
+/// One session's activity on the digest's day
+struct DigestEntry {
+    session_id: String,
+    title: String,
+    messages: usize,
+    cost: f64,
+    first: DateTime<Local>,
+    last: DateTime<Local>,
+    files: Vec<String>,
+}
+
+/// Accepts `today`, `yesterday`, or a date as YYYY-MM-DD.
+pub fn parse_digest_date(value: &str) -> Result<NaiveDate> {
+    let today = Local::now().date_naive();
+    match value.trim() {
+        "today" => Ok(today),
+        "yesterday" => Ok(today - Days::new(1)),
+        other => NaiveDate::parse_from_str(other, "%Y-%m-%d")
+            .map_err(|_| SessionFinderError::Usage(format!(
+                "Invalid --date {:?}: use today, yesterday, or YYYY-MM-DD",
+                value
+            ))),
+    }
+}
+
+/// The directory `--save` writes to: `--dir`, else `[digest] dir` from the config file.
+pub fn digest_dir(dir: Option<&str>) -> Result<PathBuf> {
+    let configured = Config::load()?.digest.dir;
+    let dir = dir
+        .map(str::to_string)
+        .or(configured)
+        .ok_or_else(|| {
+            SessionFinderError::Usage(
+                "No digest directory: pass --dir or set dir under [digest] in the config file".to_string(),
+            )
+        })?;
+    Ok(match dir.strip_prefix("~/") {
+        Some(rest) => Path::new(&std::env::var("HOME")?).join(rest),
+        None => PathBuf::from(dir),
+    })
+}
+
+/// Writes a Markdown summary of the sessions active on `date` (local time): per project, each
+/// session's title, messages, active time, estimated cost, and the files it edited that day.
+/// Printed, or saved as `DATE.md` in `save_dir`, replacing an earlier digest of the same day.
+pub fn run_digest(date: NaiveDate, project_filter: Option<&String>, save_dir: Option<&Path>, render: RenderOptions) -> Result<()> {
+    let start = Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+        .earliest()
+        .ok_or_else(|| SessionFinderError::Usage(format!("{} has no midnight in the local time zone", date)))?;
+
+    let options = SearchOptions { render, ..SearchOptions::default() };
+    let mut projects: BTreeMap<String, Vec<DigestEntry>> = BTreeMap::new();
+    for (path, project) in selected_session_files(project_filter, None)? {
+        // Sessions last written before the day began have nothing from it
+        let modified: DateTime<Local> = DateTime::from(fs::metadata(&path)?.modified()?);
+        if modified < start {
+            continue;
+        }
+        let day: Vec<SessionMessage> = parse_session_messages(&read_session_text(&path)?)?
+            .into_iter()
+            .filter(|msg| message_time(msg).is_some_and(|sent| sent.with_timezone(&Local).date_naive() == date))
+            .collect();
+        let times: Vec<DateTime<Local>> = day.iter().filter_map(message_time).map(|sent| sent.with_timezone(&Local)).collect();
+        let (Some(&first), Some(&last)) = (times.iter().min(), times.iter().max()) else {
+            continue;
+        };
+        let cost = day
+            .iter()
+            .filter_map(|msg| msg.message.as_ref())
+            .filter_map(|inner| Some(estimate_cost(inner.model.as_deref(), inner.usage.as_ref()?)))
+            .fold(0.0, |total, cost| total + cost);
+        let Some(info) = analyze_session_file(&path, &[], None, None, &options)? else {
+            continue;
+        };
+        projects.entry(project).or_default().push(DigestEntry {
+            title: session_title(&info),
+            session_id: info.session_id,
+            messages: day.len(),
+            cost,
+            first,
+            last,
+            files: relative_files(&day, &session_working_dir(&path)),
+        });
+    }
+    for entries in projects.values_mut() {
+        entries.sort_by(|a, b| a.first.cmp(&b.first).then_with(|| a.session_id.cmp(&b.session_id)));
+    }
+
+    let rendered = render_digest(date, &projects, render);
+    match save_dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            let digest_path = dir.join(format!("{}.md", date.format("%Y-%m-%d")));
+            // The day's earlier digest may have been annotated by hand, so it is kept
+            let mut trash = TrashOperation::begin("digest");
+            if fs::read_to_string(&digest_path).is_ok_and(|existing| existing != rendered) {
+                trash.discard_file(&digest_path)?;
+            }
+            fs::write(&digest_path, rendered)?;
+            println!("{}", digest_path.display());
+            trash.finish();
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Files the messages edited, relative to the session's working directory where inside it.
+fn relative_files(messages: &[SessionMessage], working_dir: &str) -> Vec<String> {
+    files_touched(messages, working_dir)
+        .into_iter()
+        .map(|file| match Path::new(&file).strip_prefix(working_dir) {
+            Ok(relative) if !working_dir.is_empty() => relative.to_string_lossy().into_owned(),
+            _ => file,
+        })
+        .collect()
+}
+
+fn render_digest(date: NaiveDate, projects: &BTreeMap<String, Vec<DigestEntry>>, render: RenderOptions) -> String {
+    let mut out = format!("# Claude activity, {}\n\n", date.format("%A %Y-%m-%d"));
+    let entries: Vec<&DigestEntry> = projects.values().flatten().collect();
+    if entries.is_empty() {
+        out.push_str("No sessions.\n");
+        return out;
+    }
+    let active: i64 = entries.iter().map(|entry| (entry.last - entry.first).num_seconds()).sum();
+    let _ = writeln!(
+        out,
+        "{} session{} in {} project{}: {} messages, {} active, ${:.2} estimated",
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" },
+        projects.len(),
+        if projects.len() == 1 { "" } else { "s" },
+        entries.iter().map(|entry| entry.messages).sum::<usize>(),
+        format_duration(active),
+        entries.iter().fold(0.0, |total, entry| total + entry.cost)
+    );
+
+    for (project, entries) in projects {
+        let _ = write!(out, "\n## {}\n\n", project);
+        for entry in entries {
+            let _ = writeln!(
+                out,
+                "- {}{}{} **{}** (`{}`): {} messages, {} active, ${:.2}",
+                entry.first.format("%H:%M"),
+                render.glyph(Glyph::Range),
+                entry.last.format("%H:%M"),
+                entry.title,
+                short_id(&entry.session_id),
+                entry.messages,
+                format_duration((entry.last - entry.first).num_seconds()),
+                entry.cost
+            );
+            if !entry.files.is_empty() {
+                let mut files: Vec<String> =
+                    entry.files.iter().take(DIGEST_FILE_LIMIT).map(|file| format!("`{}`", file)).collect();
+                if entry.files.len() > DIGEST_FILE_LIMIT {
+                    files.push(format!("{} more", entry.files.len() - DIGEST_FILE_LIMIT));
+                }
+                let _ = writeln!(out, "  - Edited {}", files.join(", "));
+            }
+        }
+    }
+    out
+}
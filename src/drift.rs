@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::glyphs::Glyph;
+use crate::render::RenderOptions;
+use crate::resolve::read_session_source;
+use crate::resume::session_working_dir;
+use crate::timeline::parse_session_messages;
+use crate::{Content, SessionMessage};
+
+/// A change a session made to a file through Write, Edit, or MultiEdit
+#[derive(Debug, Clone)]
+pub enum FileChange {
+    Write(String),
+    Edit { old: String, new: String, replace_all: bool },
+}
+
+impl FileChange {
+    /// The file after this change, if the change applies to it.
+    pub fn apply(&self, content: Option<&str>) -> Option<String> {
+        match self {
+            FileChange::Write(written) => Some(written.clone()),
+            FileChange::Edit { old, new, replace_all } => {
+                let content = content?;
+                if old.is_empty() || !content.contains(old.as_str()) {
+                    None
+                } else if *replace_all {
+                    Some(content.replace(old.as_str(), new))
+                } else {
+                    Some(content.replacen(old.as_str(), new, 1))
+                }
+            }
+        }
+    }
+
+    /// Whether the change can still be seen in the file's current content.
+    fn present_in(&self, current: &str, session_final: Option<&str>) -> bool {
+        match self {
+            // A later edit changes what was written, so only the session's last version counts
+            FileChange::Write(written) => current == written || session_final == Some(current),
+            FileChange::Edit { old, new, .. } if new.is_empty() => !current.contains(old.as_str()),
+            FileChange::Edit { new, .. } => current.contains(new.as_str()),
+        }
+    }
+}
+
+/// The changes a session made, in order, with the file each was made to. Calls whose result
+/// was an error (a failed match, a rejected call) changed nothing and are left out.
+pub fn file_changes(messages: &[SessionMessage]) -> Vec<(String, FileChange)> {
+    let failed: HashSet<&str> = messages
+        .iter()
+        .filter_map(|msg| match msg.message.as_ref()?.content.as_ref()? {
+            Content::Array(blocks) => Some(blocks),
+            Content::Text(_) => None,
+        })
+        .flatten()
+        .filter(|block| block.r#type == "tool_result" && block.is_error == Some(true))
+        .filter_map(|block| block.tool_use_id.as_deref())
+        .collect();
+
+    let mut changes = Vec::new();
+    for msg in messages {
+        let Some(Content::Array(blocks)) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+            continue;
+        };
+        for block in blocks.iter().filter(|block| block.r#type == "tool_use") {
+            if block.id.as_deref().is_some_and(|id| failed.contains(id)) {
+                continue;
+            }
+            let Some(input) = block.input.as_ref() else {
+                continue;
+            };
+            let Some(file_path) = input.get("file_path").and_then(|path| path.as_str()) else {
+                continue;
+            };
+            let text = |value: &serde_json::Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            let edit = |value: &serde_json::Value| {
+                Some(FileChange::Edit {
+                    old: text(value, "old_string")?,
+                    new: text(value, "new_string")?,
+                    replace_all: value.get("replace_all").and_then(|v| v.as_bool()).unwrap_or(false),
+                })
+            };
+            match block.name.as_deref() {
+                Some("Write") => changes.extend(text(input, "content").map(|content| (file_path.to_string(), FileChange::Write(content)))),
+                Some("Edit") => changes.extend(edit(input).map(|change| (file_path.to_string(), change))),
+                Some("MultiEdit") => {
+                    let edits = input.get("edits").and_then(|edits| edits.as_array()).map(Vec::as_slice).unwrap_or_default();
+                    changes.extend(edits.iter().filter_map(edit).map(|change| (file_path.to_string(), change)));
+                }
+                _ => {}
+            }
+        }
+    }
+    changes
+}
+
+/// How much of a session's work on one file is still on disk
+struct FileDrift {
+    path: String,
+    changes: usize,
+    present: usize,
+    exists: bool,
+    /// The file is exactly as the session left it
+    unchanged: bool,
+}
+
+/// Compares what a session did to each file it edited with the file as it is now, reporting
+/// how many of the session's edits survive, to tell whether its work made it into the repo.
+pub fn run_drift(session_ref: &str, render: RenderOptions) -> Result<bool> {
+    let (path, session_id, content) = read_session_source(session_ref)?;
+    let messages = parse_session_messages(&content)?;
+    let working_dir = PathBuf::from(session_working_dir(&path));
+
+    let mut files: Vec<(String, Vec<FileChange>)> = Vec::new();
+    for (file, change) in file_changes(&messages) {
+        match files.iter_mut().find(|(known, _)| *known == file) {
+            Some((_, changes)) => changes.push(change),
+            None => files.push((file, vec![change])),
+        }
+    }
+    if files.is_empty() {
+        println!("Session {} made no file edits.", session_id);
+        return Ok(false);
+    }
+
+    let drifts: Vec<FileDrift> = files
+        .iter()
+        .map(|(file, changes)| file_drift(&working_dir, file, changes))
+        .collect();
+    let total: usize = drifts.iter().map(|drift| drift.changes).sum();
+    let present: usize = drifts.iter().map(|drift| drift.present).sum();
+    println!(
+        "Session {}: {} of {} session edit{} still present across {} file{}\n",
+        session_id,
+        present,
+        total,
+        if total == 1 { "" } else { "s" },
+        drifts.len(),
+        if drifts.len() == 1 { "" } else { "s" }
+    );
+
+    let width = drifts.iter().map(|drift| drift.path.chars().count()).max().unwrap_or(0);
+    for drift in &drifts {
+        let (mark, detail) = if !drift.exists {
+            (render.glyph(Glyph::Failed), "file no longer exists".to_string())
+        } else if drift.unchanged {
+            (render.glyph(Glyph::Ok), "unchanged since the session".to_string())
+        } else if drift.present == drift.changes {
+            (render.glyph(Glyph::Ok), "all edits present, file changed since".to_string())
+        } else if drift.present == 0 {
+            (render.glyph(Glyph::Failed), "no edits present".to_string())
+        } else {
+            ("~", "some edits reverted or rewritten".to_string())
+        };
+        println!(
+            "  {} {:<width$}  {:>3} of {:<3} {}",
+            mark,
+            drift.path,
+            drift.present,
+            drift.changes,
+            detail,
+            width = width
+        );
+    }
+    Ok(true)
+}
+
+fn file_drift(working_dir: &Path, file: &str, changes: &[FileChange]) -> FileDrift {
+    let full_path = working_dir.join(file);
+    let path = full_path
+        .strip_prefix(working_dir)
+        .ok()
+        .filter(|relative| !working_dir.as_os_str().is_empty() && !relative.as_os_str().is_empty())
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .unwrap_or_else(|| file.to_string());
+
+    // Only known when the session wrote the whole file, then edits all applied
+    let mut session_final: Option<String> = None;
+    for change in changes {
+        session_final = change.apply(session_final.as_deref());
+    }
+
+    let Ok(current) = fs::read_to_string(&full_path) else {
+        return FileDrift { path, changes: changes.len(), present: 0, exists: full_path.exists(), unchanged: false };
+    };
+    let present = changes
+        .iter()
+        .filter(|change| change.present_in(&current, session_final.as_deref()))
+        .count();
+    FileDrift {
+        path,
+        changes: changes.len(),
+        present,
+        exists: true,
+        unchanged: session_final.as_deref() == Some(current.as_str()),
+    }
+}
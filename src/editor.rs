@@ -0,0 +1,47 @@
+use std::path::Path;
+use std::process;
+
+use crate::error::{Result, SessionFinderError};
+use crate::resolve::STDIN_REFERENCE;
+
+/// Opens the session file in `$EDITOR` at the given 1-based JSONL line. VS Code and Sublime
+/// take `FILE:LINE`; everything else gets `+LINE FILE`, which vim, emacs, nano, and most
+/// terminal editors understand.
+pub fn open_in_editor(path: &Path, line: usize) -> Result<()> {
+    if path == Path::new(STDIN_REFERENCE) {
+        return Err(SessionFinderError::Usage("--edit needs a session file; this one was read from stdin".to_string()));
+    }
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .ok_or_else(|| SessionFinderError::Environment("--edit needs $EDITOR to be set".to_string()))?;
+
+    // $EDITOR may carry its own flags, such as `code -w`
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or_default();
+    let mut args: Vec<String> = words.map(str::to_string).collect();
+    let name = Path::new(program)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            args.push("--goto".to_string());
+            args.push(format!("{}:{}", path.display(), line));
+        }
+        "subl" | "zed" => args.push(format!("{}:{}", path.display(), line)),
+        _ => {
+            args.push(format!("+{}", line));
+            args.push(path.display().to_string());
+        }
+    }
+
+    let status = process::Command::new(program)
+        .args(&args)
+        .status()
+        .map_err(|e| SessionFinderError::backend("$EDITOR", format!("Could not run $EDITOR ({}): {}", program, e)))?;
+    if !status.success() {
+        return Err(SessionFinderError::backend("$EDITOR", format!("{} exited with status: {}", program, status)));
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Why a command failed, by class, so callers can handle each differently; the CLI picks its
+/// exit status from it. Library functions return it directly; only the command line wraps it
+/// in `anyhow::Error`, where `downcast_ref` recovers it.
+#[derive(Debug)]
+pub enum SessionFinderError {
+    /// None of the directories sessions are read from exist
+    RootNotFound { path: PathBuf },
+    /// A session reference matched no session, or more than one (the candidates, newest first)
+    SessionNotFound { reference: String, candidates: Vec<CandidateSession> },
+    /// A session reference that can't name any session, such as `@-0`
+    InvalidReference(String),
+    /// A request that can't be carried out as given: an unknown `--format`, a malformed
+    /// `--date`, or a note or bookmark that isn't there
+    Usage(String),
+    /// A file that isn't in the format expected, with the line the problem is on when known
+    ParseError { path: PathBuf, line: Option<usize>, message: String },
+    /// A program or service a command relies on is missing or failed: ripgrep, the daemon,
+    /// sqlite3, the AWS CLI, or `$EDITOR`
+    BackendUnavailable { backend: String, message: String },
+    /// Something else the machine lacks, such as `HOME` or an encryption key
+    Environment(String),
+    Io(std::io::Error),
+}
+
+/// A session an ambiguous reference could mean, as listed in the error
+#[derive(Debug, Clone)]
+pub struct CandidateSession {
+    pub session_id: String,
+    pub modified: Option<DateTime<Utc>>,
+    pub project: String,
+    pub title: String,
+}
+
+pub type Result<T, E = SessionFinderError> = std::result::Result<T, E>;
+
+impl SessionFinderError {
+    pub fn backend(backend: &str, message: impl fmt::Display) -> Self {
+        SessionFinderError::BackendUnavailable { backend: backend.to_string(), message: message.to_string() }
+    }
+
+    /// A TOML file that failed to parse, with the line the parser stopped at.
+    pub fn toml(path: PathBuf, content: &str, error: toml::de::Error) -> Self {
+        let line = error.span().map(|span| content[..span.start].matches('\n').count() + 1);
+        SessionFinderError::ParseError { path, line, message: error.message().to_string() }
+    }
+
+    /// A JSON file that failed to parse, with the line the parser stopped at.
+    pub fn json(path: &Path, error: serde_json::Error) -> Self {
+        let line = (error.line() > 0).then(|| error.line());
+        SessionFinderError::ParseError { path: path.to_path_buf(), line, message: error.to_string() }
+    }
+
+    /// Whether the failure lies with the machine rather than with what was asked of it.
+    pub fn is_environmental(&self) -> bool {
+        matches!(
+            self,
+            SessionFinderError::RootNotFound { .. }
+                | SessionFinderError::BackendUnavailable { .. }
+                | SessionFinderError::Environment(_)
+                | SessionFinderError::Io(_)
+        )
+    }
+}
+
+/// Whether `error` comes from the named backend being missing or failing.
+pub fn is_backend_failure(error: &SessionFinderError, name: &str) -> bool {
+    matches!(error, SessionFinderError::BackendUnavailable { backend, .. } if backend == name)
+}
+
+impl fmt::Display for SessionFinderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionFinderError::RootNotFound { path } => write!(f, "Projects directory not found: {:?}", path),
+            SessionFinderError::SessionNotFound { reference, candidates } if candidates.is_empty() => {
+                write!(f, "Could not resolve session path: {}", reference)
+            }
+            SessionFinderError::SessionNotFound { reference, candidates } => {
+                write!(f, "Session reference '{}' is ambiguous; {} sessions match:", reference, candidates.len())?;
+                for candidate in candidates {
+                    let modified = candidate
+                        .modified
+                        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default();
+                    write!(f, "\n  {}  {}  {}  {}", candidate.session_id, modified, candidate.project, candidate.title)?;
+                }
+                Ok(())
+            }
+            SessionFinderError::InvalidReference(reason) | SessionFinderError::Usage(reason) => f.write_str(reason),
+            SessionFinderError::ParseError { path, line: Some(line), message } => {
+                write!(f, "Could not parse {} (line {}): {}", path.display(), line, message)
+            }
+            SessionFinderError::ParseError { path, line: None, message } => {
+                write!(f, "Could not parse {}: {}", path.display(), message)
+            }
+            SessionFinderError::BackendUnavailable { message, .. } | SessionFinderError::Environment(message) => {
+                f.write_str(message)
+            }
+            SessionFinderError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionFinderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SessionFinderError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SessionFinderError {
+    fn from(e: std::io::Error) -> Self {
+        SessionFinderError::Io(e)
+    }
+}
+
+impl From<std::env::VarError> for SessionFinderError {
+    fn from(e: std::env::VarError) -> Self {
+        SessionFinderError::Environment(e.to_string())
+    }
+}
+
+// Serializing fails only when the writer does, or for values that can't be represented,
+// which none of ours are
+impl From<serde_json::Error> for SessionFinderError {
+    fn from(e: serde_json::Error) -> Self {
+        SessionFinderError::Io(e.into())
+    }
+}
+
+impl From<toml::ser::Error> for SessionFinderError {
+    fn from(e: toml::ser::Error) -> Self {
+        SessionFinderError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for SessionFinderError {
+    fn from(e: rusqlite::Error) -> Self {
+        SessionFinderError::backend("sqlite", format!("SQLite failed: {}", e))
+    }
+}
+
+impl From<walkdir::Error> for SessionFinderError {
+    fn from(e: walkdir::Error) -> Self {
+        SessionFinderError::Io(e.into())
+    }
+}
@@ -0,0 +1,110 @@
+use regex::RegexSet;
+use serde::Deserialize;
+use std::sync::{Arc, RwLock};
+
+use crate::config::config_path;
+use crate::error::{Result, SessionFinderError};
+use crate::ErrorInfo;
+
+/// One way of recognising an error in message text. Extra rules come from `[[error_rules]]`
+/// tables in the config file and are tried before the built-in ones:
+///
+/// ```toml
+/// [[error_rules]]
+/// pattern = "FATAL: .*database"
+/// error_type = "database"
+/// severity = "error"
+/// source = "postgres"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorRule {
+    /// Regex searched for anywhere in the text
+    pub pattern: String,
+    pub error_type: String,
+    /// `error` or `warning`; only errors count as something a later success fixed
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    pub source: Option<String>,
+}
+
+fn default_severity() -> String {
+    "error".to_string()
+}
+
+/// Built-in rules as (pattern, error type, severity, source), most specific first
+const BUILT_IN_RULES: &[(&str, &str, &str, Option<&str>)] = &[
+    (r"error\[E\d+\]|cannot find", "compilation", "error", Some("rustc")),
+    (r"panicked at", "runtime", "error", Some("rust")),
+    (r"error TS\d+:", "compilation", "error", Some("tsc")),
+    (r"Traceback \(most recent call last\)", "runtime", "error", Some("python")),
+    (r"(?m)^FAILED \S+::|=+ .*\d+ failed.* =+|(?m)^E\s+assert", "test_failure", "error", Some("pytest")),
+    (r"(?m)^\s*FAIL\s+\S+\.(?:test|spec)\.[jt]sx?|Tests:\s+\d+ failed|(?m)^\s*● .+ › ", "test_failure", "error", Some("jest")),
+    (r"(?m)^\s+at .+\(.+\.(?:js|mjs|cjs|ts|tsx):\d+:\d+\)", "runtime", "error", Some("node")),
+    (r"npm ERR!", "tool_error", "error", Some("npm")),
+    (r"Error response from daemon|failed to solve|(?i:docker: .*error)", "tool_error", "error", Some("docker")),
+    (
+        r"\bHTTP/\d(?:\.\d)? 5\d\d\b|\b(?i:status(?: code)?):? 5\d\d\b|\b5\d\d (?:Internal Server Error|Bad Gateway|Service Unavailable|Gateway Timeout)\b",
+        "http",
+        "error",
+        Some("http"),
+    ),
+    (r"Permission denied|No such file", "tool_error", "error", Some("system")),
+    (r"(?m)^(?:\w+\.)*\w+(?:Error|Exception): ", "runtime", "error", None),
+    (r"warning:", "compilation", "warning", Some("rustc")),
+];
+
+/// Every rule compiled into one set, so a message is scanned once
+pub struct ErrorRules {
+    set: RegexSet,
+    rules: Vec<ErrorRule>,
+}
+
+static RULES: RwLock<Option<Arc<ErrorRules>>> = RwLock::new(None);
+
+impl ErrorRules {
+    fn new(user_rules: &[ErrorRule]) -> Result<Self> {
+        let built_in = BUILT_IN_RULES.iter().map(|(pattern, error_type, severity, source)| ErrorRule {
+            pattern: pattern.to_string(),
+            error_type: error_type.to_string(),
+            severity: severity.to_string(),
+            source: source.map(str::to_string),
+        });
+        let rules: Vec<ErrorRule> = user_rules.iter().cloned().chain(built_in).collect();
+        let invalid = |e: regex::Error| SessionFinderError::ParseError {
+            path: config_path().unwrap_or_default(),
+            line: None,
+            message: format!("invalid error rule pattern: {}", e),
+        };
+        for rule in user_rules {
+            regex::Regex::new(&rule.pattern).map_err(invalid)?;
+        }
+        let set = RegexSet::new(rules.iter().map(|rule| &rule.pattern)).map_err(invalid)?;
+        Ok(ErrorRules { set, rules })
+    }
+
+    /// The first rule, in order, that matches the text.
+    pub fn detect(&self, text: &str) -> Option<ErrorInfo> {
+        let rule = &self.rules[self.set.matches(text).iter().next()?];
+        Some(ErrorInfo {
+            error_type: rule.error_type.clone(),
+            severity: rule.severity.clone(),
+            source: rule.source.clone(),
+        })
+    }
+}
+
+/// Puts the config file's rules in front of the built-in ones, in place of any installed before.
+pub fn install_error_rules(user_rules: &[ErrorRule]) -> Result<()> {
+    let rules = Arc::new(ErrorRules::new(user_rules)?);
+    *RULES.write().unwrap_or_else(|e| e.into_inner()) = Some(rules);
+    Ok(())
+}
+
+/// The rules in effect: the built-in ones unless `install_error_rules` added more.
+pub fn error_rules() -> Arc<ErrorRules> {
+    if let Some(rules) = RULES.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return Arc::clone(rules);
+    }
+    let mut rules = RULES.write().unwrap_or_else(|e| e.into_inner());
+    Arc::clone(rules.get_or_insert_with(|| Arc::new(ErrorRules::new(&[]).expect("built-in error rules compile"))))
+}
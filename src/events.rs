@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::timeline::{extract_content_text, parse_session_messages};
+use crate::title::session_title;
+use crate::{decode_project_path, extract_session_id, Content};
+
+/// Kinds of session activity the daemon can announce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// The session stopped growing for a while
+    SessionFinished,
+    /// Several tool calls failed in a short time
+    ErrorBurst,
+    /// A watched term turned up in a new message
+    Term,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 3] = [EventKind::SessionFinished, EventKind::ErrorBurst, EventKind::Term];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::SessionFinished => "session_finished",
+            EventKind::ErrorBurst => "error_burst",
+            EventKind::Term => "term",
+        }
+    }
+}
+
+/// Something that happened in a session, noticed as its file grew
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionEvent {
+    pub kind: EventKind,
+    pub session_id: String,
+    pub title: String,
+    pub project: String,
+    pub path: PathBuf,
+    /// What happened, in a sentence
+    pub detail: String,
+}
+
+impl SessionEvent {
+    /// A headline for notifications.
+    pub fn summary(&self) -> String {
+        match self.kind {
+            EventKind::SessionFinished => format!("Session finished: {}", self.title),
+            EventKind::ErrorBurst => format!("Session failing: {}", self.title),
+            EventKind::Term => format!("Watched term in: {}", self.title),
+        }
+    }
+}
+
+/// When activity counts as an event
+#[derive(Debug, Clone)]
+pub struct EventThresholds {
+    /// Lowercase terms to watch for
+    pub terms: Vec<String>,
+    /// How long a session must stop growing to count as finished
+    pub finished_after: Duration,
+    /// Failed tool calls within `error_window` that make a burst
+    pub error_burst: usize,
+    pub error_window: Duration,
+}
+
+/// What has been seen of one session file since the daemon started
+struct SessionState {
+    /// Bytes read so far, up to the end of the last whole line
+    offset: u64,
+    last_grew: Instant,
+    finished_announced: bool,
+    errors: VecDeque<Instant>,
+    burst_announced: Option<Instant>,
+}
+
+/// Reads what is appended to session files and turns it into events.
+pub struct EventDetector {
+    thresholds: EventThresholds,
+    sessions: HashMap<PathBuf, SessionState>,
+}
+
+impl EventDetector {
+    pub fn new(thresholds: EventThresholds) -> Self {
+        EventDetector { thresholds, sessions: HashMap::new() }
+    }
+
+    /// Events from session files that grew, given with their size when last seen (0 for new
+    /// files), and from sessions that have now been quiet long enough to have finished.
+    pub fn observe(&mut self, grown: &[(PathBuf, u64)]) -> Vec<SessionEvent> {
+        let now = Instant::now();
+        let mut events = Vec::new();
+        for (path, previous_size) in grown {
+            let state = self.sessions.entry(path.clone()).or_insert_with(|| SessionState {
+                offset: *previous_size,
+                last_grew: now,
+                finished_announced: false,
+                errors: VecDeque::new(),
+                burst_announced: None,
+            });
+            let Some(appended) = read_appended(path, &mut state.offset) else {
+                continue;
+            };
+            state.last_grew = now;
+            state.finished_announced = false;
+
+            let messages = parse_session_messages(&appended).unwrap_or_default();
+            let mut found_terms: Vec<&str> = Vec::new();
+            for msg in &messages {
+                let Some(content) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+                    continue;
+                };
+                if let Content::Array(blocks) = content {
+                    let failed = blocks.iter().filter(|block| block.r#type == "tool_result" && block.is_error == Some(true));
+                    state.errors.extend(failed.map(|_| now));
+                }
+                let text = extract_content_text(content).to_lowercase();
+                for term in &self.thresholds.terms {
+                    if !found_terms.contains(&term.as_str()) && text.contains(term.as_str()) {
+                        found_terms.push(term);
+                    }
+                }
+            }
+            for term in found_terms {
+                events.push(session_event(EventKind::Term, path, format!("\"{}\" appeared in a new message", term)));
+            }
+
+            while state.errors.front().is_some_and(|time| now.duration_since(*time) > self.thresholds.error_window) {
+                state.errors.pop_front();
+            }
+            let in_last_burst = state
+                .burst_announced
+                .is_some_and(|announced| now.duration_since(announced) <= self.thresholds.error_window);
+            if state.errors.len() >= self.thresholds.error_burst && !in_last_burst {
+                state.burst_announced = Some(now);
+                let detail = format!(
+                    "{} tool calls failed in the last {} minute(s)",
+                    state.errors.len(),
+                    self.thresholds.error_window.as_secs().div_ceil(60)
+                );
+                events.push(session_event(EventKind::ErrorBurst, path, detail));
+            }
+        }
+
+        for (path, state) in &mut self.sessions {
+            if !state.finished_announced && now.duration_since(state.last_grew) >= self.thresholds.finished_after {
+                state.finished_announced = true;
+                let detail = format!("No new messages for {} minute(s)", self.thresholds.finished_after.as_secs().div_ceil(60));
+                events.push(session_event(EventKind::SessionFinished, path, detail));
+            }
+        }
+        events
+    }
+}
+
+/// Whole lines written past `offset`, moving it past them; `None` when there are none. A
+/// file that shrank was rewritten, so it is read again from the start.
+fn read_appended(path: &Path, offset: &mut u64) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    if file.metadata().ok()?.len() < *offset {
+        *offset = 0;
+    }
+    file.seek(SeekFrom::Start(*offset)).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    // A line still being written is read once it is finished
+    let complete = bytes.iter().rposition(|&byte| byte == b'\n')? + 1;
+    bytes.truncate(complete);
+    *offset += complete as u64;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn session_event(kind: EventKind, path: &Path, detail: String) -> SessionEvent {
+    let session_id = extract_session_id(path).unwrap_or_default();
+    // Notifications and webhooks aren't terminal output, so titles keep their usual markers
+    let title = session_title(path, &session_id).unwrap_or_else(|| session_id.clone());
+    SessionEvent {
+        kind,
+        title,
+        project: decode_project_path(path).unwrap_or_default(),
+        session_id,
+        path: path.to_path_buf(),
+        detail,
+    }
+}
@@ -0,0 +1,24 @@
+use crate::error::SessionFinderError;
+
+// Exit statuses, following grep: success means something was found
+pub const EXIT_FOUND: i32 = 0;
+pub const EXIT_NOT_FOUND: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_ENVIRONMENT: i32 = 3;
+
+/// Environment problems (no `HOME`, no projects directory, a missing or failing backend such
+/// as ripgrep, or an I/O error such as permission denied or a full disk) exit with 3; anything
+/// else that stopped the command is treated as a usage error (bad option values, unknown
+/// session references, unparseable files) and exits with 2.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    let environmental = error.chain().any(|cause| {
+        cause.downcast_ref::<SessionFinderError>().is_some_and(SessionFinderError::is_environmental)
+            || cause.is::<std::env::VarError>()
+            || cause.is::<std::io::Error>()
+    });
+    if environmental {
+        EXIT_ENVIRONMENT
+    } else {
+        EXIT_USAGE
+    }
+}
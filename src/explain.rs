@@ -0,0 +1,13 @@
+/// Prints one step of the search plan to stderr, when `--explain-plan` asked for it.
+pub fn explain(explaining: bool, step: &str) {
+    if explaining {
+        eprintln!("plan: {}", step);
+    }
+}
+
+/// Reports how many sessions a filter stage let through of those it was given.
+pub fn explain_stage(explaining: bool, stage: &str, before: usize, after: usize) {
+    if explaining {
+        eprintln!("plan: {:<28} {:>6} -> {:<6} ({} eliminated)", stage, before, after, before - after);
+    }
+}
@@ -0,0 +1,484 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, SessionFinderError};
+use crate::file_ops::files_touched;
+use crate::matcher::SearchOptions;
+use crate::render::RenderOptions;
+use crate::resolve::{all_session_files, read_session_text, resolve_session_path};
+use crate::resume::{resume_command, session_working_dir};
+use crate::timeline::{content_type_label, entry_excerpt, format_match_score, interrupted_label, message_heading, parse_session_messages};
+use crate::trash::TrashOperation;
+use crate::{analyze_session_file, Content, ContextMessage, SessionInfo, SessionMessage, TimelineExtraction};
+
+/// Number of common terms used as tags when a session has no query topics
+const FALLBACK_TAG_COUNT: usize = 5;
+
+/// Note flavours written by `export` and `sync-notes`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteFormat {
+    Obsidian,
+    Org,
+}
+
+impl NoteFormat {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "obsidian" | "markdown" => Ok(NoteFormat::Obsidian),
+            "org" => Ok(NoteFormat::Org),
+            other => Err(SessionFinderError::Usage(format!(
+                "Format '{}' is not supported for notes (use obsidian or org)",
+                other
+            ))),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            NoteFormat::Obsidian => "md",
+            NoteFormat::Org => "org",
+        }
+    }
+}
+
+/// File formats `timeline --output` writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimelineFormat {
+    Json,
+    Markdown,
+    Html,
+}
+
+impl TimelineFormat {
+    /// The format named by `--format`, else the one the file's extension implies.
+    pub fn for_output(path: &Path, name: Option<&str>) -> Result<Self> {
+        let extension = path.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase);
+        match name.or(extension.as_deref()) {
+            Some("json") => Ok(TimelineFormat::Json),
+            Some("markdown" | "md") => Ok(TimelineFormat::Markdown),
+            Some("html" | "htm") => Ok(TimelineFormat::Html),
+            _ => Err(SessionFinderError::Usage(format!(
+                "Can't tell which format to write {} in; use a .json, .md, or .html file or --format json|markdown|html",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// Writes a timeline to `path`, so an investigation can be kept or shared.
+pub fn write_timeline(
+    timeline: &TimelineExtraction,
+    path: &Path,
+    format: TimelineFormat,
+    full_content: bool,
+    render: RenderOptions,
+) -> Result<()> {
+    let rendered = match format {
+        TimelineFormat::Json => serde_json::to_string_pretty(timeline)? + "\n",
+        TimelineFormat::Markdown => render_timeline_markdown(timeline, full_content, render),
+        TimelineFormat::Html => render_timeline_html(timeline, full_content, render),
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut trash = TrashOperation::begin("timeline");
+    if fs::read_to_string(path).is_ok_and(|existing| existing != rendered) {
+        trash.discard_file(path)?;
+    }
+    fs::write(path, rendered)?;
+    eprintln!("Wrote {} timeline entries to {}", timeline.timeline.len(), path.display());
+    trash.finish();
+    Ok(())
+}
+
+fn timeline_title(timeline: &TimelineExtraction) -> String {
+    format!("Timeline for \"{}\" in session {}", timeline.query_term, timeline.session_id)
+}
+
+fn render_timeline_markdown(timeline: &TimelineExtraction, full_content: bool, render: RenderOptions) -> String {
+    let mut out = String::new();
+
+    out.push_str("---\n");
+    out.push_str(&format!("session_id: {}\n", timeline.session_id));
+    out.push_str(&format!("session_file: {}\n", yaml_string(&timeline.session_path.to_string_lossy())));
+    out.push_str(&format!("query: {}\n", yaml_string(&timeline.query_term)));
+    out.push_str(&format!("matches: {}\n", timeline.total_matches));
+    out.push_str("tags:\n  - claude-session-timeline\n");
+    out.push_str("---\n\n");
+
+    out.push_str(&format!("# {}\n\n", timeline_title(timeline)));
+    if timeline.timeline.len() < timeline.total_matches {
+        out.push_str(&format!(
+            "Top {} of {} matching messages by score.\n\n",
+            timeline.timeline.len(),
+            timeline.total_matches
+        ));
+    }
+
+    for entry in &timeline.timeline {
+        out.push_str(&format!(
+            "## {} {}: {}{}\n\n",
+            message_heading(entry.message_index, entry.line_number, entry.timestamp.as_deref()),
+            entry.role,
+            content_type_label(&entry.classified_content.content_type, render),
+            interrupted_label(entry.interrupted, render)
+        ));
+        out.push_str(&format!("Score: {}\n\n", format_match_score(&entry.match_score)));
+        if let Some(outcome) = entry.command_outcome {
+            out.push_str(&format!("Command: {}\n\n", outcome.label(render)));
+        }
+        push_markdown_context(&mut out, "Context before", &entry.context_before);
+        for line in entry_excerpt(timeline, entry, full_content, true).lines() {
+            out.push_str(&format!("> {}\n", line));
+        }
+        out.push('\n');
+        push_markdown_context(&mut out, "Context after", &entry.context_after);
+    }
+    out
+}
+
+fn push_markdown_context(out: &mut String, label: &str, context: &[ContextMessage]) {
+    if context.is_empty() {
+        return;
+    }
+    out.push_str(&format!("*{}:*\n\n", label));
+    for ctx in context {
+        out.push_str(&format!("- {}\n", ctx.summary.replace('\n', " ")));
+    }
+    out.push('\n');
+}
+
+fn render_timeline_html(timeline: &TimelineExtraction, full_content: bool, render: RenderOptions) -> String {
+    let title = html_escape(&timeline_title(timeline));
+    let mut out = String::new();
+
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", title));
+    out.push_str("<style>body { font-family: sans-serif; max-width: 60em; margin: auto; } \
+                  pre { white-space: pre-wrap; background: #f6f6f6; padding: 0.5em; } \
+                  .meta, .context { color: #666; }</style>\n");
+    out.push_str("</head>\n<body>\n");
+    out.push_str(&format!("<h1>{}</h1>\n", title));
+    out.push_str(&format!("<p class=\"meta\">{}</p>\n", html_escape(&timeline.session_path.to_string_lossy())));
+    if timeline.timeline.len() < timeline.total_matches {
+        out.push_str(&format!(
+            "<p>Top {} of {} matching messages by score.</p>\n",
+            timeline.timeline.len(),
+            timeline.total_matches
+        ));
+    }
+
+    for entry in &timeline.timeline {
+        out.push_str("<section>\n");
+        out.push_str(&format!(
+            "<h2>{} {}: {}{}</h2>\n",
+            html_escape(&message_heading(entry.message_index, entry.line_number, entry.timestamp.as_deref())),
+            html_escape(&entry.role),
+            html_escape(&content_type_label(&entry.classified_content.content_type, render)),
+            interrupted_label(entry.interrupted, render)
+        ));
+        out.push_str(&format!("<p class=\"meta\">Score: {}</p>\n", html_escape(&format_match_score(&entry.match_score))));
+        if let Some(outcome) = entry.command_outcome {
+            out.push_str(&format!("<p class=\"meta\">Command: {}</p>\n", outcome.label(render)));
+        }
+        for ctx in &entry.context_before {
+            out.push_str(&format!("<p class=\"context\">{}</p>\n", html_escape(&ctx.summary)));
+        }
+        out.push_str(&format!("<pre>{}</pre>\n", html_escape(&entry_excerpt(timeline, entry, full_content, false))));
+        for ctx in &entry.context_after {
+            out.push_str(&format!("<p class=\"context\">{}</p>\n", html_escape(&ctx.summary)));
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Everything a note needs about one session
+struct SessionNote {
+    info: SessionInfo,
+    title: String,
+    date: String,
+    tags: Vec<String>,
+    files_touched: Vec<String>,
+    resume: String,
+}
+
+/// Writes one note per session into `dir`. With `changed_only`, sessions whose note is already
+/// newer than the session file are skipped, so repeated runs only export new or resumed sessions.
+pub fn export_notes(
+    session_refs: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    options: &SearchOptions,
+    format: NoteFormat,
+    dir: &Path,
+    changed_only: bool,
+) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut written = 0;
+    let mut unchanged = 0;
+    // Notes may have been edited by hand since they were written, so earlier versions are kept
+    let mut trash = TrashOperation::begin(if changed_only { "sync-notes" } else { "export" });
+
+    for info in select_sessions(session_refs, &[], project_filter, recent_days, options)? {
+        let session_path = info.path.clone();
+        let note = build_note(info)?;
+        let note_path = dir.join(format!(
+            "{}-{}.{}",
+            note.date,
+            short_id(&note.info.session_id),
+            format.extension()
+        ));
+
+        if changed_only && note_is_current(&note_path, &session_path) {
+            unchanged += 1;
+            continue;
+        }
+
+        let rendered = match format {
+            NoteFormat::Obsidian => render_obsidian(&note),
+            NoteFormat::Org => render_org(&note),
+        };
+        if fs::read_to_string(&note_path).is_ok_and(|existing| existing != rendered) {
+            trash.discard_file(&note_path)?;
+        }
+        fs::write(&note_path, rendered)?;
+        println!("{}", note_path.display());
+        written += 1;
+    }
+
+    if changed_only {
+        eprintln!("Exported {} note(s), {} already up to date", written, unchanged);
+    } else {
+        eprintln!("Exported {} note(s)", written);
+    }
+    trash.finish();
+    Ok(())
+}
+
+/// The referenced sessions, or every session when none are given, that pass the project and
+/// recency filters, analysed for the search terms.
+pub fn select_sessions(
+    session_refs: &[&str],
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    options: &SearchOptions,
+) -> Result<Vec<SessionInfo>> {
+    let session_paths: Vec<PathBuf> = if session_refs.is_empty() {
+        all_session_files()?
+    } else {
+        session_refs
+            .iter()
+            .map(|session_ref| resolve_session_path(session_ref))
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut sessions = Vec::new();
+    for session_path in session_paths {
+        if let Some(info) = analyze_session_file(&session_path, search_terms, project_filter, recent_days, options)? {
+            sessions.push(info);
+        }
+    }
+    Ok(sessions)
+}
+
+/// Target file of every Write/Edit/MultiEdit tool call, once per call, in order.
+pub fn file_edits(messages: &[SessionMessage]) -> Vec<&str> {
+    let mut edits = Vec::new();
+
+    for msg in messages {
+        let Some(Content::Array(blocks)) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+            continue;
+        };
+        for block in blocks {
+            let is_write = block.r#type == "tool_use"
+                && matches!(block.name.as_deref(), Some("Write" | "Edit" | "MultiEdit"));
+            if !is_write {
+                continue;
+            }
+            let file_path = block.input.as_ref()
+                .and_then(|input| input.get("file_path"))
+                .and_then(|path| path.as_str());
+            if let Some(file_path) = file_path {
+                edits.push(file_path);
+            }
+        }
+    }
+
+    edits
+}
+
+fn build_note(info: SessionInfo) -> Result<SessionNote> {
+    let content = read_session_text(&info.path)?;
+    let messages = parse_session_messages(&content)?;
+
+    let title = session_title(&info);
+    let date = session_date(&messages, &info);
+
+    let tag_sources: Vec<&str> = if info.topics.is_empty() {
+        info.common_terms
+            .iter()
+            .take(FALLBACK_TAG_COUNT)
+            .map(|term| term.split('(').next().unwrap_or(term))
+            .collect()
+    } else {
+        info.topics.iter().map(|topic| topic.as_str()).collect()
+    };
+    let mut tags: Vec<String> = tag_sources.into_iter().map(tag_name).filter(|tag| !tag.is_empty()).collect();
+    tags.sort();
+    tags.dedup();
+
+    let resume = resume_command(&session_working_dir(&info.path), &info.session_id);
+
+    Ok(SessionNote {
+        files_touched: files_touched(&messages, &session_working_dir(&info.path)),
+        info,
+        title,
+        date,
+        tags,
+        resume,
+    })
+}
+
+/// The session's summary or inferred title, else its ID.
+pub fn session_title(info: &SessionInfo) -> String {
+    info.title.clone().unwrap_or_else(|| info.session_id.clone())
+}
+
+/// Date of the first timestamped message, falling back to the file's modification date.
+pub fn session_date(messages: &[SessionMessage], info: &SessionInfo) -> String {
+    messages
+        .iter()
+        .filter_map(|msg| msg.timestamp.as_deref())
+        .filter(|timestamp| timestamp.as_bytes().get(4) == Some(&b'-'))
+        .find_map(|timestamp| timestamp.get(..10).map(str::to_string))
+        .unwrap_or_else(|| info.last_modified.format("%Y-%m-%d").to_string())
+}
+
+fn render_obsidian(note: &SessionNote) -> String {
+    let info = &note.info;
+    let mut out = String::new();
+
+    out.push_str("---\n");
+    out.push_str(&format!("title: {}\n", yaml_string(&note.title)));
+    out.push_str(&format!("session_id: {}\n", info.session_id));
+    out.push_str(&format!("project: {}\n", yaml_string(&info.project_path)));
+    out.push_str(&format!("date: {}\n", note.date));
+    out.push_str(&format!("modified: {}\n", info.last_modified.format("%Y-%m-%dT%H:%M:%SZ")));
+    out.push_str("tags:\n  - claude-session\n");
+    for tag in &note.tags {
+        out.push_str(&format!("  - {}\n", tag));
+    }
+    if !note.files_touched.is_empty() {
+        out.push_str("files:\n");
+        for file in &note.files_touched {
+            out.push_str(&format!("  - {}\n", yaml_string(file)));
+        }
+    }
+    out.push_str("---\n\n");
+
+    out.push_str(&format!("# {}\n\n", note.title));
+    out.push_str(&format!("Resume: `{}`\n\n", note.resume));
+
+    if !note.files_touched.is_empty() {
+        out.push_str("## Files touched\n\n");
+        for file in &note.files_touched {
+            out.push_str(&format!("- [{}](file://{})\n", file_label(file), file.replace(' ', "%20")));
+        }
+        out.push('\n');
+    }
+
+    push_markdown_messages(&mut out, "First messages", &info.first_messages);
+    push_markdown_messages(&mut out, "Last messages", &info.last_messages);
+    out
+}
+
+fn render_org(note: &SessionNote) -> String {
+    let info = &note.info;
+    let mut out = String::new();
+
+    out.push_str(&format!("#+TITLE: {}\n", note.title));
+    out.push_str(&format!("#+DATE: [{}]\n", note.date));
+    let tags: Vec<&str> = std::iter::once("claude_session")
+        .chain(note.tags.iter().map(|tag| tag.as_str()))
+        .collect();
+    // Org tags cannot contain dashes
+    out.push_str(&format!("#+FILETAGS: :{}:\n", tags.join(":").replace('-', "_")));
+    out.push_str(":PROPERTIES:\n");
+    out.push_str(&format!(":SESSION_ID: {}\n", info.session_id));
+    out.push_str(&format!(":PROJECT: {}\n", info.project_path));
+    out.push_str(&format!(":MODIFIED: {}\n", info.last_modified.format("%Y-%m-%d %H:%M")));
+    out.push_str(":END:\n\n");
+
+    out.push_str(&format!("Resume: ~{}~\n\n", note.resume));
+
+    if !note.files_touched.is_empty() {
+        out.push_str("* Files touched\n");
+        for file in &note.files_touched {
+            out.push_str(&format!("- [[file:{}][{}]]\n", file, file_label(file)));
+        }
+        out.push('\n');
+    }
+
+    for (heading, messages) in [("First messages", &info.first_messages), ("Last messages", &info.last_messages)] {
+        if messages.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("* {}\n", heading));
+        for msg in messages {
+            out.push_str(&format!("- {}\n", msg.replace('\n', " ")));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn push_markdown_messages(out: &mut String, heading: &str, messages: &[String]) {
+    if messages.is_empty() {
+        return;
+    }
+    out.push_str(&format!("## {}\n\n", heading));
+    for msg in messages {
+        out.push_str(&format!("- {}\n", msg.replace('\n', " ")));
+    }
+    out.push('\n');
+}
+
+fn note_is_current(note_path: &Path, session_path: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (modified(note_path), modified(session_path)) {
+        (Some(note), Some(session)) => note >= session,
+        _ => false,
+    }
+}
+
+/// First block of a Claude Code UUID; imported sessions with other ID schemes keep the full ID.
+pub fn short_id(session_id: &str) -> &str {
+    match session_id.split_once('-') {
+        Some((first, _)) if session_id.len() == 36 => first,
+        _ => session_id,
+    }
+}
+
+fn file_label(file: &str) -> &str {
+    Path::new(file).file_name().and_then(|name| name.to_str()).unwrap_or(file)
+}
+
+/// Lowercase, dash-separated tag made only of characters note apps accept in tags
+fn tag_name(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn yaml_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
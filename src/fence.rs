@@ -0,0 +1,91 @@
+use std::ops::Range;
+
+/// A fenced code block (```` ``` ```` or `~~~`) in message text
+pub struct CodeBlock<'a> {
+    /// First word of the info string after the opening fence, such as `rust`
+    pub language: Option<&'a str>,
+    /// The lines between the fences
+    pub code: &'a str,
+    /// The text ended before a closing fence, as when a reply was cut off
+    pub unterminated: bool,
+    /// Byte range of the whole block in the text, fences included
+    pub span: Range<usize>,
+}
+
+/// The first code block in `text` with any code in it.
+pub fn first_code_block(text: &str) -> Option<CodeBlock<'_>> {
+    code_blocks(text).next()
+}
+
+/// Every code block in `text` with any code in it. Fences follow CommonMark: up to three
+/// spaces of indent, three or more backticks or tildes, closed by a run of the same character
+/// at least as long. An unclosed fence runs to the end of the text.
+pub fn code_blocks(text: &str) -> impl Iterator<Item = CodeBlock<'_>> {
+    let mut lines = line_spans(text);
+    std::iter::from_fn(move || {
+        while let Some((block_start, line_end, line)) = lines.next() {
+            let Some((fence_char, fence_len, info)) = opening_fence(line) else {
+                continue;
+            };
+            let code_start = line_end;
+            let mut code_end = text.len();
+            let mut block_end = text.len();
+            let mut unterminated = true;
+            for (start, end, line) in lines.by_ref() {
+                if is_closing_fence(line, fence_char, fence_len) {
+                    code_end = start;
+                    block_end = end;
+                    unterminated = false;
+                    break;
+                }
+            }
+            let code = text[code_start..code_end].trim_end_matches(['\n', '\r']);
+            if code.trim().is_empty() {
+                continue;
+            }
+            let language = info
+                .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+                .next()
+                .filter(|language| !language.is_empty());
+            return Some(CodeBlock { language, code, unterminated, span: block_start..block_end });
+        }
+        None
+    })
+}
+
+/// Each line of `text` with its start and end byte offsets; the end includes the newline.
+fn line_spans(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut start = 0;
+    text.split_inclusive('\n').map(move |line| {
+        let span = (start, start + line.len(), line.trim_end_matches(['\n', '\r']));
+        start += line.len();
+        span
+    })
+}
+
+/// The fence character, its run length, and the info string, if `line` opens a fence.
+fn opening_fence(line: &str) -> Option<(char, usize, &str)> {
+    let (fence_char, fence_len, rest) = fence_run(line)?;
+    // A backtick in the info string means inline code such as ```a``` rather than a fence
+    if fence_char == '`' && rest.contains('`') {
+        return None;
+    }
+    Some((fence_char, fence_len, rest.trim()))
+}
+
+fn is_closing_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    fence_run(line).is_some_and(|(c, len, rest)| c == fence_char && len >= fence_len && rest.trim().is_empty())
+}
+
+/// A run of three or more backticks or tildes after at most three spaces of indent, with
+/// whatever follows it.
+fn fence_run(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let rest = trimmed.trim_start_matches(fence_char);
+    let fence_len = trimmed.len() - rest.len();
+    (fence_len >= 3).then_some((fence_char, fence_len, rest))
+}
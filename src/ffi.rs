@@ -0,0 +1,72 @@
+use serde_json::{json, Value};
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::rpc::handle_request;
+
+/// Answers one JSON-RPC method with parameters given as JSON, as a JSON string the caller
+/// frees with `session_finder_free`: `{"result": ...}`, or `{"error": {"code", "message"}}`.
+fn call(method: &str, params_json: *const c_char) -> *mut c_char {
+    let response = catch_unwind(AssertUnwindSafe(|| {
+        if params_json.is_null() {
+            return error(-32602, "params must not be NULL");
+        }
+        // Safety: the caller passes a NUL-terminated string that outlives the call
+        let params = match unsafe { CStr::from_ptr(params_json) }.to_str() {
+            Ok(params) => params,
+            Err(e) => return error(-32602, &format!("params are not UTF-8: {}", e)),
+        };
+        let params: Value = match serde_json::from_str(params) {
+            Ok(params) => params,
+            Err(e) => return error(-32700, &e.to_string()),
+        };
+        match handle_request(method, params) {
+            Ok(result) => json!({ "result": result }),
+            Err((code, message)) => error(code, &message),
+        }
+    }))
+    .unwrap_or_else(|_| error(-32603, "session-finder panicked"));
+    // JSON escapes control characters, so the text has no interior NUL
+    CString::new(response.to_string()).expect("JSON has no NUL bytes").into_raw()
+}
+
+fn error(code: i64, message: &str) -> Value {
+    json!({ "error": { "code": code, "message": message } })
+}
+
+/// Searches sessions with the `search` method's parameters, e.g.
+/// `{"terms": ["oauth"], "project": "myapp", "limit": 5}`.
+///
+/// # Safety
+///
+/// `params_json` must be NULL or a NUL-terminated string. Free the result with
+/// `session_finder_free`.
+#[no_mangle]
+pub unsafe extern "C" fn session_finder_search(params_json: *const c_char) -> *mut c_char {
+    call("search", params_json)
+}
+
+/// Extracts a session's timeline with the `timeline` method's parameters, e.g.
+/// `{"session": "abc123", "terms": ["oauth"], "context": 2}`.
+///
+/// # Safety
+///
+/// `params_json` must be NULL or a NUL-terminated string. Free the result with
+/// `session_finder_free`.
+#[no_mangle]
+pub unsafe extern "C" fn session_finder_timeline(params_json: *const c_char) -> *mut c_char {
+    call("timeline", params_json)
+}
+
+/// Frees a string returned by this library; NULL is ignored.
+///
+/// # Safety
+///
+/// `text` must be NULL or a string returned by `session_finder_search` or
+/// `session_finder_timeline`, not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn session_finder_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
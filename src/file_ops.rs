@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use crate::command_status::bash_outcomes;
+use crate::error::{Result, SessionFinderError};
+use crate::export::{select_sessions, session_date, short_id};
+use crate::glyphs::Glyph;
+use crate::matcher::SearchOptions;
+use crate::render::RenderOptions;
+use crate::resolve::read_session_text;
+use crate::resume::session_working_dir;
+use crate::timeline::parse_session_messages;
+use crate::{Content, SessionMessage};
+
+/// Something a session did to a file: an edit through Write, Edit, or MultiEdit, or a rename
+/// or deletion through `mv`, `git mv`, `rm`, or `git rm` in a Bash command
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileEvent {
+    Edited(String),
+    Renamed { from: String, to: String },
+    Deleted(String),
+}
+
+/// What the session did to files, in order. Paths in shell commands are resolved against the
+/// session's working directory (and any `cd` earlier in the same command); commands that
+/// failed moved nothing and are skipped, as are those using globs or variables.
+pub fn file_events(messages: &[SessionMessage], working_dir: &str) -> Vec<FileEvent> {
+    let outcomes = bash_outcomes(messages);
+    let mut events = Vec::new();
+    for msg in messages {
+        let Some(Content::Array(blocks)) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+            continue;
+        };
+        for block in blocks.iter().filter(|block| block.r#type == "tool_use") {
+            let input = block.input.as_ref();
+            match block.name.as_deref() {
+                Some("Write" | "Edit" | "MultiEdit") => {
+                    if let Some(file_path) = input.and_then(|input| input.get("file_path")).and_then(|path| path.as_str()) {
+                        events.push(FileEvent::Edited(resolve(Path::new(working_dir), file_path)));
+                    }
+                }
+                Some("Bash") => {
+                    let failed = block.id.as_ref().and_then(|id| outcomes.get(id)).is_some_and(|outcome| outcome.failed());
+                    let command = input.and_then(|input| input.get("command")).and_then(|command| command.as_str());
+                    if let Some(command) = command.filter(|_| !failed) {
+                        events.extend(command_events(command, working_dir));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    events
+}
+
+/// Renames and deletions a shell command makes, following `cd` between its commands.
+fn command_events(command: &str, working_dir: &str) -> Vec<FileEvent> {
+    let mut dir = PathBuf::from(working_dir);
+    let mut events = Vec::new();
+    for words in shell_commands(command) {
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let (program, args) = match words.as_slice() {
+            ["git", subcommand @ ("mv" | "rm"), args @ ..] => (*subcommand, args),
+            [program @ ("mv" | "rm" | "cd"), args @ ..] => (*program, args),
+            _ => continue,
+        };
+        // Globs and variables can't be resolved without running the shell
+        if args.iter().any(|arg| arg.contains(['*', '?', '[', '$', '~'])) {
+            continue;
+        }
+        let args = without_redirections(args);
+        let mut flags_done = false;
+        let paths: Vec<&str> = args
+            .iter()
+            .copied()
+            .filter(|arg| {
+                if flags_done || !arg.starts_with('-') || *arg == "-" {
+                    return true;
+                }
+                flags_done = *arg == "--";
+                false
+            })
+            .collect();
+        // `git rm --cached` only untracks the file
+        if program == "rm" && args.contains(&"--cached") {
+            continue;
+        }
+        match (program, paths.as_slice()) {
+            ("cd", [target]) => dir = PathBuf::from(resolve(&dir, target)),
+            ("mv", [sources @ .., target]) if !sources.is_empty() => {
+                let into_dir = sources.len() > 1 || target.ends_with('/');
+                for source in sources {
+                    let to = match Path::new(source).file_name().filter(|_| into_dir) {
+                        Some(name) => resolve(&dir, &Path::new(target).join(name).to_string_lossy()),
+                        None => resolve(&dir, target),
+                    };
+                    events.push(FileEvent::Renamed { from: resolve(&dir, source), to });
+                }
+            }
+            ("rm", targets) => events.extend(targets.iter().map(|target| FileEvent::Deleted(resolve(&dir, target)))),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Arguments other than redirections such as `2>/dev/null` or `> log`.
+fn without_redirections<'a>(args: &[&'a str]) -> Vec<&'a str> {
+    let mut kept = Vec::new();
+    let mut skip_next = false;
+    for arg in args {
+        if std::mem::take(&mut skip_next) {
+            continue;
+        }
+        match arg.find(['>', '<']) {
+            Some(position) => skip_next = position == arg.len() - 1,
+            None => kept.push(*arg),
+        }
+    }
+    kept
+}
+
+/// The words of each simple command in a shell command line, split at `&&`, `||`, `;`, `|`,
+/// and newlines, with quotes and backslashes removed.
+fn shell_commands(line: &str) -> Vec<Vec<String>> {
+    let mut commands = vec![Vec::new()];
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c if c.is_whitespace() || matches!(c, '&' | '|' | ';') => {
+                commands.last_mut().unwrap().extend(word.take());
+                if c != ' ' && c != '\t' && !commands.last().unwrap().is_empty() {
+                    commands.push(Vec::new());
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    commands.last_mut().unwrap().extend(word);
+    commands.retain(|words| !words.is_empty());
+    commands
+}
+
+/// `path` made absolute against `dir`, with `.` and `..` folded away.
+fn resolve(dir: &Path, path: &str) -> String {
+    let mut resolved = PathBuf::new();
+    for component in dir.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved.to_string_lossy().into_owned()
+}
+
+/// Final names of the files the session edited or renamed, in the order first touched; edits
+/// to a file it later renamed count under the new name, and files it deleted are left out.
+pub fn files_touched(messages: &[SessionMessage], working_dir: &str) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for event in file_events(messages, working_dir) {
+        match event {
+            FileEvent::Edited(path) => {
+                if !files.contains(&path) {
+                    files.push(path);
+                }
+            }
+            FileEvent::Renamed { from, to } => {
+                let mut renamed_any = false;
+                for file in files.iter_mut() {
+                    if let Some(renamed) = renamed_path(file, &from, &to) {
+                        *file = renamed;
+                        renamed_any = true;
+                    }
+                }
+                if !renamed_any {
+                    files.push(to);
+                }
+                let mut seen = HashSet::new();
+                files.retain(|file| seen.insert(file.clone()));
+            }
+            FileEvent::Deleted(path) => files.retain(|file| *file != path && !Path::new(file).starts_with(&path)),
+        }
+    }
+    files
+}
+
+/// The name `path` has after `from` is renamed to `to`, if the rename moves it, either as the
+/// file itself or inside a moved directory.
+pub fn renamed_path(path: &str, from: &str, to: &str) -> Option<String> {
+    let rest = Path::new(path).strip_prefix(from).ok()?;
+    Some(if rest.as_os_str().is_empty() { to.to_string() } else { Path::new(to).join(rest).to_string_lossy().into_owned() })
+}
+
+/// Lists the sessions that edited, renamed, or deleted `file`, oldest first, following it
+/// across renames made in any session so its history before a move is found too.
+pub fn run_touched(file: &str, project_filter: Option<&String>, recent_days: Option<i64>, render: RenderOptions) -> Result<()> {
+    let mut sessions = select_sessions(&[], &[], project_filter, recent_days, &SearchOptions::default())?;
+    sessions.sort_by(|a, b| a.last_modified.cmp(&b.last_modified).then_with(|| a.session_id.cmp(&b.session_id)));
+
+    let mut histories = Vec::new();
+    for info in sessions {
+        let messages = parse_session_messages(&read_session_text(&info.path)?)?;
+        let events = file_events(&messages, &session_working_dir(&info.path));
+        if !events.is_empty() {
+            histories.push((session_date(&messages, &info), info, events));
+        }
+    }
+
+    let current_dir = std::env::current_dir()?;
+    // Every name the file has had: names matching the argument, then those renamed to or from one
+    let mut names: HashSet<String> = histories
+        .iter()
+        .flat_map(|(_, _, events)| events)
+        .flat_map(|event| match event {
+            FileEvent::Edited(path) | FileEvent::Deleted(path) => vec![path],
+            FileEvent::Renamed { from, to } => vec![from, to],
+        })
+        .filter(|path| names_file(path, file, &current_dir))
+        .cloned()
+        .collect();
+    loop {
+        let before = names.len();
+        for (_, _, events) in &histories {
+            for event in events {
+                if let FileEvent::Renamed { from, to } = event {
+                    if names.contains(from) || names.contains(to) {
+                        names.insert(from.clone());
+                        names.insert(to.clone());
+                    }
+                }
+            }
+        }
+        if names.len() == before {
+            break;
+        }
+    }
+    if names.is_empty() {
+        return Err(SessionFinderError::Usage(format!("No session edited, renamed, or deleted {}", file)));
+    }
+
+    for (date, info, events) in &histories {
+        let mut edits: Vec<(&str, usize)> = Vec::new();
+        let mut actions = Vec::new();
+        for event in events {
+            match event {
+                FileEvent::Edited(path) if names.contains(path) => match edits.iter_mut().find(|(known, _)| known == path) {
+                    Some((_, count)) => *count += 1,
+                    None => edits.push((path, 1)),
+                },
+                FileEvent::Renamed { from, to } if names.contains(from) => actions.push(format!("renamed {} {} {}", from, render.glyph(Glyph::Arrow), to)),
+                FileEvent::Deleted(path) if names.contains(path) => actions.push(format!("deleted {}", path)),
+                _ => {}
+            }
+        }
+        if edits.is_empty() && actions.is_empty() {
+            continue;
+        }
+        println!("{} {}  {}", short_id(&info.session_id), date, info.title.as_deref().unwrap_or("(untitled)"));
+        for (path, count) in edits {
+            println!("    edited {} ({} time{})", path, count, if count == 1 { "" } else { "s" });
+        }
+        for action in actions {
+            println!("    {}", action);
+        }
+    }
+    Ok(())
+}
+
+/// Whether a session's absolute `path` is the file the user named, absolutely or relative to
+/// the current directory, or by its trailing components.
+fn names_file(path: &str, file: &str, current_dir: &Path) -> bool {
+    path == resolve(current_dir, file) || Path::new(path).ends_with(file)
+}
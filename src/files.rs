@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use crate::error::Result;
+use crate::export::{select_sessions, session_date, short_id};
+use crate::file_ops::{file_events, renamed_path, FileEvent};
+use crate::matcher::SearchOptions;
+use crate::resolve::read_session_text;
+use crate::resume::session_working_dir;
+use crate::stats::{delimited_field, StatsFormat};
+use crate::timeline::parse_session_messages;
+
+/// One file's edits across all selected sessions
+struct FileActivity {
+    path: String,
+    edits: usize,
+    /// Sessions that edited the file, with their date and edit count, in first-edited order
+    sessions: Vec<(String, String, usize)>,
+    /// Earlier names, from `mv` and `git mv` in Bash commands
+    renamed_from: Vec<String>,
+    /// Removed by `rm` or `git rm` after its last edit
+    deleted: bool,
+}
+
+/// Ranks every file written or edited by the selected sessions by number of edits, listing
+/// which sessions touched each one. Files renamed by Bash commands are followed to their new
+/// names, and those deleted are marked.
+pub fn run_files_report(
+    session_refs: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    format: StatsFormat,
+) -> Result<()> {
+    let mut files: Vec<FileActivity> = Vec::new();
+
+    let mut sessions = select_sessions(session_refs, &[], project_filter, recent_days, &SearchOptions::default())?;
+    sessions.sort_by(|a, b| a.last_modified.cmp(&b.last_modified).then_with(|| a.session_id.cmp(&b.session_id)));
+    for info in &sessions {
+        let content = read_session_text(&info.path)?;
+        let messages = parse_session_messages(&content)?;
+        let date = session_date(&messages, info);
+
+        for event in file_events(&messages, &session_working_dir(&info.path)) {
+            match event {
+                FileEvent::Edited(path) => {
+                    let position = files.iter().position(|file| file.path == path).unwrap_or_else(|| {
+                        files.push(FileActivity { path, edits: 0, sessions: Vec::new(), renamed_from: Vec::new(), deleted: false });
+                        files.len() - 1
+                    });
+                    let file = &mut files[position];
+                    file.edits += 1;
+                    file.deleted = false;
+                    match file.sessions.last_mut() {
+                        Some((session_id, _, count)) if *session_id == info.session_id => *count += 1,
+                        _ => file.sessions.push((info.session_id.clone(), date.clone(), 1)),
+                    }
+                }
+                FileEvent::Renamed { from, to } => {
+                    for file in files.iter_mut() {
+                        if let Some(renamed) = renamed_path(&file.path, &from, &to) {
+                            file.renamed_from.push(std::mem::replace(&mut file.path, renamed));
+                            file.deleted = false;
+                        }
+                    }
+                    merge_same_paths(&mut files);
+                }
+                FileEvent::Deleted(path) => {
+                    for file in files.iter_mut().filter(|file| Path::new(&file.path).starts_with(&path)) {
+                        file.deleted = true;
+                    }
+                }
+            }
+        }
+    }
+    files.sort_by(|a, b| b.edits.cmp(&a.edits).then_with(|| a.path.cmp(&b.path)));
+
+    match format {
+        StatsFormat::Text => print_text(&files),
+        StatsFormat::Csv => print_delimited(&files, ','),
+        StatsFormat::Tsv => print_delimited(&files, '\t'),
+    }
+    Ok(())
+}
+
+/// A rename onto a file that already had edits makes one file of the two.
+fn merge_same_paths(files: &mut Vec<FileActivity>) {
+    let mut index = 0;
+    while index < files.len() {
+        if let Some(first) = files[..index].iter().position(|file| file.path == files[index].path) {
+            let merged = files.remove(index);
+            let file = &mut files[first];
+            file.edits += merged.edits;
+            file.renamed_from.extend(merged.renamed_from);
+            for (session_id, date, count) in merged.sessions {
+                match file.sessions.iter_mut().find(|(known, _, _)| *known == session_id) {
+                    Some((_, _, known_count)) => *known_count += count,
+                    None => file.sessions.push((session_id, date, count)),
+                }
+            }
+        } else {
+            index += 1;
+        }
+    }
+}
+
+fn print_text(files: &[FileActivity]) {
+    if files.is_empty() {
+        println!("No edited files found.");
+        return;
+    }
+
+    let width = files[0].edits.to_string().len();
+    for file in files {
+        let mut notes = Vec::new();
+        if !file.renamed_from.is_empty() {
+            notes.push(format!("renamed from {}", file.renamed_from.join(", ")));
+        }
+        if file.deleted {
+            notes.push("deleted".to_string());
+        }
+        println!(
+            "{:>width$} edit{} in {} session{}  {}{}",
+            file.edits,
+            if file.edits == 1 { "" } else { "s" },
+            file.sessions.len(),
+            if file.sessions.len() == 1 { "" } else { "s" },
+            file.path,
+            if notes.is_empty() { String::new() } else { format!(" ({})", notes.join("; ")) },
+            width = width
+        );
+        for (session_id, date, count) in &file.sessions {
+            println!("{:>width$}   {} {} ({})", "", short_id(session_id), date, count, width = width);
+        }
+    }
+}
+
+fn print_delimited(files: &[FileActivity], delimiter: char) {
+    let header = ["file", "edits", "sessions", "session_ids", "renamed_from", "deleted"];
+    println!("{}", header.join(&delimiter.to_string()));
+
+    for file in files {
+        let session_ids: Vec<&str> = file.sessions.iter().map(|(session_id, _, _)| session_id.as_str()).collect();
+        let fields = [
+            delimited_field(&file.path, delimiter),
+            file.edits.to_string(),
+            file.sessions.len().to_string(),
+            delimited_field(&session_ids.join(" "), delimiter),
+            delimited_field(&file.renamed_from.join(" "), delimiter),
+            file.deleted.to_string(),
+        ];
+        println!("{}", fields.join(&delimiter.to_string()));
+    }
+}
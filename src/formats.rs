@@ -0,0 +1,687 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{json, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::encode_project_path;
+use crate::error::{Result, SessionFinderError};
+use crate::resolve::imports_dir;
+
+/// One conversation from another tool, normalized to Claude Code's message model
+pub struct ImportedSession {
+    pub session_id: String,
+    pub title: Option<String>,
+    /// Directory the conversation was about, when the tool records one
+    pub cwd: Option<String>,
+    pub messages: Vec<ImportedMessage>,
+}
+
+pub struct ImportedMessage {
+    pub role: &'static str,
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Content blocks in Claude Code's shape: `text`, `tool_use`, or `tool_result`
+    pub content: Vec<Value>,
+}
+
+impl ImportedMessage {
+    fn text(role: &'static str, timestamp: Option<DateTime<Utc>>, text: String) -> Self {
+        ImportedMessage { role, timestamp, content: vec![json!({ "type": "text", "text": text })] }
+    }
+}
+
+/// A transcript format from another AI coding tool
+pub trait SessionFormat {
+    /// Name used for `--source` and shown as the source of imported sessions
+    fn name(&self) -> &'static str;
+
+    /// Whether the path looks like this format, for `--source auto`. `content` is the file's
+    /// text, or `None` for directories and binary files such as databases.
+    fn detect(&self, path: &Path, content: Option<&str>) -> bool;
+
+    /// Every conversation at the path; a single export can hold many
+    fn parse(&self, path: &Path, content: Option<&str>) -> Result<Vec<ImportedSession>>;
+
+    /// Where the tool keeps its history when installed with default settings, for `import`
+    /// without paths. Only locations that exist are returned.
+    fn default_locations(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+pub fn session_formats() -> Vec<Box<dyn SessionFormat>> {
+    vec![
+        Box::new(ChatGptExport),
+        Box::new(CodexLog),
+        Box::new(AiderHistory),
+        Box::new(CursorChats),
+        Box::new(OpenCodeStorage),
+    ]
+}
+
+/// Converts transcripts from other tools and writes them as session files under the imports
+/// directory, where searches, timelines, and exports pick them up. Re-importing a file
+/// replaces the sessions it produced before. With no paths, every tool's default history
+/// location that exists is imported.
+pub fn run_import(paths: &[&str], source: &str) -> Result<()> {
+    let formats = session_formats();
+
+    if paths.is_empty() {
+        let mut found = false;
+        for format in formats.iter().filter(|format| source == "auto" || format.name() == source) {
+            for path in format.default_locations() {
+                found = true;
+                import_path(format.as_ref(), &path)?;
+            }
+        }
+        if !found {
+            eprintln!("No history from supported tools found in their default locations");
+        }
+        return Ok(());
+    }
+
+    for path in paths {
+        let path = Path::new(path);
+        let content = read_text(path)?;
+        let format = if source == "auto" {
+            formats
+                .iter()
+                .find(|format| format.detect(path, content.as_deref()))
+                .ok_or_else(|| SessionFinderError::Usage(format!(
+                    "Could not recognize the format of {}; pass --source",
+                    path.display()
+                )))?
+        } else {
+            formats
+                .iter()
+                .find(|format| format.name() == source)
+                .ok_or_else(|| SessionFinderError::Usage(format!("Unknown source format: {}", source)))?
+        };
+        import_path(format.as_ref(), path)?;
+    }
+
+    Ok(())
+}
+
+fn import_path(format: &dyn SessionFormat, path: &Path) -> Result<()> {
+    let content = read_text(path)?;
+    let sessions = format.parse(path, content.as_deref())?;
+    for session in &sessions {
+        write_imported_session(format.name(), session)?;
+    }
+    eprintln!("Imported {} session(s) from {} ({})", sessions.len(), path.display(), format.name());
+    Ok(())
+}
+
+/// The file's text; `None` for directories and files that aren't UTF-8.
+fn read_text(path: &Path) -> Result<Option<String>> {
+    if path.is_dir() {
+        return Ok(None);
+    }
+    let bytes =
+        fs::read(path).map_err(|e| io::Error::new(e.kind(), format!("Could not read {}: {}", path.display(), e)))?;
+    Ok(String::from_utf8(bytes).ok())
+}
+
+/// Text formats have nothing to parse without the file's contents.
+fn require_text<'a>(path: &Path, content: Option<&'a str>) -> Result<&'a str> {
+    content.ok_or_else(|| SessionFinderError::ParseError {
+        path: path.to_path_buf(),
+        line: None,
+        message: "not a text file".to_string(),
+    })
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+/// Writes the session as JSONL that `parse_session_messages` reads like any Claude Code session.
+fn write_imported_session(source: &str, session: &ImportedSession) -> Result<PathBuf> {
+    // Group by working directory when known, like Claude Code's project folders
+    let project = match &session.cwd {
+        Some(cwd) => encode_project_path(Path::new(cwd)),
+        None => source.to_string(),
+    };
+    let dir = imports_dir()?.join(source).join(project);
+    fs::create_dir_all(&dir)?;
+
+    let mut lines = Vec::new();
+    if let Some(title) = &session.title {
+        lines.push(json!({ "type": "summary", "summary": title }).to_string());
+    }
+    let mut parent: Option<String> = None;
+    for (index, message) in session.messages.iter().enumerate() {
+        let uuid = format!("{}-{}", session.session_id, index);
+        lines.push(
+            json!({
+                "type": message.role,
+                "uuid": uuid,
+                "parentUuid": parent,
+                "sessionId": session.session_id,
+                "cwd": session.cwd,
+                "timestamp": message.timestamp.map(|ts| ts.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+                "message": { "role": message.role, "content": message.content },
+            })
+            .to_string(),
+        );
+        parent = Some(uuid);
+    }
+
+    let path = dir.join(format!("{}.jsonl", sanitize_id(&session.session_id)));
+    fs::write(&path, lines.join("\n") + "\n")?;
+    Ok(path)
+}
+
+fn sanitize_id(id: &str) -> String {
+    id.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' }).collect()
+}
+
+fn epoch_seconds(value: Option<&Value>) -> Option<DateTime<Utc>> {
+    let seconds = value?.as_f64()?;
+    DateTime::from_timestamp(seconds as i64, ((seconds.fract()) * 1e9) as u32)
+}
+
+fn epoch_millis(value: Option<&Value>) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(value?.as_i64()?)
+}
+
+fn rfc3339(value: Option<&Value>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value?.as_str()?).ok().map(|ts| ts.with_timezone(&Utc))
+}
+
+/// OpenAI ChatGPT data export (`conversations.json`): an array of conversations, each a tree
+/// of messages in `mapping` whose current branch ends at `current_node`
+struct ChatGptExport;
+
+impl SessionFormat for ChatGptExport {
+    fn name(&self) -> &'static str {
+        "chatgpt"
+    }
+
+    fn detect(&self, _path: &Path, content: Option<&str>) -> bool {
+        content.is_some_and(|content| content.trim_start().starts_with('[') && content.contains("\"mapping\""))
+    }
+
+    fn parse(&self, path: &Path, content: Option<&str>) -> Result<Vec<ImportedSession>> {
+        let conversations: Vec<Value> =
+            serde_json::from_str(require_text(path, content)?).map_err(|e| SessionFinderError::json(path, e))?;
+        let mut sessions = Vec::new();
+
+        for conversation in &conversations {
+            let Some(mapping) = conversation.get("mapping").and_then(|m| m.as_object()) else {
+                continue;
+            };
+            let id = conversation
+                .get("conversation_id")
+                .or_else(|| conversation.get("id"))
+                .and_then(|id| id.as_str())
+                .ok_or_else(|| SessionFinderError::ParseError {
+                    path: path.to_path_buf(),
+                    line: None,
+                    message: "ChatGPT conversation without an id".to_string(),
+                })?;
+
+            // Follow parents from the current node to the root, then read the branch in order
+            let mut branch = Vec::new();
+            let mut node_id = conversation.get("current_node").and_then(|n| n.as_str());
+            while let Some(node) = node_id.and_then(|id| mapping.get(id)) {
+                branch.push(node);
+                node_id = node.get("parent").and_then(|p| p.as_str());
+            }
+            branch.reverse();
+
+            let messages: Vec<ImportedMessage> = branch
+                .iter()
+                .filter_map(|node| {
+                    let message = node.get("message")?;
+                    let role = match message.pointer("/author/role")?.as_str()? {
+                        "user" => "user",
+                        "assistant" | "tool" => "assistant",
+                        _ => return None,
+                    };
+                    let text: Vec<&str> = message
+                        .pointer("/content/parts")?
+                        .as_array()?
+                        .iter()
+                        .filter_map(|part| part.as_str())
+                        .filter(|part| !part.trim().is_empty())
+                        .collect();
+                    if text.is_empty() {
+                        return None;
+                    }
+                    Some(ImportedMessage::text(role, epoch_seconds(message.get("create_time")), text.join("\n")))
+                })
+                .collect();
+
+            sessions.push(ImportedSession {
+                session_id: id.to_string(),
+                title: conversation.get("title").and_then(|t| t.as_str()).map(|t| t.to_string()),
+                cwd: None,
+                messages,
+            });
+        }
+
+        Ok(sessions)
+    }
+}
+
+/// Codex CLI rollout log (`~/.codex/sessions/**/rollout-*.jsonl`): one JSON record per line,
+/// either wrapped as `{timestamp, type, payload}` or, in older versions, bare response items
+struct CodexLog;
+
+impl SessionFormat for CodexLog {
+    fn name(&self) -> &'static str {
+        "codex"
+    }
+
+    fn detect(&self, path: &Path, content: Option<&str>) -> bool {
+        let Some(content) = content else {
+            return false;
+        };
+        let first_line = content.lines().next().unwrap_or_default();
+        path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("rollout-"))
+            || first_line.contains("\"session_meta\"")
+            || (first_line.contains("\"instructions\"") && first_line.contains("\"id\""))
+    }
+
+    fn parse(&self, path: &Path, content: Option<&str>) -> Result<Vec<ImportedSession>> {
+        let content = require_text(path, content)?;
+        let mut session_id = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut cwd = None;
+        let mut messages = Vec::new();
+
+        for line in content.lines() {
+            let Ok(record) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            let timestamp = rfc3339(record.get("timestamp"));
+            let item = record.get("payload").unwrap_or(&record);
+
+            if record.get("type").and_then(|t| t.as_str()) == Some("session_meta") || item.get("instructions").is_some() {
+                if let Some(id) = item.get("id").and_then(|id| id.as_str()) {
+                    session_id = id.to_string();
+                }
+                cwd = item.get("cwd").and_then(|c| c.as_str()).map(|c| c.to_string()).or(cwd);
+                continue;
+            }
+
+            match item.get("type").and_then(|t| t.as_str()) {
+                Some("message") => {
+                    let role = match item.get("role").and_then(|r| r.as_str()) {
+                        Some("user") => "user",
+                        Some("assistant") => "assistant",
+                        _ => continue,
+                    };
+                    let text: Vec<&str> = item
+                        .get("content")
+                        .and_then(|c| c.as_array())
+                        .map(|blocks| blocks.iter().filter_map(|b| b.get("text").and_then(|t| t.as_str())).collect())
+                        .unwrap_or_default();
+                    // Codex injects its environment context as user messages
+                    if text.is_empty() || text.iter().all(|t| t.trim_start().starts_with("<environment_context>")) {
+                        continue;
+                    }
+                    messages.push(ImportedMessage::text(role, timestamp, text.join("\n")));
+                }
+                Some("function_call") => {
+                    let name = item.get("name").and_then(|n| n.as_str()).unwrap_or("tool");
+                    let input = item
+                        .get("arguments")
+                        .and_then(|a| a.as_str())
+                        .and_then(|a| serde_json::from_str::<Value>(a).ok())
+                        .unwrap_or(Value::Null);
+                    messages.push(ImportedMessage {
+                        role: "assistant",
+                        timestamp,
+                        content: vec![json!({ "type": "tool_use", "name": name, "input": input })],
+                    });
+                }
+                Some("function_call_output") => {
+                    let output = item.get("output").and_then(|o| o.as_str()).unwrap_or_default();
+                    messages.push(ImportedMessage {
+                        role: "user",
+                        timestamp,
+                        content: vec![json!({ "type": "tool_result", "content": output })],
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(vec![ImportedSession { session_id, title: None, cwd, messages }])
+    }
+
+    fn default_locations(&self) -> Vec<PathBuf> {
+        let sessions_dir = match std::env::var_os("CODEX_HOME") {
+            Some(codex_home) => PathBuf::from(codex_home).join("sessions"),
+            None => match home_dir() {
+                Some(home) => home.join(".codex").join("sessions"),
+                None => return Vec::new(),
+            },
+        };
+        walkdir::WalkDir::new(sessions_dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file() && entry.file_name().to_string_lossy().starts_with("rollout-"))
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+}
+
+/// Aider chat history (`.aider.chat.history.md`): each run starts with
+/// `# aider chat started at ...`, user prompts are `#### ` lines, and `> ` lines are Aider's
+/// own output, which is skipped
+struct AiderHistory;
+
+const AIDER_SESSION_HEADER: &str = "# aider chat started at ";
+
+impl SessionFormat for AiderHistory {
+    fn name(&self) -> &'static str {
+        "aider"
+    }
+
+    fn detect(&self, _path: &Path, content: Option<&str>) -> bool {
+        content.is_some_and(|content| content.contains(AIDER_SESSION_HEADER))
+    }
+
+    fn parse(&self, path: &Path, content: Option<&str>) -> Result<Vec<ImportedSession>> {
+        let content = require_text(path, content)?;
+        // The history file lives in the repository Aider ran in
+        let cwd = fs::canonicalize(path)
+            .ok()
+            .and_then(|full| full.parent().map(|dir| dir.to_string_lossy().into_owned()));
+        let mut sessions: Vec<ImportedSession> = Vec::new();
+        let mut started: Option<DateTime<Utc>> = None;
+        let mut assistant_lines: Vec<&str> = Vec::new();
+
+        fn flush_assistant(sessions: &mut [ImportedSession], lines: &mut Vec<&str>, timestamp: Option<DateTime<Utc>>) {
+            let text = lines.join("\n").trim().to_string();
+            lines.clear();
+            if let (Some(session), false) = (sessions.last_mut(), text.is_empty()) {
+                session.messages.push(ImportedMessage::text("assistant", timestamp, text));
+            }
+        }
+
+        for line in content.lines() {
+            if let Some(date) = line.strip_prefix(AIDER_SESSION_HEADER) {
+                flush_assistant(&mut sessions, &mut assistant_lines, started);
+                started = NaiveDateTime::parse_from_str(date.trim(), "%Y-%m-%d %H:%M:%S")
+                    .ok()
+                    .map(|naive| naive.and_utc());
+                let stamp = started.map(|ts| ts.format("%Y%m%d%H%M%S").to_string()).unwrap_or_else(|| sessions.len().to_string());
+                sessions.push(ImportedSession {
+                    session_id: format!("aider-{}", stamp),
+                    title: None,
+                    cwd: cwd.clone(),
+                    messages: Vec::new(),
+                });
+            } else if let Some(prompt) = line.strip_prefix("#### ") {
+                flush_assistant(&mut sessions, &mut assistant_lines, started);
+                if let Some(session) = sessions.last_mut() {
+                    // Consecutive prompt lines are one multi-line message
+                    match session.messages.last_mut() {
+                        Some(last) if last.role == "user" => {
+                            if let Some(text) = last.content[0].get_mut("text") {
+                                *text = Value::String(format!("{}\n{}", text.as_str().unwrap_or_default(), prompt));
+                            }
+                        }
+                        _ => session.messages.push(ImportedMessage::text("user", started, prompt.to_string())),
+                    }
+                }
+            } else if !line.starts_with('>') {
+                assistant_lines.push(line);
+            }
+        }
+        flush_assistant(&mut sessions, &mut assistant_lines, started);
+
+        Ok(sessions)
+    }
+}
+
+/// Cursor's chat history, kept in the `cursorDiskKV` table of its global `state.vscdb`
+/// SQLite database: `composerData:<id>` holds each conversation and, in newer versions, its
+/// messages live under `bubbleId:<id>:<bubble>`. Read with the `sqlite3` command-line tool.
+struct CursorChats;
+
+/// Cursor's bubble `type` for a user message; assistant messages are `2`
+const CURSOR_USER_BUBBLE: i64 = 1;
+
+impl SessionFormat for CursorChats {
+    fn name(&self) -> &'static str {
+        "cursor"
+    }
+
+    fn detect(&self, path: &Path, _content: Option<&str>) -> bool {
+        path.extension().is_some_and(|ext| ext == "vscdb")
+    }
+
+    fn parse(&self, path: &Path, _content: Option<&str>) -> Result<Vec<ImportedSession>> {
+        let output = process::Command::new("sqlite3")
+            .args(["-readonly", "-json"])
+            .arg(path)
+            .arg("SELECT key, value FROM cursorDiskKV WHERE key LIKE 'composerData:%' OR key LIKE 'bubbleId:%'")
+            .output()
+            .map_err(|e| SessionFinderError::backend("sqlite3", format!("sqlite3 failed: {}. Reading Cursor history needs 'sqlite3' in your PATH", e)))?;
+        if !output.status.success() {
+            return Err(SessionFinderError::backend("sqlite3", format!(
+                "Could not read Cursor history from {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        // sqlite3 prints nothing at all when no rows match
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let rows: Vec<Value> = if stdout.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_str(&stdout).map_err(|e| SessionFinderError::json(path, e))?
+        };
+        let mut composers = Vec::new();
+        let mut bubbles = std::collections::HashMap::new();
+        for row in &rows {
+            let (Some(key), Some(value)) = (row.get("key").and_then(|k| k.as_str()), row.get("value").and_then(|v| v.as_str())) else {
+                continue;
+            };
+            let Ok(value) = serde_json::from_str::<Value>(value) else {
+                continue;
+            };
+            if let Some(id) = key.strip_prefix("composerData:") {
+                composers.push((id.to_string(), value));
+            } else if let Some(bubble) = key.strip_prefix("bubbleId:") {
+                bubbles.insert(bubble.to_string(), value);
+            }
+        }
+
+        let mut sessions = Vec::new();
+        for (id, composer) in composers {
+            let created = epoch_millis(composer.get("createdAt"));
+            // Older versions store the bubbles inline; newer ones only store their headers
+            let conversation: Vec<&Value> = match composer.get("conversation").and_then(|c| c.as_array()) {
+                Some(inline) if !inline.is_empty() => inline.iter().collect(),
+                _ => composer
+                    .get("fullConversationHeadersOnly")
+                    .and_then(|h| h.as_array())
+                    .map(|headers| {
+                        headers
+                            .iter()
+                            .filter_map(|header| header.get("bubbleId").and_then(|b| b.as_str()))
+                            .filter_map(|bubble| bubbles.get(&format!("{}:{}", id, bubble)))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            };
+
+            let mut messages = Vec::new();
+            for bubble in conversation {
+                let timestamp = rfc3339(bubble.get("createdAt")).or_else(|| epoch_millis(bubble.get("createdAt"))).or(created);
+                let role = if bubble.get("type").and_then(|t| t.as_i64()) == Some(CURSOR_USER_BUBBLE) { "user" } else { "assistant" };
+                let mut content = Vec::new();
+                if let Some(text) = bubble.get("text").and_then(|t| t.as_str()).filter(|t| !t.trim().is_empty()) {
+                    content.push(json!({ "type": "text", "text": text }));
+                }
+                if let Some(tool) = bubble.get("toolFormerData").filter(|tool| tool.get("name").is_some()) {
+                    let input = tool
+                        .get("rawArgs")
+                        .and_then(|a| a.as_str())
+                        .and_then(|a| serde_json::from_str::<Value>(a).ok())
+                        .unwrap_or(Value::Null);
+                    content.push(json!({ "type": "tool_use", "name": tool["name"], "input": input }));
+                    if let Some(result) = tool.get("result").and_then(|r| r.as_str()) {
+                        messages.push(ImportedMessage { role, timestamp, content: std::mem::take(&mut content) });
+                        content.push(json!({ "type": "tool_result", "content": result }));
+                        messages.push(ImportedMessage { role: "user", timestamp, content: std::mem::take(&mut content) });
+                    }
+                }
+                if !content.is_empty() {
+                    messages.push(ImportedMessage { role, timestamp, content });
+                }
+            }
+            if messages.is_empty() {
+                continue;
+            }
+
+            sessions.push(ImportedSession {
+                session_id: format!("cursor-{}", id),
+                title: composer.get("name").and_then(|n| n.as_str()).map(|n| n.to_string()),
+                cwd: None,
+                messages,
+            });
+        }
+
+        Ok(sessions)
+    }
+
+    fn default_locations(&self) -> Vec<PathBuf> {
+        let Some(home) = home_dir() else {
+            return Vec::new();
+        };
+        let user_dir = if cfg!(target_os = "macos") {
+            home.join("Library").join("Application Support").join("Cursor").join("User")
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(".config"))
+                .join("Cursor")
+                .join("User")
+        };
+        let database = user_dir.join("globalStorage").join("state.vscdb");
+        if database.is_file() {
+            vec![database]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// OpenCode's storage directory (`~/.local/share/opencode/storage`): one JSON file per
+/// session under `session/<project>/`, per message under `message/<session>/`, and per message
+/// part (text or tool call) under `part/<message>/`
+struct OpenCodeStorage;
+
+impl SessionFormat for OpenCodeStorage {
+    fn name(&self) -> &'static str {
+        "opencode"
+    }
+
+    fn detect(&self, path: &Path, _content: Option<&str>) -> bool {
+        path.join("session").is_dir() && path.join("message").is_dir()
+    }
+
+    fn parse(&self, path: &Path, _content: Option<&str>) -> Result<Vec<ImportedSession>> {
+        let mut sessions = Vec::new();
+
+        for info in json_files(&path.join("session"), 2) {
+            let Some(id) = info.get("id").and_then(|id| id.as_str()) else {
+                continue;
+            };
+
+            let mut session_messages = json_files(&path.join("message").join(id), 1);
+            session_messages.sort_by_key(|message| {
+                (message.pointer("/time/created").and_then(|t| t.as_i64()), message.get("id").and_then(|i| i.as_str()).map(|i| i.to_string()))
+            });
+
+            let mut messages = Vec::new();
+            for message in &session_messages {
+                let role = match message.get("role").and_then(|r| r.as_str()) {
+                    Some("user") => "user",
+                    Some("assistant") => "assistant",
+                    _ => continue,
+                };
+                let timestamp = epoch_millis(message.pointer("/time/created"));
+                let Some(message_id) = message.get("id").and_then(|i| i.as_str()) else {
+                    continue;
+                };
+
+                // Part IDs sort in creation order
+                let mut parts = json_files(&path.join("part").join(message_id), 1);
+                parts.sort_by(|a, b| a.get("id").and_then(|i| i.as_str()).cmp(&b.get("id").and_then(|i| i.as_str())));
+
+                let mut content = Vec::new();
+                let mut results = Vec::new();
+                for part in &parts {
+                    match part.get("type").and_then(|t| t.as_str()) {
+                        Some("text") if part.get("synthetic").and_then(|s| s.as_bool()) != Some(true) => {
+                            if let Some(text) = part.get("text").and_then(|t| t.as_str()).filter(|t| !t.trim().is_empty()) {
+                                content.push(json!({ "type": "text", "text": text }));
+                            }
+                        }
+                        Some("tool") => {
+                            let name = part.get("tool").and_then(|t| t.as_str()).unwrap_or("tool");
+                            content.push(json!({ "type": "tool_use", "name": name, "input": part.pointer("/state/input") }));
+                            if let Some(output) = part.pointer("/state/output").and_then(|o| o.as_str()) {
+                                results.push(json!({ "type": "tool_result", "content": output }));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                if !content.is_empty() {
+                    messages.push(ImportedMessage { role, timestamp, content });
+                }
+                if !results.is_empty() {
+                    messages.push(ImportedMessage { role: "user", timestamp, content: results });
+                }
+            }
+
+            sessions.push(ImportedSession {
+                session_id: id.to_string(),
+                title: info.get("title").and_then(|t| t.as_str()).map(|t| t.to_string()),
+                cwd: info.get("directory").and_then(|d| d.as_str()).map(|d| d.to_string()),
+                messages,
+            });
+        }
+
+        Ok(sessions)
+    }
+
+    fn default_locations(&self) -> Vec<PathBuf> {
+        let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+            Some(data_home) => PathBuf::from(data_home),
+            None => match home_dir() {
+                Some(home) => home.join(".local").join("share"),
+                None => return Vec::new(),
+            },
+        };
+        let storage = data_dir.join("opencode").join("storage");
+        if self.detect(&storage, None) {
+            vec![storage]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Parsed `.json` files found up to `depth` directories below `dir`; unreadable ones are skipped.
+fn json_files(dir: &Path, depth: usize) -> Vec<Value> {
+    walkdir::WalkDir::new(dir)
+        .max_depth(depth)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect()
+}
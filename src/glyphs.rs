@@ -0,0 +1,81 @@
+/// Bar heights for sparklines, lowest first
+const LEVELS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_LEVELS: [char; 9] = [' ', '.', ':', '-', '=', '+', '*', '#', '@'];
+
+/// Markers printed around session content, each with an ASCII stand-in for terminals and logs
+/// that garble emoji and box-drawing characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyph {
+    Write,
+    Edit,
+    Tool,
+    Mcp,
+    /// Between an MCP server and its tool
+    Within,
+    Arrow,
+    Up,
+    Down,
+    Ok,
+    Failed,
+    Interrupted,
+    Active,
+    Later,
+    Ellipsis,
+    /// Between the ends of a range, as in `messages 3–9`
+    Range,
+    Times,
+    /// Either side of a sparkline
+    Rule,
+}
+
+impl Glyph {
+    /// The marker, or its ASCII stand-in under `--ascii`.
+    pub fn render(self, ascii: bool) -> &'static str {
+        if ascii {
+            match self {
+                Glyph::Write => "[write]",
+                Glyph::Edit => "[edit]",
+                Glyph::Tool => "[tool]",
+                Glyph::Mcp => "[mcp]",
+                Glyph::Within => ">",
+                Glyph::Arrow => "->",
+                Glyph::Up => "^",
+                Glyph::Down => "v",
+                Glyph::Ok => "[ok]",
+                Glyph::Failed => "[x]",
+                Glyph::Interrupted => "[!]",
+                Glyph::Active => "*",
+                Glyph::Later => "...",
+                Glyph::Ellipsis => "...",
+                Glyph::Range => "-",
+                Glyph::Times => "x",
+                Glyph::Rule => "|",
+            }
+        } else {
+            match self {
+                Glyph::Write => "📝",
+                Glyph::Edit => "✏️",
+                Glyph::Tool => "🔧",
+                Glyph::Mcp => "🔌",
+                Glyph::Within => "›",
+                Glyph::Arrow => "→",
+                Glyph::Up => "↑",
+                Glyph::Down => "↓",
+                Glyph::Ok => "✓",
+                Glyph::Failed => "✗",
+                Glyph::Interrupted => "⛔",
+                Glyph::Active => "🟢",
+                Glyph::Later => "⏳",
+                Glyph::Ellipsis => "…",
+                Glyph::Range => "–",
+                Glyph::Times => "×",
+                Glyph::Rule => "│",
+            }
+        }
+    }
+}
+
+/// Bar heights for sparklines, from none to the peak.
+pub fn sparkline_levels(ascii: bool) -> &'static [char; 9] {
+    if ascii { &ASCII_LEVELS } else { &LEVELS }
+}
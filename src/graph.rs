@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::error::{Result, SessionFinderError};
+use crate::export::{select_sessions, session_date, session_title, short_id};
+use crate::file_ops::files_touched;
+use crate::matcher::SearchOptions;
+use crate::render::RenderOptions;
+use crate::resolve::read_session_text;
+use crate::resume::session_working_dir;
+use crate::timeline::parse_session_messages;
+use crate::text::truncate_text;
+
+/// Longest session title shown inside a diagram node
+const NODE_TITLE_LEN: usize = 40;
+
+/// Diagram languages `export` can draw session relationships in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    Mermaid,
+    Dot,
+}
+
+impl GraphFormat {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            "dot" | "graphviz" => Ok(GraphFormat::Dot),
+            other => Err(SessionFinderError::Usage(format!(
+                "Format '{}' is not a diagram format (use mermaid or dot)",
+                other
+            ))),
+        }
+    }
+}
+
+/// One session as a diagram node
+struct SessionNode {
+    node_id: String,
+    session_id: String,
+    project: String,
+    date: String,
+    title: String,
+    /// Messages with more than one child, i.e. places the conversation was rewound and retried
+    branch_points: usize,
+    uuids: HashSet<String>,
+    /// Parents referenced by this session that it does not contain itself
+    foreign_parents: HashSet<String>,
+    files: Vec<String>,
+}
+
+/// An edge between two sessions, by node index
+enum Relation {
+    /// Next session in the same project
+    Followed(usize, usize),
+    /// Second session continues from a message of the first (resumed or forked)
+    Forked(usize, usize),
+    /// Both sessions wrote or edited the same files
+    SharedFiles(usize, usize, Vec<String>),
+}
+
+/// Prints a diagram of sessions per project in time order, with fork and shared-file links.
+pub fn export_graph(
+    session_refs: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    format: GraphFormat,
+    render: RenderOptions,
+) -> Result<()> {
+    let mut nodes = Vec::new();
+    let options = SearchOptions { render, ..SearchOptions::default() };
+    for info in select_sessions(session_refs, &[], project_filter, recent_days, &options)? {
+        let content = read_session_text(&info.path)?;
+        let messages = parse_session_messages(&content)?;
+
+        let uuids: HashSet<String> = messages.iter().filter_map(|msg| msg.uuid.clone()).collect();
+        let mut child_counts: HashMap<&str, usize> = HashMap::new();
+        let mut foreign_parents = HashSet::new();
+        for parent in messages.iter().filter_map(|msg| msg.parent_uuid.as_deref()) {
+            *child_counts.entry(parent).or_insert(0) += 1;
+            if !uuids.contains(parent) {
+                foreign_parents.insert(parent.to_string());
+            }
+        }
+
+        nodes.push(SessionNode {
+            node_id: format!("s_{}", short_id(&info.session_id).replace(|c: char| !c.is_ascii_alphanumeric(), "_")),
+            session_id: info.session_id.clone(),
+            project: info.project_path.clone(),
+            date: session_date(&messages, &info),
+            title: truncate_text(&session_title(&info), NODE_TITLE_LEN),
+            branch_points: child_counts.values().filter(|&&count| count > 1).count(),
+            files: files_touched(&messages, &session_working_dir(&info.path)),
+            uuids,
+            foreign_parents,
+        });
+    }
+    nodes.sort_by(|a, b| (&a.project, &a.date, &a.session_id).cmp(&(&b.project, &b.date, &b.session_id)));
+
+    let relations = relations(&nodes);
+    let diagram = match format {
+        GraphFormat::Mermaid => render_mermaid(&nodes, &relations),
+        GraphFormat::Dot => render_dot(&nodes, &relations),
+    };
+    print!("{}", diagram);
+    Ok(())
+}
+
+fn relations(nodes: &[SessionNode]) -> Vec<Relation> {
+    let mut relations = Vec::new();
+
+    // Nodes are sorted by project then date, so neighbours in the same project are consecutive
+    for (index, pair) in nodes.windows(2).enumerate() {
+        if pair[0].project == pair[1].project {
+            relations.push(Relation::Followed(index, index + 1));
+        }
+    }
+
+    for (child, node) in nodes.iter().enumerate() {
+        for (parent, other) in nodes.iter().enumerate() {
+            if parent != child && node.foreign_parents.iter().any(|uuid| other.uuids.contains(uuid)) {
+                relations.push(Relation::Forked(parent, child));
+            }
+        }
+    }
+
+    for first in 0..nodes.len() {
+        for second in first + 1..nodes.len() {
+            let shared: Vec<String> = nodes[first]
+                .files
+                .iter()
+                .filter(|file| nodes[second].files.contains(file))
+                .cloned()
+                .collect();
+            if !shared.is_empty() {
+                relations.push(Relation::SharedFiles(first, second, shared));
+            }
+        }
+    }
+
+    relations
+}
+
+fn node_label(node: &SessionNode) -> String {
+    let mut label = format!("{} {}\n{}", node.date, short_id(&node.session_id), node.title);
+    if node.branch_points > 0 {
+        label.push_str(&format!("\n{} branch point(s)", node.branch_points));
+    }
+    label
+}
+
+fn shared_files_label(files: &[String]) -> String {
+    let first = files[0].rsplit('/').next().unwrap_or(&files[0]);
+    match files.len() {
+        1 => first.to_string(),
+        count => format!("{} +{}", first, count - 1),
+    }
+}
+
+fn render_mermaid(nodes: &[SessionNode], relations: &[Relation]) -> String {
+    let escape = |text: &str| {
+        text.replace('"', "#quot;")
+            .replace('<', "#lt;")
+            .replace('>', "#gt;")
+            .replace('\n', "<br/>")
+    };
+    let mut out = String::from("flowchart LR\n");
+
+    let mut projects: BTreeMap<&str, Vec<&SessionNode>> = BTreeMap::new();
+    for node in nodes {
+        projects.entry(&node.project).or_default().push(node);
+    }
+    for (index, (project, project_nodes)) in projects.iter().enumerate() {
+        out.push_str(&format!("  subgraph p{}[\"{}\"]\n", index, escape(project)));
+        for node in project_nodes {
+            out.push_str(&format!("    {}[\"{}\"]\n", node.node_id, escape(&node_label(node))));
+        }
+        out.push_str("  end\n");
+    }
+
+    for relation in relations {
+        match relation {
+            Relation::Followed(from, to) => {
+                out.push_str(&format!("  {} --> {}\n", nodes[*from].node_id, nodes[*to].node_id))
+            }
+            Relation::Forked(from, to) => {
+                out.push_str(&format!("  {} -. fork .-> {}\n", nodes[*from].node_id, nodes[*to].node_id))
+            }
+            Relation::SharedFiles(first, second, files) => out.push_str(&format!(
+                "  {} -.-|\"{}\"| {}\n",
+                nodes[*first].node_id,
+                escape(&shared_files_label(files)),
+                nodes[*second].node_id
+            )),
+        }
+    }
+
+    out
+}
+
+fn render_dot(nodes: &[SessionNode], relations: &[Relation]) -> String {
+    let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    let mut out = String::from("digraph sessions {\n  rankdir=LR;\n  node [shape=box];\n");
+
+    let mut projects: BTreeMap<&str, Vec<&SessionNode>> = BTreeMap::new();
+    for node in nodes {
+        projects.entry(&node.project).or_default().push(node);
+    }
+    for (index, (project, project_nodes)) in projects.iter().enumerate() {
+        out.push_str(&format!("  subgraph cluster_{} {{\n    label=\"{}\";\n", index, escape(project)));
+        for node in project_nodes {
+            out.push_str(&format!("    {} [label=\"{}\"];\n", node.node_id, escape(&node_label(node))));
+        }
+        out.push_str("  }\n");
+    }
+
+    for relation in relations {
+        match relation {
+            Relation::Followed(from, to) => {
+                out.push_str(&format!("  {} -> {};\n", nodes[*from].node_id, nodes[*to].node_id))
+            }
+            Relation::Forked(from, to) => out.push_str(&format!(
+                "  {} -> {} [style=dashed, label=\"fork\"];\n",
+                nodes[*from].node_id, nodes[*to].node_id
+            )),
+            Relation::SharedFiles(first, second, files) => out.push_str(&format!(
+                "  {} -> {} [style=dotted, dir=none, label=\"{}\"];\n",
+                nodes[*first].node_id,
+                nodes[*second].node_id,
+                escape(&shared_files_label(files))
+            )),
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
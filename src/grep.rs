@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::Result;
+use crate::matcher::{query_matcher, SearchOptions};
+use crate::prefilter::for_each_matching_file;
+use crate::resolve::{read_session_text, session_roots};
+use crate::text::truncate_text;
+use crate::time_range::{carried_message_times, TimeRange};
+use crate::timeline::{find_matching_messages, parse_session_messages};
+use crate::{decode_project_path, extract_session_id};
+
+/// Longest excerpt printed for one matching line
+const MAX_LINE_WIDTH: usize = 200;
+
+/// Prints every line of message text containing a term as `session_id:message:role: line`,
+/// across all sessions (or those given with `--file`), oldest session first, instead of
+/// ranking sessions. Messages are picked as timelines pick them (`--types`, `--in-code`,
+/// excluded terms), then by role and time. Returns whether anything matched.
+pub fn run_grep(
+    search_terms: &[&str],
+    input_files: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    role: Option<&str>,
+    time_range: &TimeRange,
+    options: &SearchOptions,
+) -> Result<bool> {
+    let cutoff = recent_days.map(|days| Utc::now() - chrono::Duration::days(days));
+    let mut files: Vec<(DateTime<Utc>, PathBuf)> = Vec::new();
+    for file in input_files {
+        let path = PathBuf::from(file);
+        files.push((DateTime::from(fs::metadata(&path)?.modified()?), path));
+    }
+    let roots = if input_files.is_empty() { session_roots()? } else { Vec::new() };
+    for root in roots {
+        for_each_matching_file(&root, search_terms, false, |path, _| {
+            let path = root.join(path);
+            let in_project = project_filter.is_none_or(|filter| decode_project_path(&path).is_ok_and(|project| project.contains(filter.as_str())));
+            if options.ignore.ignores_file(&path) || !in_project {
+                return Ok(true);
+            }
+            let modified: DateTime<Utc> = DateTime::from(fs::metadata(&path)?.modified()?);
+            if cutoff.is_none_or(|cutoff| modified >= cutoff) {
+                files.push((modified, path));
+            }
+            Ok(true)
+        })?;
+    }
+    files.sort();
+
+    let matcher = query_matcher(search_terms);
+    let mut found = false;
+    for (_, path) in files {
+        let session_id = extract_session_id(&path)?;
+        let messages = parse_session_messages(&read_session_text(&path)?)?;
+        let times = carried_message_times(&messages);
+        for index in find_matching_messages(&messages, search_terms, options) {
+            let Some(message) = messages[index].message.as_ref() else {
+                continue;
+            };
+            let message_role = message.role.as_deref().unwrap_or_default();
+            if role.is_some_and(|role| role != message_role) || !time_range.contains(times[index]) {
+                continue;
+            }
+            let Some(content) = message.content.as_ref() else {
+                continue;
+            };
+            for line in options.match_text(content).lines().filter(|line| matcher.is_match(&line.to_lowercase())) {
+                found = true;
+                println!("{}:{}:{}: {}", session_id, index, message_role, truncate_text(line.trim(), MAX_LINE_WIDTH));
+            }
+        }
+    }
+    Ok(found)
+}
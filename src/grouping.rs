@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::error::{Result, SessionFinderError};
+use crate::ranking::SortOrder;
+use crate::{rank_and_limit_sessions, SessionInfo};
+
+/// How `--group-by` splits search results
+#[derive(Clone, Copy)]
+pub enum ResultGrouping {
+    Project,
+    /// By the day the session was last modified
+    Day,
+}
+
+impl ResultGrouping {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "project" => Ok(ResultGrouping::Project),
+            "day" => Ok(ResultGrouping::Day),
+            other => Err(SessionFinderError::Usage(format!("Unknown grouping: {} (expected project or day)", other))),
+        }
+    }
+
+    pub fn key(self, project_path: &str, last_modified: DateTime<Utc>) -> String {
+        match self {
+            ResultGrouping::Project => project_path.to_string(),
+            ResultGrouping::Day => last_modified.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+/// One group of search results: the first `shown` of the ranked sessions belong to it
+pub struct ResultGroup {
+    pub key: String,
+    /// Matching sessions in the group before the limit
+    pub total: usize,
+    pub shown: usize,
+}
+
+/// Ranks the sessions within each group by `sort` and keeps `limit` per group. Returns the kept sessions
+/// group by group, with the groups that partition them: projects ordered by their best
+/// session, days newest first.
+pub fn group_sessions(
+    sessions: Vec<SessionInfo>,
+    grouping: ResultGrouping,
+    limit: usize,
+    sort: SortOrder,
+) -> (Vec<SessionInfo>, Vec<ResultGroup>) {
+    let mut order: Vec<String> = Vec::new();
+    let mut members: HashMap<String, Vec<SessionInfo>> = HashMap::new();
+    for session in rank_and_limit_sessions(sessions, usize::MAX, sort) {
+        let key = grouping.key(&session.project_path, session.last_modified);
+        if !members.contains_key(&key) {
+            order.push(key.clone());
+        }
+        members.entry(key).or_default().push(session);
+    }
+    if let ResultGrouping::Day = grouping {
+        order.sort_by(|a, b| b.cmp(a));
+    }
+
+    let mut kept = Vec::new();
+    let mut groups = Vec::new();
+    for key in order {
+        let sessions = members.remove(&key).unwrap_or_default();
+        let total = sessions.len();
+        let shown = total.min(limit);
+        kept.extend(sessions.into_iter().take(shown));
+        groups.push(ResultGroup { key, total, shown });
+    }
+    (kept, groups)
+}
@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use crate::code_analysis::analyzer_named;
+use crate::fence::code_blocks;
+
+/// Lines of Edit tool calls that introduce the old and new text rather than being code
+const EDIT_MARKERS: &[&str] = &["--- Replace:", "+++ With:"];
+
+/// The name syntect knows a language by, from a fence tag, language name, or file extension.
+/// Its bundled syntaxes have no TypeScript, whose code JavaScript's colours suit well enough.
+fn syntax_token(language: &str) -> &str {
+    match analyzer_named(language).map(|analyzer| analyzer.name()) {
+        Some("rust") => "rs",
+        Some("python") => "py",
+        Some("javascript" | "typescript") => "js",
+        Some("go") => "go",
+        Some("sql") => "sql",
+        Some("shell") => "sh",
+        _ => language,
+    }
+}
+
+/// Whether code is coloured: only with the `highlight` feature, and only on a terminal.
+fn highlighting() -> bool {
+    cfg!(feature = "highlight") && std::io::stdout().is_terminal()
+}
+
+/// Code coloured for its language on a terminal; unchanged when piped, or when the language
+/// is unknown.
+pub fn highlight_code<'a>(code: &'a str, language: Option<&str>) -> Cow<'a, str> {
+    match language.filter(|_| highlighting()) {
+        Some(language) => colour(code, syntax_token(language), &[]).map_or(Cow::Borrowed(code), Cow::Owned),
+        None => Cow::Borrowed(code),
+    }
+}
+
+/// Message text with the code in its fenced blocks coloured for the fence's language.
+pub fn highlight_fenced(text: &str) -> Cow<'_, str> {
+    if !highlighting() {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::new();
+    let mut copied = 0;
+    for block in code_blocks(text) {
+        let Some(coloured) = block.language.and_then(|language| colour(block.code, syntax_token(language), &[])) else {
+            continue;
+        };
+        // The code starts on the line after the opening fence
+        let code_start = block.span.start + text[block.span.clone()].find('\n').map_or(0, |newline| newline + 1);
+        out.push_str(&text[copied..code_start]);
+        out.push_str(&coloured);
+        copied = code_start + block.code.len();
+    }
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    out.push_str(&text[copied..]);
+    Cow::Owned(out)
+}
+
+/// The language of a file, as the token `highlight_tool_content` takes: its extension.
+pub fn file_language(file: &str) -> Option<&str> {
+    Path::new(file).extension().and_then(|extension| extension.to_str())
+}
+
+/// A Write or Edit call as timelines show it, with the code after its first line coloured for
+/// the language of the file it writes.
+pub fn highlight_tool_content<'a>(text: &'a str, language: &str) -> Cow<'a, str> {
+    let Some((heading, code)) = text.split_once('\n').filter(|_| highlighting()) else {
+        return Cow::Borrowed(text);
+    };
+    match colour(code, syntax_token(language), EDIT_MARKERS) {
+        Some(coloured) => Cow::Owned(format!("{}\n{}", heading, coloured)),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// `code` in 24-bit terminal colours, leaving `plain` lines alone. Each line's indentation is
+/// left uncoloured ahead of the colour codes so wrapping still sees it, and each line ends by
+/// resetting the colour.
+#[cfg(feature = "highlight")]
+fn colour(code: &str, token: &str, plain: &[&str]) -> Option<String> {
+    use std::sync::OnceLock;
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::ThemeSet;
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    let syntaxes = SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines);
+    let syntax = syntaxes.find_syntax_by_token(token)?;
+    let theme = &THEMES.get_or_init(ThemeSet::load_defaults).themes["base16-ocean.dark"];
+    let mut highlighter = HighlightLines::new(syntax, theme);
+
+    let mut out = String::new();
+    for line in LinesWithEndings::from(code) {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[content.len()..];
+        if plain.contains(&content) {
+            out.push_str(line);
+            continue;
+        }
+        let ranges = highlighter.highlight_line(line, syntaxes).ok()?;
+        let indent = content.len() - content.trim_start().len();
+        let mut skip = indent;
+        let mut body = Vec::new();
+        for (style, text) in ranges {
+            if skip >= text.len() {
+                skip -= text.len();
+                continue;
+            }
+            body.push((style, &text[skip..]));
+            skip = 0;
+        }
+        out.push_str(&content[..indent]);
+        out.push_str(as_24_bit_terminal_escaped(&body, false).trim_end_matches(['\n', '\r']));
+        out.push_str("\x1b[0m");
+        out.push_str(ending);
+    }
+    Some(out)
+}
+
+#[cfg(not(feature = "highlight"))]
+fn colour(_code: &str, _token: &str, _plain: &[&str]) -> Option<String> {
+    None
+}
@@ -0,0 +1,63 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::decode_project_path;
+use crate::resolve::extract_session_id_from_path;
+
+/// Sessions searches never return, from the `[ignore]` table of the config file:
+///
+/// ```toml
+/// [ignore]
+/// projects = ["/tmp/*", "*/scratch"]
+/// sessions = ["3f2a9c1e-..."]
+/// min_messages = 4
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IgnoreRules {
+    /// Project path globs; `*` matches any run of characters, `?` any one
+    #[serde(default)]
+    projects: Vec<String>,
+    /// Session IDs or ID prefixes
+    #[serde(default)]
+    sessions: Vec<String>,
+    /// Sessions with fewer messages than this are test runs or false starts
+    min_messages: Option<usize>,
+}
+
+impl IgnoreRules {
+    /// Whether the session file is ignored by project or ID, which needs no parsing.
+    pub fn ignores_file(&self, path: &Path) -> bool {
+        let ignored_id = !self.sessions.is_empty()
+            && extract_session_id_from_path(path)
+                .is_ok_and(|id| self.sessions.iter().any(|ignored| id.starts_with(ignored.as_str())));
+        let ignored_project = !self.projects.is_empty()
+            && decode_project_path(path)
+                .is_ok_and(|project| self.projects.iter().any(|pattern| glob_matches(pattern, &project)));
+        ignored_id || ignored_project
+    }
+
+    pub fn ignores_length(&self, message_count: usize) -> bool {
+        self.min_messages.is_some_and(|min| message_count < min)
+    }
+
+    /// The rules in a line, for `--explain-plan`; `None` when nothing is ignored.
+    pub fn describe(&self) -> Option<String> {
+        let mut rules = Vec::new();
+        if !self.projects.is_empty() {
+            rules.push(format!("projects {}", self.projects.join(", ")));
+        }
+        if !self.sessions.is_empty() {
+            rules.push(format!("sessions {}", self.sessions.join(", ")));
+        }
+        if let Some(min) = self.min_messages {
+            rules.push(format!("sessions under {} messages", min));
+        }
+        (!rules.is_empty()).then(|| rules.join("; "))
+    }
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let regex = regex::escape(pattern).replace(r"\*", ".*").replace(r"\?", ".");
+    Regex::new(&format!("^{}$", regex)).is_ok_and(|regex| regex.is_match(text))
+}
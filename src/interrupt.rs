@@ -0,0 +1,70 @@
+use crate::{Content, SessionMessage};
+
+/// Text Claude Code records as a user message when a request is stopped with Esc
+const INTERRUPT_MARKERS: &[&str] = &["[Request interrupted by user", "[Request cancelled by user"];
+
+/// Tool result Claude Code records when a tool call is rejected at the permission prompt
+const REJECTED_TOOL_MARKER: &str = "The user doesn't want to proceed with this tool use";
+
+/// How a turn was cut short
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interruption {
+    /// The user stopped the response
+    Request,
+    /// The user rejected a tool call
+    ToolCall,
+}
+
+/// Whether this message is one of Claude Code's interruption markers.
+pub fn interruption(msg: &SessionMessage) -> Option<Interruption> {
+    if msg.msg_type != "user" {
+        return None;
+    }
+    match msg.message.as_ref()?.content.as_ref()? {
+        Content::Text(text) => is_interrupt_marker(text).then_some(Interruption::Request),
+        Content::Array(blocks) => blocks.iter().find_map(|block| match block.r#type.as_str() {
+            "text" => block.text.as_deref().filter(|text| is_interrupt_marker(text)).map(|_| Interruption::Request),
+            "tool_result" => block
+                .content
+                .as_ref()
+                .filter(|content| content.to_string().contains(REJECTED_TOOL_MARKER))
+                .map(|_| Interruption::ToolCall),
+            _ => None,
+        }),
+    }
+}
+
+fn is_interrupt_marker(text: &str) -> bool {
+    let text = text.trim_start();
+    INTERRUPT_MARKERS.iter().any(|marker| text.starts_with(marker))
+}
+
+/// For each message, whether it belongs to a turn that was interrupted: everything from the
+/// user prompt that started the turn through the interruption marker.
+pub fn interrupted_turns(messages: &[SessionMessage]) -> Vec<bool> {
+    let mut interrupted = vec![false; messages.len()];
+    let mut turn_start = 0;
+
+    for (index, msg) in messages.iter().enumerate() {
+        if interruption(msg).is_some() {
+            interrupted[turn_start..=index].iter_mut().for_each(|flag| *flag = true);
+            turn_start = index + 1;
+        } else if is_user_prompt(msg) {
+            turn_start = index;
+        }
+    }
+
+    interrupted
+}
+
+/// A message the user typed, as opposed to tool results, which are also sent as user messages.
+pub fn is_user_prompt(msg: &SessionMessage) -> bool {
+    if msg.msg_type != "user" {
+        return false;
+    }
+    match msg.message.as_ref().and_then(|m| m.content.as_ref()) {
+        Some(Content::Text(_)) => true,
+        Some(Content::Array(blocks)) => blocks.iter().all(|block| block.r#type != "tool_result"),
+        None => false,
+    }
+}
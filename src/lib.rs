@@ -0,0 +1,3694 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Instant;
+use tracing::{debug, info, warn};
+
+mod active;
+mod aliases;
+mod annotate;
+#[cfg(all(unix, feature = "tokio"))]
+mod async_daemon;
+mod backup;
+mod baseline;
+mod bench;
+mod chunks;
+mod claude_data;
+mod clipboard;
+mod code_analysis;
+mod command_status;
+mod commands;
+mod config;
+mod crypto;
+mod daemon;
+mod density;
+mod digest;
+mod drift;
+mod editor;
+mod error;
+mod exit;
+mod explain;
+mod error_rules;
+mod events;
+mod export;
+mod fence;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod file_ops;
+mod files;
+mod formats;
+mod local_config;
+mod logging;
+mod machines;
+mod matcher;
+mod memory;
+mod new_sessions;
+mod notify;
+mod pack;
+mod prefilter;
+#[cfg(feature = "python")]
+mod python;
+mod ranking;
+mod related_terms;
+mod render;
+mod replay;
+mod glyphs;
+mod graph;
+mod grep;
+mod ignore;
+mod grouping;
+mod highlight;
+mod interrupt;
+mod resolve;
+mod resume;
+mod schedule;
+mod rpc;
+mod preview;
+mod prune;
+mod schema;
+mod scope;
+mod session_index;
+mod shell_init;
+mod show;
+mod sqlite;
+mod stats;
+mod time_budget;
+mod time_range;
+mod store;
+mod success;
+mod sync;
+mod term_stats;
+#[cfg(test)]
+mod test_support;
+mod text;
+mod timeline;
+mod title;
+mod tokens;
+mod tools;
+mod trash;
+mod trend;
+mod webhook;
+mod workspace;
+mod wrap;
+use active::is_active;
+use aliases::expand_aliases;
+use annotate::{run_bookmark, run_note, run_tag};
+use baseline::{compare_with_baseline, display_baseline_comparison};
+use bench::run_bench;
+use chunks::run_chunks;
+pub use chunks::{chunk_sessions, Chunk, ChunkOptions};
+use claude_data::{claude_data, todo_summary, Todo};
+use clipboard::copy_to_clipboard;
+use code_analysis::{analyzer_named, language_analyzers};
+use command_status::CommandOutcome;
+use commands::{command_marker, slash_commands, split_command_scopes};
+pub use config::Config;
+use daemon::{cache_analysis, cached_analysis, cached_analyzer, caching_analyses, query_daemon, run_daemon};
+use crypto::generate_key_file;
+use density::display_density;
+use drift::run_drift;
+use editor::open_in_editor;
+pub use error::{CandidateSession, Result, SessionFinderError};
+use error_rules::install_error_rules;
+use error::is_backend_failure;
+use exit::{exit_code, EXIT_FOUND, EXIT_NOT_FOUND};
+use explain::{explain, explain_stage};
+use export::select_sessions;
+pub use export::{export_notes, write_timeline, NoteFormat, TimelineFormat};
+use file_ops::run_touched;
+use files::run_files_report;
+use formats::run_import;
+use glyphs::Glyph;
+use graph::{export_graph, GraphFormat};
+use grep::run_grep;
+use grouping::{group_sessions, ResultGroup};
+pub use grouping::ResultGrouping;
+use local_config::LocalConfig;
+use logging::init_logging;
+use matcher::{query_matcher, Exclusions, TermMatcher};
+pub use matcher::SearchOptions;
+use pack::run_pack;
+use prefilter::{for_each_matching_file, FileMatches};
+pub use memory::MemoryBudget;
+use memory::SessionChunks;
+use new_sessions::run_new;
+use digest::{digest_dir, parse_digest_date, run_digest};
+use schedule::{install_schedule, parse_daily_time, remove_schedule};
+use prune::run_prune;
+use trash::run_undo;
+use backup::{run_backup_manifest, run_backup_verify};
+use machines::{describe_owner, owner_dir, remap_project_root, set_project_roots};
+use ranking::SORT_ORDER_NAMES;
+pub use ranking::SortOrder;
+use related_terms::{display_related_terms, related_terms};
+pub use render::RenderOptions;
+use resolve::{STDIN_REFERENCE, TEAM_SOURCE_PREFIX, extract_session_id_from_path, read_session_text, read_session_title, session_roots, session_source, resolve_recent_session, resolve_session_path, without_identical_copies, ProjectMatch};
+use replay::run_replay;
+use resume::{resume_command, run_resume, session_working_dir};
+use rpc::run_rpc_server;
+use preview::{matched_excerpts, PreviewCollector, PreviewRole, PREVIEW_ROLE_NAMES};
+pub use preview::PreviewOptions;
+use schema::{parse_unterminated_line, ParseDiagnostics};
+use scope::CONTENT_TYPE_NAMES;
+pub use scope::MatchScope;
+use session_index::{boundary_hash, SessionGrowth, SessionIndex};
+use shell_init::{complete_from_env, shell_init_script};
+use show::show_messages;
+use sqlite::export_sqlite;
+use stats::{run_tool_stats, Usage};
+pub use stats::{run_stats, StatsFormat, StatsGrouping};
+use store::Store;
+pub use time_budget::TimeBudget;
+pub use time_range::TimeRange;
+pub use sync::{sync_pull, sync_push, PushOptions};
+use term_stats::{run_term_stats, TermOptions};
+use title::infer_title;
+use tokens::{estimate_tokens, TokenCounter, TokenEstimate, DEFAULT_CONTEXT_WINDOW};
+use tools::session_uses_tool;
+pub use tools::ToolFilter;
+use trend::{parse_since, run_trend, TrendPeriod};
+use workspace::{in_workspace, repository_root};
+use timeline::{
+    parse_session_messages_with_diagnostics, extract_content_text, first_entry_excerpt, display_timeline, display_timeline_quickfix,
+    extract_code_diff_timeline, display_code_diff_timeline, display_code_diff_quickfix, match_summary, CodeDiffSelection, MatchCounter, CODE_DIFF_TOOLS,
+};
+pub use timeline::{extract_timeline, TimelineSearch};
+#[cfg(feature = "tokio")]
+pub use timeline::extract_timeline_async;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionMessage {
+    #[serde(rename = "type")]
+    msg_type: String,
+    uuid: Option<String>,
+    #[serde(rename = "parentUuid")]
+    parent_uuid: Option<String>,
+    cwd: Option<String>,
+    #[serde(rename = "gitBranch")]
+    git_branch: Option<String>,
+    #[serde(rename = "isSidechain")]
+    is_sidechain: Option<bool>,
+    #[serde(rename = "userType")]
+    user_type: Option<String>,
+    /// Structured result of a tool call, recorded next to its `tool_result` block
+    #[serde(rename = "toolUseResult")]
+    tool_use_result: Option<serde_json::Value>,
+    message: Option<InnerMessage>,
+    timestamp: Option<String>,
+    /// 1-based line of the session file this message was parsed from
+    #[serde(skip)]
+    line_number: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InnerMessage {
+    role: Option<String>,
+    content: Option<Content>,
+    model: Option<String>,
+    #[serde(skip_serializing)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Content {
+    Text(String),
+    Array(Vec<ContentBlock>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ContentBlock {
+    r#type: String,
+    text: Option<String>,
+    name: Option<String>,
+    input: Option<serde_json::Value>,
+    /// ID of a `tool_use` block, referenced by its `tool_result`
+    id: Option<String>,
+    tool_use_id: Option<String>,
+    /// Set on `tool_result` blocks for calls that failed
+    is_error: Option<bool>,
+    /// Output of a `tool_result`: a string or an array of text blocks
+    content: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassifiedContent {
+    pub raw_content: String,
+    pub content_type: ContentType,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum ContentType {
+    PlainText,
+    CodeBlock(CodeInfo),
+    ToolCall(ToolInfo), 
+    ErrorMessage(ErrorInfo),
+    SuccessResponse(SuccessInfo),
+    Discussion,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeInfo {
+    pub language: Option<String>,
+    pub is_complete: bool,
+    pub line_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolInfo {
+    pub tool_name: String,
+    #[allow(dead_code)]
+    pub action_type: String,
+    pub target_files: Vec<String>,
+    /// Server providing the tool, for MCP tools
+    pub mcp_server: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SuccessInfo {
+    /// From 0 to 1; see `success::success_confidence`
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorInfo {
+    pub error_type: String,
+    pub severity: String,
+    pub source: Option<String>,
+}
+
+/// A session file as search results describe it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub path: PathBuf,
+    pub session_id: String,
+    pub project_path: String,
+    pub last_modified: DateTime<Utc>,
+    pub line_count: usize,
+    /// Messages parsed from the file
+    pub message_count: usize,
+    pub topics: Vec<String>,
+    pub first_messages: Vec<String>,
+    pub last_messages: Vec<String>,
+    pub common_terms: Vec<String>,
+    pub file_size_bytes: u64,
+    pub parse_diagnostics: ParseDiagnostics,
+    /// `claude`, or the tool an imported session came from
+    pub source: String,
+    /// Slash commands the session ran, with how often
+    pub slash_commands: Vec<(String, usize)>,
+    /// The session's summary, its title in Claude's history index, or a title inferred from
+    /// the first prompt
+    pub title: Option<String>,
+    /// The session's todo list as Claude last recorded it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub todos: Vec<Todo>,
+    /// Where the search terms occur
+    pub matches: MatchSummary,
+    /// Size in context if resumed
+    pub tokens: TokenEstimate,
+    /// Matching lines as the prefilter found them, for sessions found by searching the
+    /// projects tree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefilter: Option<FileMatches>,
+    /// Excerpts of the first few matching messages, read at the prefilter's line offsets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_messages: Vec<String>,
+    /// Claude Code is still writing the session
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub active: bool,
+    /// How much was appended since search results last showed the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub growth: Option<SessionGrowth>,
+}
+
+/// Search term hits in one session
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MatchSummary {
+    /// Every occurrence of any term
+    pub occurrences: usize,
+    /// Messages containing a term
+    pub messages: usize,
+    pub first_message: Option<usize>,
+    pub last_message: Option<usize>,
+    /// Occurrences inside file reads (`Read`, `Grep`, `Glob` calls and their results)
+    pub read_occurrences: usize,
+}
+
+impl MatchSummary {
+    /// The terms only turn up where the session opened or searched files
+    fn only_in_reads(&self) -> bool {
+        self.occurrences == 0 && self.read_occurrences > 0
+    }
+}
+
+/// A session's messages matching the search terms, with the messages around them
+#[derive(Debug, Serialize)]
+pub struct TimelineExtraction {
+    pub session_id: String,
+    pub session_path: PathBuf,
+    pub query_term: String,
+    pub search_terms: Vec<String>,
+    pub total_matches: usize,
+    pub timeline: Vec<TimelineEntry>,
+    pub parse_diagnostics: ParseDiagnostics,
+    /// When each message in the session was sent, for `--density`
+    #[serde(skip)]
+    message_times: Vec<Option<DateTime<Utc>>>,
+    /// Entries are ordered by score (`--top`) rather than by time
+    #[serde(skip)]
+    ranked: bool,
+}
+
+/// A matching message in a timeline
+#[derive(Debug, Serialize)]
+pub struct TimelineEntry {
+    pub message_index: usize,
+    pub line_number: usize,
+    pub timestamp: Option<String>,
+    pub role: String,
+    pub classified_content: ClassifiedContent,
+    pub match_score: MatchScore,
+    /// Part of a turn the user interrupted or whose tool call they rejected
+    pub interrupted: bool,
+    /// How the Bash commands the message runs or reports on ended
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_outcome: Option<CommandOutcome>,
+    pub context_before: Vec<ContextMessage>,
+    pub context_after: Vec<ContextMessage>,
+}
+
+/// A message shown around a timeline entry
+#[derive(Debug, Serialize)]
+pub struct ContextMessage {
+    pub message_index: usize,
+    pub timestamp: Option<String>,
+    /// Role and the start of the message's text
+    pub summary: String,
+}
+
+/// How well a message matches the search terms
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchScore {
+    pub score: f64,
+    pub term_hits: usize,
+    pub terms_matched: usize,
+    pub terms_total: usize,
+    /// Shortest span (in bytes) containing every matched term, when more than one term matched
+    pub proximity_span: Option<usize>,
+}
+
+/// Runs the command line, printing any error, and returns the process's exit status.
+pub fn run_cli() -> i32 {
+    match run() {
+        Ok(true) => EXIT_FOUND,
+        Ok(false) => EXIT_NOT_FOUND,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            exit_code(&e)
+        }
+    }
+}
+
+/// Runs the requested command, returning whether it found anything.
+fn run() -> anyhow::Result<bool> {
+    complete_from_env(build_cli);
+    let config = Config::load()?;
+    let matches = build_cli().get_matches_from(expand_aliases(std::env::args_os(), &config.aliases)?);
+    let subcommand = matches.subcommand();
+    init_logging(subcommand.map(|(_, sub)| sub).unwrap_or(&matches).get_count("verbose"));
+    // Directory defaults are for commands that look through sessions; the rest never read them
+    let local_config = match subcommand {
+        Some((name, _)) if !SESSION_SUBCOMMANDS.contains(&name) => LocalConfig::default(),
+        _ => LocalConfig::for_current_dir(),
+    };
+    install_error_rules(&config.error_rules)?;
+    set_project_roots(&config.project_roots);
+    let cx = Invocation::new(&config, &matches, &local_config)?;
+
+    let Some((name, sub_matches)) = subcommand else {
+        return run_top_level(&cx);
+    };
+    if !FORMAT_SUBCOMMANDS.contains(&name) {
+        check_format(cx.format, false, name == "timeline" && sub_matches.contains_id("output"))?;
+    }
+    match name {
+        "export" | "sync-notes" => run_export_command(&cx, name, sub_matches)?,
+        "stats" => run_stats_command(&cx, sub_matches)?,
+        "files" => {
+            let session_refs = values(sub_matches, "sessions");
+            let format = StatsFormat::from_name(cx.format.unwrap_or("text"))?;
+            run_files_report(&session_refs, cx.project_filter, cx.recent_days, format)?;
+        }
+        "touched" => {
+            let path = sub_matches.get_one::<String>("path").unwrap();
+            run_touched(path, cx.project_filter, cx.recent_days, cx.render)?;
+        }
+        "chunks" => {
+            let mut session_refs = values(sub_matches, "sessions");
+            session_refs.extend(&cx.input_files);
+            let options = ChunkOptions {
+                max_chars: *sub_matches.get_one::<usize>("chunk_size").unwrap(),
+                overlap: *sub_matches.get_one::<usize>("overlap").unwrap(),
+                render: cx.render,
+            };
+            run_chunks(&session_refs, cx.project_filter, cx.recent_days, options)?;
+        }
+        "trend" => run_trend(
+            &values(sub_matches, "terms"),
+            cx.project_filter,
+            parse_since(sub_matches.get_one::<String>("since").unwrap())?,
+            TrendPeriod::from_name(sub_matches.get_one::<String>("by").unwrap())?,
+            sub_matches.get_flag("sparkline"),
+            StatsFormat::from_name(cx.format.unwrap_or("text"))?,
+            &cx.search_options,
+        )?,
+        "grep" => {
+            let role = sub_matches.get_one::<String>("role").map(|s| s.as_str());
+            let terms = values(sub_matches, "terms");
+            let (project_filter, recent_days) = (cx.project_filter, cx.recent_days);
+            let options = &cx.search_options;
+            return Ok(run_grep(&terms, &cx.input_files, project_filter, recent_days, role, &cx.time_range, options)?);
+        }
+        "bench" => {
+            let runs = *sub_matches.get_one::<usize>("runs").unwrap();
+            let terms = values(sub_matches, "terms");
+            run_bench(&terms, cx.project_filter, cx.recent_days, cx.limit, runs, &cx.search_options)?;
+        }
+        "daemon" => run_daemon()?,
+        "new" => run_new(sub_matches.get_flag("since_last_run"), cx.project_filter, cx.recent_days, cx.render)?,
+        "digest" => {
+            let date = parse_digest_date(sub_matches.get_one::<String>("date").unwrap())?;
+            let dir = sub_matches.get_one::<String>("dir").map(|s| s.as_str());
+            let save_dir = if sub_matches.get_flag("save") { Some(digest_dir(dir)?) } else { None };
+            run_digest(date, cx.project_filter, save_dir.as_deref(), cx.render)?;
+        }
+        "schedule" => match sub_matches.subcommand() {
+            Some(("install", install_matches)) => install_schedule(
+                parse_daily_time(install_matches.get_one::<String>("daily").unwrap())?,
+                install_matches.get_one::<String>("dir").map(|s| s.as_str()),
+            )?,
+            _ => remove_schedule()?,
+        },
+        "tag" => run_tag(sub_matches.get_one::<String>("session").unwrap(), &values(sub_matches, "tags"))?,
+        "bookmark" => run_bookmark(
+            sub_matches.get_one::<String>("session").unwrap(),
+            sub_matches.get_one::<usize>("message").copied(),
+            sub_matches.contains_id("label").then(|| values(sub_matches, "label").join(" ")),
+            sub_matches.get_flag("remove"),
+        )?,
+        "note" => run_note(
+            sub_matches.get_one::<String>("session").unwrap(),
+            sub_matches.get_one::<usize>("message").copied(),
+            sub_matches.contains_id("text").then(|| values(sub_matches, "text").join(" ")),
+            sub_matches.get_one::<usize>("remove").copied(),
+        )?,
+        "backup" => match sub_matches.subcommand() {
+            Some(("verify", verify_matches)) => {
+                let dir = Path::new(verify_matches.get_one::<String>("dir").unwrap());
+                return Ok(run_backup_verify(dir, cx.render)?);
+            }
+            _ => run_backup_manifest()?,
+        },
+        "undo" => run_undo(sub_matches.get_one::<String>("operation").map(|s| s.as_str()))?,
+        "prune" => {
+            let older_than = *sub_matches.get_one::<i64>("older_than").unwrap();
+            return Ok(run_prune(older_than, cx.project_filter, sub_matches.get_flag("dry_run"))?);
+        }
+        "import" => run_import(&values(sub_matches, "paths"), sub_matches.get_one::<String>("source").unwrap())?,
+        "sync" => run_sync_command(&cx, sub_matches)?,
+        "show" | "pack" | "drift" | "replay" => return run_session_command(&cx, name, sub_matches),
+        "timeline" | "code-diff" => {
+            let (session_path, terms) = select_session(sub_matches, cx.project_filter, &cx.input_files)?;
+            let session_path = session_path.to_string_lossy();
+            return if name == "timeline" {
+                let output = sub_matches.get_one::<String>("output").map(Path::new);
+                run_timeline(&cx, &session_path, &terms, output)
+            } else {
+                run_code_diff(&cx, &session_path, &terms)
+            };
+        }
+        "lsp-like" => run_rpc_server()?,
+        "init" => print!("{}", shell_init_script(sub_matches.get_one::<String>("shell").unwrap())?),
+        "resume" => {
+            let query = values(sub_matches, "query");
+            run_resume(&query, cx.project_filter, cx.recent_days, sub_matches.get_flag("exec"), cx.copy)?;
+        }
+        _ => unreachable!("build_cli has no {} subcommand", name),
+    }
+    Ok(true)
+}
+
+/// Subcommands that look through sessions, and so take their defaults from `.session-finder.toml`
+const SESSION_SUBCOMMANDS: &[&str] = &[
+    "export", "sync-notes", "stats", "files", "touched", "chunks", "trend", "grep", "bench", "new", "digest", "prune",
+    "sync", "show", "pack", "drift", "replay", "resume", "timeline", "code-diff",
+];
+
+/// Subcommands that check `--format` themselves
+const FORMAT_SUBCOMMANDS: &[&str] =
+    &["export", "sync-notes", "stats", "files", "touched", "chunks", "trend", "grep", "bench"];
+
+/// What commands take from the command line, the config file, and `.session-finder.toml`
+struct Invocation<'a> {
+    config: &'a Config,
+    matches: &'a ArgMatches,
+    /// The subcommand's matches, or the top level's without one; shared options are global,
+    /// so either carries them
+    options: &'a ArgMatches,
+    local_config: &'a LocalConfig,
+    project_filter: Option<&'a String>,
+    recent_days: Option<i64>,
+    limit: usize,
+    format: Option<&'a str>,
+    input_files: Vec<&'a str>,
+    context_size: usize,
+    top_entries: Option<usize>,
+    edit_entry: Option<usize>,
+    time_range: TimeRange,
+    tool_filters: Vec<ToolFilter>,
+    search_options: SearchOptions,
+    render: RenderOptions,
+    copy: bool,
+    quiet: bool,
+    verbose: bool,
+}
+
+impl<'a> Invocation<'a> {
+    fn new(config: &'a Config, matches: &'a ArgMatches, local_config: &'a LocalConfig) -> anyhow::Result<Self> {
+        let options = matches.subcommand().map(|(_, sub)| sub).unwrap_or(matches);
+        let project_filter = options
+            .get_one::<String>("project")
+            .or(local_config.project.as_ref().filter(|_| !matches.get_flag("global")));
+        let time_range = TimeRange::parse(
+            options.get_one::<String>("after").map(|s| s.as_str()),
+            options.get_one::<String>("before").map(|s| s.as_str()),
+        )?;
+        let tool_filters = options.get_many::<String>("tool")
+            .map(|vals| vals.map(|s| ToolFilter::parse(s)).collect::<Result<_>>())
+            .transpose()?
+            .unwrap_or_default();
+
+        let mut search_options =
+            SearchOptions::from_config(config, !options.get_flag("no_ignore"), !options.get_flag("no_self_filter"));
+        if let Some(sort) = matches.get_one::<String>("sort").or(local_config.sort.as_ref()) {
+            search_options.sort = SortOrder::from_name(sort)?;
+        }
+        search_options.stopwords = local_config.stopword_set();
+        search_options.explain_plan = matches.get_flag("explain_plan");
+        if let Some(max_memory) = options.get_one::<String>("max_memory").or(config.max_memory.as_ref()) {
+            search_options.memory_budget = Some(MemoryBudget::parse(max_memory)?);
+        }
+        if let Some(budget) = matches.get_one::<String>("budget") {
+            search_options.time_budget = Some(TimeBudget::starting_now(TimeBudget::parse(budget)?));
+        }
+        if options.get_flag("in_code") {
+            search_options.scope = MatchScope::Code;
+        } else if options.get_flag("in_prose") {
+            search_options.scope = MatchScope::Prose;
+        }
+        if let Some(lang) = options.get_one::<String>("lang") {
+            let analyzer = analyzer_named(lang).ok_or_else(|| {
+                let known: Vec<&str> = language_analyzers().iter().map(|analyzer| analyzer.name()).collect();
+                anyhow!("Unknown language {:?} for --lang; known languages: {}", lang, known.join(", "))
+            })?;
+            search_options.language = Some(analyzer);
+            // Only code has a language, so matches are confined to it
+            search_options.scope = MatchScope::Code;
+        }
+        if let Some(types) = options.get_many::<String>("types") {
+            let types: Vec<&'static str> =
+                types.filter_map(|t| CONTENT_TYPE_NAMES.iter().copied().find(|name| name == t)).collect();
+            search_options.content_types = Some(types);
+        }
+        if let Some(roles) = options.get_many::<String>("preview_roles") {
+            search_options.preview.roles = roles.filter_map(|role| PreviewRole::from_name(role)).collect();
+        }
+        let width = options.get_one::<String>("width").map(|s| s.parse::<usize>()).transpose()?;
+        let render = RenderOptions::for_stdout(options.get_flag("ascii") || config.ascii, width);
+        search_options.render = render;
+        if let Some(count) = options.get_one::<String>("preview").map(|s| s.parse::<usize>()).transpose()? {
+            search_options.preview.count = count;
+        }
+        if let Some(width) = options.get_one::<String>("preview_width").map(|s| s.parse::<usize>()).transpose()? {
+            search_options.preview.width = width;
+        }
+        if let Some(count) = matches.get_one::<String>("common_terms").map(|s| s.parse::<usize>()).transpose()? {
+            search_options.preview.common_terms = count;
+        }
+
+        Ok(Invocation {
+            config,
+            matches,
+            options,
+            local_config,
+            project_filter,
+            recent_days: options.get_one::<String>("recent").map(|s| s.parse::<i64>()).transpose()?,
+            limit: options.get_one::<String>("limit").unwrap().parse()?,
+            format: options.get_one::<String>("format").map(|s| s.as_str()),
+            input_files: values(options, "file"),
+            context_size: options.get_one::<String>("context").unwrap().parse()?,
+            top_entries: options.get_one::<String>("top").map(|s| s.parse::<usize>()).transpose()?,
+            edit_entry: options.get_one::<String>("edit").map(|s| s.parse::<usize>()).transpose()?,
+            time_range,
+            tool_filters,
+            search_options,
+            render,
+            copy: options.get_flag("copy"),
+            quiet: options.get_flag("quiet"),
+            verbose: options.get_count("verbose") > 0,
+        })
+    }
+}
+
+/// The values of a multi-valued argument, or none.
+fn values<'a>(matches: &'a ArgMatches, id: &str) -> Vec<&'a str> {
+    matches.get_many::<String>(id).map(|vals| vals.map(|s| s.as_str()).collect()).unwrap_or_default()
+}
+
+/// Rejects a `--format` the command can't write: `json` is for search results, and `json`,
+/// `markdown`, and `html` are for timelines written with `--output`.
+fn check_format(format: Option<&str>, search: bool, timeline_output: bool) -> anyhow::Result<()> {
+    match format {
+        Some(format @ ("obsidian" | "org" | "mermaid" | "dot" | "sqlite" | "csv" | "tsv")) => {
+            let commands = match format {
+                "csv" | "tsv" => "stats, files, and trend",
+                "mermaid" | "dot" | "sqlite" => "export",
+                _ => "export and sync-notes",
+            };
+            Err(anyhow!("--format {} is only supported by {}", format, commands))
+        }
+        Some(format @ ("markdown" | "html")) if !timeline_output => {
+            Err(anyhow!("--format {} is only supported by timeline --output", format))
+        }
+        Some("json") if !search && !timeline_output => {
+            Err(anyhow!("--format json is only supported by search and timeline --output"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runs a command line without a subcommand: a search, or the legacy `--timeline` and
+/// `--code-diff` flags, which take the search terms as theirs.
+fn run_top_level(cx: &Invocation) -> anyhow::Result<bool> {
+    let search_terms = values(cx.matches, "query");
+    if let Some(session) = cx.matches.get_one::<String>("timeline") {
+        check_format(cx.format, false, false)?;
+        run_timeline(cx, session, &search_terms, None)
+    } else if let Some(session) = cx.matches.get_one::<String>("code_diff") {
+        check_format(cx.format, false, false)?;
+        run_code_diff(cx, session, &search_terms)
+    } else {
+        check_format(cx.format, true, false)?;
+        run_search(cx, &search_terms)
+    }
+}
+
+/// `export` and `sync-notes`: notes, graphs, or a database of the sessions given or found.
+fn run_export_command(cx: &Invocation, name: &str, sub_matches: &ArgMatches) -> anyhow::Result<()> {
+    let session_refs = values(sub_matches, "sessions");
+    let (project_filter, recent_days) = (cx.project_filter, cx.recent_days);
+    if name == "export" && matches!(cx.format, Some("mermaid" | "dot")) {
+        let format = GraphFormat::from_name(cx.format.unwrap())?;
+        export_graph(&session_refs, project_filter, recent_days, format, cx.render)?;
+    } else if name == "export" && cx.format == Some("sqlite") {
+        let output = sub_matches
+            .get_one::<String>("output")
+            .ok_or_else(|| anyhow!("export --format sqlite needs --output FILE for the database"))?;
+        export_sqlite(&session_refs, project_filter, recent_days, Path::new(output))?;
+    } else {
+        export_notes(
+            &session_refs,
+            project_filter,
+            recent_days,
+            &cx.search_options,
+            NoteFormat::from_name(cx.format.unwrap_or("obsidian"))?,
+            Path::new(sub_matches.get_one::<String>("dir").unwrap()),
+            name == "sync-notes",
+        )?;
+    }
+    Ok(())
+}
+
+/// `stats`: message counts and costs, tool calls with `--tools`, or word counts with `--terms`.
+fn run_stats_command(cx: &Invocation, sub_matches: &ArgMatches) -> anyhow::Result<()> {
+    let grouping = StatsGrouping::from_name(sub_matches.get_one::<String>("by").unwrap())?;
+    if sub_matches.get_flag("terms") {
+        let options = TermOptions {
+            stopped: sub_matches.get_flag("stopped"),
+            stopwords: cx.local_config.stopword_set(),
+            min_count: *sub_matches.get_one::<usize>("min_count").unwrap(),
+        };
+        run_term_stats(cx.project_filter, cx.recent_days, grouping, options, cx.format)?;
+        return Ok(());
+    }
+    let format = StatsFormat::from_name(cx.format.unwrap_or("text"))?;
+    if sub_matches.get_flag("tools") {
+        run_tool_stats(cx.project_filter, cx.recent_days, grouping, format)?;
+    } else {
+        run_stats(cx.project_filter, cx.recent_days, grouping, format)?;
+    }
+    Ok(())
+}
+
+/// `sync push`, `sync pull`, and `sync keygen`.
+fn run_sync_command(cx: &Invocation, sub_matches: &ArgMatches) -> anyhow::Result<()> {
+    let sync_user = |action_matches: &ArgMatches| match action_matches.get_one::<String>("user") {
+        Some(user) => Ok(user.clone()),
+        None => std::env::var("USER").map_err(|_| anyhow!("Could not determine your user name; pass --user")),
+    };
+    let sync_machine = |action_matches: &ArgMatches| {
+        action_matches.get_one::<String>("machine").or(cx.config.machine.as_ref()).cloned()
+    };
+    match sub_matches.subcommand() {
+        Some(("push", push_matches)) => {
+            let options = PushOptions {
+                redact: !push_matches.get_flag("no-redact"),
+                compress: push_matches.get_flag("compress"),
+                encrypt: push_matches.get_flag("encrypt"),
+            };
+            let remote = push_matches.get_one::<String>("remote").unwrap();
+            let owner = owner_dir(&sync_user(push_matches)?, sync_machine(push_matches).as_deref());
+            sync_push(remote, &owner, cx.project_filter.map(|s| s.as_str()), &options)?;
+        }
+        Some(("pull", pull_matches)) => {
+            let remote = pull_matches.get_one::<String>("remote").unwrap();
+            sync_pull(remote, &sync_user(pull_matches)?, sync_machine(pull_matches).as_deref())?;
+        }
+        _ => generate_key_file()?,
+    }
+    Ok(())
+}
+
+/// `show`, `pack`, `drift`, and `replay`, which each take one session and no search terms.
+fn run_session_command(cx: &Invocation, name: &str, sub_matches: &ArgMatches) -> anyhow::Result<bool> {
+    let (session_path, terms) = select_session(sub_matches, cx.project_filter, &cx.input_files)?;
+    if !terms.is_empty() {
+        return Err(anyhow!("{} takes no search terms: {}", name, terms.join(" ")));
+    }
+    let session_path = session_path.to_string_lossy();
+    match name {
+        "show" => {
+            let around: usize = sub_matches.get_one::<String>("around").unwrap().parse()?;
+            show_messages(&session_path, around, cx.context_size, cx.edit_entry.is_some(), cx.render)?;
+        }
+        "pack" => {
+            let output = sub_matches.get_one::<String>("output").map(Path::new);
+            run_pack(&session_path, output, cx.copy, cx.render)?;
+        }
+        "drift" => return Ok(run_drift(&session_path, cx.render)?),
+        _ => run_replay(&session_path, cx.render)?,
+    }
+    Ok(true)
+}
+
+/// Prints, writes to `output`, or opens in the editor a session's messages matching `search_terms`.
+fn run_timeline(
+    cx: &Invocation,
+    session_path: &str,
+    search_terms: &[&str],
+    output: Option<&Path>,
+) -> anyhow::Result<bool> {
+    let options = cx.options;
+    let full_content = options.get_flag("full_content");
+    let mut timeline = extract_timeline(
+        session_path,
+        &TimelineSearch {
+            search_terms,
+            context_size: cx.context_size,
+            top_entries: cx.top_entries,
+            tool_filters: &cx.tool_filters,
+            exclude_interrupted: options.get_flag("exclude_interrupted"),
+            time_range: &cx.time_range,
+            options: &cx.search_options,
+        },
+    )?;
+    if options.get_flag("failed_commands") {
+        timeline.timeline.retain(|entry| entry.command_outcome.is_some_and(CommandOutcome::failed));
+    }
+    if options.get_flag("complete_code_only") {
+        timeline.timeline.retain(|entry| {
+            matches!(&entry.classified_content.content_type, ContentType::CodeBlock(info) if info.is_complete)
+        });
+    }
+    if cx.verbose {
+        eprintln!("Parse diagnostics for {}: {}", timeline.session_id, timeline.parse_diagnostics.summary(cx.render));
+    }
+    let found = !timeline.timeline.is_empty();
+    match (cx.edit_entry, output) {
+        // Nothing matched, so there is no line to open; the exit status says so
+        (Some(_), _) if !found => {}
+        (Some(nth), _) => {
+            let entry = nth.checked_sub(1).and_then(|index| timeline.timeline.get(index)).ok_or_else(|| {
+                anyhow!("--edit {}: the timeline only has {} entries", nth, timeline.timeline.len())
+            })?;
+            open_in_editor(&timeline.session_path, entry.line_number)?;
+        }
+        (None, Some(path)) => {
+            write_timeline(&timeline, path, TimelineFormat::for_output(path, cx.format)?, full_content, cx.render)?;
+        }
+        (None, None) if cx.quiet => {}
+        (None, None) if cx.format == Some("quickfix") => display_timeline_quickfix(&timeline),
+        (None, None) => {
+            if options.get_flag("density") {
+                display_density(&timeline, cx.render);
+            }
+            display_timeline(&timeline, full_content, cx.render)?;
+        }
+    }
+    if cx.copy {
+        if let Some(excerpt) = first_entry_excerpt(&timeline, full_content) {
+            copy_to_clipboard(&excerpt, "matched excerpt")?;
+        }
+    }
+    Ok(found)
+}
+
+/// Prints the code a session wrote or ran around messages matching `search_terms`.
+fn run_code_diff(cx: &Invocation, session_path: &str, search_terms: &[&str]) -> anyhow::Result<bool> {
+    let options = cx.options;
+    let mut selection = match options.get_many::<String>("tools") {
+        Some(tools) => CodeDiffSelection::only(&tools.map(|s| s.as_str()).collect::<Vec<_>>()),
+        None => CodeDiffSelection::all(),
+    };
+    if options.get_flag("no_bash") {
+        selection = selection.without("Bash");
+    }
+    let mut code_diff_timeline = extract_code_diff_timeline(
+        session_path,
+        search_terms,
+        cx.context_size,
+        &selection,
+        options.get_flag("exclude_interrupted"),
+        &cx.time_range,
+        cx.render,
+    )?;
+    if options.get_flag("complete_code_only") {
+        code_diff_timeline.code_changes.retain(|entry| entry.complete);
+    }
+    if options.get_flag("failed_commands") {
+        code_diff_timeline.code_changes.retain(|entry| entry.command_outcome.is_some_and(CommandOutcome::failed));
+    }
+    code_diff_timeline.code_changes.retain(|entry| cx.search_options.language_selected(entry.language.as_deref()));
+    if cx.verbose {
+        eprintln!(
+            "Parse diagnostics for {}: {}",
+            code_diff_timeline.session_id,
+            code_diff_timeline.parse_diagnostics.summary(cx.render)
+        );
+    }
+    if !cx.quiet {
+        if cx.format == Some("quickfix") {
+            display_code_diff_quickfix(&code_diff_timeline);
+        } else {
+            display_code_diff_timeline(&code_diff_timeline, cx.render)?;
+        }
+    }
+    if cx.copy {
+        if let Some(entry) = code_diff_timeline.code_changes.first() {
+            copy_to_clipboard(&entry.code_content, "code change")?;
+        }
+    }
+    Ok(!code_diff_timeline.code_changes.is_empty())
+}
+
+/// Finds, ranks, and prints the sessions matching `search_terms` and the filters given.
+fn run_search(cx: &Invocation, search_terms: &[&str]) -> anyhow::Result<bool> {
+    let (matches, option_matches, config) = (cx.matches, cx.options, cx.config);
+    let (project_filter, recent_days, limit, format) = (cx.project_filter, cx.recent_days, cx.limit, cx.format);
+    let input_files = &cx.input_files;
+    let tool_filters = &cx.tool_filters;
+    let render = cx.render;
+    let mut search_options = cx.search_options.clone();
+    let tag_filter: Vec<&str> = option_matches.get_many::<String>("tag")
+        .map(|vals| vals.map(|s| s.as_str()).collect())
+        .unwrap_or_else(|| cx.local_config.tags.iter().map(|s| s.as_str()).collect());
+    let exclude_reads = matches.get_flag("exclude_reads");
+    let exclude_active = matches.get_flag("exclude_active");
+    let active_only = matches.get_flag("active_only");
+    let stream = matches.get_flag("stream");
+    let show_related_terms = matches.get_flag("related_terms");
+    let context_window = config.context_window.unwrap_or(DEFAULT_CONTEXT_WINDOW);
+    let min_matches = matches.get_one::<String>("min_matches").map(|s| s.parse::<usize>()).transpose()?;
+    let grouping = matches.get_one::<String>("group_by").map(|s| ResultGrouping::from_name(s)).transpose()?;
+    let quickfix = format == Some("quickfix");
+
+    if search_terms.is_empty() && tag_filter.is_empty() {
+        return Err(anyhow!("Search terms are required for regular search mode"));
+    }
+    // `command:/review` scopes the search to sessions that ran /review; alone, it finds them all
+    let (command_scopes, search_terms) = split_command_scopes(search_terms);
+    let command_markers: Vec<String> = command_scopes.iter().map(|command| command_marker(command)).collect();
+    let search_terms: Vec<&str> = if search_terms.is_empty() {
+        command_markers.iter().map(|marker| marker.as_str()).collect()
+    } else {
+        search_terms
+    };
+    // Inside a repository, searches are about it unless told otherwise
+    if project_filter.is_none() && input_files.is_empty() && !matches.get_flag("global") {
+        if let Some(root) = std::env::current_dir().ok().and_then(|dir| repository_root(&dir)) {
+            search_options.workspace = Some(root);
+        }
+    }
+    let store = Store::load()?;
+    if stream && matches!(format, Some("json" | "quickfix")) {
+        return Err(anyhow!("--stream prints text results only"));
+    }
+    if show_related_terms && quickfix {
+        return Err(anyhow!("--related-terms prints text or JSON only"));
+    }
+    if matches.contains_id("baseline") && quickfix {
+        return Err(anyhow!("--baseline prints text or JSON only"));
+    }
+    let explaining = search_options.explain_plan;
+    if explaining {
+        if let Some(path) = &cx.local_config.path {
+            explain(explaining, &format!("use defaults from {}", path.display()));
+        }
+        let per_group = if grouping.is_some() { " per group" } else { "" };
+        explain(explaining, &format!("show at most {} sessions{}", limit, per_group));
+        if let Some(budget) = &search_options.time_budget {
+            explain(explaining, &format!("finish within {:?}, newest sessions analysed first", budget.limit()));
+        }
+        if let Some(rules) = search_options.ignore.describe() {
+            explain(explaining, &format!("ignore {}", rules));
+        }
+        if !search_options.exclusions.is_empty() {
+            explain(explaining, &format!("skip messages mentioning {}", config.exclude_terms.join(", ")));
+        }
+        match search_options.scope {
+            MatchScope::All => {}
+            MatchScope::Code => explain(explaining, "count matches in code only"),
+            MatchScope::Prose => explain(explaining, "count matches in prose only"),
+        }
+        if let Some(types) = option_matches.get_many::<String>("types") {
+            let types: Vec<&str> = types.map(|t| t.as_str()).collect();
+            explain(explaining, &format!("count matches only in messages classified as {}", types.join(", ")));
+        }
+    }
+    // Filters applied to analysed sessions, in order, with their --explain-plan stages
+    let mut session_filters: Vec<SessionFilter> = Vec::new();
+    if !tag_filter.is_empty() {
+        session_filters.push((
+            format!("--tag {}", tag_filter.join(",")),
+            Box::new(|session| store.has_tags(&session.session_id, &tag_filter)),
+        ));
+    }
+    if exclude_reads {
+        session_filters.push(("--exclude-reads".to_string(), Box::new(|session| !session.matches.only_in_reads())));
+    }
+    if exclude_active {
+        session_filters.push(("--exclude-active".to_string(), Box::new(|session| !session.active)));
+    }
+    if active_only {
+        session_filters.push(("--active-only".to_string(), Box::new(|session| session.active)));
+    }
+    if let Some(min_matches) = min_matches {
+        session_filters.push((
+            format!("--min-matches {}", min_matches),
+            Box::new(move |session| session.matches.occurrences >= min_matches),
+        ));
+    }
+    // ripgrep found the terms somewhere in the file, maybe only outside the scope
+    if search_options.scope != MatchScope::All || search_options.restricts_content_types() {
+        session_filters.push(("matches in scope".to_string(), Box::new(|session| session.matches.occurrences > 0)));
+    }
+    if !command_scopes.is_empty() {
+        session_filters.push((
+            format!("command:{}", command_scopes.join(",")),
+            Box::new(|session| {
+                command_scopes
+                    .iter()
+                    .all(|command| session.slash_commands.iter().any(|(used, _)| used == command))
+            }),
+        ));
+    }
+    let passes_filters = |session: &SessionInfo| -> Result<bool> {
+        Ok(session_filters.iter().all(|(_, keep)| keep(session))
+            && (tool_filters.is_empty() || session_uses_tool(&session.path, tool_filters)?))
+    };
+    // The filters that need no analysis also run on every file ripgrep finds, so they
+    // don't cut into the shortlist
+    let passes_file_filters = |path: &Path| -> bool {
+        (tag_filter.is_empty() || extract_session_id(path).is_ok_and(|id| store.has_tags(&id, &tag_filter)))
+            && (!exclude_active || !is_active(path))
+            && (!active_only || is_active(path))
+    };
+    let cheap_filters = !tag_filter.is_empty() || exclude_active || active_only;
+    let mut session_index = SessionIndex::load()?;
+    // Under --stream, each session is printed once it's analysed and passes the filters
+    let mut streamed: HashSet<String> = HashSet::new();
+    let mut stream_session = |session: &SessionInfo| -> Result<bool> {
+        if streamed.len() < limit && !streamed.contains(&session.session_id) && passes_filters(session)? {
+            if streamed.is_empty() {
+                println!("Streaming results as sessions are analysed:\n");
+            }
+            streamed.insert(session.session_id.clone());
+            let mut session = session.clone();
+            note_growth(std::slice::from_mut(&mut session), &mut session_index);
+            display_session(streamed.len(), &session, &store, context_window, render);
+        }
+        Ok(streamed.len() < limit)
+    };
+
+    let sessions = if search_terms.is_empty() {
+        // Tags alone list every session carrying them
+        let tagged = store.sessions_with_tags(&tag_filter);
+        let tagged: Vec<&str> = tagged.iter().map(|id| id.as_str()).collect();
+        if tagged.is_empty() { Vec::new() } else { select_sessions(&tagged, &[], project_filter, recent_days, &search_options)? }
+    } else {
+        let mut sessions = if input_files.is_empty() {
+            // A running daemon answers from its warm index, unless this search changes what
+            // counts as a match or has budgets of its own
+            let group_by = matches.get_one::<String>("group_by").map(|s| s.as_str());
+            let daemon_options = SearchOptions::from_config(config, true, true);
+            let from_daemon = if search_options.scope == MatchScope::All
+                && !search_options.restricts_content_types()
+                && !option_matches.get_flag("no_self_filter")
+                && !explaining
+                && search_options.workspace.is_none()
+                && search_options.stopwords.is_empty()
+                && search_options.preview == daemon_options.preview
+                && search_options.render.ascii == daemon_options.render.ascii
+                && session_filters.is_empty()
+                && tool_filters.is_empty()
+                && search_options.time_budget.is_none()
+                // The daemon applies the config file's max_memory itself
+                && option_matches.get_one::<String>("max_memory").is_none()
+            {
+                let (sort, no_ignore) = (search_options.sort, option_matches.get_flag("no_ignore"));
+                match query_daemon(&search_terms, project_filter, recent_days, limit, group_by, sort, no_ignore) {
+                    Ok(found) => found,
+                    // A daemon that can't answer is no reason to fail the search
+                    Err(e) if is_backend_failure(&e, "daemon") => {
+                        warn!("{:#}; searching here instead", e);
+                        None
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            } else {
+                None
+            };
+            let search = SessionSearch {
+                search_terms: &search_terms,
+                project_filter,
+                recent_days,
+                limit,
+                grouping,
+                options: &search_options,
+                file_filter: cheap_filters.then_some(&passes_file_filters as &PathCheck),
+                session_filter: Some(&passes_filters),
+            };
+            match from_daemon {
+                Some(sessions) => sessions,
+                None if stream => find_sessions_streaming(&search, &mut stream_session)?,
+                None => find_sessions_streaming(&search, &mut |_| Ok(true))?,
+            }
+        } else {
+            find_sessions_in_files(input_files, &search_terms, project_filter, recent_days, &search_options)?
+        };
+        // Sessions whose notes mention a term count as matches even if the transcript doesn't
+        let noted: Vec<String> = store.sessions_with_notes_matching(&search_terms)
+            .into_iter()
+            .filter(|id| !sessions.iter().any(|session| &session.session_id == id))
+            .collect();
+        if !noted.is_empty() && input_files.is_empty() {
+            let noted: Vec<&str> = noted.iter().map(|id| id.as_str()).collect();
+            sessions.extend(select_sessions(&noted, &search_terms, project_filter, recent_days, &search_options)?);
+            explain(explaining, &format!("add {} session(s) whose notes mention the terms", noted.len()));
+        }
+        for (stage, keep) in &session_filters {
+            retain_explained(&mut sessions, explaining, stage, |session| keep(session));
+        }
+        if !tool_filters.is_empty() {
+            let before = sessions.len();
+            let mut using_tool = Vec::new();
+            for session in sessions {
+                if session_uses_tool(&session.path, tool_filters)? {
+                    using_tool.push(session);
+                }
+            }
+            sessions = using_tool;
+            explain_stage(explaining, "--tool", before, sessions.len());
+        }
+        sessions
+    };
+    if stream {
+        // Sessions that didn't come from the analysis workers: the daemon's, --file's, noted ones
+        for session in &sessions {
+            stream_session(session)?;
+        }
+    }
+    if cx.verbose {
+        report_parse_diagnostics(&sessions, render);
+    }
+    let sessions_count = sessions.len();
+    let (mut top_sessions, groups) = match grouping {
+        Some(grouping) => {
+            let (sessions, groups) = group_sessions(sessions, grouping, limit, search_options.sort);
+            (sessions, Some(groups))
+        }
+        None => (rank_and_limit_sessions(sessions, limit, search_options.sort), None),
+    };
+    explain_stage(explaining, "--limit", sessions_count, top_sessions.len());
+    note_growth(&mut top_sessions, &mut session_index);
+    session_index.save()?;
+    let mut found = !top_sessions.is_empty();
+    if stream {
+        found = !streamed.is_empty();
+        let sorted = matches.contains_id("sort") || cx.local_config.sort.is_some();
+        let sort = sorted.then_some(search_options.sort);
+        display_stream_end(&streamed, &top_sessions, sort, search_options.workspace.as_deref());
+    } else if !cx.quiet {
+        if show_related_terms {
+            let related = related_terms(&top_sessions, &search_terms, &search_options)?;
+            if format == Some("json") {
+                println!("{}", serde_json::to_string_pretty(&related)?);
+            } else {
+                display_related_terms(&related, top_sessions.len());
+            }
+        } else if let Some(baseline) = matches.get_one::<String>("baseline") {
+            let comparison = compare_with_baseline(Path::new(baseline), &top_sessions)?;
+            if format == Some("json") {
+                println!("{}", serde_json::to_string_pretty(&comparison)?);
+            } else {
+                display_baseline_comparison(&comparison, render);
+            }
+        } else if format == Some("json") {
+            println!("{}", serde_json::to_string_pretty(&top_sessions)?);
+        } else if quickfix {
+            for session in &top_sessions {
+                let timeline = extract_timeline(
+                    &session.path.to_string_lossy(),
+                    &TimelineSearch {
+                        search_terms: &search_terms,
+                        context_size: 0,
+                        top_entries: None,
+                        tool_filters,
+                        exclude_interrupted: option_matches.get_flag("exclude_interrupted"),
+                        time_range: &cx.time_range,
+                        options: &search_options,
+                    },
+                )?;
+                display_timeline_quickfix(&timeline);
+            }
+        } else {
+            let workspace = search_options.workspace.as_deref();
+            display_results(&top_sessions, groups.as_deref(), context_window, render, workspace)?;
+        }
+    }
+    if cx.copy {
+        if let Some(session) = top_sessions.first() {
+            let command = resume_command(&session_working_dir(&session.path), &session.session_id);
+            copy_to_clipboard(&command, "resume command")?;
+        }
+    }
+    if let Some(budget) = &search_options.time_budget {
+        budget.report();
+    }
+    Ok(found)
+}
+
+fn build_cli() -> Command {
+    Command::new("session-finder")
+        .about("Find and analyze Claude Code sessions")
+        .arg(
+            Arg::new("query")
+                .help("Search terms to find in sessions")
+                .long_help(
+                    "Search terms to find in sessions. A first term that names a subcommand (files, grep, show, \
+                     ...) runs it instead; put -- before the terms to search for it: session-finder -- files foo",
+                )
+                .required(false)
+                .num_args(0..),
+        )
+        .arg(
+            Arg::new("project")
+                .short('p')
+                .long("project")
+                .help("Filter by project path")
+                .value_name("PATH")
+                .global(true),
+        )
+        .arg(
+            Arg::new("limit")
+                .short('l')
+                .long("limit")
+                .help("Maximum number of results to return")
+                .value_name("NUM")
+                .default_value("10")
+                .global(true),
+        )
+        .arg(
+            Arg::new("recent")
+                .short('r')
+                .long("recent")
+                .help("Show only sessions from the last N days")
+                .value_name("DAYS")
+                .global(true),
+        )
+        .arg(
+            Arg::new("min_matches")
+                .long("min-matches")
+                .help("Drop sessions where the search terms occur fewer than N times in total")
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("exclude_reads")
+                .long("exclude-reads")
+                .help("Drop sessions where the terms only appear in Read/Grep/Glob calls and their results")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("common_terms")
+                .long("terms")
+                .help("Common terms listed per session [default: 50]")
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("exclude_active")
+                .long("exclude-active")
+                .help("Drop sessions Claude Code is still writing")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("active_only")
+                .long("active-only")
+                .help("Show only sessions Claude Code is still writing")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("exclude_active"),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .help("Order results by relevance (query topics, then recency), by most recent, or by most matches")
+                .value_name("ORDER")
+                .value_parser(SORT_ORDER_NAMES.to_vec()),
+        )
+        .arg(
+            Arg::new("related_terms")
+                .long("related-terms")
+                .help("Instead of listing the sessions found, report the words most associated with the query in them")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["stream", "baseline", "group_by"]),
+        )
+        .arg(
+            Arg::new("stream")
+                .long("stream")
+                .help("Print each session as soon as it is analysed instead of ranking them all first; with --sort, the ranked list follows")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["group_by", "baseline", "quiet"]),
+        )
+        .arg(
+            Arg::new("budget")
+                .long("budget")
+                .help("Finish the search within DURATION (e.g. 5s), cutting scanning and analysis short and saying what was skipped")
+                .value_name("DURATION"),
+        )
+        .arg(
+            Arg::new("global")
+                .long("global")
+                .help("Search every project, even inside a git repository (where searches otherwise only cover sessions run in it)")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("project"),
+        )
+        .arg(
+            Arg::new("explain_plan")
+                .long("explain-plan")
+                .help("Print to stderr the roots, ripgrep command, and filters a search uses, and how many sessions each stage eliminated")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("baseline")
+                .long("baseline")
+                .help("Report new, vanished, and re-ranked sessions compared with results saved by --format json")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("group_by")
+                .long("group-by")
+                .help("Group search results under a heading per project or per day, applying --limit to each group")
+                .value_name("GROUPING")
+                .value_parser(["project", "day"]),
+        )
+        .arg(
+            Arg::new("timeline")
+                .short('t')
+                .long("timeline")
+                .help("Extract timeline for specific session")
+                .value_name("SESSION_ID_OR_PATH"),
+        )
+        .arg(
+            Arg::new("context")
+                .short('c')
+                .long("context")
+                .help("Number of context messages before/after each match")
+                .value_name("NUM")
+                .default_value("2")
+                .global(true),
+        )
+        .arg(
+            Arg::new("code_diff")
+                .short('d')
+                .long("code-diff")
+                .help("Extract timeline of code diffs for specific session")
+                .value_name("SESSION_ID_OR_PATH"),
+        )
+        .arg(
+            Arg::new("top")
+                .long("top")
+                .help("Show only the N highest-scoring timeline entries")
+                .value_name("N")
+                .global(true),
+        )
+        .arg(
+            Arg::new("tools")
+                .long("tools")
+                .help("Code diff: show only calls to these tools (comma-separated; Edit includes MultiEdit), without code blocks")
+                .value_name("TOOLS")
+                .value_delimiter(',')
+                .value_parser(CODE_DIFF_TOOLS.to_vec())
+                .ignore_case(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("no_bash")
+                .long("no-bash")
+                .help("Code diff: leave out Bash commands")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("edit")
+                .long("edit")
+                .help("Open the session file in $EDITOR at the first timeline entry, or the Nth (show: at --around)")
+                .value_name("N")
+                .num_args(0..=1)
+                .default_missing_value("1")
+                .global(true),
+        )
+        .arg(
+            Arg::new("no_ignore")
+                .long("no-ignore")
+                .help("Include sessions the config file's [ignore] list would leave out of searches")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("no_self_filter")
+                .long("no-self-filter")
+                .help("Count messages mentioning the config file's exclude_terms as matches")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("max_memory")
+                .long("max-memory")
+                .help("Analyse sessions in chunks to stay within SIZE of memory (e.g. 256M), for small machines or huge sessions")
+                .value_name("SIZE")
+                .global(true),
+        )
+        .arg(
+            Arg::new("after")
+                .long("after")
+                .help("Only timeline entries sent at or after TIME (YYYY-MM-DD[ HH:MM[:SS]] UTC, or RFC 3339)")
+                .value_name("TIME")
+                .global(true),
+        )
+        .arg(
+            Arg::new("before")
+                .long("before")
+                .help("Only timeline entries sent before TIME")
+                .value_name("TIME")
+                .global(true),
+        )
+        .arg(
+            Arg::new("density")
+                .long("density")
+                .help("Chart when messages and matches occurred over the session before the timeline")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("complete_code_only")
+                .long("complete-code-only")
+                .help("Timelines: only complete code blocks; code diffs: leave out code blocks that look cut off or partial")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("in_code")
+                .long("in-code")
+                .help("Match query terms only inside fenced code blocks and code written by Edit/Write tools, not prose")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("in_prose")
+                .global(true),
+        )
+        .arg(
+            Arg::new("lang")
+                .long("lang")
+                .value_name("LANGUAGE")
+                .help("Only code in this language (rust, python, ts, ...), by fence tag or edited file's extension; implies --in-code")
+                .conflicts_with("in_prose")
+                .global(true),
+        )
+        .arg(
+            Arg::new("in_prose")
+                .long("in-prose")
+                .help("Match query terms only in discussion text, ignoring code blocks and tool input and output")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("types")
+                .long("types")
+                .value_name("TYPES")
+                .help("Count matches only in messages of these kinds (tool, error, code, discussion, success), comma-separated")
+                .value_delimiter(',')
+                .value_parser(clap::builder::PossibleValuesParser::new(CONTENT_TYPE_NAMES))
+                .global(true),
+        )
+        .arg(
+            Arg::new("preview_roles")
+                .long("preview-roles")
+                .value_name("ROLES")
+                .help("Kinds of message shown as first and last messages (user, assistant, tool), comma-separated [default: user,assistant]")
+                .value_delimiter(',')
+                .value_parser(clap::builder::PossibleValuesParser::new(PREVIEW_ROLE_NAMES))
+                .global(true),
+        )
+        .arg(
+            Arg::new("preview")
+                .long("preview")
+                .visible_alias("preview-count")
+                .value_name("N")
+                .help("Messages shown from each end of a session [default: 8]")
+                .global(true),
+        )
+        .arg(
+            Arg::new("preview_width")
+                .long("preview-width")
+                .value_name("N")
+                .help("Widest message excerpt in previews, in columns [default: 200]")
+                .global(true),
+        )
+        .arg(
+            Arg::new("failed_commands")
+                .long("failed-commands")
+                .help("Only Bash commands that failed (error result or non-zero exit code) in timelines and code diffs")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("exclude_interrupted")
+                .long("exclude-interrupted")
+                .help("Leave turns the user interrupted or whose tool calls they rejected out of timelines")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("full_content")
+                .long("full-content")
+                .help("Show full message content in timelines instead of excerpts around matches")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Output format: text or quickfix (path:line: excerpt per match); json for search results; json, markdown, or html for timeline --output; obsidian, org, mermaid, dot, or sqlite for export; csv or tsv for stats")
+                .value_name("FORMAT")
+                .value_parser(["text", "quickfix", "json", "markdown", "html", "obsidian", "org", "mermaid", "dot", "sqlite", "csv", "tsv"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("copy")
+                .long("copy")
+                .help("Copy the top result's resume command (or, for timelines, the first matched excerpt) to the clipboard")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("tag")
+                .long("tag")
+                .help("Only show sessions tagged with TAG (repeat to require several)")
+                .value_name("TAG")
+                .action(ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("tool")
+                .long("tool")
+                .help("Only sessions and timeline entries with calls to TOOL: a tool name such as Bash, mcp for any MCP tool, mcp:SERVER, or mcp:SERVER/TOOL (repeatable)")
+                .value_name("TOOL")
+                .action(ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("file")
+                .long("file")
+                .help("Search or analyze this session file instead of ~/.claude/projects (repeatable; - reads stdin for timeline and code-diff)")
+                .value_name("PATH")
+                .action(ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Print nothing; only report through the exit status whether anything matched")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("Log progress and parse problems to stderr (-v phases and timings, -vv every file; RUST_LOG overrides)")
+                .action(ArgAction::Count)
+                .global(true),
+        )
+        .arg(
+            Arg::new("width")
+                .long("width")
+                .value_name("COLUMNS")
+                .help("Wrap output to COLUMNS, even when piped [default: the terminal's width; no wrapping when piped]")
+                .global(true),
+        )
+        .arg(
+            Arg::new("ascii")
+                .long("ascii")
+                .help("Print ASCII markers instead of emoji and other symbols, for plain terminals and logs")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .subcommand(
+            session_subcommand("timeline", "Extract timeline for a session").arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("Write the timeline to FILE as JSON, Markdown, or HTML (from --format or the extension) instead of printing it")
+                    .value_name("FILE"),
+            ),
+        )
+        .subcommand(session_subcommand("code-diff", "Extract timeline of code diffs for a session"))
+        .subcommand(
+            session_subcommand("show", "Print a message and the messages around it in full, without term matching")
+                .arg(
+                    Arg::new("around")
+                        .long("around")
+                        .help("Index of the message to show, as given in timelines (use --context to widen)")
+                        .value_name("INDEX")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            session_subcommand(
+                "pack",
+                "Write a Markdown briefing on a session (goal, decisions, files changed, todos, final state) to start a new one from",
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("Write the briefing to this file instead of printing it")
+                    .value_name("FILE"),
+            ),
+        )
+        .subcommand(session_subcommand(
+            "drift",
+            "Report how many of a session's file edits are still present in the files on disk",
+        ))
+        .subcommand(session_subcommand(
+            "replay",
+            "Step through a session message by message as it happened: n/p to move, /TEXT to search, c or e for the next code change or error",
+        ))
+        .subcommand(
+            Command::new("resume")
+                .about("Print the command to resume the best matching session from its project directory")
+                .arg(
+                    Arg::new("query")
+                        .help("Session ID, prefix, title words, @-N, or search terms")
+                        .value_name("SESSION_OR_TERMS")
+                        .required(true)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("exec")
+                        .long("exec")
+                        .help("Run the resume command instead of printing it")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            notes_subcommand("export", "Write one note per session (Obsidian Markdown or Org) into a directory, or all to a SQLite database").arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("Database file to write with --format sqlite")
+                    .value_name("FILE"),
+            ),
+        )
+        .subcommand(notes_subcommand(
+            "sync-notes",
+            "Export notes only for sessions that are new or changed since their note was written",
+        ))
+        .subcommand(
+            Command::new("stats")
+                .about("Report messages, tokens, estimated cost, and active time per session or per day")
+                .visible_alias("cost")
+                .arg(
+                    Arg::new("by")
+                        .long("by")
+                        .help("One row per session, per project and day, or per project")
+                        .value_name("GROUPING")
+                        .value_parser(["session", "day", "project"])
+                        .default_value("session"),
+                )
+                .arg(
+                    Arg::new("tools")
+                        .long("tools")
+                        .help("Break down tool calls per tool: count, failure rate, and average result size")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("terms")
+                        .long("terms")
+                        .help("Dump every word's frequency in message text per row, for analysis elsewhere (text, csv, tsv, or json)")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("tools"),
+                )
+                .arg(
+                    Arg::new("stopped")
+                        .long("stopped")
+                        .help("With --terms, skip boilerplate words, stopwords, and words under three letters, as common terms do")
+                        .action(ArgAction::SetTrue)
+                        .requires("terms"),
+                )
+                .arg(
+                    Arg::new("min_count")
+                        .long("min-count")
+                        .help("With --terms, leave out words counted fewer than N times in a row")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("1")
+                        .requires("terms"),
+                ),
+        )
+        .subcommand(
+            Command::new("files")
+                .about("Rank files by how often sessions edited them, with the sessions that did")
+                .arg(
+                    Arg::new("sessions")
+                        .help("Sessions to include (default: all sessions matching --project/--recent)")
+                        .value_name("SESSION")
+                        .num_args(0..),
+                ),
+        )
+        .subcommand(
+            Command::new("touched")
+                .about("List the sessions that edited, renamed, or deleted a file, following it across renames")
+                .arg(
+                    Arg::new("path")
+                        .help("File path: absolute, relative to the current directory, or its trailing components")
+                        .value_name("FILE")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("new")
+                .about("List sessions started or changed since the last run (or the last day), to review unattended work")
+                .arg(
+                    Arg::new("since_last_run")
+                        .long("since-last-run")
+                        .help("Look back to the previous run of `new` instead of --recent days (default 1)")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("digest")
+                .about("Summarise a day's sessions as Markdown for a journal: per project, titles, time, cost, and files edited")
+                .arg(
+                    Arg::new("date")
+                        .long("date")
+                        .help("Day to summarise, in local time: today, yesterday, or YYYY-MM-DD")
+                        .value_name("DATE")
+                        .default_value("today"),
+                )
+                .arg(
+                    Arg::new("save")
+                        .long("save")
+                        .help("Save as DATE.md in --dir or the [digest] dir from the config file, instead of printing")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(digest_dir_arg().requires("save")),
+        )
+        .subcommand(
+            Command::new("schedule")
+                .about("Save the digest automatically every day with a launchd agent or systemd user timer")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("install")
+                        .about("Install (or replace) the daily digest job")
+                        .arg(
+                            Arg::new("daily")
+                                .long("daily")
+                                .help("Local time to save the day's digest, as HH:MM")
+                                .value_name("TIME")
+                                .required(true),
+                        )
+                        .arg(digest_dir_arg()),
+                )
+                .subcommand(Command::new("remove").about("Remove the daily digest job")),
+        )
+        .subcommand(
+            Command::new("chunks")
+                .about("Split sessions into overlapping chunks with metadata, as JSON Lines for embedding")
+                .arg(
+                    Arg::new("sessions")
+                        .help("Sessions to chunk (default: all sessions matching --project/--recent)")
+                        .value_name("SESSION")
+                        .num_args(0..),
+                )
+                .arg(
+                    Arg::new("chunk_size")
+                        .long("chunk-size")
+                        .help("Most characters of text per chunk")
+                        .value_name("CHARS")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("2000"),
+                )
+                .arg(
+                    Arg::new("overlap")
+                        .long("overlap")
+                        .help("Messages each chunk repeats from the end of the previous one")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("1"),
+                ),
+        )
+        .subcommand(
+            Command::new("trend")
+                .about("Count messages matching a term per day or week, to see whether it comes up more or less often")
+                .arg(
+                    Arg::new("terms")
+                        .help("Terms to count, any of them, case-insensitively")
+                        .value_name("TERM")
+                        .num_args(1..)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .help("Start of the trend: days or weeks back (90d, 12w) or a date")
+                        .value_name("WHEN")
+                        .default_value("30d"),
+                )
+                .arg(
+                    Arg::new("by")
+                        .long("by")
+                        .help("Count per day or per week (weeks start on Monday)")
+                        .value_name("PERIOD")
+                        .value_parser(["day", "week"])
+                        .default_value("day"),
+                )
+                .arg(
+                    Arg::new("sparkline")
+                        .long("sparkline")
+                        .help("Print a one-line sparkline instead of a table")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("grep")
+                .about("Print every matching line across sessions as session_id:message:role: text, without ranking")
+                .arg(
+                    Arg::new("terms")
+                        .help("Terms to match, any of them, case-insensitively")
+                        .value_name("TERM")
+                        .num_args(1..)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("role")
+                        .long("role")
+                        .help("Only match messages from this role")
+                        .value_name("ROLE")
+                        .value_parser(["user", "assistant"]),
+                ),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Time each phase of a search over your sessions, to measure performance on your own data")
+                .arg(
+                    Arg::new("terms")
+                        .help("Search terms to benchmark")
+                        .value_name("TERM")
+                        .num_args(1..)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("runs")
+                        .long("runs")
+                        .help("Repeat the benchmark this many times and report the median and fastest")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("5"),
+                ),
+        )
+        .subcommand(
+            Command::new("tag")
+                .about("Add (+tag) or remove (-tag) tags on a session, or list its tags")
+                .arg(Arg::new("session").help("Session to tag").value_name("SESSION").required(true))
+                .arg(
+                    Arg::new("tags")
+                        .help("Tags to add as +tag or remove as -tag")
+                        .value_name("TAG")
+                        .num_args(0..)
+                        .allow_hyphen_values(true),
+                ),
+        )
+        .subcommand(
+            Command::new("bookmark")
+                .about("Bookmark a message of a session by its timeline index, or list the session's bookmarks")
+                .arg(Arg::new("session").help("Session containing the message").value_name("SESSION").required(true))
+                .arg(
+                    Arg::new("message")
+                        .help("Message index, as shown in timelines")
+                        .value_name("INDEX")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(Arg::new("label").help("What the message is about").value_name("LABEL").num_args(0..))
+                .arg(
+                    Arg::new("remove")
+                        .long("remove")
+                        .help("Remove the bookmark instead of adding it")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("note")
+                .about("Attach a note to a session or one of its messages, or list the session's notes")
+                .arg(Arg::new("session").help("Session to annotate").value_name("SESSION").required(true))
+                .arg(Arg::new("text").help("Note text").value_name("TEXT").num_args(0..))
+                .arg(
+                    Arg::new("message")
+                        .long("message")
+                        .help("Attach the note to this message index, as shown in timelines")
+                        .value_name("INDEX")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("remove")
+                        .long("remove")
+                        .help("Remove the Nth note, as numbered in the listing")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .conflicts_with("text"),
+                ),
+        )
+        .subcommand(
+            Command::new("backup")
+                .about("Record hashes of every session file, and check backup copies against them")
+                .subcommand_required(true)
+                .subcommand(Command::new("manifest").about("Hash every session file and store the manifest, replacing the last one"))
+                .subcommand(
+                    Command::new("verify")
+                        .about("Check that a backup of ~/.claude (or its projects directory) has every session file intact and every indexed session")
+                        .arg(
+                            Arg::new("dir")
+                                .help("The backup copy")
+                                .value_name("DIR")
+                                .required(true),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("undo")
+                .about("Restore what an operation moved to the trash (removed annotations, overwritten notes), or list those operations")
+                .arg(
+                    Arg::new("operation")
+                        .help("Operation ID, as printed when it ran (default: list the trash)")
+                        .value_name("OPERATION_ID"),
+                ),
+        )
+        .subcommand(
+            Command::new("prune")
+                .about("Move sessions not modified in the last N days to the trash, where `undo` can restore them (honours --project)")
+                .arg(
+                    Arg::new("older_than")
+                        .long("older-than")
+                        .value_name("DAYS")
+                        .required(true)
+                        .value_parser(clap::value_parser!(i64).range(1..))
+                        .help("Prune sessions last modified more than this many days ago"),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .help("List the sessions that would be pruned without moving them"),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Import transcripts from other AI tools (ChatGPT export, Codex CLI, Aider, Cursor, OpenCode) so they can be searched")
+                .arg(
+                    Arg::new("paths")
+                        .help("Files to import: conversations.json, rollout-*.jsonl, .aider.chat.history.md, Cursor's state.vscdb, OpenCode's storage directory. Without paths, imports from each tool's default location")
+                        .value_name("PATH")
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("source")
+                        .long("source")
+                        .help("Format of the files; auto detects it from each file")
+                        .value_name("FORMAT")
+                        .value_parser(["auto", "chatgpt", "codex", "aider", "cursor", "opencode"])
+                        .default_value("auto"),
+                ),
+        )
+        .subcommand(
+            Command::new("sync")
+                .about("Share sessions with a team through a shared directory or S3 bucket")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("push")
+                        .about("Copy your sessions to the remote, redacting credentials")
+                        .arg(remote_arg())
+                        .arg(user_arg())
+                        .arg(machine_arg())
+                        .arg(
+                            Arg::new("no-redact")
+                                .long("no-redact")
+                                .help("Copy sessions as they are, without replacing API keys, tokens, and passwords")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("compress")
+                                .long("compress")
+                                .help("Gzip session files on the remote")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("encrypt")
+                                .long("encrypt")
+                                .help("Encrypt session files with the team's age key (SESSION_FINDER_KEY, SESSION_FINDER_KEY_FILE, or ~/.claude/session-finder/key.txt)")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("pull")
+                        .about("Copy teammates' sessions from the remote so searches include them, decrypting as needed")
+                        .arg(remote_arg())
+                        .arg(user_arg())
+                        .arg(machine_arg()),
+                )
+                .subcommand(
+                    Command::new("keygen").about("Create the age key used by push --encrypt and pull"),
+                ),
+        )
+        .subcommand(
+            Command::new("daemon")
+                .about("Keep session analyses in memory and answer searches over a Unix socket; searches use it while it runs"),
+        )
+        .subcommand(
+            Command::new("lsp-like")
+                .about("Serve search, timeline, and transcript requests as JSON-RPC over stdio"),
+        )
+        .subcommand(
+            Command::new("init")
+                .about("Print shell integration (key binding and completions) for your shell")
+                .arg(
+                    Arg::new("shell")
+                        .help("Shell to generate integration for")
+                        .required(true)
+                        .value_parser(["bash", "zsh", "fish"]),
+                ),
+        )
+}
+
+fn notes_subcommand(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(
+            Arg::new("sessions")
+                .help("Sessions to export (default: all sessions matching --project/--recent)")
+                .value_name("SESSION")
+                .num_args(0..),
+        )
+        .arg(
+            Arg::new("dir")
+                .long("dir")
+                .help("Directory to write notes into")
+                .value_name("DIR")
+                .default_value("."),
+        )
+}
+
+fn digest_dir_arg() -> Arg {
+    Arg::new("dir")
+        .long("dir")
+        .help("Directory to save digests into (default: [digest] dir from the config file)")
+        .value_name("DIR")
+}
+
+fn remote_arg() -> Arg {
+    Arg::new("remote")
+        .long("remote")
+        .help("Shared directory or s3:// URL holding the team's sessions")
+        .value_name("PATH_OR_URL")
+        .required(true)
+}
+
+fn machine_arg() -> Arg {
+    Arg::new("machine")
+        .long("machine")
+        .help("Name of this machine, so your sessions from several machines are kept apart and pulled onto each other (default: machine in the config file)")
+        .value_name("NAME")
+}
+
+fn user_arg() -> Arg {
+    Arg::new("user")
+        .long("user")
+        .help("Name your sessions are shared under (default: $USER)")
+        .value_name("NAME")
+}
+
+/// Builds a subcommand operating on one session, chosen either by reference or by a
+/// relative selector such as `--latest`.
+fn session_subcommand(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(
+            Arg::new("args")
+                .help("Session ID, prefix, path, title words, or @-N, followed by search terms. \
+                       With a selector flag, all arguments are search terms.")
+                .value_name("SESSION_AND_TERMS")
+                .num_args(0..),
+        )
+        .arg(
+            Arg::new("latest")
+                .long("latest")
+                .help("Use the most recent session (within --project if given)")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["nth", "last_for_project"]),
+        )
+        .arg(
+            Arg::new("nth")
+                .long("nth")
+                .help("Use the Nth most recent session (1 = latest, within --project if given)")
+                .value_name("N")
+                .conflicts_with("last_for_project"),
+        )
+        .arg(
+            Arg::new("last_for_project")
+                .long("last-for-project")
+                .help("Use the most recent session for --project, or for the current directory")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Picks the session for a session subcommand and returns it with the remaining search terms.
+fn select_session<'a>(
+    sub_matches: &'a clap::ArgMatches,
+    project_filter: Option<&String>,
+    input_files: &[&str],
+) -> anyhow::Result<(PathBuf, Vec<&'a str>)> {
+    let mut args: Vec<&str> = sub_matches.get_many::<String>("args")
+        .map(|vals| vals.map(|s| s.as_str()).collect())
+        .unwrap_or_default();
+
+    // An explicit --file is the session, so every argument is a search term
+    match input_files {
+        [] => {}
+        [file] => return Ok((PathBuf::from(file), args)),
+        _ => return Err(anyhow!("Only one --file can be given for a single-session command")),
+    }
+
+    let nth = if sub_matches.get_flag("latest") {
+        Some(1)
+    } else {
+        sub_matches.get_one::<String>("nth").map(|s| s.parse::<usize>()).transpose()?
+    };
+
+    if sub_matches.get_flag("last_for_project") {
+        let project = match project_filter {
+            Some(filter) => ProjectMatch::Contains(filter.clone()),
+            None => ProjectMatch::Directory(std::env::current_dir()?),
+        };
+        return Ok((resolve_recent_session(1, Some(&project))?, args));
+    }
+
+    if let Some(nth) = nth {
+        let project = project_filter.map(|filter| ProjectMatch::Contains(filter.clone()));
+        return Ok((resolve_recent_session(nth, project.as_ref())?, args));
+    }
+
+    if args.is_empty() {
+        return Err(anyhow!("A session reference or a selector (--latest, --nth, --last-for-project) is required"));
+    }
+    let session_ref = args.remove(0);
+    if session_ref == STDIN_REFERENCE {
+        return Ok((PathBuf::from(STDIN_REFERENCE), args));
+    }
+    Ok((resolve_session_path(session_ref)?, args))
+}
+
+/// How many cheaply scored candidates per requested result get the full analysis pass
+const ANALYSIS_CANDIDATES_PER_RESULT: usize = 5;
+
+/// Session files waiting for an analysis worker, per worker: enough to keep workers busy while
+/// ripgrep runs, few enough that they don't analyse much the shortlist then drops
+const ANALYSIS_QUEUE_PER_WORKER: usize = 2;
+
+/// `sessions.retain(keep)`, reporting the stage under `--explain-plan`
+fn retain_explained(
+    sessions: &mut Vec<SessionInfo>,
+    explaining: bool,
+    stage: &str,
+    keep: impl FnMut(&SessionInfo) -> bool,
+) {
+    let before = sessions.len();
+    sessions.retain(keep);
+    explain_stage(explaining, stage, before, sessions.len());
+}
+
+/// Threads analysing sessions in full: one per core, or one under `--max-memory`, which
+/// budgets for a single session in memory at a time.
+fn analysis_workers(options: &SearchOptions) -> usize {
+    match options.memory_budget {
+        Some(_) => 1,
+        None => std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+    }
+}
+
+/// A check analysed sessions must pass, with its `--explain-plan` stage
+type SessionFilter<'a> = (String, Box<dyn Fn(&SessionInfo) -> bool + Sync + 'a>);
+
+/// A cheap check on a session file's path, such as its tags, made before any analysis
+type PathCheck<'a> = dyn Fn(&Path) -> bool + Sync + 'a;
+
+/// A check on an analysed session
+type SessionCheck<'a> = dyn Fn(&SessionInfo) -> Result<bool> + Sync + 'a;
+
+/// A session file the scan found: its matches, when it was last written, and where it is
+type Candidate = (FileMatches, DateTime<Utc>, PathBuf);
+
+/// What analysis workers and the scan report to `find_sessions_streaming`
+enum SearchEvent {
+    /// An analysed session file, or why it couldn't be
+    Analysed(PathBuf, Box<Result<Option<SessionInfo>>>),
+    /// The scan is done: every candidate, best first, and those queued for analysis while the
+    /// scan went on
+    Scanned(Result<(Vec<Candidate>, HashSet<PathBuf>)>),
+    /// The scan or an analysis panicked
+    Panicked(Box<dyn std::any::Any + Send>),
+}
+
+/// Finds sessions mentioning any of the search terms in two phases: ripgrep match counts and
+/// file metadata rank the files, and only the most promising `limit * ANALYSIS_CANDIDATES_PER_RESULT`
+/// are read and analyzed in full, more following while fewer than `limit` of those pass the
+/// search's filters. The phases overlap: files ripgrep reports are queued for worker threads
+/// to analyse while the scan goes on, as long as the workers keep up, and the rest of the
+/// shortlist is queued when the scan ends. Only shortlisted sessions are returned, so results
+/// don't depend on which files happened to be analysed early.
+pub fn find_sessions(
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    limit: usize,
+    grouping: Option<ResultGrouping>,
+    options: &SearchOptions,
+) -> Result<Vec<SessionInfo>> {
+    let search = SessionSearch {
+        search_terms,
+        project_filter,
+        recent_days,
+        limit,
+        grouping,
+        options,
+        file_filter: None,
+        session_filter: None,
+    };
+    find_sessions_streaming(&search, &mut |_| Ok(true))
+}
+
+/// What `find_sessions` looks for
+pub struct SessionSearch<'a> {
+    pub search_terms: &'a [&'a str],
+    pub project_filter: Option<&'a String>,
+    pub recent_days: Option<i64>,
+    pub limit: usize,
+    pub grouping: Option<ResultGrouping>,
+    pub options: &'a SearchOptions,
+    /// A check every file containing a term must pass before the shortlist is cut, such as
+    /// `--tag`; it should be cheap, as it sees every match
+    pub file_filter: Option<&'a PathCheck<'a>>,
+    /// A check analysed sessions must pass to count towards `limit`. While fewer than `limit`
+    /// pass, more candidates are analysed, until there are none left.
+    pub session_filter: Option<&'a SessionCheck<'a>>,
+}
+
+/// Like `find_sessions`, also handing each session to `on_session` as soon as it is analysed,
+/// shortlisted or not, until `on_session` returns false.
+pub fn find_sessions_streaming(
+    search: &SessionSearch,
+    on_session: &mut dyn FnMut(&SessionInfo) -> Result<bool>,
+) -> Result<Vec<SessionInfo>> {
+    let SessionSearch { search_terms, project_filter, recent_days, limit, grouping, options, .. } = *search;
+    let ignore = &options.ignore;
+    let started = Instant::now();
+    let workers = analysis_workers(options);
+    let (queue, queued) = mpsc::sync_channel::<PathBuf>(workers * ANALYSIS_QUEUE_PER_WORKER);
+    let queued = Mutex::new(queued);
+    let (events_sender, events) = mpsc::channel::<SearchEvent>();
+    // Set once the search has what it needs, so files still queued aren't analysed
+    let finished = AtomicBool::new(false);
+    let (outcome, shortlist, mut analyzed) = std::thread::scope(|scope| {
+        // Owned here, so the workers see the queue close however this closure ends
+        let queue = queue;
+        for _ in 0..workers {
+            let (queued, events_sender, finished) = (&queued, events_sender.clone(), &finished);
+            scope.spawn(move || loop {
+                let next = queued.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                let Ok(path) = next else {
+                    break;
+                };
+                if finished.load(Ordering::Relaxed) {
+                    continue;
+                }
+                let analysis = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    if options.time_budget.as_ref().is_some_and(TimeBudget::skips_session) {
+                        Ok(None)
+                    } else {
+                        analyze_session_file(&path, search_terms, project_filter, recent_days, options)
+                    }
+                }));
+                let event = match analysis {
+                    Ok(analysis) => SearchEvent::Analysed(path, Box::new(analysis)),
+                    Err(panic) => SearchEvent::Panicked(panic),
+                };
+                if events_sender.send(event).is_err() {
+                    break;
+                }
+            });
+        }
+        {
+            let (queue, events_sender) = (queue.clone(), events_sender.clone());
+            scope.spawn(move || {
+                let scanned = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| scan_candidates(search, &queue)));
+                let _ = events_sender.send(scanned.map_or_else(SearchEvent::Panicked, SearchEvent::Scanned));
+            });
+        }
+        drop(events_sender);
+
+        let mut analyzed: HashMap<PathBuf, Result<Option<SessionInfo>>> = HashMap::new();
+        let mut shortlist: Option<Shortlist> = None;
+        let mut requested: HashSet<PathBuf> = HashSet::new();
+        let mut streaming = true;
+        let mut outcome = Ok(());
+        'events: for event in events.iter() {
+            match event {
+                SearchEvent::Analysed(path, analysis) => {
+                    if let Ok(Some(session)) = analysis.as_ref() {
+                        if streaming && !ignore.ignores_length(session.message_count) {
+                            match on_session(session) {
+                                Ok(more) => streaming = more,
+                                Err(e) => {
+                                    outcome = Err(SearchFailure::Error(e));
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    analyzed.insert(path, *analysis);
+                }
+                SearchEvent::Scanned(Ok((candidates, queued_early))) => {
+                    debug!(analyzed_early = queued_early.len(), "Queued for analysis during the scan");
+                    shortlist = Some(Shortlist::new(candidates, limit, grouping, options));
+                    requested = queued_early;
+                }
+                SearchEvent::Scanned(Err(e)) => {
+                    outcome = Err(SearchFailure::Error(e));
+                    break;
+                }
+                SearchEvent::Panicked(panic) => {
+                    outcome = Err(SearchFailure::Panic(panic));
+                    break;
+                }
+            }
+            let Some(shortlist) = shortlist.as_mut() else {
+                continue;
+            };
+            // Whether more are needed is only known once everything picked so far is analysed.
+            // A batch may be analysed already, having been queued during the scan, in which
+            // case no event will come for it and the next batch is picked straight away.
+            while shortlist.picked().all(|path| analyzed.contains_key(path)) {
+                let batch = shortlist.next_batch(|path| passes_search(analyzed.get(path), search));
+                if batch.is_empty() {
+                    break 'events;
+                }
+                for path in batch {
+                    if requested.insert(path.clone()) {
+                        debug!(file = %path.display(), "Analyzing");
+                        if queue.send(path).is_err() {
+                            break 'events;
+                        }
+                    }
+                }
+            }
+        }
+        finished.store(true, Ordering::Relaxed);
+        (outcome, shortlist, analyzed)
+    });
+    match outcome {
+        Ok(()) => {}
+        Err(SearchFailure::Error(e)) => return Err(e),
+        Err(SearchFailure::Panic(panic)) => std::panic::resume_unwind(panic),
+    }
+    let shortlist = shortlist.map(Shortlist::into_picked).unwrap_or_default();
+
+    let shortlisted = shortlist.len();
+    let mut sessions = Vec::new();
+    for (file_matches, full_path) in shortlist {
+        let Some(analysis) = analyzed.remove(&full_path) else {
+            continue;
+        };
+        if let Some(mut session_info) = analysis? {
+            if ignore.ignores_length(session_info.message_count) {
+                debug!(file = %full_path.display(), messages = session_info.message_count, "Skipped: too short");
+                continue;
+            }
+            session_info.matched_messages = matched_excerpts(&full_path, &file_matches, search_terms, &options.preview);
+            session_info.prefilter = Some(file_matches);
+            sessions.push(session_info);
+        }
+    }
+    info!(
+        analyzed = sessions.len(),
+        workers,
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "Search done"
+    );
+    explain_stage(options.explain_plan, "analysis (min_messages)", shortlisted, sessions.len());
+
+    Ok(sessions)
+}
+
+/// Why a search stopped before it was done
+enum SearchFailure {
+    Error(SessionFinderError),
+    Panic(Box<dyn std::any::Any + Send>),
+}
+
+/// Whether an analysed session counts towards the search's limit: it is long enough and
+/// passes the search's `session_filter`. Sessions that couldn't be analysed don't.
+fn passes_search(analysis: Option<&Result<Option<SessionInfo>>>, search: &SessionSearch) -> bool {
+    let Some(Ok(Some(session))) = analysis else {
+        return false;
+    };
+    !search.options.ignore.ignores_length(session.message_count)
+        && search.session_filter.is_none_or(|keep| keep(session).unwrap_or(false))
+}
+
+/// `find_sessions` on a tokio runtime. ripgrep's output and the session files are read as they
+/// arrive rather than on a thread blocked waiting for them, and the shortlisted sessions are
+/// analysed as concurrent tasks. Sessions come back in the same order as from `find_sessions`.
+#[cfg(feature = "tokio")]
+pub async fn find_sessions_async(
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    limit: usize,
+    grouping: Option<ResultGrouping>,
+    options: &SearchOptions,
+) -> Result<Vec<SessionInfo>> {
+    use prefilter::count_matching_lines_async;
+
+    let search = SessionSearch {
+        search_terms,
+        project_filter,
+        recent_days,
+        limit,
+        grouping,
+        options,
+        file_filter: None,
+        session_filter: None,
+    };
+    let roots = session_roots()?;
+    let mut filter = ScanFilter::new(&roots, &search);
+    for root in &roots {
+        if search.options.time_budget.as_ref().is_some_and(TimeBudget::stops_scan) {
+            break;
+        }
+        for (file_path, file_matches) in count_matching_lines_async(root, search_terms).await? {
+            let full_path = root.join(file_path);
+            if !filter.wants(&full_path)? {
+                continue;
+            }
+            let last_modified: DateTime<Utc> = DateTime::from(tokio::fs::metadata(&full_path).await?.modified()?);
+            filter.admit(file_matches, last_modified, full_path);
+        }
+    }
+    let mut shortlist = Shortlist::new(filter.ranked(), limit, grouping, options);
+
+    let terms: Arc<[String]> = search_terms.iter().map(|term| term.to_string()).collect();
+    let project = project_filter.cloned();
+    let shared_options = Arc::new(options.clone());
+    let mut analyzed: HashMap<PathBuf, Result<Option<SessionInfo>>> = HashMap::new();
+    loop {
+        let batch = shortlist.next_batch(|path| passes_search(analyzed.get(path), &search));
+        if batch.is_empty() {
+            break;
+        }
+        let mut analyses = tokio::task::JoinSet::new();
+        for full_path in batch {
+            let (terms, project, options) = (terms.clone(), project.clone(), shared_options.clone());
+            analyses.spawn(async move {
+                let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+                let analysis = if options.time_budget.as_ref().is_some_and(TimeBudget::skips_session) {
+                    Ok(None)
+                } else {
+                    analyze_session_file_async(&full_path, &terms, project.as_ref(), recent_days, &options).await
+                };
+                (full_path, analysis)
+            });
+        }
+        while let Some(joined) = analyses.join_next().await {
+            let (full_path, analysis) = joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+            analyzed.insert(full_path, analysis);
+        }
+    }
+
+    let shortlist = shortlist.into_picked();
+    let shortlisted = shortlist.len();
+    let mut sessions = Vec::new();
+    for (file_matches, full_path) in shortlist {
+        let Some(analysis) = analyzed.remove(&full_path) else {
+            continue;
+        };
+        if let Some(mut session_info) = analysis? {
+            if options.ignore.ignores_length(session_info.message_count) {
+                debug!(file = %full_path.display(), messages = session_info.message_count, "Skipped: too short");
+                continue;
+            }
+            session_info.prefilter = Some(file_matches);
+            sessions.push(session_info);
+        }
+    }
+    explain_stage(options.explain_plan, "analysis (min_messages)", shortlisted, sessions.len());
+    // Matched lines are read from their offsets off the runtime's workers
+    let terms: Vec<String> = search_terms.iter().map(|term| term.to_string()).collect();
+    let preview = options.preview.clone();
+    let sessions = tokio::task::spawn_blocking(move || {
+        let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+        for session in &mut sessions {
+            if let Some(file_matches) = &session.prefilter {
+                session.matched_messages = matched_excerpts(&session.path, file_matches, &terms, &preview);
+            }
+        }
+        sessions
+    })
+    .await
+    .map_err(io::Error::other)?;
+    Ok(sessions)
+}
+
+/// The first phase of `find_sessions`: scores every file containing a term by matching lines
+/// and recency without reading it, and ranks them, best first. Files are queued for analysis
+/// as they turn up while there is room; those queued are returned with the ranking.
+fn scan_candidates(search: &SessionSearch, queue: &mpsc::SyncSender<PathBuf>) -> Result<(Vec<Candidate>, HashSet<PathBuf>)> {
+    let roots = session_roots()?;
+    let mut filter = ScanFilter::new(&roots, search);
+    let mut queued: HashSet<PathBuf> = HashSet::new();
+    for root in &roots {
+        if search.options.time_budget.as_ref().is_some_and(TimeBudget::stops_scan) {
+            break;
+        }
+        for_each_matching_file(root, search.search_terms, search.options.explain_plan, |file_path, file_matches| {
+            let full_path = root.join(file_path);
+            if !filter.wants(&full_path)? {
+                return Ok(true);
+            }
+            let last_modified: DateTime<Utc> = DateTime::from(fs::metadata(&full_path)?.modified()?);
+            if filter.admit(file_matches, last_modified, full_path.clone()) && queue.try_send(full_path.clone()).is_ok() {
+                queued.insert(full_path);
+            }
+            // Under --budget, the scan gets half the time and analysis the rest
+            if search.options.time_budget.as_ref().is_some_and(TimeBudget::stops_scan) {
+                return Ok(false);
+            }
+            Ok(true)
+        })?;
+    }
+    Ok((filter.ranked(), queued))
+}
+
+/// The files a scan found, the filters they must get through to be analysed, and how many got
+/// through each, for `--explain-plan`
+struct ScanFilter<'a> {
+    project_filter: Option<&'a String>,
+    recent_days: Option<i64>,
+    options: &'a SearchOptions,
+    file_filter: Option<&'a PathCheck<'a>>,
+    started: Instant,
+    matched: usize,
+    not_ignored: usize,
+    in_project: usize,
+    in_repository: usize,
+    passed_file_filter: usize,
+    candidates: Vec<Candidate>,
+}
+
+impl<'a> ScanFilter<'a> {
+    fn new(roots: &[PathBuf], search: &SessionSearch<'a>) -> Self {
+        info!(roots = ?roots, terms = ?search.search_terms, "Searching sessions");
+        explain(search.options.explain_plan, &format!(
+            "scan {} for {}",
+            roots.iter().map(|root| root.display().to_string()).collect::<Vec<_>>().join(", "),
+            search.search_terms.join(" OR ")
+        ));
+        ScanFilter {
+            project_filter: search.project_filter,
+            recent_days: search.recent_days,
+            options: search.options,
+            file_filter: search.file_filter,
+            started: Instant::now(),
+            matched: 0,
+            not_ignored: 0,
+            in_project: 0,
+            in_repository: 0,
+            passed_file_filter: 0,
+            candidates: Vec::new(),
+        }
+    }
+
+    /// Whether a file containing a term is worth looking up: it isn't on the ignore list, is
+    /// in the `--project` and the current repository, and passes the search's `file_filter`.
+    fn wants(&mut self, full_path: &Path) -> Result<bool> {
+        self.matched += 1;
+        if self.options.ignore.ignores_file(full_path) {
+            debug!(file = %full_path.display(), "Skipped: on the ignore list");
+            return Ok(false);
+        }
+        self.not_ignored += 1;
+        if let Some(filter) = self.project_filter {
+            if !decode_project_path(full_path)?.contains(filter.as_str()) {
+                debug!(file = %full_path.display(), "Skipped: outside --project {}", filter);
+                return Ok(false);
+            }
+        }
+        self.in_project += 1;
+        if self.options.workspace.as_ref().is_some_and(|root| !in_workspace(full_path, root)) {
+            debug!(file = %full_path.display(), "Skipped: outside the current repository");
+            return Ok(false);
+        }
+        self.in_repository += 1;
+        if self.file_filter.is_some_and(|keep| !keep(full_path)) {
+            debug!(file = %full_path.display(), "Skipped: filtered out before the shortlist");
+            return Ok(false);
+        }
+        self.passed_file_filter += 1;
+        Ok(true)
+    }
+
+    /// Takes a file `wants` let through as a candidate unless it is older than `--recent`,
+    /// returning whether it did.
+    fn admit(&mut self, file_matches: FileMatches, last_modified: DateTime<Utc>, full_path: PathBuf) -> bool {
+        let cutoff = self.recent_days.map(|days| Utc::now() - chrono::Duration::days(days));
+        if cutoff.is_some_and(|cutoff| last_modified < cutoff) {
+            debug!(file = %full_path.display(), "Skipped: older than --recent");
+            return false;
+        }
+        self.candidates.push((file_matches, last_modified, full_path));
+        true
+    }
+
+    /// Every candidate, the most promising first: the newest under `--sort recent`, otherwise
+    /// by matching lines and then recency.
+    fn ranked(self) -> Vec<Candidate> {
+        let ScanFilter { project_filter, recent_days, options, matched, not_ignored, in_project, .. } = self;
+        let in_repository = self.in_repository;
+        let explaining = options.explain_plan;
+        // Before ranking, while Claude Code's own copy of a session still comes before pulled ones
+        let candidates_before = self.candidates.len();
+        let mut candidates = without_identical_copies(self.candidates, |(_, _, path)| path.as_path());
+        if candidates.len() < candidates_before {
+            debug!(dropped = candidates_before - candidates.len(), "Skipped identical copies of sessions");
+        }
+        explain(explaining, &format!("{} session files contain a term", matched));
+        explain_stage(explaining, "ignore rules", matched, not_ignored);
+        if let Some(filter) = project_filter {
+            explain_stage(explaining, &format!("--project {}", filter), not_ignored, in_project);
+        }
+        if let Some(root) = &options.workspace {
+            explain_stage(explaining, &format!("repository {}", root.display()), in_project, in_repository);
+        }
+        if self.file_filter.is_some() {
+            explain_stage(explaining, "session filters (before the shortlist)", in_repository, self.passed_file_filter);
+        }
+        if let Some(days) = recent_days {
+            explain_stage(explaining, &format!("--recent {}", days), self.passed_file_filter, candidates.len());
+        }
+        let by_matches = |a: &Candidate, b: &Candidate| {
+            b.0.occurrences.cmp(&a.0.occurrences).then(b.0.lines.cmp(&a.0.lines))
+        };
+        match options.sort {
+            SortOrder::Recent => {
+                candidates.sort_by(|a, b| b.1.cmp(&a.1).then(by_matches(a, b)).then_with(|| a.2.cmp(&b.2)))
+            }
+            SortOrder::Matches | SortOrder::Relevance => {
+                candidates.sort_by(|a, b| by_matches(a, b).then(b.1.cmp(&a.1)).then_with(|| a.2.cmp(&b.2)))
+            }
+        }
+        info!(
+            matched,
+            after_filters = candidates.len(),
+            elapsed_ms = self.started.elapsed().as_millis() as u64,
+            "Prefilter phase done"
+        );
+        candidates
+    }
+}
+
+/// The ranked candidates and which of them were picked for analysis. Candidates are picked
+/// `ANALYSIS_CANDIDATES_PER_RESULT` for each result still wanted, best first, and again as long
+/// as too few of those picked pass the search's checks; with grouping, each group is picked
+/// from separately.
+struct Shortlist {
+    candidates: Vec<Candidate>,
+    /// Each candidate's group; all the same without grouping
+    groups: Vec<String>,
+    picked: Vec<bool>,
+    /// Whether each picked candidate passed, once known
+    passed: Vec<Option<bool>>,
+    limit: usize,
+    /// Under `--budget`, analyse the newest of each batch first
+    newest_first: bool,
+    explaining: bool,
+}
+
+impl Shortlist {
+    fn new(candidates: Vec<Candidate>, limit: usize, grouping: Option<ResultGrouping>, options: &SearchOptions) -> Self {
+        let groups = candidates
+            .iter()
+            .map(|(_, last_modified, path)| match grouping {
+                Some(grouping) => grouping.key(&decode_project_path(path).unwrap_or_default(), *last_modified),
+                None => String::new(),
+            })
+            .collect();
+        let count = candidates.len();
+        Shortlist {
+            candidates,
+            groups,
+            picked: vec![false; count],
+            passed: vec![None; count],
+            limit,
+            newest_first: options.time_budget.is_some(),
+            explaining: options.explain_plan,
+        }
+    }
+
+    /// Paths of the candidates picked so far.
+    fn picked(&self) -> impl Iterator<Item = &Path> {
+        self.candidates.iter().zip(&self.picked).filter(|(_, picked)| **picked).map(|((_, _, path), _)| path.as_path())
+    }
+
+    /// Picks more candidates for each group with fewer than `limit` passing, judging those
+    /// already picked with `passes`, and returns their paths: none once every group has enough
+    /// or has run out. Under `--budget` a batch is newest first, so the sessions left without
+    /// time are the oldest.
+    fn next_batch(&mut self, mut passes: impl FnMut(&Path) -> bool) -> Vec<PathBuf> {
+        let mut passing: HashMap<&str, usize> = HashMap::new();
+        for index in 0..self.candidates.len() {
+            if !self.picked[index] {
+                continue;
+            }
+            let passed = *self.passed[index].get_or_insert_with(|| passes(&self.candidates[index].2));
+            *passing.entry(&self.groups[index]).or_default() += usize::from(passed);
+        }
+        let mut wanted: HashMap<String, usize> = HashMap::new();
+        let mut batch = Vec::new();
+        for index in 0..self.candidates.len() {
+            if self.picked[index] {
+                continue;
+            }
+            let group = &self.groups[index];
+            let still_wanted = wanted.entry(group.clone()).or_insert_with(|| {
+                let missing = self.limit.saturating_sub(passing.get(group.as_str()).copied().unwrap_or(0));
+                missing.saturating_mul(ANALYSIS_CANDIDATES_PER_RESULT)
+            });
+            if *still_wanted > 0 {
+                *still_wanted -= 1;
+                self.picked[index] = true;
+                batch.push(index);
+            }
+        }
+        if self.newest_first {
+            batch.sort_by(|&a, &b| self.candidates[b].1.cmp(&self.candidates[a].1).then_with(|| a.cmp(&b)));
+        }
+        batch.into_iter().map(|index| self.candidates[index].2.clone()).collect()
+    }
+
+    /// The picked candidates, best first, reporting the shortlist under `--explain-plan`.
+    fn into_picked(self) -> Vec<(FileMatches, PathBuf)> {
+        let ranked = self.candidates.len();
+        let picked: Vec<(FileMatches, PathBuf)> = self
+            .candidates
+            .into_iter()
+            .zip(self.picked)
+            .filter(|(_, picked)| *picked)
+            .map(|((file_matches, _, path), _)| (file_matches, path))
+            .collect();
+        let stage = format!("shortlist ({} per result)", ANALYSIS_CANDIDATES_PER_RESULT);
+        explain_stage(self.explaining, &stage, ranked, picked.len());
+        picked
+    }
+}
+
+/// Like `find_sessions`, but over session files given with `--file` instead of the projects tree.
+fn find_sessions_in_files(
+    files: &[&str],
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    options: &SearchOptions,
+) -> Result<Vec<SessionInfo>> {
+    let matcher = query_matcher(search_terms);
+    let mut sessions = Vec::new();
+
+    for file in files {
+        if *file == STDIN_REFERENCE {
+            return Err(SessionFinderError::Usage(
+                "Reading a session from stdin is supported by timeline and code-diff only".to_string(),
+            ));
+        }
+        let path = Path::new(file);
+        let content = read_session_text(path).map_err(|e| match e {
+            SessionFinderError::Io(e) => io::Error::new(e.kind(), format!("Could not read {}: {}", file, e)).into(),
+            e => e,
+        })?;
+        let content = content.to_lowercase();
+        if !matcher.is_match(&content) {
+            debug!(file, "Skipped: no search term found");
+            continue;
+        }
+        if let Some(session_info) = analyze_session_file(path, search_terms, project_filter, recent_days, options)? {
+            sessions.push(session_info);
+        }
+    }
+
+    Ok(sessions)
+}
+
+fn analyze_session_file(
+    file_path: &Path,
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    options: &SearchOptions,
+) -> Result<Option<SessionInfo>> {
+    let metadata = fs::metadata(file_path)?;
+    let last_modified = DateTime::from(metadata.modified()?);
+    let file_size_bytes = metadata.len();
+    
+    // Check if file is recent enough
+    if let Some(days) = recent_days {
+        let cutoff = Utc::now() - chrono::Duration::days(days);
+        if last_modified < cutoff {
+            return Ok(None);
+        }
+    }
+    
+    let session_id = extract_session_id(file_path)?;
+    let project_path = decode_project_path(file_path)?;
+    
+    // Check project filter
+    if let Some(filter) = project_filter {
+        if !project_path.contains(filter) {
+            return Ok(None);
+        }
+    }
+    
+    let analysis = match cached_analysis(file_path, search_terms, last_modified, file_size_bytes) {
+        Some(analysis) => analysis,
+        None if caching_analyses() => analyze_appended(file_path, search_terms, last_modified, file_size_bytes, options)?,
+        None => match options.memory_budget {
+            Some(budget) => analyze_session_in_chunks(file_path, search_terms, budget.chunk_bytes(), options)?,
+            None => analyze_whole_session(file_path, search_terms, options)?,
+        },
+    };
+    let (topics, first_messages, last_messages, common_terms) = analysis.content;
+    let title = read_session_title(file_path)
+        .or_else(|| claude_data().title(&session_id).map(str::to_string))
+        .or(analysis.title);
+    let todos = claude_data().todos(&session_id).to_vec();
+    
+    Ok(Some(SessionInfo {
+        path: file_path.to_path_buf(),
+        session_id,
+        project_path,
+        last_modified,
+        line_count: analysis.line_count,
+        message_count: analysis.message_count,
+        topics,
+        first_messages,
+        last_messages,
+        common_terms,
+        file_size_bytes,
+        parse_diagnostics: analysis.parse_diagnostics,
+        source: session_source(file_path),
+        slash_commands: analysis.slash_commands,
+        title,
+        todos,
+        matches: analysis.matches,
+        tokens: analysis.tokens,
+        prefilter: None,
+        matched_messages: Vec::new(),
+        active: is_active(file_path),
+        growth: None,
+    }))
+}
+
+/// `analyze_session_file` through `tokio::fs`. Sessions are read whole, and under
+/// `--max-memory` parsed a chunk at a time from that text.
+#[cfg(feature = "tokio")]
+async fn analyze_session_file_async(
+    file_path: &Path,
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    options: &SearchOptions,
+) -> Result<Option<SessionInfo>> {
+    use resolve::read_session_title_async;
+
+    let metadata = tokio::fs::metadata(file_path).await?;
+    let last_modified = DateTime::from(metadata.modified()?);
+    let file_size_bytes = metadata.len();
+    if let Some(days) = recent_days {
+        if last_modified < Utc::now() - chrono::Duration::days(days) {
+            return Ok(None);
+        }
+    }
+    let session_id = extract_session_id(file_path)?;
+    let project_path = decode_project_path(file_path)?;
+    if project_filter.is_some_and(|filter| !project_path.contains(filter.as_str())) {
+        return Ok(None);
+    }
+
+    let analysis = match cached_analysis(file_path, search_terms, last_modified, file_size_bytes) {
+        Some(analysis) => analysis,
+        None if caching_analyses() => {
+            analyze_appended_async(file_path, search_terms, last_modified, file_size_bytes, options).await?
+        }
+        None => {
+            let mut analyzer = SessionAnalyzer::new(search_terms, options);
+            let chunk_bytes = options.memory_budget.map_or(usize::MAX, |budget| budget.chunk_bytes());
+            analyzer.advance_async(file_path, chunk_bytes).await?;
+            analyzer.finish()
+        }
+    };
+    let summary_title = read_session_title_async(file_path).await;
+    let (topics, first_messages, last_messages, common_terms) = analysis.content;
+    let title = summary_title
+        .or_else(|| claude_data().title(&session_id).map(str::to_string))
+        .or(analysis.title);
+    let todos = claude_data().todos(&session_id).to_vec();
+
+    Ok(Some(SessionInfo {
+        path: file_path.to_path_buf(),
+        session_id,
+        project_path,
+        last_modified,
+        line_count: analysis.line_count,
+        message_count: analysis.message_count,
+        topics,
+        first_messages,
+        last_messages,
+        common_terms,
+        file_size_bytes,
+        parse_diagnostics: analysis.parse_diagnostics,
+        source: session_source(file_path),
+        slash_commands: analysis.slash_commands,
+        title,
+        todos,
+        matches: analysis.matches,
+        tokens: analysis.tokens,
+        prefilter: None,
+        matched_messages: Vec::new(),
+        active: is_active(file_path),
+        growth: None,
+    }))
+}
+
+/// What `analyze_session_file` reads out of a session's messages
+#[derive(Clone)]
+struct SessionAnalysis {
+    line_count: usize,
+    message_count: usize,
+    content: SessionContentAnalysis,
+    parse_diagnostics: ParseDiagnostics,
+    slash_commands: Vec<(String, usize)>,
+    /// Inferred from the first prompt
+    title: Option<String>,
+    matches: MatchSummary,
+    tokens: TokenEstimate,
+}
+
+fn analyze_whole_session(file_path: &Path, search_terms: &[&str], options: &SearchOptions) -> Result<SessionAnalysis> {
+    analyze_session_text(&read_session_text(file_path)?, search_terms, options)
+}
+
+fn analyze_session_text(content: &str, search_terms: &[&str], options: &SearchOptions) -> Result<SessionAnalysis> {
+    let (messages, parse_diagnostics) = parse_session_messages_with_diagnostics(content);
+    Ok(SessionAnalysis {
+        line_count: content.lines().count(),
+        message_count: messages.len(),
+        content: analyze_session_content_enhanced(&messages, search_terms, options)?,
+        parse_diagnostics,
+        slash_commands: slash_commands(&messages),
+        title: infer_title(&messages, options.render),
+        matches: match_summary(&messages, search_terms, options),
+        tokens: estimate_tokens(&messages),
+    })
+}
+
+/// Under `--max-memory`, the session is parsed and analysed `chunk_bytes` at a time, and each
+/// chunk is dropped once it has been counted.
+fn analyze_session_in_chunks(file_path: &Path, search_terms: &[&str], chunk_bytes: usize, options: &SearchOptions) -> Result<SessionAnalysis> {
+    let mut analyzer = SessionAnalyzer::new(search_terms, options);
+    analyzer.advance(file_path, chunk_bytes)?;
+    Ok(analyzer.finish())
+}
+
+/// In the daemon: continues the analysis kept from before a session was appended to (resumed
+/// in Claude Code), reading only the new lines, or analyses it from the start if it was
+/// rewritten or never analysed for these terms.
+fn analyze_appended(
+    file_path: &Path,
+    search_terms: &[&str],
+    last_modified: DateTime<Utc>,
+    file_size_bytes: u64,
+    options: &SearchOptions,
+) -> Result<SessionAnalysis> {
+    let mut analyzer = match cached_analyzer(file_path, search_terms) {
+        Some(analyzer) if analyzer.continues_in(file_path) => analyzer,
+        _ => SessionAnalyzer::new(search_terms, options),
+    };
+    let resumed_at = analyzer.offset;
+    analyzer.advance(file_path, options.memory_budget.map_or(usize::MAX, |budget| budget.chunk_bytes()))?;
+    debug!(file = %file_path.display(), from = resumed_at, to = analyzer.offset, "Analysed");
+    let analysis = analyzer.clone().finish();
+    cache_analysis(file_path, search_terms, last_modified, file_size_bytes, &analysis, analyzer);
+    Ok(analysis)
+}
+
+/// `analyze_appended` through `tokio::fs`.
+#[cfg(feature = "tokio")]
+async fn analyze_appended_async(
+    file_path: &Path,
+    search_terms: &[&str],
+    last_modified: DateTime<Utc>,
+    file_size_bytes: u64,
+    options: &SearchOptions,
+) -> Result<SessionAnalysis> {
+    let mut analyzer = match cached_analyzer(file_path, search_terms) {
+        Some(analyzer) if analyzer.continues_in_async(file_path).await => analyzer,
+        _ => SessionAnalyzer::new(search_terms, options),
+    };
+    let resumed_at = analyzer.offset;
+    let chunk_bytes = options.memory_budget.map_or(usize::MAX, |budget| budget.chunk_bytes());
+    analyzer.advance_async(file_path, chunk_bytes).await?;
+    debug!(file = %file_path.display(), from = resumed_at, to = analyzer.offset, "Analysed");
+    let analysis = analyzer.clone().finish();
+    cache_analysis(file_path, search_terms, last_modified, file_size_bytes, &analysis, analyzer);
+    Ok(analysis)
+}
+
+/// A session's analysis as far as it has been read, which can carry on from there when the
+/// session is appended to.
+#[derive(Clone)]
+struct SessionAnalyzer {
+    content: ContentAnalyzer,
+    matches: MatchCounter,
+    tokens: TokenCounter,
+    slash_commands: Vec<(String, usize)>,
+    title: Option<String>,
+    /// How the title's markers are drawn
+    render: RenderOptions,
+    message_count: usize,
+    line_count: usize,
+    diagnostics: ParseDiagnostics,
+    /// End of the last complete line read
+    offset: u64,
+    /// Hash of the content just before `offset`, which an append leaves unchanged
+    boundary: String,
+    /// A last line still being written: analysed in the results, read again next time
+    partial_line: Option<String>,
+}
+
+impl SessionAnalyzer {
+    fn new(search_terms: &[&str], options: &SearchOptions) -> Self {
+        SessionAnalyzer {
+            content: ContentAnalyzer::new(search_terms, options),
+            matches: MatchCounter::new(search_terms, options),
+            tokens: TokenCounter::default(),
+            slash_commands: Vec::new(),
+            title: None,
+            render: options.render,
+            message_count: 0,
+            line_count: 0,
+            diagnostics: ParseDiagnostics::default(),
+            offset: 0,
+            boundary: String::new(),
+            partial_line: None,
+        }
+    }
+
+    /// Whether the file still starts with what was analysed, so only what follows is new.
+    fn continues_in(&self, file_path: &Path) -> bool {
+        fs::metadata(file_path).is_ok_and(|metadata| metadata.len() >= self.offset)
+            && boundary_hash(file_path, self.offset).is_ok_and(|hash| hash == self.boundary)
+    }
+
+    /// Reads and analyses the file from where the last call stopped.
+    fn advance(&mut self, file_path: &Path, chunk_bytes: usize) -> Result<()> {
+        let diagnostics = std::mem::take(&mut self.diagnostics);
+        let mut chunks = SessionChunks::resume(file_path, chunk_bytes, self.offset, self.line_count, diagnostics)?;
+        while let Some(messages) = chunks.next_chunk()? {
+            self.add(&messages);
+        }
+        self.offset = chunks.offset;
+        self.line_count = chunks.line_count;
+        self.diagnostics = chunks.diagnostics;
+        self.partial_line = chunks.partial_line;
+        self.boundary = boundary_hash(file_path, self.offset)?;
+        Ok(())
+    }
+
+    /// `continues_in` through `tokio::fs`.
+    #[cfg(feature = "tokio")]
+    async fn continues_in_async(&self, file_path: &Path) -> bool {
+        tokio::fs::metadata(file_path).await.is_ok_and(|metadata| metadata.len() >= self.offset)
+            && session_index::boundary_hash_async(file_path, self.offset).await.is_ok_and(|hash| hash == self.boundary)
+    }
+
+    /// `advance` through `tokio::fs`, holding one chunk of the file at a time.
+    #[cfg(feature = "tokio")]
+    async fn advance_async(&mut self, file_path: &Path, chunk_bytes: usize) -> Result<()> {
+        let diagnostics = std::mem::take(&mut self.diagnostics);
+        let mut chunks =
+            SessionChunks::resume_async(file_path, chunk_bytes, self.offset, self.line_count, diagnostics).await?;
+        while let Some(messages) = chunks.next_chunk_async().await? {
+            self.add(&messages);
+        }
+        self.offset = chunks.offset;
+        self.line_count = chunks.line_count;
+        self.diagnostics = chunks.diagnostics;
+        self.partial_line = chunks.partial_line;
+        self.boundary = session_index::boundary_hash_async(file_path, self.offset).await?;
+        Ok(())
+    }
+
+    fn add(&mut self, messages: &[SessionMessage]) {
+        self.content.add(messages);
+        for (offset, msg) in messages.iter().enumerate() {
+            self.matches.add(self.message_count + offset, messages, offset);
+            self.tokens.add(msg);
+        }
+        for (command, count) in slash_commands(messages) {
+            match self.slash_commands.iter_mut().find(|(known, _)| *known == command) {
+                Some((_, total)) => *total += count,
+                None => self.slash_commands.push((command, count)),
+            }
+        }
+        if self.title.is_none() {
+            self.title = infer_title(messages, self.render);
+        }
+        self.message_count += messages.len();
+    }
+
+    /// The analysis, counting a last line still being written as it stands.
+    fn finish(mut self) -> SessionAnalysis {
+        if let Some(line) = self.partial_line.take() {
+            self.line_count += 1;
+            let messages: Vec<SessionMessage> =
+                parse_unterminated_line(&line, self.line_count, &mut self.diagnostics).into_iter().collect();
+            self.add(&messages);
+        }
+        SessionAnalysis {
+            line_count: self.line_count,
+            message_count: self.message_count,
+            content: self.content.finish(),
+            parse_diagnostics: self.diagnostics,
+            slash_commands: self.slash_commands,
+            title: self.title,
+            matches: self.matches.summary,
+            tokens: self.tokens.finish(),
+        }
+    }
+}
+
+fn extract_session_id(file_path: &Path) -> Result<String> {
+    extract_session_id_from_path(file_path)
+}
+
+/// Encodes a project directory the way Claude Code names its per-project session folders.
+fn encode_project_path(project_dir: &Path) -> String {
+    project_dir
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' })
+        .collect()
+}
+
+fn decode_project_path(file_path: &Path) -> Result<String> {
+    let parent = file_path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .unwrap_or("");
+    
+    let decoded = decode_project_dir(parent);
+    Ok(remap_project_root(&decoded).unwrap_or(decoded))
+}
+
+/// A project folder's name as the path it stands for, before any `[project_roots]` mapping.
+fn decode_project_dir(name: &str) -> String {
+    // Decode escaped path: -Users-amar-repos-project -> /Users/amar/repos/project
+    match name.strip_prefix('-') {
+        Some(encoded) => format!("/{}", encoded.replace('-', "/")),
+        None => name.to_string(),
+    }
+}
+
+/// Topics, first messages, last messages, and common terms extracted from a session.
+type SessionContentAnalysis = (Vec<String>, Vec<String>, Vec<String>, Vec<String>);
+
+fn analyze_session_content_enhanced(messages: &[SessionMessage], search_terms: &[&str], options: &SearchOptions) -> Result<SessionContentAnalysis> {
+    let mut analyzer = ContentAnalyzer::new(search_terms, options);
+    analyzer.add(messages);
+    Ok(analyzer.finish())
+}
+
+/// Accumulates a `SessionContentAnalysis` over a session's messages, which may arrive in
+/// chunks. Only the first and last few message excerpts are kept.
+#[derive(Clone)]
+struct ContentAnalyzer {
+    matcher: Arc<TermMatcher>,
+    exclusions: Exclusions,
+    topics: Vec<String>,
+    previews: PreviewCollector,
+    word_freq: HashMap<String, usize>,
+    /// How many of the most frequent words are kept
+    common_terms: usize,
+    /// False once `--budget` is half used: word frequencies are the costliest part
+    count_words: bool,
+    stopwords: HashSet<String>,
+}
+
+impl ContentAnalyzer {
+    fn new(search_terms: &[&str], options: &SearchOptions) -> Self {
+        ContentAnalyzer {
+            matcher: query_matcher(search_terms),
+            exclusions: options.exclusions.clone(),
+            topics: Vec::new(),
+            previews: PreviewCollector::new(&options.preview, options.render),
+            word_freq: HashMap::new(),
+            common_terms: options.preview.common_terms,
+            count_words: !options.time_budget.as_ref().is_some_and(TimeBudget::skips_common_terms),
+            stopwords: options.stopwords.clone(),
+        }
+    }
+
+    fn add(&mut self, messages: &[SessionMessage]) {
+        for msg in messages {
+            self.previews.add(msg);
+            let Some(inner_msg) = &msg.message else { continue };
+            let (Some(_), Some(content)) = (&inner_msg.role, &inner_msg.content) else { continue };
+            let content_text = extract_content_text(content);
+            if content_text.is_empty() {
+                continue;
+            }
+
+            let lowercase_text = content_text.to_lowercase();
+
+            // Extract topics from content matching search terms
+            if !self.exclusions.is_excluded(&lowercase_text) {
+                for regex in self.matcher.matching_topic_regexes(&lowercase_text) {
+                    extract_topics_from_text(&content_text, regex, &mut self.topics);
+                }
+            }
+
+            if !self.count_words {
+                continue;
+            }
+            // Count word frequencies for common terms (filtering boilerplate)
+            for word in lowercase_text.split_whitespace() {
+                let clean_word = word.trim_matches(|c: char| !c.is_alphanumeric());
+                if is_common_term_word(clean_word, &self.stopwords) {
+                    match self.word_freq.get_mut(clean_word) {
+                        Some(count) => *count += 1,
+                        None => {
+                            self.word_freq.insert(clean_word.to_string(), 1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> SessionContentAnalysis {
+        // The most common meaningful terms
+        let mut common_terms: Vec<(String, usize)> = self.word_freq.into_iter().collect();
+        common_terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let common_terms: Vec<String> = common_terms.into_iter().take(self.common_terms).map(|(word, count)| format!("{}({})", word, count)).collect();
+
+        // Deduplicate topics
+        let mut topics = self.topics;
+        topics.sort();
+        topics.dedup();
+
+        let (first_messages, last_messages) = self.previews.finish();
+        (topics, first_messages, last_messages, common_terms)
+    }
+}
+
+fn extract_topics_from_text(text: &str, topic_regex: &Regex, topics: &mut Vec<String>) {
+    for mat in topic_regex.find_iter(text) {
+        let topic = mat.as_str().trim();
+        if topic.len() > 3 && topic.len() < 50 {
+            topics.push(topic.to_string());
+        }
+    }
+}
+
+
+
+/// Orders sessions by the chosen sort, breaking ties by recency and then session ID, so the
+/// same sessions always come out in the same order whatever order they were found in.
+pub fn rank_and_limit_sessions(mut sessions: Vec<SessionInfo>, limit: usize, sort: SortOrder) -> Vec<SessionInfo> {
+    match sort {
+        SortOrder::Recent => sessions.sort_by(newest_first),
+        SortOrder::Matches => sessions.sort_by(|a, b| {
+            b.matches.occurrences.cmp(&a.matches.occurrences).then_with(|| newest_first(a, b))
+        }),
+        SortOrder::Relevance => sort_by_relevance(&mut sessions),
+    }
+    sessions.into_iter().take(limit).collect()
+}
+
+/// Newest first; sessions modified at the same moment by session ID.
+fn newest_first(a: &SessionInfo, b: &SessionInfo) -> std::cmp::Ordering {
+    b.last_modified.cmp(&a.last_modified).then_with(|| a.session_id.cmp(&b.session_id))
+}
+
+fn sort_by_relevance(sessions: &mut [SessionInfo]) {
+    // Sort by relevance (more topics = higher relevance) and recency; sessions that only
+    // read a matching file go last
+    sessions.sort_by(|a, b| {
+        a.matches.only_in_reads().cmp(&b.matches.only_in_reads())
+            .then_with(|| b.topics.len().cmp(&a.topics.len()))
+            .then_with(|| newest_first(a, b))
+    });
+}
+
+/// Whether a lowercase word can be one of a session's common terms: three letters or more,
+/// and neither boilerplate nor a stopword from `.session-finder.toml`.
+fn is_common_term_word(word: &str, stopwords: &HashSet<String>) -> bool {
+    word.len() > 2 && !is_boilerplate_word(word) && !stopwords.contains(word)
+}
+
+fn is_boilerplate_word(word: &str) -> bool {
+    matches!(word,
+        // Common English words
+        "the" | "and" | "for" | "with" | "that" | "this" | "but" | "not" | "are" | "was" | "were" |
+        "has" | "had" | "have" | "can" | "will" | "would" | "could" | "should" | "may" | "might" |
+        "get" | "put" | "set" | "run" | "add" | "see" | "now" | "all" | 
+        "one" | "two" | "three" | "four" | "five" | "six" | "seven" | "eight" | "nine" | "ten" |
+        "from" | "into" | "over" | "then" | "when" | "what" | "where" | "which" | "who" | "why" | "how" |
+        "you" | "your" | "i'm" | "i'll" | "i've" | "it's" | "we're" | "they" | "them" | "their" |
+        "more" | "most" | "some" | "any" | "each" | "both" | "other" | "same" | "next" | "last" |
+        "first" | "out" | "off" | "way" | "too" | "own" | "just" | "only" | "also" | "back" |
+        
+        // Programming boilerplate
+        "let" | "mut" | "use" | "pub" | "impl" | "struct" | "enum" | "trait" | "fn" |
+        "async" | "await" | "self" | "super" | "crate" | "mod" | "extern" | "const" | "static" |
+        "str" | "string" | "bool" | "true" | "false" | "none" | "ok" | "err" | "result" |
+        "vec" | "option" | "clone" | "default" | "debug" | "derive" |
+        "cargo" | "toml" | "src" | "lib" | "main" | "test" | "tests" | "target" | "build" |
+        
+        // Claude Code / JSONL boilerplate
+        "user" | "assistant" | "message" | "content" | "role" | "timestamp" | "session" |
+        "request" | "response" | "interrupted" | "tool" |
+        
+        // Common version numbers and paths that appear frequently
+        "100644" | "registry" | "https" | "github" | "com" | "crates" | "index" |
+        
+        // Common technical terms that don't add much context
+        "code" | "line" | "file" | "path" | "name" | "text" | "data" | "info" | "log" |
+        "check" | "fix" | "update" | "change" | "version" | "issue" | "error" | "warning" |
+        "output" | "input" | "return" | "function" | "method" | "call" | "create" | "make" |
+        "work" | "working" | "works" | "used" | "using" | "added" | "removed" | "fixed" |
+        "need" | "needs" | "want" | "trying" | "looks" | "seems" | "actually" | "really" |
+        "good" | "great" | "perfect" | "okay" | "right" | "correct" | "wrong" | "better" |
+        "think" | "know" | "understand" | "mean" | "say" | "tell" | "show" | "find" |
+        "help" | "try" | "attempt" | "continue" | "start" | "stop" | "end" | "done" |
+        "here" | "there" |
+        "before" | "after" | "during" | "while" | "until" | "since" | "about" | "around" |
+        "above" | "below" | "under" | "through" | "across" | "between" | "among" |
+        "without" | "within" | "outside" | "inside" | "instead" | "besides" | "except" |
+        "including" | "excluding" | "according" | "regarding" | "concerning" | "despite" |
+        "however" | "therefore" | "otherwise" | "moreover" | "furthermore" | "nevertheless" |
+        "although" | "because" | "unless" | "whether" | "either" | "neither" |
+        "different" | "similar" | "various" | "several" | "multiple" | "single" | "individual" |
+        "general" | "specific" | "particular" | "special" | "common" | "normal" | "regular" |
+        "current" | "previous" | "recent" | "latest" | "original" | "initial" | "final" |
+        "example" | "instance" | "case" | "situation" | "condition" | "state" | "status" |
+        "problem" | "solution" | "answer" | "question" | "reason" | "cause" |
+        "important" | "necessary" | "required" | "optional" | "available" | "possible" |
+        "simple" | "complex" | "easy" | "difficult" | "hard" | "soft" | "quick" | "slow" |
+        "big" | "small" | "large" | "little" | "long" | "short" | "high" | "low" |
+        "full" | "empty" | "complete" | "incomplete" | "total" | "partial" | "whole" |
+        "sure" | "certain" | "unclear" | "unknown" | "obvious" | "clear" | "visible" |
+        "open" | "close" | "closed" | "old" | "fresh" | "clean" | "dirty" |
+        "ready" | "busy" | "free" | "active" | "inactive" | "enabled" | "disabled" |
+        "public" | "private" | "local" | "remote" | "external" | "internal" | "native"
+    )
+}
+
+/// Prints, on stderr, each analyzed session with unparseable or unexpected lines, then totals.
+fn report_parse_diagnostics(sessions: &[SessionInfo], render: RenderOptions) {
+    let mut totals = ParseDiagnostics::default();
+    for session in sessions {
+        if session.parse_diagnostics.problem_count() > 0 {
+            eprintln!("Parse diagnostics for {}: {}", session.session_id, session.parse_diagnostics.summary(render));
+        }
+        totals.merge(&session.parse_diagnostics);
+    }
+    eprintln!("Parsed {} session(s): {}", sessions.len(), totals.summary(render));
+}
+
+/// Says nothing matched, and that only the current repository was searched when it was.
+fn display_no_results(workspace: Option<&Path>) {
+    println!("No sessions found matching your criteria.");
+    if let Some(root) = workspace {
+        println!("Only sessions run in {} were searched; --global searches every project.", root.display());
+    }
+}
+
+/// Ends `--stream` output with how many sessions were printed and, when a sort order was
+/// asked for, the ranked results, since streamed sessions come in the order they were analysed.
+fn display_stream_end(
+    streamed: &HashSet<String>,
+    ranked: &[SessionInfo],
+    sort: Option<SortOrder>,
+    workspace: Option<&Path>,
+) {
+    if streamed.is_empty() {
+        display_no_results(workspace);
+        return;
+    }
+    println!("Streamed {} session(s).", streamed.len());
+    if let Some(sort) = sort.filter(|_| !ranked.is_empty()) {
+        println!("\nRanked by {}:", sort.name());
+        for (i, session) in ranked.iter().enumerate() {
+            println!("{}. {} ({})", i + 1, session.title.as_deref().unwrap_or("(untitled)"), session.session_id);
+        }
+    }
+}
+
+/// Prints the results, under a heading per group when `--group-by` is given. `groups`
+/// partitions `sessions` in order. Without any, says whether only `workspace` was searched.
+fn display_results(
+    sessions: &[SessionInfo],
+    groups: Option<&[ResultGroup]>,
+    context_window: u64,
+    render: RenderOptions,
+    workspace: Option<&Path>,
+) -> Result<()> {
+    if sessions.is_empty() {
+        display_no_results(workspace);
+        return Ok(());
+    }
+    
+    let store = Store::load()?;
+    let Some(groups) = groups else {
+        println!("Found {} relevant session(s):\n", sessions.len());
+        for (i, session) in sessions.iter().enumerate() {
+            display_session(i + 1, session, &store, context_window, render);
+        }
+        return Ok(());
+    };
+
+    println!("Found {} relevant session(s) in {} group(s):\n", sessions.len(), groups.len());
+    let mut start = 0;
+    for group in groups {
+        if group.shown < group.total {
+            println!("== {} ({} session(s), showing {}) ==\n", group.key, group.total, group.shown);
+        } else {
+            println!("== {} ({} session(s)) ==\n", group.key, group.total);
+        }
+        for (i, session) in sessions[start..start + group.shown].iter().enumerate() {
+            display_session(start + i + 1, session, &store, context_window, render);
+        }
+        start += group.shown;
+    }
+    
+    Ok(())
+}
+
+/// Records each session in the session index, noting on it how much was appended since the
+/// index last saw it.
+fn note_growth(sessions: &mut [SessionInfo], index: &mut SessionIndex) {
+    for session in sessions {
+        session.growth = index
+            .observe(&session.path, session.file_size_bytes, session.last_modified, session.title.as_deref())
+            .unwrap_or_else(|e| {
+                warn!("Could not index {}: {}", session.path.display(), e);
+                None
+            });
+    }
+}
+
+fn display_session(number: usize, session: &SessionInfo, store: &Store, context_window: u64, render: RenderOptions) {
+    let active = if session.active { format!(" {} active", render.glyph(Glyph::Active)) } else { String::new() };
+    match &session.title {
+        Some(title) => {
+            println!("{}. {}{}", number, title, active);
+            println!("   Session: {}", session.session_id);
+        }
+        None => println!("{}. Session: {}{}", number, session.session_id, active),
+    }
+    println!("   File: {}", session.path.display());
+    println!("   Project: {}", session.project_path);
+    let matches = &session.matches;
+    let reads = if matches.read_occurrences > 0 {
+        format!("; {} more in file reads", matches.read_occurrences)
+    } else {
+        String::new()
+    };
+    match (matches.first_message, matches.last_message) {
+        (Some(first), Some(last)) => println!(
+            "   Matches: {} across {} message(s) (first at message {}, last at message {}){}",
+            matches.occurrences, matches.messages, first, last, reads
+        ),
+        _ if matches.read_occurrences > 0 => {
+            println!("   Matches: only in file reads ({})", matches.read_occurrences)
+        }
+        _ => {}
+    }
+    if let Some(owner) = session.source.strip_prefix(TEAM_SOURCE_PREFIX) {
+        println!("   Shared by: {}", describe_owner(owner));
+    } else if session.source != "claude" {
+        println!("   Source: {} (imported)", session.source);
+    }
+    let tags = store.tags(&session.session_id);
+    if !tags.is_empty() {
+        println!("   Tags: {}", tags.join(", "));
+    }
+    if !session.slash_commands.is_empty() {
+        let commands: Vec<String> = session.slash_commands
+            .iter()
+            .map(|(command, count)| if *count == 1 { command.clone() } else { format!("{} {}{}", command, render.glyph(Glyph::Times), count) })
+            .collect();
+        println!("   Commands: {}", commands.join(", "));
+    }
+    for note in store.notes(&session.session_id) {
+        match note.message {
+            Some(message) => println!("   Note (message {}): {}", message, note.text),
+            None => println!("   Note: {}", note.text),
+        }
+    }
+    println!("   Modified: {}", session.last_modified.format("%Y-%m-%d %H:%M:%S UTC"));
+    if let Some(growth) = &session.growth {
+        println!("   Resumed: {}", growth.describe());
+    }
+    println!("   Size: {} bytes, {} lines", session.file_size_bytes, session.line_count);
+    println!("   Context: {}", session.tokens.describe(context_window));
+    if !session.todos.is_empty() {
+        println!("   Todos: {}", todo_summary(&session.todos));
+    }
+    
+    if !session.topics.is_empty() {
+        println!("   Topics: {}", session.topics.join(", "));
+    }
+    
+    if !session.matched_messages.is_empty() {
+        println!("   Matched:");
+        for msg in &session.matched_messages {
+            println!("{}", render.wrap_indented(msg, "     ", "       "));
+        }
+    }
+    
+    if !session.first_messages.is_empty() {
+        println!("   First messages:");
+        for msg in &session.first_messages {
+            println!("{}", render.wrap_indented(msg, "     ", "       "));
+        }
+    }
+    
+    if !session.last_messages.is_empty() {
+        println!("   Last messages:");
+        for msg in &session.last_messages {
+            println!("{}", render.wrap_indented(msg, "     ", "       "));
+        }
+    }
+    
+    if !session.common_terms.is_empty() {
+        println!("{}", render.wrap_indented(&session.common_terms.join(", "), "   Common terms: ", "     "));
+    }
+    
+    if session.source == "claude" {
+        println!("   Resume: {}", resume_command(&session_working_dir(&session.path), &session.session_id));
+    }
+    println!();
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use test_support::{fake_home, write_session};
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn candidate(name: &str, occurrences: usize) -> Candidate {
+        let file_matches = FileMatches { lines: occurrences, occurrences, line_offsets: Vec::new() };
+        (file_matches, Utc::now(), PathBuf::from(format!("/sessions/{}.jsonl", name)))
+    }
+
+    #[test]
+    fn shortlist_picks_more_while_too_few_pass() {
+        let candidates: Vec<Candidate> = (0..12).map(|index| candidate(&index.to_string(), 12 - index)).collect();
+        let mut shortlist = Shortlist::new(candidates, 1, None, &SearchOptions::default());
+        let first = shortlist.next_batch(|_| unreachable!("nothing is picked yet"));
+        assert_eq!(first.len(), ANALYSIS_CANDIDATES_PER_RESULT);
+        let second = shortlist.next_batch(|_| false);
+        assert_eq!(second.len(), ANALYSIS_CANDIDATES_PER_RESULT);
+        assert!(shortlist.next_batch(|path| path.ends_with("7.jsonl")).is_empty());
+        assert_eq!(shortlist.into_picked().len(), 2 * ANALYSIS_CANDIDATES_PER_RESULT);
+    }
+
+    #[test]
+    fn shortlist_stops_when_candidates_run_out() {
+        let mut shortlist = Shortlist::new(vec![candidate("only", 1)], 3, None, &SearchOptions::default());
+        assert_eq!(shortlist.next_batch(|_| false).len(), 1);
+        assert!(shortlist.next_batch(|_| false).is_empty());
+    }
+
+    #[test]
+    fn sort_recent_shortlists_the_newest_sessions() {
+        let (_home, home) = fake_home();
+        let busy = ["gadget gadget gadget gadget gadget", "gadget", "ok", "fine", "done"];
+        for index in 0..12 {
+            write_session(home, "-work-gadgets", &format!("old-{}", index), &busy, 30 * DAY);
+        }
+        write_session(home, "-work-gadgets", "new", &["one gadget", "ok", "fine", "sure", "done"], DAY / 24);
+
+        let project = "work/gadgets".to_string();
+        let defaults = SearchOptions::from_config(&Config::default(), true, true);
+        let found = |sort: SortOrder| -> Vec<String> {
+            let options = SearchOptions { sort, ..defaults.clone() };
+            let sessions = find_sessions(&["gadget"], Some(&project), None, 2, None, &options).unwrap();
+            let ranked = rank_and_limit_sessions(sessions, 2, sort);
+            ranked.into_iter().map(|session| session.session_id).collect()
+        };
+        assert_eq!(found(SortOrder::Recent)[0], "new");
+        assert!(!found(SortOrder::Matches).contains(&"new".to_string()));
+    }
+
+    #[test]
+    fn each_search_uses_its_own_stopwords() {
+        let (_home, home) = fake_home();
+        let messages = ["sprocket gearbox gearbox", "gearbox again", "ok", "fine", "done"];
+        write_session(home, "-work-sprockets", "gears", &messages, DAY);
+
+        let project = "work/sprockets".to_string();
+        let common_terms = |stopwords: &[&str]| -> Vec<String> {
+            let stopwords = stopwords.iter().map(|word| word.to_string()).collect();
+            let options = SearchOptions { stopwords, ..SearchOptions::from_config(&Config::default(), true, true) };
+            let sessions = find_sessions(&["sprocket"], Some(&project), None, 1, None, &options).unwrap();
+            sessions[0].common_terms.clone()
+        };
+        let mentions_gearbox = |terms: Vec<String>| terms.iter().any(|term| term.starts_with("gearbox("));
+        assert!(!mentions_gearbox(common_terms(&["gearbox"])));
+        assert!(mentions_gearbox(common_terms(&[])));
+    }
+
+    #[test]
+    fn previews_excerpt_the_matched_lines_at_their_offsets() {
+        let (_home, home) = fake_home();
+        let long_reply = format!("{} the gasket leaks {}", "filler ".repeat(100), "more ".repeat(100));
+        let messages = ["start here", "ok", "which part?", &long_reply, "done"];
+        write_session(home, "-work-gaskets", "leak", &messages, DAY);
+
+        let project = "work/gaskets".to_string();
+        let options = SearchOptions::from_config(&Config::default(), true, true);
+        let sessions = find_sessions(&["gasket"], Some(&project), None, 1, None, &options).unwrap();
+        let matched = &sessions[0].matched_messages;
+        assert_eq!(matched.len(), 1);
+        assert!(matched[0].starts_with("assistant: ..."));
+        assert!(matched[0].contains("the gasket leaks"));
+    }
+
+    #[test]
+    fn filters_find_sessions_ranked_below_the_first_shortlist() {
+        let (_home, home) = fake_home();
+        let prose = ["the widget widget widget is blue", "widget widget yes", "widget ok", "more widget", "done"];
+        for index in 0..12 {
+            write_session(home, "-work-widgets", &format!("prose-{}", index), &prose, 30 * DAY);
+        }
+        let code = ["fix it", "```rust\nlet widget = 1;\n```", "ok", "fine", "done"];
+        write_session(home, "-work-widgets", "code", &code, DAY);
+
+        let project = "work/widgets".to_string();
+        let options = SearchOptions { scope: MatchScope::Code, ..SearchOptions::from_config(&Config::default(), true, true) };
+        let in_code = |session: &SessionInfo| -> Result<bool> { Ok(session.matches.occurrences > 0) };
+        let search = SessionSearch {
+            search_terms: &["widget"],
+            project_filter: Some(&project),
+            recent_days: None,
+            limit: 1,
+            grouping: None,
+            options: &options,
+            file_filter: None,
+            session_filter: Some(&in_code),
+        };
+        let sessions = find_sessions_streaming(&search, &mut |_| Ok(true)).unwrap();
+        assert!(sessions.iter().any(|session| session.session_id == "code"));
+
+        let not_prose = |path: &Path| !path.to_string_lossy().contains("prose");
+        let search = SessionSearch { file_filter: Some(&not_prose), session_filter: None, ..search };
+        let sessions = find_sessions_streaming(&search, &mut |_| Ok(true)).unwrap();
+        assert_eq!(sessions.iter().map(|session| session.session_id.as_str()).collect::<Vec<_>>(), ["code"]);
+    }
+}
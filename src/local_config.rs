@@ -0,0 +1,65 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+use crate::error::{Result, SessionFinderError};
+
+/// Name of the per-directory settings file, found by walking up from the current directory
+pub const LOCAL_CONFIG_FILE: &str = ".session-finder.toml";
+
+/// Search defaults for one repository or directory tree, from the nearest
+/// `.session-finder.toml`; flags given on the command line take precedence.
+#[derive(Debug, Default, Deserialize)]
+pub struct LocalConfig {
+    /// Default for `--project`
+    pub project: Option<String>,
+    /// Default `--tag` filters for searches
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Words never shown among a session's common terms, on top of the built-in ones
+    #[serde(default)]
+    pub stopwords: Vec<String>,
+    /// Default for `--sort`
+    pub sort: Option<String>,
+    /// The file these came from
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl LocalConfig {
+    /// Reads the `.session-finder.toml` nearest to `dir`, or the defaults if there is none.
+    pub fn discover(dir: &Path) -> Result<Self> {
+        let Some(path) = dir.ancestors().map(|ancestor| ancestor.join(LOCAL_CONFIG_FILE)).find(|path| path.is_file()) else {
+            return Ok(LocalConfig::default());
+        };
+        let content = fs::read_to_string(&path)?;
+        let mut config: LocalConfig =
+            toml::from_str(&content).map_err(|e| SessionFinderError::toml(path.clone(), &content, e))?;
+        config.path = Some(path);
+        Ok(config)
+    }
+
+    /// Reads the `.session-finder.toml` nearest to the current directory. A file that can't be
+    /// read or parsed, or a current directory that no longer exists, is warned about and skipped.
+    pub fn for_current_dir() -> Self {
+        let dir = match std::env::current_dir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                warn!("Could not find the current directory ({}); not looking for {}", e, LOCAL_CONFIG_FILE);
+                return LocalConfig::default();
+            }
+        };
+        Self::discover(&dir).unwrap_or_else(|e| {
+            warn!("{:#}; ignoring it", e);
+            LocalConfig::default()
+        })
+    }
+
+    /// The stopwords, lowercased, as `SearchOptions` takes them.
+    pub fn stopword_set(&self) -> HashSet<String> {
+        self.stopwords.iter().map(|word| word.to_lowercase()).collect()
+    }
+}
@@ -0,0 +1,23 @@
+use std::io::IsTerminal;
+use tracing_subscriber::EnvFilter;
+
+/// Sends log events to stderr. `RUST_LOG` takes precedence; otherwise each `-v` lowers the
+/// threshold one level from warnings: `-v` info (phases and timings), `-vv` debug (every file
+/// and filter decision), `-vvv` trace.
+pub fn init_logging(verbosity: u8) {
+    let level = match verbosity {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("session_finder={}", level)));
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_target(false)
+        .init();
+}
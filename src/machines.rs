@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Project roots on other machines with what they are on this one, longest first
+static PROJECT_ROOTS: RwLock<Vec<(String, String)>> = RwLock::new(Vec::new());
+
+/// Sets the `[project_roots]` mapping, so sessions from a machine that keeps projects
+/// elsewhere (`/Users/me/code` on a laptop, `/home/me/src` here) count as the same projects.
+/// Replaces any mapping set before.
+pub fn set_project_roots(roots: &HashMap<String, String>) {
+    let mut roots: Vec<(String, String)> = roots
+        .iter()
+        .map(|(other, local)| (other.trim_end_matches('/').to_string(), local.trim_end_matches('/').to_string()))
+        .collect();
+    roots.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    *PROJECT_ROOTS.write().unwrap_or_else(|e| e.into_inner()) = roots;
+}
+
+/// The project path with another machine's root replaced by this machine's, if one matches.
+pub fn remap_project_root(project: &str) -> Option<String> {
+    PROJECT_ROOTS.read().unwrap_or_else(|e| e.into_inner()).iter().find_map(|(other, local)| {
+        let rest = Path::new(project).strip_prefix(other).ok()?;
+        Some(if rest.as_os_str().is_empty() { local.clone() } else { format!("{}/{}", local, rest.display()) })
+    })
+}
+
+/// The remote directory a user's sessions from one machine are pushed to: `user@machine`, or
+/// just `user` when no machine is named.
+pub fn owner_dir(user: &str, machine: Option<&str>) -> String {
+    match machine {
+        Some(machine) => format!("{}@{}", user, machine),
+        None => user.to_string(),
+    }
+}
+
+/// Whether `sync pull` should leave a remote directory alone because it holds sessions from
+/// this machine, which are already searched where Claude Code wrote them. Without a machine
+/// name, every directory of the user's counts as this machine's.
+pub fn is_own_dir(owner: &str, user: &str, machine: Option<&str>) -> bool {
+    match owner.split_once('@') {
+        None => owner == user,
+        Some((owner_user, owner_machine)) => owner_user == user && machine.is_none_or(|machine| machine == owner_machine),
+    }
+}
+
+/// `alice, from machine desktop` for a pulled session's owner directory.
+pub fn describe_owner(owner: &str) -> String {
+    match owner.split_once('@') {
+        Some((user, machine)) => format!("{}, from machine {}", user, machine),
+        None => owner.to_string(),
+    }
+}
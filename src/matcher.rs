@@ -0,0 +1,247 @@
+use aho_corasick::AhoCorasick;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::code_analysis::LanguageAnalyzer;
+use crate::config::Config;
+use crate::ignore::IgnoreRules;
+use crate::memory::MemoryBudget;
+use crate::preview::PreviewOptions;
+use crate::ranking::SortOrder;
+use crate::render::RenderOptions;
+use crate::scope::{content_type_name, language_selected, MatchScope};
+use crate::time_budget::TimeBudget;
+use crate::{Content, ContentType};
+
+/// The search terms of one query, compiled once and shared by every session the query
+/// analyses. Terms are found case-insensitively in a single aho-corasick pass over lowercased
+/// text, and each term carries the regex that pulls topics out around it.
+pub struct TermMatcher {
+    /// The position of each non-empty term in the query, lowercased
+    terms: Vec<(usize, String)>,
+    automaton: Option<AhoCorasick>,
+    topic_regexes: Vec<Regex>,
+}
+
+/// A query's terms and their compiled matcher
+type CachedMatcher = (Vec<String>, Arc<TermMatcher>);
+
+/// The matcher for the last query, which is usually the only one in a run
+static LAST_QUERY: OnceLock<Mutex<Option<CachedMatcher>>> = OnceLock::new();
+
+/// How a search matches and what it leaves out: sessions on the config file's ignore list,
+/// messages mentioning one of its excluded phrases, and matches outside the scope, language,
+/// and kinds of message asked for. Also how results are ordered and previewed. The default
+/// leaves out nothing.
+#[derive(Clone, Default)]
+pub struct SearchOptions {
+    pub ignore: IgnoreRules,
+    pub exclusions: Exclusions,
+    /// `--in-code` or `--in-prose`: which parts of a message terms are matched against
+    pub scope: MatchScope,
+    /// `--lang`: the one language whose code counts
+    pub language: Option<&'static dyn LanguageAnalyzer>,
+    /// `--types`: the kinds of message whose matches count, by `content_type_name`
+    pub content_types: Option<Vec<&'static str>>,
+    /// `--sort`: how results are ordered before `--limit` applies
+    pub sort: SortOrder,
+    pub preview: PreviewOptions,
+    /// How previewed excerpts are drawn
+    pub render: RenderOptions,
+    /// Words left out of common terms on top of the built-in ones, lowercase; from
+    /// `.session-finder.toml`
+    pub stopwords: HashSet<String>,
+    /// The repository a search is confined to, unless `--project` or `--global` is given
+    pub workspace: Option<PathBuf>,
+    /// `--max-memory`: sessions are then analysed one at a time, a chunk at a time
+    pub memory_budget: Option<MemoryBudget>,
+    /// `--budget`, which starts counting when it is made
+    pub time_budget: Option<TimeBudget>,
+    /// `--explain-plan`: print each stage of the search to stderr
+    pub explain_plan: bool,
+}
+
+impl SearchOptions {
+    /// The config's ignore list, exclusions, and preview and ASCII defaults, with the ignore
+    /// list and exclusions left out when `--no-ignore` or `--no-self-filter` turned them off.
+    pub fn from_config(config: &Config, use_ignore: bool, self_filter: bool) -> Self {
+        let defaults = PreviewOptions::default();
+        SearchOptions {
+            ignore: if use_ignore { config.ignore.clone() } else { IgnoreRules::default() },
+            exclusions: Exclusions::from_config(config, self_filter),
+            preview: PreviewOptions {
+                count: config.preview.unwrap_or(defaults.count),
+                width: config.preview_width.unwrap_or(defaults.width),
+                common_terms: config.terms.unwrap_or(defaults.common_terms),
+                ..defaults
+            },
+            render: RenderOptions { ascii: config.ascii, width: None },
+            ..SearchOptions::default()
+        }
+    }
+
+    /// The part of a message's text terms are matched against.
+    pub(crate) fn match_text<'a>(&self, content: &'a Content) -> Cow<'a, str> {
+        self.scope.text(content, self.language)
+    }
+
+    /// Whether code in `language`, a canonical name such as `rust`, passes `--lang`.
+    pub fn language_selected(&self, language: Option<&str>) -> bool {
+        language_selected(self.language, language)
+    }
+
+    /// Whether `--types` leaves some kinds of message out.
+    pub fn restricts_content_types(&self) -> bool {
+        self.content_types.is_some()
+    }
+
+    /// Whether matches in a message of this kind count under `--types`.
+    pub fn content_type_selected(&self, content_type: &ContentType) -> bool {
+        self.content_types.as_ref().is_none_or(|names| names.contains(&content_type_name(content_type)))
+    }
+}
+
+/// Phrases from the config file's `exclude_terms`, lowercased; messages containing one are
+/// never matches. The default excludes nothing.
+#[derive(Debug, Clone, Default)]
+pub struct Exclusions {
+    terms: Vec<String>,
+}
+
+impl Exclusions {
+    pub fn new(terms: &[String]) -> Self {
+        Exclusions { terms: terms.iter().map(|term| term.to_lowercase()).filter(|term| !term.is_empty()).collect() }
+    }
+
+    /// The config's `exclude_terms`, or none when `--no-self-filter` turns the filter off.
+    pub fn from_config(config: &Config, self_filter: bool) -> Self {
+        if self_filter {
+            Exclusions::new(&config.exclude_terms)
+        } else {
+            Exclusions::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Whether already lowercased message text mentions an excluded phrase, so it shouldn't
+    /// count as a match.
+    pub fn is_excluded(&self, lowercase_text: &str) -> bool {
+        self.terms.iter().any(|term| lowercase_text.contains(term.as_str()))
+    }
+}
+
+/// The compiled matcher for `search_terms`, built the first time the query is seen.
+pub fn query_matcher(search_terms: &[&str]) -> Arc<TermMatcher> {
+    let mut last = LAST_QUERY.get_or_init(Mutex::default).lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((terms, matcher)) = last.as_ref() {
+        if terms.iter().map(String::as_str).eq(search_terms.iter().copied()) {
+            return Arc::clone(matcher);
+        }
+    }
+    let matcher = Arc::new(TermMatcher::new(search_terms));
+    *last = Some((search_terms.iter().map(|term| term.to_string()).collect(), Arc::clone(&matcher)));
+    matcher
+}
+
+impl TermMatcher {
+    fn new(search_terms: &[&str]) -> Self {
+        let terms: Vec<(usize, String)> = search_terms
+            .iter()
+            .map(|term| term.to_lowercase())
+            .enumerate()
+            .filter(|(_, term)| !term.is_empty())
+            .collect();
+        let automaton = (!terms.is_empty())
+            .then(|| AhoCorasick::new(terms.iter().map(|(_, term)| term)).expect("literal terms always compile"));
+        let topic_regexes = terms
+            .iter()
+            .map(|(index, _)| {
+                Regex::new(&format!(r"(?i)\b{}\b[\w\s]*", regex::escape(search_terms[*index])))
+                    .expect("escaped terms always compile")
+            })
+            .collect();
+        TermMatcher { terms, automaton, topic_regexes }
+    }
+
+    /// Whether already lowercased text contains any term.
+    pub fn is_match(&self, lowercase_text: &str) -> bool {
+        self.automaton.as_ref().is_some_and(|automaton| automaton.is_match(lowercase_text))
+    }
+
+    /// Every occurrence in already lowercased text, as (byte offset, index of the term in the
+    /// query), in order. Occurrences of one term don't overlap, as with `str::matches`, but
+    /// different terms may overlap each other.
+    pub fn find_all(&self, lowercase_text: &str) -> Vec<(usize, usize)> {
+        let Some(automaton) = &self.automaton else {
+            return Vec::new();
+        };
+        let mut next_start = vec![0; self.terms.len()];
+        let mut hits = Vec::new();
+        for found in automaton.find_overlapping_iter(lowercase_text) {
+            let pattern = found.pattern().as_usize();
+            if found.start() >= next_start[pattern] {
+                next_start[pattern] = found.end();
+                hits.push((found.start(), self.terms[pattern].0));
+            }
+        }
+        hits.sort_unstable();
+        hits
+    }
+
+    /// Occurrences of all terms in already lowercased text.
+    pub fn count(&self, lowercase_text: &str) -> usize {
+        self.find_all(lowercase_text).len()
+    }
+
+    /// The topic regexes of the terms that occur in already lowercased text.
+    pub fn matching_topic_regexes(&self, lowercase_text: &str) -> Vec<&Regex> {
+        let hits = self.find_all(lowercase_text);
+        self.terms
+            .iter()
+            .zip(&self.topic_regexes)
+            .filter(|((index, _), _)| hits.iter().any(|(_, term)| term == index))
+            .map(|(_, regex)| regex)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).expect("valid config")
+    }
+
+    #[test]
+    fn default_excludes_nothing() {
+        let exclusions = Exclusions::from_config(&Config::default(), true);
+        assert!(exclusions.is_empty());
+        assert!(!exclusions.is_excluded("running session-finder for tokio"));
+        assert!(!exclusions.is_excluded(""));
+    }
+
+    #[test]
+    fn config_terms_exclude_matching_text() {
+        let exclusions = Exclusions::from_config(&config(r#"exclude_terms = ["Session-Finder", ""]"#), true);
+        assert!(exclusions.is_excluded("running session-finder for tokio"));
+        assert!(!exclusions.is_excluded("fixing the tokio timeout"));
+    }
+
+    #[test]
+    fn no_self_filter_turns_exclusion_off() {
+        let matches = crate::build_cli().get_matches_from(["session-finder", "--no-self-filter", "tokio"]);
+        let exclusions = Exclusions::from_config(
+            &config(r#"exclude_terms = ["session-finder"]"#),
+            !matches.get_flag("no_self_filter"),
+        );
+        assert!(exclusions.is_empty());
+        assert!(!exclusions.is_excluded("running session-finder for tokio"));
+    }
+}
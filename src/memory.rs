@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::error::{Result, SessionFinderError};
+use crate::resolve::{is_encoded_session_file, read_session_text};
+use crate::schema::{parse_session_line, ParseDiagnostics};
+use crate::SessionMessage;
+
+/// Parsed messages take several times the space of their JSON, so a chunk of raw lines gets
+/// this fraction of the budget
+const CHUNK_FRACTION: u64 = 8;
+
+/// Smallest chunk read at a time, however low the budget
+const MIN_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// A byte count with an optional `K`, `M`, or `G` suffix (powers of 1024), e.g. `512M`.
+pub fn parse_byte_size(value: &str) -> Result<u64> {
+    let invalid = || {
+        SessionFinderError::Usage(format!("Invalid size {:?}: use a number with K, M, or G, e.g. 512M", value))
+    };
+    let trimmed = value.trim();
+    let digits = trimmed.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier = match trimmed[digits.len()..].to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+    digits
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|count| count.checked_mul(multiplier))
+        .filter(|bytes| *bytes > 0)
+        .ok_or_else(invalid)
+}
+
+/// `--max-memory`: how much analysing sessions may hold in memory at once. Sessions are
+/// analysed one at a time, each read and parsed in chunks rather than whole.
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryBudget {
+    bytes: u64,
+}
+
+impl MemoryBudget {
+    /// Accepts a byte count with an optional `K`, `M`, or `G` suffix (powers of 1024), e.g. `512M`.
+    pub fn parse(value: &str) -> Result<Self> {
+        Ok(MemoryBudget { bytes: parse_byte_size(value)? })
+    }
+
+    /// Raw session lines to read and parse at a time
+    pub fn chunk_bytes(&self) -> usize {
+        (self.bytes / CHUNK_FRACTION).max(MIN_CHUNK_BYTES) as usize
+    }
+}
+
+/// Reads a session file a chunk of messages at a time. A single line longer than the chunk
+/// size still has to be held whole. `R` is a `BufRead`, or a tokio `AsyncBufRead` for
+/// `resume_async`.
+pub struct SessionChunks<R = Box<dyn BufRead>> {
+    reader: R,
+    chunk_bytes: usize,
+    /// Bytes read so far, through the end of the last complete line
+    pub offset: u64,
+    /// Lines read so far, blank ones included
+    pub line_count: usize,
+    pub diagnostics: ParseDiagnostics,
+    /// A last line without its newline yet, which Claude Code may be midway through writing;
+    /// it isn't parsed or counted in `offset`
+    pub partial_line: Option<String>,
+}
+
+impl SessionChunks {
+    /// Reads from `offset`, the start of the file or the end of a line, after `line_count`
+    /// lines were read. A compressed or encrypted session is decoded whole first, and `offset`
+    /// counts bytes of its text.
+    pub fn resume(path: &Path, chunk_bytes: usize, offset: u64, line_count: usize, diagnostics: ParseDiagnostics) -> Result<Self> {
+        let reader: Box<dyn BufRead> = if is_encoded_session_file(path) {
+            let mut text = Cursor::new(read_session_text(path)?.into_bytes());
+            text.set_position(offset);
+            Box::new(text)
+        } else {
+            let mut file = File::open(path)?;
+            file.seek(SeekFrom::Start(offset))?;
+            Box::new(BufReader::new(file))
+        };
+        Ok(SessionChunks { reader, chunk_bytes, offset, line_count, diagnostics, partial_line: None })
+    }
+
+    /// The next messages, parsed from at most about `chunk_bytes` of the file, or `None` at the end.
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<SessionMessage>>> {
+        let mut messages = Vec::new();
+        let mut read = 0;
+        let mut line = Vec::new();
+        while read < self.chunk_bytes {
+            line.clear();
+            let length = self.reader.read_until(b'\n', &mut line)?;
+            if length == 0 || !self.take_line(&line, &mut messages) {
+                break;
+            }
+            read += length;
+        }
+        Ok(if read == 0 && messages.is_empty() { None } else { Some(messages) })
+    }
+}
+
+/// A session's reader on a tokio runtime
+#[cfg(feature = "tokio")]
+pub type AsyncSessionReader = std::pin::Pin<Box<dyn tokio::io::AsyncBufRead + Send>>;
+
+#[cfg(feature = "tokio")]
+impl SessionChunks<AsyncSessionReader> {
+    /// `resume` through `tokio::fs`. Only a compressed or encrypted session is read whole.
+    pub async fn resume_async(
+        path: &Path,
+        chunk_bytes: usize,
+        offset: u64,
+        line_count: usize,
+        diagnostics: ParseDiagnostics,
+    ) -> Result<Self> {
+        use crate::resolve::read_session_text_async;
+        use tokio::io::AsyncSeekExt;
+
+        let reader: AsyncSessionReader = if is_encoded_session_file(path) {
+            let mut text = Cursor::new(read_session_text_async(path).await?.into_bytes());
+            text.set_position(offset);
+            Box::pin(text)
+        } else {
+            let mut file = tokio::fs::File::open(path).await?;
+            file.seek(SeekFrom::Start(offset)).await?;
+            Box::pin(tokio::io::BufReader::new(file))
+        };
+        Ok(SessionChunks { reader, chunk_bytes, offset, line_count, diagnostics, partial_line: None })
+    }
+
+    /// `next_chunk` without blocking the runtime.
+    pub async fn next_chunk_async(&mut self) -> Result<Option<Vec<SessionMessage>>> {
+        use tokio::io::AsyncBufReadExt;
+
+        let mut messages = Vec::new();
+        let mut read = 0;
+        let mut line = Vec::new();
+        while read < self.chunk_bytes {
+            line.clear();
+            let length = self.reader.read_until(b'\n', &mut line).await?;
+            if length == 0 || !self.take_line(&line, &mut messages) {
+                break;
+            }
+            read += length;
+        }
+        Ok(if read == 0 && messages.is_empty() { None } else { Some(messages) })
+    }
+}
+
+impl<R> SessionChunks<R> {
+    /// Parses a line just read into `messages`, returning false if it has no newline yet:
+    /// that is kept as `partial_line`, and reading should stop.
+    fn take_line(&mut self, line: &[u8], messages: &mut Vec<SessionMessage>) -> bool {
+        let text = String::from_utf8_lossy(line);
+        let Some(text) = text.strip_suffix('\n') else {
+            self.partial_line = Some(text.into_owned());
+            return false;
+        };
+        self.offset += line.len() as u64;
+        self.line_count += 1;
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        messages.extend(parse_session_line(text, self.line_count, &mut self.diagnostics));
+        true
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::analyze_session_file;
+use crate::error::{Result, SessionFinderError};
+use crate::export::short_id;
+use crate::matcher::SearchOptions;
+use crate::render::RenderOptions;
+use crate::resolve::{all_session_files, read_session_text};
+use crate::timeline::{message_time, parse_session_messages};
+
+/// Days `new` looks back without `--since-last-run`, or before its first run
+const DEFAULT_DAYS: i64 = 1;
+
+/// Where `new` records when it last ran.
+fn last_run_path() -> Result<PathBuf> {
+    Ok(Path::new(&std::env::var("HOME")?)
+        .join(".claude")
+        .join("session-finder")
+        .join("last-run"))
+}
+
+fn read_last_run() -> Result<Option<DateTime<Utc>>> {
+    let path = last_run_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)?;
+    DateTime::parse_from_rfc3339(content.trim())
+        .map(|time| Some(time.with_timezone(&Utc)))
+        .map_err(|e| SessionFinderError::ParseError { path: path.clone(), line: Some(1), message: e.to_string() })
+}
+
+fn write_last_run(time: DateTime<Utc>) -> Result<()> {
+    let path = last_run_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, format!("{}\n", time.to_rfc3339()))?;
+    Ok(())
+}
+
+/// Lists sessions started or continued since the last run of `new` (with `since_last_run`) or
+/// in the last `recent_days`, oldest first, then records this run.
+pub fn run_new(since_last_run: bool, project_filter: Option<&String>, recent_days: Option<i64>, render: RenderOptions) -> Result<()> {
+    let now = Utc::now();
+    let days = recent_days.unwrap_or(DEFAULT_DAYS);
+    let (cutoff, described) = match read_last_run()?.filter(|_| since_last_run) {
+        Some(last_run) => (last_run, "last run".to_string()),
+        None => (now - Duration::days(days), format!("last {} day{}", days, if days == 1 { "" } else { "s" })),
+    };
+
+    let options = SearchOptions { render, ..SearchOptions::default() };
+    let mut sessions = Vec::new();
+    for path in all_session_files()? {
+        let modified = fs::metadata(&path)?.modified().map(DateTime::<Utc>::from)?;
+        if modified < cutoff {
+            continue;
+        }
+        let Some(info) = analyze_session_file(&path, &[], project_filter, None, &options)? else {
+            continue;
+        };
+        let content = read_session_text(&path)?;
+        let started = parse_session_messages(&content)?.iter().find_map(message_time);
+        sessions.push((info, started.is_none_or(|started| started >= cutoff)));
+    }
+    sessions.sort_by(|(a, _), (b, _)| a.last_modified.cmp(&b.last_modified).then_with(|| a.session_id.cmp(&b.session_id)));
+
+    let since = cutoff.format("%Y-%m-%d %H:%M UTC");
+    if sessions.is_empty() {
+        println!("No sessions started or changed since {} ({}).", since, described);
+    } else {
+        println!(
+            "{} session{} started or changed since {} ({}):\n",
+            sessions.len(),
+            if sessions.len() == 1 { "" } else { "s" },
+            since,
+            described
+        );
+        for (info, is_new) in &sessions {
+            println!(
+                "{:<8} {}  {}  {}",
+                if *is_new { "new" } else { "updated" },
+                info.last_modified.format("%Y-%m-%d %H:%M"),
+                short_id(&info.session_id),
+                info.title.as_deref().unwrap_or("(untitled)")
+            );
+            println!("{:<8} {}", "", info.project_path);
+        }
+    }
+    write_last_run(now)
+}
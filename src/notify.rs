@@ -0,0 +1,167 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::events::{EventDetector, EventKind, EventThresholds, SessionEvent};
+use crate::webhook::{post_event, WebhookFormat};
+
+/// The `[notify]` section of `config.toml`: which session events the daemon announces, and how
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotifyConfig {
+    /// Show native desktop notifications (needs the `notifications` feature)
+    #[serde(default)]
+    pub desktop: bool,
+    /// URL to POST each event to as JSON
+    pub webhook: Option<String>,
+    /// `json` (the event's fields) or `slack` (a message for Slack incoming webhooks)
+    #[serde(default)]
+    pub webhook_format: WebhookFormat,
+    /// Events to announce; all of them by default
+    #[serde(default = "all_events")]
+    pub events: Vec<EventKind>,
+    /// Terms that raise a `term` event when a new message mentions them
+    #[serde(default)]
+    pub terms: Vec<String>,
+    /// Seconds without new messages before a session counts as finished
+    #[serde(default = "default_finished_after")]
+    pub finished_after_secs: u64,
+    /// Failed tool calls within `error_window_secs` that count as a burst
+    #[serde(default = "default_error_burst")]
+    pub error_burst: usize,
+    #[serde(default = "default_error_window")]
+    pub error_window_secs: u64,
+    /// Shell command to run when a notification is clicked, per event, e.g.
+    /// `session_finished = "session-finder show {session}"`
+    #[serde(default)]
+    pub commands: HashMap<EventKind, String>,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            desktop: false,
+            webhook: None,
+            webhook_format: WebhookFormat::default(),
+            events: all_events(),
+            terms: Vec::new(),
+            finished_after_secs: default_finished_after(),
+            error_burst: default_error_burst(),
+            error_window_secs: default_error_window(),
+            commands: HashMap::new(),
+        }
+    }
+}
+
+fn all_events() -> Vec<EventKind> {
+    EventKind::ALL.to_vec()
+}
+
+fn default_finished_after() -> u64 {
+    120
+}
+
+fn default_error_burst() -> usize {
+    3
+}
+
+fn default_error_window() -> u64 {
+    300
+}
+
+/// Watches session files for the configured events and announces them.
+pub struct Notifier {
+    config: NotifyConfig,
+    detector: EventDetector,
+}
+
+impl Notifier {
+    /// A notifier for the daemon, or `None` when nothing is configured to receive events.
+    pub fn from_config(config: NotifyConfig) -> Option<Self> {
+        if !config.desktop && config.webhook.is_none() {
+            return None;
+        }
+        if config.desktop && cfg!(not(feature = "notifications")) {
+            warn!("[notify] desktop = true, but this build has no desktop notifications; rebuild with --features notifications");
+        }
+        let thresholds = EventThresholds {
+            terms: config.terms.iter().map(|term| term.to_lowercase()).collect(),
+            finished_after: Duration::from_secs(config.finished_after_secs),
+            error_burst: config.error_burst.max(1),
+            error_window: Duration::from_secs(config.error_window_secs),
+        };
+        Some(Notifier { detector: EventDetector::new(thresholds), config })
+    }
+
+    /// Looks for events in session files that grew (with their size when last seen), and in
+    /// sessions gone quiet, announcing those configured.
+    pub fn observe(&mut self, grown: &[(PathBuf, u64)]) {
+        for event in self.detector.observe(grown) {
+            if !self.config.events.contains(&event.kind) {
+                continue;
+            }
+            debug!(event = event.kind.name(), session = %event.session_id, "Session event");
+            if self.config.desktop {
+                show_desktop(&event, self.config.commands.get(&event.kind).map(|command| click_command(command, &event)));
+            }
+            if let Some(url) = &self.config.webhook {
+                post_event(url, &event, self.config.webhook_format);
+            }
+        }
+    }
+}
+
+/// The configured command with `{session}`, `{path}`, and `{project}` filled in.
+fn click_command(command: &str, event: &SessionEvent) -> String {
+    command
+        .replace("{session}", &event.session_id)
+        .replace("{path}", &event.path.to_string_lossy())
+        .replace("{project}", &event.project)
+}
+
+#[cfg(feature = "notifications")]
+fn show_desktop(event: &SessionEvent, command: Option<String>) {
+    let mut notification = notify_rust::Notification::new();
+    notification.appname("session-finder").summary(&event.summary()).body(&event.detail);
+    if command.is_some() {
+        notification.action("default", "Open");
+    }
+    let handle = match notification.show() {
+        Ok(handle) => handle,
+        Err(e) => {
+            warn!("Could not show a notification: {}", e);
+            return;
+        }
+    };
+    // macOS only reports clicks to apps running its event loop
+    #[cfg(not(target_os = "macos"))]
+    if let Some(command) = command {
+        std::thread::spawn(move || {
+            handle.wait_for_action(|action| {
+                if action == "default" {
+                    run_command(&command);
+                }
+            })
+        });
+    }
+    #[cfg(target_os = "macos")]
+    let _ = (handle, command);
+}
+
+/// Without desktop support the event is only logged.
+#[cfg(not(feature = "notifications"))]
+fn show_desktop(event: &SessionEvent, _command: Option<String>) {
+    tracing::info!("{}: {}", event.summary(), event.detail);
+}
+
+#[cfg(all(feature = "notifications", not(target_os = "macos")))]
+fn run_command(command: &str) {
+    #[cfg(windows)]
+    let status = std::process::Command::new("cmd").args(["/C", command]).status();
+    #[cfg(not(windows))]
+    let status = std::process::Command::new("sh").args(["-c", command]).status();
+    if let Err(e) = status {
+        warn!("Could not run {:?}: {}", command, e);
+    }
+}
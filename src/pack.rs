@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::claude_data::{claude_data, Todo};
+use crate::clipboard::copy_to_clipboard;
+use crate::error::Result;
+use crate::export::file_edits;
+use crate::render::RenderOptions;
+use crate::resolve::{read_session_source, read_session_title};
+use crate::resume::session_working_dir;
+use crate::scope::prose_text;
+use crate::text::truncate_text;
+use crate::timeline::{classify_message_content, extract_content_text, parse_session_messages};
+use crate::title::{first_prompt, infer_title};
+use crate::tokens::estimate_tokens;
+use crate::{Content, ContentType, SessionMessage};
+
+/// Widest a quoted decision, problem, or todo gets, in columns
+const ITEM_WIDTH: usize = 200;
+
+/// Widest the goal and the final state get
+const PASSAGE_WIDTH: usize = 800;
+
+/// Most decisions and problems listed; the latest are kept
+const MAX_ITEMS: usize = 8;
+
+/// Phrases that mark a sentence as a decision about how to proceed
+const DECISION_PHRASES: &[&str] = &[
+    "decided", "let's go with", "we'll go with", "going with", "instead of", "chose", "we should use",
+    "i'll use", "let's use", "switch to", "rather than",
+];
+
+/// Writes a Markdown briefing on a session (goal, plan, decisions, problems, files changed,
+/// open todos, final state) for starting a new session where a long one left off. Prints it
+/// unless `output` names a file.
+pub fn run_pack(session_ref: &str, output: Option<&Path>, copy: bool, render: RenderOptions) -> Result<()> {
+    let (path, session_id, content) = read_session_source(session_ref)?;
+    let messages = parse_session_messages(&content)?;
+    let title = read_session_title(&path)
+        .or_else(|| claude_data().title(&session_id).map(str::to_string))
+        .or_else(|| infer_title(&messages, render));
+    let pack = render_pack(&messages, &session_id, title.as_deref(), &session_working_dir(&path));
+
+    match output {
+        Some(output) => {
+            fs::write(output, &pack)?;
+            eprintln!("Wrote {}", output.display());
+        }
+        None => print!("{}", pack),
+    }
+    if copy {
+        copy_to_clipboard(&pack, "resume pack")?;
+    }
+    Ok(())
+}
+
+fn render_pack(messages: &[SessionMessage], session_id: &str, title: Option<&str>, project: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Resume pack: {}\n", title.unwrap_or(session_id));
+    let _ = writeln!(
+        out,
+        "Briefing on Claude Code session `{}` in `{}` ({} messages, {} tokens), to pick up its work in a new session.\n",
+        session_id,
+        project,
+        messages.len(),
+        estimate_tokens(messages).tokens
+    );
+
+    if let Some(goal) = first_prompt(messages) {
+        let _ = writeln!(out, "## Goal\n\n{}\n", quote(&goal, PASSAGE_WIDTH));
+    }
+    if let Some(plan) = last_plan(messages) {
+        let _ = writeln!(out, "## Plan\n\n{}\n", plan.trim());
+    }
+    push_list(&mut out, "Key decisions", &decisions(messages));
+    push_list(&mut out, "Problems hit", &problems(messages));
+
+    let mut files: Vec<(&str, usize)> = Vec::new();
+    for file in file_edits(messages) {
+        match files.iter_mut().find(|(known, _)| *known == file) {
+            Some((_, count)) => *count += 1,
+            None => files.push((file, 1)),
+        }
+    }
+    let files: Vec<String> = files
+        .iter()
+        .map(|(file, count)| if *count == 1 { format!("`{}`", file) } else { format!("`{}` ({} edits)", file, count) })
+        .collect();
+    push_list(&mut out, "Files changed", &files);
+
+    push_list(&mut out, "Outstanding todos", &outstanding_todos(messages, session_id));
+
+    if let Some(last) = last_assistant_text(messages) {
+        let _ = writeln!(out, "## Final state\n\n{}\n", quote(&last, PASSAGE_WIDTH));
+    }
+    out
+}
+
+fn push_list(out: &mut String, heading: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "## {}\n", heading);
+    for item in items {
+        let _ = writeln!(out, "- {}", item);
+    }
+    out.push('\n');
+}
+
+/// `text` on one line, cut to `width` columns
+fn quote(text: &str, width: usize) -> String {
+    truncate_text(&text.split_whitespace().collect::<Vec<_>>().join(" "), width)
+}
+
+/// The plan from the last `ExitPlanMode` call, which is the one the user approved or the
+/// latest they saw.
+fn last_plan(messages: &[SessionMessage]) -> Option<String> {
+    messages.iter().rev().find_map(|msg| {
+        tool_inputs(msg, "ExitPlanMode")
+            .last()
+            .and_then(|input| input.get("plan")?.as_str().map(str::to_string))
+    })
+}
+
+/// Input of each call to `tool` in a message
+fn tool_inputs<'a>(msg: &'a SessionMessage, tool: &str) -> Vec<&'a serde_json::Value> {
+    let Some(Content::Array(blocks)) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+        return Vec::new();
+    };
+    blocks
+        .iter()
+        .filter(|block| block.r#type == "tool_use" && block.name.as_deref() == Some(tool))
+        .filter_map(|block| block.input.as_ref())
+        .collect()
+}
+
+/// Sentences of discussion, outside code, that record a choice, latest last.
+fn decisions(messages: &[SessionMessage]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut decisions = Vec::new();
+    for (index, msg) in messages.iter().enumerate() {
+        let Some(content) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+            continue;
+        };
+        let prose = prose_text(&extract_content_text(content));
+        for sentence in prose.split_inclusive(['.', '!', '?', '\n']) {
+            let lower = sentence.to_lowercase();
+            if DECISION_PHRASES.iter().any(|phrase| lower.contains(phrase)) && seen.insert(lower.trim().to_string()) {
+                decisions.push(format!("{} (message {})", quote(sentence, ITEM_WIDTH), index));
+            }
+        }
+    }
+    latest(decisions)
+}
+
+/// Errors the session ran into, and whether a success response followed each.
+fn problems(messages: &[SessionMessage]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for index in 0..messages.len() {
+        match classify_message_content(messages, index).content_type {
+            ContentType::ErrorMessage(error) if error.severity == "error" => {
+                let source = error.source.filter(|source| *source != error.error_type);
+                let label = match source {
+                    Some(source) => format!("{} error ({})", error.error_type, source),
+                    None => format!("{} error", error.error_type),
+                };
+                open.push(problems.len());
+                problems.push(format!("{} at message {}", label, index));
+            }
+            ContentType::SuccessResponse(_) => {
+                for problem in open.drain(..) {
+                    problems[problem].push_str(&format!(", resolved by message {}", index));
+                }
+            }
+            _ => {}
+        }
+    }
+    for problem in open {
+        problems[problem].push_str(", unresolved");
+    }
+    latest(problems)
+}
+
+/// The last `MAX_ITEMS`, as the end of a session is what the next one continues from
+fn latest(mut items: Vec<String>) -> Vec<String> {
+    let skip = items.len().saturating_sub(MAX_ITEMS);
+    items.drain(..skip);
+    items
+}
+
+/// Items not yet completed in the session's last `TodoWrite` list, or in the todo list Claude
+/// recorded for it when the transcript has none.
+fn outstanding_todos(messages: &[SessionMessage], session_id: &str) -> Vec<String> {
+    let todos: Vec<Todo> = match messages
+        .iter()
+        .rev()
+        .find_map(|msg| tool_inputs(msg, "TodoWrite").last().and_then(|input| input.get("todos")))
+    {
+        Some(todos) => serde_json::from_value(todos.clone()).unwrap_or_default(),
+        None => claude_data().todos(session_id).to_vec(),
+    };
+    todos
+        .iter()
+        .filter(|todo| todo.status != "completed")
+        .map(|todo| format!("[ ] {} ({})", quote(&todo.content, ITEM_WIDTH), todo.status.replace('_', " ")))
+        .collect()
+}
+
+/// What the assistant last said, which usually sums up where things stand.
+fn last_assistant_text(messages: &[SessionMessage]) -> Option<String> {
+    messages.iter().rev().find_map(|msg| {
+        let inner = msg.message.as_ref()?;
+        if inner.role.as_deref() != Some("assistant") {
+            return None;
+        }
+        let text = extract_content_text(inner.content.as_ref()?);
+        (!text.trim().is_empty()).then(|| text.into_owned())
+    })
+}
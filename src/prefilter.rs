@@ -0,0 +1,376 @@
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::time::Instant;
+use tracing::{debug, info};
+
+use crate::error::{Result, SessionFinderError};
+use crate::explain::explain;
+use crate::matcher::{query_matcher, TermMatcher};
+use crate::resolve::{is_encoded_session_file, is_session_file, open_session};
+use crate::resume::shell_quote;
+
+/// Where the search terms turned up in one session file, found without parsing it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileMatches {
+    /// Lines containing a term
+    pub lines: usize,
+    /// Every occurrence of a term in those lines
+    pub occurrences: usize,
+    /// Byte offset of each matching line in the file, for reading excerpts without a rescan
+    pub line_offsets: Vec<u64>,
+}
+
+/// Session files under `projects_dir` containing any of the search terms, with paths relative
+/// to `projects_dir`. ripgrep finds them fast; without it, every session file is read and
+/// matched here instead.
+pub fn count_matching_lines(projects_dir: &Path, search_terms: &[&str]) -> Result<Vec<(PathBuf, FileMatches)>> {
+    let mut files = Vec::new();
+    for_each_matching_file(projects_dir, search_terms, false, |path, matches| {
+        files.push((path, matches));
+        Ok(true)
+    })?;
+    Ok(files)
+}
+
+/// Like `count_matching_lines`, handing each file over as soon as its matches are counted
+/// rather than when the scan ends, so work on it can start while the scan goes on. The scan
+/// stops early if `on_file` returns false. With `explaining`, how the files are searched is
+/// told under `--explain-plan`.
+pub fn for_each_matching_file(
+    projects_dir: &Path,
+    search_terms: &[&str],
+    explaining: bool,
+    mut on_file: impl FnMut(PathBuf, FileMatches) -> Result<bool>,
+) -> Result<()> {
+    let stopped = Cell::new(false);
+    let mut on_file = |path, matches| {
+        let more = on_file(path, matches)?;
+        stopped.set(!more);
+        Ok(more)
+    };
+    if count_matches_with_ripgrep(projects_dir, search_terms, explaining, &mut on_file)? {
+        // ripgrep can't see into sessions pulled compressed or encrypted, so those are read here
+        if !stopped.get() {
+            count_matches_in_process(projects_dir, search_terms, &mut on_file, is_encoded_session_file)?;
+        }
+        return Ok(());
+    }
+    info!("ripgrep is not installed; matching session files in-process");
+    explain(explaining, &format!(
+        "ripgrep is not installed; reading every session file under {} and matching in-process",
+        projects_dir.display()
+    ));
+    count_matches_in_process(projects_dir, search_terms, &mut on_file, is_session_file)
+}
+
+/// Like `count_matches_with_ripgrep`, without ripgrep, over the session files `include` picks:
+/// lines are matched with the query's matcher, case-insensitively, as `rg -i -F` would.
+/// Offsets in compressed or encrypted files count bytes of their decoded text.
+fn count_matches_in_process(
+    projects_dir: &Path,
+    search_terms: &[&str],
+    on_file: &mut impl FnMut(PathBuf, FileMatches) -> Result<bool>,
+    include: fn(&Path) -> bool,
+) -> Result<()> {
+    let matcher = query_matcher(search_terms);
+    let started = Instant::now();
+    let mut files = 0;
+    // Hidden directories and files are skipped, as ripgrep skips them
+    let walker = walkdir::WalkDir::new(projects_dir)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.'));
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || !include(path) {
+            continue;
+        }
+        let reader = match open_session(path) {
+            Ok(reader) => reader,
+            Err(e) => {
+                debug!(file = %path.display(), error = %e, "Skipped: unreadable");
+                continue;
+            }
+        };
+        let matches = count_matches_in(reader, &matcher);
+        if matches.lines > 0 {
+            files += 1;
+            if !on_file(path.strip_prefix(projects_dir).unwrap_or(path).to_path_buf(), matches)? {
+                break;
+            }
+        }
+    }
+    debug!(files, elapsed_ms = started.elapsed().as_millis() as u64, "In-process scan finished");
+    Ok(())
+}
+
+/// The lines of a session's text containing a term, matched as `count_matches_in_process` matches them.
+fn count_matches_in(reader: impl BufRead, matcher: &TermMatcher) -> FileMatches {
+    let mut matches = FileMatches::default();
+    let mut offset = 0;
+    for line in reader.split(b'\n').map_while(|line| line.ok()) {
+        let count = matcher.count(&String::from_utf8_lossy(&line).to_lowercase());
+        if count > 0 {
+            matches.lines += 1;
+            matches.occurrences += count;
+            matches.line_offsets.push(offset);
+        }
+        offset += line.len() as u64 + 1;
+    }
+    matches
+}
+
+/// The first `count` matching lines, read from their offsets rather than by matching the file
+/// again. Compressed or encrypted files are decoded only as far as the last line wanted.
+pub fn read_matching_lines(path: &Path, matches: &FileMatches, count: usize) -> Result<Vec<String>> {
+    let offsets = &matches.line_offsets[..count.min(matches.line_offsets.len())];
+    let mut lines = Vec::new();
+    let mut read_line = |reader: &mut dyn BufRead| -> io::Result<u64> {
+        let mut line = Vec::new();
+        let read = reader.read_until(b'\n', &mut line)?;
+        lines.push(String::from_utf8_lossy(&line).trim_end().to_string());
+        Ok(read as u64)
+    };
+    if is_encoded_session_file(path) {
+        let mut reader = open_session(path)?;
+        let mut position = 0;
+        for &offset in offsets {
+            position += io::copy(&mut reader.by_ref().take(offset.saturating_sub(position)), &mut io::sink())?;
+            position += read_line(&mut reader)?;
+        }
+    } else {
+        let mut reader = BufReader::new(File::open(path)?);
+        for &offset in offsets {
+            reader.seek(SeekFrom::Start(offset))?;
+            read_line(&mut reader)?;
+        }
+    }
+    Ok(lines)
+}
+
+/// One line of `rg --json` output. Only matches matter; the text of the matched line is
+/// skipped rather than kept, since session lines can be huge.
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+enum RgEvent {
+    Match {
+        path: RgText,
+        absolute_offset: u64,
+        submatches: Vec<IgnoredAny>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// A path in `rg --json` output. Paths that aren't valid UTF-8 come as base64 `bytes`
+/// instead, and are skipped since session files never have such names.
+#[derive(Deserialize)]
+struct RgText {
+    text: Option<String>,
+}
+
+/// Hands over the session files containing any of the search terms, returning false if
+/// ripgrep isn't installed. ripgrep's `--json` events are read as they arrive, so nothing is
+/// buffered but the counts for the file being reported.
+fn count_matches_with_ripgrep(
+    projects_dir: &Path,
+    search_terms: &[&str],
+    explaining: bool,
+    on_file: &mut impl FnMut(PathBuf, FileMatches) -> Result<bool>,
+) -> Result<bool> {
+    let args = ripgrep_args(search_terms);
+    debug!(cwd = %projects_dir.display(), "Running rg {}", args.join(" "));
+    let started = Instant::now();
+    let spawned = process::Command::new("rg")
+        .args(&args)
+        .current_dir(projects_dir)
+        // ripgrep searches stdin rather than the directory when stdin isn't a terminal
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(SessionFinderError::backend("ripgrep", format!("Ripgrep failed: {}", e))),
+    };
+    explain(explaining, &format!(
+        "in {}: rg {}",
+        projects_dir.display(),
+        args.iter().map(|arg| shell_quote(arg)).collect::<Vec<_>>().join(" ")
+    ));
+
+    let mut files = RipgrepFiles::default();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    for line in BufReader::new(stdout).split(b'\n') {
+        let Some((finished, matches)) = files.add_line(&line?) else {
+            continue;
+        };
+        if !on_file(finished, matches)? {
+            debug!(elapsed_ms = started.elapsed().as_millis() as u64, "Stopped ripgrep early");
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(true);
+        }
+    }
+    if let Some((finished, matches)) = files.finish() {
+        on_file(finished, matches)?;
+    }
+    let output = child.wait_with_output()?;
+    debug!(status = %output.status, elapsed_ms = started.elapsed().as_millis() as u64, "ripgrep finished");
+    check_ripgrep_status(output.status, &output.stderr)?;
+    Ok(true)
+}
+
+fn ripgrep_args<'a>(search_terms: &[&'a str]) -> Vec<&'a str> {
+    // -F for literal mode to avoid regex interpretation issues
+    let mut args = vec!["--json", "-i", "-F", "--glob", "*.jsonl"];
+    for term in search_terms {
+        args.extend(["-e", term]);
+    }
+    args
+}
+
+/// Files and their matches as ripgrep's `--json` output reports them. ripgrep reports all of
+/// one file's matches before moving on to the next, so a file is complete once another one's
+/// matches start.
+#[derive(Default)]
+struct RipgrepFiles {
+    current: Option<(PathBuf, FileMatches)>,
+}
+
+impl RipgrepFiles {
+    /// Counts one line of output, returning the file it showed to be complete, if any.
+    fn add_line(&mut self, line: &[u8]) -> Option<(PathBuf, FileMatches)> {
+        let Ok(RgEvent::Match { path, absolute_offset, submatches }) = serde_json::from_slice::<RgEvent>(line) else {
+            return None;
+        };
+        let path = path.text.map(PathBuf::from)?;
+        let mut finished = None;
+        if self.current.as_ref().is_none_or(|(known, _)| *known != path) {
+            finished = self.current.replace((path, FileMatches::default()));
+        }
+        let (_, matches) = self.current.as_mut().expect("just set");
+        matches.lines += 1;
+        matches.occurrences += submatches.len().max(1);
+        matches.line_offsets.push(absolute_offset);
+        finished
+    }
+
+    /// The file being counted when the output ended.
+    fn finish(self) -> Option<(PathBuf, FileMatches)> {
+        self.current
+    }
+}
+
+fn check_ripgrep_status(status: process::ExitStatus, stderr: &[u8]) -> Result<()> {
+    // Exit code 1 means no matches, which is fine; 2 is an error
+    match status.code() {
+        Some(0) => {}
+        Some(1) => info!("ripgrep found no files containing the search terms"),
+        _ => {
+            return Err(SessionFinderError::backend(
+                "ripgrep",
+                format!(
+                    "Ripgrep command failed with status: {}. Error: {}",
+                    status,
+                    String::from_utf8_lossy(stderr)
+                ),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// `count_matching_lines` on a tokio runtime: ripgrep's output is read as it arrives without
+/// holding a thread, and without ripgrep (or for compressed and encrypted sessions) files are
+/// listed and read through `tokio::fs`.
+#[cfg(feature = "tokio")]
+pub async fn count_matching_lines_async(projects_dir: &Path, search_terms: &[&str]) -> Result<Vec<(PathBuf, FileMatches)>> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    let args = ripgrep_args(search_terms);
+    debug!(cwd = %projects_dir.display(), "Running rg {}", args.join(" "));
+    let spawned = tokio::process::Command::new("rg")
+        .args(&args)
+        .current_dir(projects_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            info!("ripgrep is not installed; matching session files in-process");
+            return count_matches_in_process_async(projects_dir, search_terms, is_session_file).await;
+        }
+        Err(e) => return Err(SessionFinderError::backend("ripgrep", format!("Ripgrep failed: {}", e))),
+    };
+
+    let mut files = Vec::new();
+    let mut found = RipgrepFiles::default();
+    let mut stdout = tokio::io::BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut line = Vec::new();
+    while stdout.read_until(b'\n', &mut line).await? > 0 {
+        files.extend(found.add_line(&line));
+        line.clear();
+    }
+    files.extend(found.finish());
+    let mut stderr = Vec::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        pipe.read_to_end(&mut stderr).await?;
+    }
+    check_ripgrep_status(child.wait().await?, &stderr)?;
+    // ripgrep can't see into sessions pulled compressed or encrypted, so those are read here
+    files.extend(count_matches_in_process_async(projects_dir, search_terms, is_encoded_session_file).await?);
+    Ok(files)
+}
+
+/// `count_matches_in_process` through `tokio::fs`, collecting the files rather than handing
+/// them over one at a time.
+#[cfg(feature = "tokio")]
+async fn count_matches_in_process_async(
+    projects_dir: &Path,
+    search_terms: &[&str],
+    include: fn(&Path) -> bool,
+) -> Result<Vec<(PathBuf, FileMatches)>> {
+    use crate::resolve::read_session_text_async;
+
+    let matcher = query_matcher(search_terms);
+    let mut files = Vec::new();
+    let mut dirs = vec![projects_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            // Hidden directories and files are skipped, as ripgrep skips them
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            if !file_type.is_file() || !include(&path) {
+                continue;
+            }
+            let text = match read_session_text_async(&path).await {
+                Ok(text) => text,
+                Err(e) => {
+                    debug!(file = %path.display(), error = %e, "Skipped: unreadable");
+                    continue;
+                }
+            };
+            let matches = count_matches_in(text.as_bytes(), &matcher);
+            if matches.lines > 0 {
+                files.push((path.strip_prefix(projects_dir).unwrap_or(&path).to_path_buf(), matches));
+            }
+        }
+    }
+    Ok(files)
+}
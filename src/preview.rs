@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use tracing::debug;
+
+use crate::interrupt::{interruption, is_user_prompt};
+use crate::prefilter::{read_matching_lines, FileMatches};
+use crate::render::RenderOptions;
+use crate::schema::{parse_session_line, ParseDiagnostics};
+use crate::text::truncate_text;
+use crate::timeline::{extract_content_text, format_tool_content, term_excerpt};
+use crate::title::is_wrapper;
+use crate::{Content, SessionMessage};
+
+/// Messages previewed at each end of a session without `--preview`
+const DEFAULT_PREVIEW_COUNT: usize = 8;
+
+/// Widest excerpt of a message, in terminal columns, without `--preview-width`
+const DEFAULT_PREVIEW_WIDTH: usize = 200;
+
+/// Common terms listed per session without `--terms`
+const DEFAULT_COMMON_TERMS: usize = 50;
+
+/// Assistant replies shorter than this many words ("Let me check.") say little about a session
+const MIN_PROSE_WORDS: usize = 4;
+
+/// Matching messages previewed per session, at most
+const MATCHED_PREVIEW_COUNT: usize = 3;
+
+/// Kinds of message a session's preview can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewRole {
+    /// Prompts the user typed
+    User,
+    /// What Claude wrote back, apart from tool calls
+    Assistant,
+    /// Tool calls and their results
+    Tool,
+}
+
+/// Names `--preview-roles` accepts
+pub const PREVIEW_ROLE_NAMES: &[&str] = &["user", "assistant", "tool"];
+
+/// Previews show prompts and replies unless `--preview-roles` says otherwise
+const DEFAULT_ROLES: &[PreviewRole] = &[PreviewRole::User, PreviewRole::Assistant];
+
+/// What a session's preview shows, and how many common terms are listed with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewOptions {
+    /// `--preview-roles`: the kinds of message previewed
+    pub roles: Vec<PreviewRole>,
+    /// `--preview`: messages previewed at each end of a session
+    pub count: usize,
+    /// `--preview-width`: the widest message excerpt, in terminal columns
+    pub width: usize,
+    /// `--terms`: common terms listed per session
+    pub common_terms: usize,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        PreviewOptions {
+            roles: DEFAULT_ROLES.to_vec(),
+            count: DEFAULT_PREVIEW_COUNT,
+            width: DEFAULT_PREVIEW_WIDTH,
+            common_terms: DEFAULT_COMMON_TERMS,
+        }
+    }
+}
+
+impl PreviewRole {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "user" => Some(PreviewRole::User),
+            "assistant" => Some(PreviewRole::Assistant),
+            "tool" => Some(PreviewRole::Tool),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PreviewRole::User => "user",
+            PreviewRole::Assistant => "assistant",
+            PreviewRole::Tool => "tool",
+        }
+    }
+}
+
+/// The `role: text` excerpt a message shows in previews, if it is of a selected kind. Tool
+/// results (sent as user messages), Claude Code's command wrappers and notices,
+/// interruptions, subagents' messages, and assistant asides of a few words don't count as
+/// prompts or replies.
+fn preview_excerpt(msg: &SessionMessage, options: &PreviewOptions, render: RenderOptions) -> Option<String> {
+    let roles = &options.roles;
+    if msg.is_sidechain == Some(true) {
+        return None;
+    }
+    let content = msg.message.as_ref()?.content.as_ref()?;
+    let (role, text) = match msg.msg_type.as_str() {
+        "user" if is_user_prompt(msg) => {
+            let text = extract_content_text(content);
+            if interruption(msg).is_some() || text.trim().is_empty() || is_wrapper(&text) {
+                return None;
+            }
+            (PreviewRole::User, text.into_owned())
+        }
+        "user" => (PreviewRole::Tool, tool_result_text(content)?),
+        "assistant" => {
+            let prose = extract_content_text(content);
+            if roles.contains(&PreviewRole::Assistant) && prose.split_whitespace().count() >= MIN_PROSE_WORDS {
+                (PreviewRole::Assistant, prose.into_owned())
+            } else {
+                (PreviewRole::Tool, tool_call_text(content, render)?)
+            }
+        }
+        _ => return None,
+    };
+    roles.contains(&role).then(|| excerpt(role.name(), &text, options.width))
+}
+
+/// The excerpt any message with text shows when a session has none of the selected kinds.
+fn fallback_excerpt(msg: &SessionMessage, width: usize) -> Option<String> {
+    let inner = msg.message.as_ref()?;
+    let (role, text) = (inner.role.as_deref()?, extract_content_text(inner.content.as_ref()?));
+    (!text.is_empty()).then(|| excerpt(role, &text, width))
+}
+
+fn excerpt(role: &str, text: &str, width: usize) -> String {
+    format!("{}: {}", role, truncate_text(text, width))
+}
+
+/// The first line of how timelines show the message's first tool call.
+fn tool_call_text(content: &Content, render: RenderOptions) -> Option<String> {
+    let Content::Array(blocks) = content else {
+        return None;
+    };
+    let call = blocks.iter().find(|block| block.r#type == "tool_use")?;
+    let text = format_tool_content(call.name.as_deref().unwrap_or_default(), &call.input, render);
+    text.lines().next().map(str::to_string)
+}
+
+/// The output of the message's tool results: strings, or the text of their text blocks.
+fn tool_result_text(content: &Content) -> Option<String> {
+    let Content::Array(blocks) = content else {
+        return None;
+    };
+    let texts: Vec<&str> = blocks
+        .iter()
+        .filter(|block| block.r#type == "tool_result")
+        .filter_map(|block| block.content.as_ref())
+        .flat_map(|output| match output {
+            serde_json::Value::String(text) => vec![text.as_str()],
+            serde_json::Value::Array(parts) => parts.iter().filter_map(|part| part.get("text")?.as_str()).collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    let text = texts.join(" ");
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// The first and last few excerpts of a stream of messages
+#[derive(Clone, Default)]
+struct Ends {
+    first: Vec<String>,
+    last: VecDeque<String>,
+}
+
+impl Ends {
+    fn push(&mut self, excerpt: String, count: usize) {
+        if count == 0 {
+            return;
+        }
+        if self.first.len() < count {
+            self.first.push(excerpt.clone());
+        }
+        if self.last.len() == count {
+            self.last.pop_front();
+        }
+        self.last.push_back(excerpt);
+    }
+}
+
+/// Collects a session's preview as its messages arrive, which may be in chunks.
+#[derive(Clone)]
+pub struct PreviewCollector {
+    options: PreviewOptions,
+    render: RenderOptions,
+    selected: Ends,
+    /// Every message with text, for sessions without any of the selected kinds
+    fallback: Ends,
+}
+
+impl PreviewCollector {
+    pub fn new(options: &PreviewOptions, render: RenderOptions) -> Self {
+        PreviewCollector { options: options.clone(), render, selected: Ends::default(), fallback: Ends::default() }
+    }
+
+    pub fn add(&mut self, msg: &SessionMessage) {
+        let count = self.options.count;
+        if let Some(excerpt) = preview_excerpt(msg, &self.options, self.render) {
+            self.selected.push(excerpt, count);
+        } else if self.selected.first.is_empty() {
+            if let Some(excerpt) = fallback_excerpt(msg, self.options.width) {
+                self.fallback.push(excerpt, count);
+            }
+        }
+    }
+
+    /// The first and last messages previewed.
+    pub fn finish(self) -> (Vec<String>, Vec<String>) {
+        let ends = if self.selected.first.is_empty() { self.fallback } else { self.selected };
+        (ends.first, ends.last.into())
+    }
+}
+
+/// Excerpts around the terms in the first few matching lines the prefilter found, read from
+/// their offsets instead of picked out while the session was analysed. Lines that aren't
+/// messages with text, such as summaries, are passed over.
+pub fn matched_excerpts(
+    path: &Path,
+    matches: &FileMatches,
+    search_terms: &[&str],
+    options: &PreviewOptions,
+) -> Vec<String> {
+    let lines = match read_matching_lines(path, matches, MATCHED_PREVIEW_COUNT.min(options.count)) {
+        Ok(lines) => lines,
+        Err(e) => {
+            debug!(file = %path.display(), error = %e, "No matched excerpts");
+            return Vec::new();
+        }
+    };
+    let mut diagnostics = ParseDiagnostics::default();
+    lines
+        .iter()
+        .filter_map(|line| parse_session_line(line, 0, &mut diagnostics))
+        .filter_map(|msg| {
+            let inner = msg.message?;
+            let text = extract_content_text(inner.content.as_ref()?).into_owned();
+            if text.trim().is_empty() {
+                return None;
+            }
+            let role = inner.role.as_deref().unwrap_or(&msg.msg_type);
+            Some(excerpt(role, &term_excerpt(&text, search_terms), options.width))
+        })
+        .collect()
+}
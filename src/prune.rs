@@ -0,0 +1,79 @@
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::active::is_active;
+use crate::decode_project_path;
+use crate::error::Result;
+use crate::resolve::all_session_files;
+use crate::trash::TrashOperation;
+
+/// Moves sessions last modified more than `older_than_days` ago, in projects matching
+/// `project_filter`, into the trash as one operation `undo` can reverse. Sessions still being
+/// written are kept. With `dry_run`, only lists them. Returns whether any session was old enough.
+pub fn run_prune(older_than_days: i64, project_filter: Option<&String>, dry_run: bool) -> Result<bool> {
+    let sessions = prunable_sessions(Utc::now() - Duration::days(older_than_days), project_filter)?;
+    if sessions.is_empty() {
+        println!("No sessions were last modified more than {} day(s) ago.", older_than_days);
+        return Ok(false);
+    }
+    let mut trash = TrashOperation::begin("prune");
+    for path in &sessions {
+        if dry_run {
+            println!("Would prune {}", path.display());
+        } else {
+            trash.discard_file(path)?;
+            println!("Pruned {}", path.display());
+        }
+    }
+    trash.finish();
+    Ok(true)
+}
+
+/// Session files last modified before `cutoff` and not active, oldest first.
+fn prunable_sessions(cutoff: DateTime<Utc>, project_filter: Option<&String>) -> Result<Vec<PathBuf>> {
+    let mut sessions = Vec::new();
+    for path in all_session_files()? {
+        let in_project = project_filter
+            .is_none_or(|filter| decode_project_path(&path).is_ok_and(|project| project.contains(filter.as_str())));
+        if !in_project {
+            continue;
+        }
+        let modified: DateTime<Utc> = DateTime::from(fs::metadata(&path)?.modified()?);
+        if modified < cutoff && !is_active(&path) {
+            sessions.push((modified, path));
+        }
+    }
+    sessions.sort();
+    Ok(sessions.into_iter().map(|(_, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fake_home, write_session};
+    use crate::trash::{run_undo, trash_dir};
+    use std::time::Duration as StdDuration;
+
+    const DAY: StdDuration = StdDuration::from_secs(24 * 60 * 60);
+
+    #[test]
+    fn pruned_sessions_come_back_with_undo() {
+        let (_home, home) = fake_home();
+        let old = write_session(home, "-work-prune", "old", &["a", "b", "c"], 90 * DAY);
+        let recent = write_session(home, "-work-prune", "recent", &["a", "b", "c"], DAY);
+
+        let project = "work/prune".to_string();
+        assert!(run_prune(30, Some(&project), false).unwrap());
+        assert!(!old.exists());
+        assert!(recent.exists());
+
+        let operation = fs::read_dir(trash_dir().unwrap())
+            .unwrap()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .find(|id| id.ends_with("-prune"))
+            .expect("the prune is in the trash");
+        run_undo(Some(&operation)).unwrap();
+        assert!(old.exists());
+    }
+}
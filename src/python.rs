@@ -0,0 +1,105 @@
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use serde::Serialize;
+
+use crate::chunks::{chunk_sessions, ChunkOptions};
+use crate::config::Config;
+use crate::error::{Result, SessionFinderError};
+use crate::matcher::SearchOptions;
+use crate::render::RenderOptions;
+use crate::time_range::TimeRange;
+use crate::timeline::{extract_timeline, TimelineSearch};
+use crate::{find_sessions, rank_and_limit_sessions};
+
+/// A failure raised as `RuntimeError`, with the message the CLI prints.
+fn py_error(error: SessionFinderError) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+/// The same value as Python dicts, lists, and scalars, by way of its JSON.
+fn to_python<'py>(py: Python<'py>, value: &impl Serialize) -> PyResult<Bound<'py, PyAny>> {
+    let json = serde_json::to_string(value).map_err(|e| py_error(e.into()))?;
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+/// Sessions matching the terms, best first, as the `search` JSON-RPC method returns them.
+#[pyfunction]
+#[pyo3(signature = (terms, project=None, recent_days=None, limit=10))]
+fn search<'py>(
+    py: Python<'py>,
+    terms: Vec<String>,
+    project: Option<String>,
+    recent_days: Option<i64>,
+    limit: usize,
+) -> PyResult<Bound<'py, PyAny>> {
+    let sessions = py.detach(|| -> Result<_> {
+        let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+        let options = SearchOptions::from_config(&Config::load()?, true, true);
+        let sessions = find_sessions(&terms, project.as_ref(), recent_days, limit, None, &options)?;
+        Ok(rank_and_limit_sessions(sessions, limit, options.sort))
+    })
+    .map_err(py_error)?;
+    to_python(py, &sessions)
+}
+
+/// A session's timeline around the messages matching the terms (all messages without any), as
+/// `timeline --output json` writes it. `after` and `before` take what `--after` and
+/// `--before` do.
+#[pyfunction]
+#[pyo3(signature = (session, terms=Vec::new(), context=2, top=None, after=None, before=None))]
+fn timeline<'py>(
+    py: Python<'py>,
+    session: String,
+    terms: Vec<String>,
+    context: usize,
+    top: Option<usize>,
+    after: Option<String>,
+    before: Option<String>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let timeline = py.detach(|| -> Result<_> {
+        let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+        let time_range = TimeRange::parse(after.as_deref(), before.as_deref())?;
+        let search = TimelineSearch {
+            search_terms: &terms,
+            context_size: context,
+            top_entries: top,
+            tool_filters: &[],
+            exclude_interrupted: false,
+            time_range: &time_range,
+            options: &SearchOptions::from_config(&Config::load()?, true, true),
+        };
+        extract_timeline(&session, &search)
+    })
+    .map_err(py_error)?;
+    to_python(py, &timeline)
+}
+
+/// The sessions cut into overlapping chunks for embedding, as `chunks` prints them: the
+/// given sessions, or every session matching the project and recency filters.
+#[pyfunction]
+#[pyo3(signature = (sessions=Vec::new(), project=None, recent_days=None, chunk_size=2000, overlap=1))]
+fn chunks<'py>(
+    py: Python<'py>,
+    sessions: Vec<String>,
+    project: Option<String>,
+    recent_days: Option<i64>,
+    chunk_size: usize,
+    overlap: usize,
+) -> PyResult<Bound<'py, PyAny>> {
+    let chunks = py.detach(|| -> Result<_> {
+        let session_refs: Vec<&str> = sessions.iter().map(String::as_str).collect();
+        let options = ChunkOptions { max_chars: chunk_size, overlap, render: RenderOptions::default() };
+        Ok(chunk_sessions(&session_refs, project.as_ref(), recent_days, options)?.collect::<Vec<_>>())
+    })
+    .map_err(py_error)?;
+    to_python(py, &chunks)
+}
+
+/// The `session_finder` Python module, built with `maturin` from the `python` feature.
+#[pymodule]
+fn session_finder(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(search, m)?)?;
+    m.add_function(wrap_pyfunction!(timeline, m)?)?;
+    m.add_function(wrap_pyfunction!(chunks, m)?)?;
+    Ok(())
+}
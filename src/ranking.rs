@@ -0,0 +1,39 @@
+use crate::error::{Result, SessionFinderError};
+
+/// How search results are ordered before `--limit` applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Sessions matching more query topics first, then the most recent; the default
+    #[default]
+    Relevance,
+    /// Most recently modified first
+    Recent,
+    /// Most occurrences of the terms first
+    Matches,
+}
+
+/// Names `--sort` and the `sort` setting accept
+pub const SORT_ORDER_NAMES: &[&str] = &["relevance", "recent", "matches"];
+
+impl SortOrder {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "relevance" => Ok(SortOrder::Relevance),
+            "recent" => Ok(SortOrder::Recent),
+            "matches" => Ok(SortOrder::Matches),
+            other => Err(SessionFinderError::Usage(format!(
+                "Unknown sort order '{}' (use {})",
+                other,
+                SORT_ORDER_NAMES.join(", ")
+            ))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortOrder::Relevance => "relevance",
+            SortOrder::Recent => "recent",
+            SortOrder::Matches => "matches",
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::error::Result;
+use crate::matcher::SearchOptions;
+use crate::resolve::read_session_text;
+use crate::text::words;
+use crate::timeline::parse_session_messages;
+use crate::{is_common_term_word, SessionInfo};
+
+/// Words this far either side of a query term count as near it
+const WINDOW: usize = 10;
+
+/// Words seen near the query fewer times than this are left out, as chance
+const MIN_NEARBY: usize = 2;
+
+/// Related terms reported
+const MAX_RELATED_TERMS: usize = 20;
+
+/// A word that turns up near the query more often than its overall frequency predicts
+#[derive(Debug, Serialize)]
+pub struct RelatedTerm {
+    pub term: String,
+    /// Pointwise mutual information in bits: log2 of how much likelier the word is near the
+    /// query than anywhere in the sessions
+    pub pmi: f64,
+    /// Occurrences within the window of a query term
+    pub nearby: usize,
+    /// Occurrences anywhere in the sessions
+    pub occurrences: usize,
+}
+
+/// The words most associated with the query across `sessions`, by PMI between being near a
+/// query term and the word appearing. Text is split into words as for common terms, so
+/// boilerplate and stopwords are skipped, and only the text the match scope covers counts.
+pub fn related_terms(sessions: &[SessionInfo], search_terms: &[&str], options: &SearchOptions) -> Result<Vec<RelatedTerm>> {
+    let query: Vec<Vec<String>> = search_terms
+        .iter()
+        .map(|term| words(term).collect::<Vec<_>>())
+        .filter(|words| !words.is_empty())
+        .collect();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut nearby: HashMap<String, usize> = HashMap::new();
+    let (mut total_words, mut total_nearby) = (0, 0);
+    for session in sessions {
+        let messages = parse_session_messages(&read_session_text(&session.path)?)?;
+        for content in messages.iter().filter_map(|msg| msg.message.as_ref()?.content.as_ref()) {
+            let words: Vec<String> = words(&options.match_text(content)).collect();
+            let mut in_query = vec![false; words.len()];
+            let mut near = vec![false; words.len()];
+            for start in 0..words.len() {
+                let Some(length) = query.iter().find(|term| words[start..].starts_with(term)).map(Vec::len) else {
+                    continue;
+                };
+                in_query[start..start + length].iter_mut().for_each(|flag| *flag = true);
+                let window_end = (start + length + WINDOW).min(words.len());
+                near[start.saturating_sub(WINDOW)..window_end].iter_mut().for_each(|flag| *flag = true);
+            }
+            for (index, word) in words.iter().enumerate() {
+                if in_query[index] || !is_common_term_word(word, &options.stopwords) {
+                    continue;
+                }
+                total_words += 1;
+                *occurrences.entry(word.clone()).or_default() += 1;
+                if near[index] {
+                    total_nearby += 1;
+                    *nearby.entry(word.clone()).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let mut related: Vec<RelatedTerm> = nearby
+        .into_iter()
+        .filter(|(_, count)| *count >= MIN_NEARBY)
+        .map(|(term, count)| {
+            let occurrences = occurrences[&term];
+            let pmi = ((count as f64 / total_nearby as f64) / (occurrences as f64 / total_words as f64)).log2();
+            RelatedTerm { term, pmi, nearby: count, occurrences }
+        })
+        // Words no likelier near the query than elsewhere aren't related to it
+        .filter(|term| term.pmi > 0.0)
+        .collect();
+    related.sort_by(|a, b| {
+        b.pmi
+            .total_cmp(&a.pmi)
+            .then(b.nearby.cmp(&a.nearby))
+            .then_with(|| a.term.cmp(&b.term))
+    });
+    related.truncate(MAX_RELATED_TERMS);
+    Ok(related)
+}
+
+pub fn display_related_terms(related: &[RelatedTerm], sessions: usize) {
+    if related.is_empty() {
+        println!("No terms turn up near the query often enough in {} session(s) to tell.", sessions);
+        return;
+    }
+    println!("Terms found near the query in {} session(s), most associated first:\n", sessions);
+    let width = related.iter().map(|term| term.term.chars().count()).max().unwrap_or(0);
+    println!("  {:<width$}  {:>5}  {:>6}  {:>5}", "term", "pmi", "nearby", "total", width = width);
+    for term in related {
+        println!(
+            "  {:<width$}  {:>5.2}  {:>6}  {:>5}",
+            term.term,
+            term.pmi,
+            term.nearby,
+            term.occurrences,
+            width = width
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use crate::glyphs::{sparkline_levels, Glyph};
+use crate::wrap::{output_width, wrap_indented};
+
+/// How output is drawn: with which markers, and wrapped to how many columns. The default
+/// draws emoji and box-drawing markers and doesn't wrap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// `--ascii`: ASCII stand-ins for terminals and logs that garble emoji
+    pub ascii: bool,
+    /// Columns lines are wrapped to, if any
+    pub width: Option<usize>,
+}
+
+impl RenderOptions {
+    /// Options for printing to stdout: wrapped to `--width`, or else to the terminal's width
+    /// when there is one.
+    pub fn for_stdout(ascii: bool, requested_width: Option<usize>) -> Self {
+        RenderOptions { ascii, width: output_width(requested_width) }
+    }
+
+    pub fn glyph(self, glyph: Glyph) -> &'static str {
+        glyph.render(self.ascii)
+    }
+
+    /// Bar heights for sparklines, from none to the peak.
+    pub fn sparkline_levels(self) -> &'static [char; 9] {
+        sparkline_levels(self.ascii)
+    }
+
+    /// `text` after `prefix`, its later lines after `indent`, wrapped to the width.
+    pub fn wrap_indented(self, text: &str, prefix: &str, indent: &str) -> String {
+        wrap_indented(text, prefix, indent, self.width)
+    }
+}
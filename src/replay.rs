@@ -0,0 +1,214 @@
+use serde_json::Value;
+use std::io::{BufRead, IsTerminal, Write};
+
+use crate::error::Result;
+use crate::glyphs::Glyph;
+use crate::interrupt::interrupted_turns;
+use crate::render::RenderOptions;
+use crate::resolve::read_session_source;
+use crate::show::result_text;
+use crate::store::Store;
+use crate::timeline::{
+    classify_message_content, content_type_label, extract_content_text, format_tool_content, interrupted_label,
+    message_heading, parse_session_messages,
+};
+use crate::{Content, ContentBlock, ContentType, SessionMessage};
+
+const HELP: &str = "\
+Enter or n  next message          p  previous message
+N or j N    jump to message N     /TEXT  next message containing TEXT (/ repeats)
+c           next code change      e  next error
+?           this help             q  quit";
+
+/// Steps through a session one message at a time as it happened, reading commands from the
+/// keyboard: tool calls in full, edits as diffs, and jumps to the next code change, error, or
+/// search hit.
+pub fn run_replay(session_ref: &str, render: RenderOptions) -> Result<()> {
+    let (_, session_id, content) = read_session_source(session_ref)?;
+    let messages = parse_session_messages(&content)?;
+    if messages.is_empty() {
+        println!("Session {} has no messages.", session_id);
+        return Ok(());
+    }
+    let interrupted = interrupted_turns(&messages);
+    let store = Store::load()?;
+    let color = std::io::stdout().is_terminal();
+
+    println!("=== Replaying session {} ({} messages; ? for help) ===\n", session_id, messages.len());
+    let mut current = 0;
+    let mut last_search = String::new();
+    let mut input = std::io::stdin().lock();
+    loop {
+        print_message(&messages, current, interrupted[current], &session_id, &store, color, render);
+        let next = loop {
+            print!("[{}/{}] > ", current, messages.len() - 1);
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                println!();
+                return Ok(());
+            }
+            let command = line.trim();
+            let found = match command {
+                "" | "n" => (current + 1 < messages.len()).then_some(current + 1),
+                "p" => current.checked_sub(1),
+                "c" => find_next(&messages, current, |index| is_code_change(&messages, index)),
+                "e" => find_next(&messages, current, |index| is_error(&messages, index)),
+                "q" => return Ok(()),
+                "?" | "h" => {
+                    println!("{}", HELP);
+                    continue;
+                }
+                _ if command.starts_with('/') => {
+                    let text = command[1..].trim();
+                    if !text.is_empty() {
+                        last_search = text.to_lowercase();
+                    }
+                    if last_search.is_empty() {
+                        println!("Nothing to search for yet");
+                        continue;
+                    }
+                    find_next(&messages, current, |index| {
+                        message_text(&messages[index]).to_lowercase().contains(&last_search)
+                    })
+                }
+                _ => match command.strip_prefix("j").unwrap_or(command).trim().parse::<usize>() {
+                    Ok(index) if index < messages.len() => Some(index),
+                    Ok(index) => {
+                        println!("There is no message {}; the last is {}", index, messages.len() - 1);
+                        continue;
+                    }
+                    Err(_) => {
+                        println!("Unknown command {:?}; ? lists them", command);
+                        continue;
+                    }
+                },
+            };
+            match found {
+                Some(index) => break index,
+                None => println!("No more messages that way"),
+            }
+        };
+        current = next;
+        println!();
+    }
+}
+
+/// Index of the first message after `current` the predicate accepts.
+fn find_next(messages: &[SessionMessage], current: usize, is_wanted: impl Fn(usize) -> bool) -> Option<usize> {
+    (current + 1..messages.len()).find(|&index| is_wanted(index))
+}
+
+fn tool_uses(msg: &SessionMessage) -> impl Iterator<Item = &ContentBlock> {
+    let blocks = match msg.message.as_ref().and_then(|m| m.content.as_ref()) {
+        Some(Content::Array(blocks)) => blocks.as_slice(),
+        _ => &[],
+    };
+    blocks.iter().filter(|block| block.r#type == "tool_use")
+}
+
+fn message_text(msg: &SessionMessage) -> String {
+    msg.message
+        .as_ref()
+        .and_then(|m| m.content.as_ref())
+        .map(|content| extract_content_text(content).into_owned())
+        .unwrap_or_default()
+}
+
+fn is_code_change(messages: &[SessionMessage], index: usize) -> bool {
+    tool_uses(&messages[index]).any(|block| matches!(block.name.as_deref(), Some("Write" | "Edit" | "MultiEdit")))
+}
+
+fn is_error(messages: &[SessionMessage], index: usize) -> bool {
+    let failed_tool = match messages[index].message.as_ref().and_then(|m| m.content.as_ref()) {
+        Some(Content::Array(blocks)) => blocks.iter().any(|block| block.is_error == Some(true)),
+        _ => false,
+    };
+    failed_tool || matches!(classify_message_content(messages, index).content_type, ContentType::ErrorMessage(_))
+}
+
+fn print_message(
+    messages: &[SessionMessage],
+    index: usize,
+    interrupted: bool,
+    session_id: &str,
+    store: &Store,
+    color: bool,
+    render: RenderOptions,
+) {
+    let msg = &messages[index];
+    let classified = classify_message_content(messages, index);
+    let role = msg.message.as_ref().and_then(|m| m.role.clone()).unwrap_or_default();
+    println!(
+        "{} {}: {}{}",
+        message_heading(index, msg.line_number, msg.timestamp.as_deref()),
+        role,
+        content_type_label(&classified.content_type, render),
+        interrupted_label(interrupted, render)
+    );
+    if let Some(bookmark) = store.bookmark(session_id, index) {
+        println!("  Bookmark: {}", bookmark.label.as_deref().unwrap_or("(no label)"));
+    }
+    for note in store.message_notes(session_id, index) {
+        println!("  Note: {}", note.text);
+    }
+
+    let blocks = match msg.message.as_ref().and_then(|m| m.content.as_ref()) {
+        Some(Content::Text(text)) => {
+            println!("{}", text);
+            return;
+        }
+        Some(Content::Array(blocks)) => blocks,
+        None => return,
+    };
+    for block in blocks {
+        match block.r#type.as_str() {
+            "text" => println!("{}", block.text.as_deref().unwrap_or_default()),
+            "tool_use" => print_tool_use(block, color, render),
+            "tool_result" => {
+                let output = block.content.as_ref().map(result_text).unwrap_or_default();
+                let label = if block.is_error == Some(true) { format!("{} Tool error", render.glyph(Glyph::Failed)) } else { "Tool result".to_string() };
+                println!("{}:\n{}", label, output);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Edits and writes as diffs against what they replaced; other tools as in `show`.
+fn print_tool_use(block: &ContentBlock, color: bool, render: RenderOptions) {
+    let name = block.name.as_deref().unwrap_or_default();
+    let Some(input) = block.input.as_ref().filter(|_| matches!(name, "Write" | "Edit" | "MultiEdit")) else {
+        println!("{}", format_tool_content(name, &block.input, render));
+        return;
+    };
+    let file_path = input.get("file_path").and_then(Value::as_str).unwrap_or("unknown");
+    let text = |value: &Value, key: &str| value.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    match name {
+        "Write" => {
+            println!("{} Write {}", render.glyph(Glyph::Write), file_path);
+            print_diff("", &text(input, "content"), color);
+        }
+        "Edit" => {
+            println!("{} Edit {}", render.glyph(Glyph::Edit), file_path);
+            print_diff(&text(input, "old_string"), &text(input, "new_string"), color);
+        }
+        _ => {
+            let edits = input.get("edits").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+            println!("{} MultiEdit {} ({} edits)", render.glyph(Glyph::Edit), file_path, edits.len());
+            for edit in edits {
+                print_diff(&text(edit, "old_string"), &text(edit, "new_string"), color);
+            }
+        }
+    }
+}
+
+fn print_diff(old: &str, new: &str, color: bool) {
+    let (red, green, reset) = if color { ("\x1b[31m", "\x1b[32m", "\x1b[0m") } else { ("", "", "") };
+    for line in old.lines() {
+        println!("{}- {}{}", red, line, reset);
+    }
+    for line in new.lines() {
+        println!("{}+ {}{}", green, line, reset);
+    }
+}
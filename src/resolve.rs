@@ -0,0 +1,462 @@
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::backup::content_hash;
+use crate::crypto::{decrypt, session_identity, ENCRYPTED_EXTENSION};
+use crate::error::{CandidateSession, Result, SessionFinderError};
+use crate::title::session_title;
+use crate::{decode_project_path, encode_project_path};
+
+/// How many leading lines of a session file are checked for a summary entry
+const TITLE_SCAN_LINES: usize = 20;
+
+/// A session file that a user-supplied reference could refer to
+struct SessionCandidate {
+    path: PathBuf,
+    session_id: String,
+}
+
+/// Restricts relative session selection to one project
+pub enum ProjectMatch {
+    /// Decoded project path contains the given text, like `--project`
+    Contains(String),
+    /// Session belongs to exactly this project directory
+    Directory(PathBuf),
+}
+
+fn home_dir() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| SessionFinderError::Environment("HOME is not set".to_string()))
+}
+
+pub fn claude_projects_dir() -> Result<PathBuf> {
+    Ok(home_dir()?
+        .join(".claude")
+        .join("projects"))
+}
+
+/// Where `import` writes other agents' transcripts, converted to Claude Code's format.
+pub fn imports_dir() -> Result<PathBuf> {
+    Ok(home_dir()?
+        .join(".claude")
+        .join("session-finder")
+        .join("imports"))
+}
+
+/// Where `sync pull` puts teammates' sessions, one subdirectory per user.
+pub fn team_dir() -> Result<PathBuf> {
+    Ok(home_dir()?
+        .join(".claude")
+        .join("session-finder")
+        .join("team"))
+}
+
+/// Directories searched for sessions: Claude Code's projects directory, plus imported
+/// transcripts and teammates' sessions once any exist.
+pub fn session_roots() -> Result<Vec<PathBuf>> {
+    let roots: Vec<PathBuf> = [claude_projects_dir()?, imports_dir()?, team_dir()?]
+        .into_iter()
+        .filter(|root| root.exists())
+        .collect();
+    if roots.is_empty() {
+        return Err(SessionFinderError::RootNotFound { path: claude_projects_dir()? });
+    }
+    Ok(roots)
+}
+
+/// Which tool a session came from: `claude` for Claude Code's own sessions, the name of the
+/// format it was imported from, or `team:<user>` for a teammate's synced session.
+pub fn session_source(path: &Path) -> String {
+    let first_component = |root: Result<PathBuf>| {
+        root.ok()
+            .and_then(|root| path.strip_prefix(root).ok().map(|rest| rest.to_path_buf()))
+            .and_then(|rest| rest.components().next().map(|c| c.as_os_str().to_string_lossy().into_owned()))
+    };
+    if let Some(user) = first_component(team_dir()) {
+        return format!("{}{}", TEAM_SOURCE_PREFIX, user);
+    }
+    first_component(imports_dir()).unwrap_or_else(|| "claude".to_string())
+}
+
+/// Source prefix for sessions pulled from a shared remote, followed by who pushed them
+pub const TEAM_SOURCE_PREFIX: &str = "team:";
+
+/// Session reference meaning "read the session from standard input"
+pub const STDIN_REFERENCE: &str = "-";
+
+/// Resolves a session reference to a file: an absolute path, a `.jsonl` path relative to the
+/// current directory, a full session ID, a unique ID prefix, a path relative to the projects
+/// directory, or words from the session title.
+pub fn resolve_session_path(session_ref: &str) -> Result<PathBuf> {
+    let path = Path::new(session_ref);
+
+    // If it's already a full path, use it
+    if path.is_absolute() && path.exists() {
+        return Ok(path.to_path_buf());
+    }
+
+    // Session files outside the projects tree, such as exports and backups
+    if is_session_file(path) && path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    // `@-N` refers to the Nth most recent session
+    if let Some(nth) = session_ref.strip_prefix("@-") {
+        let nth = nth
+            .parse::<usize>()
+            .map_err(|_| SessionFinderError::InvalidReference(format!("Invalid relative session reference: {}", session_ref)))?;
+        return resolve_recent_session(nth, None);
+    }
+
+    let projects_dir = claude_projects_dir()?;
+
+    // Try as relative to projects dir
+    let candidate = projects_dir.join(session_ref);
+    if candidate.is_file() {
+        return Ok(candidate);
+    }
+
+    let sessions = list_all_session_files()?;
+
+    if let Some(exact) = sessions.iter().find(|s| s.session_id == session_ref) {
+        return Ok(exact.path.clone());
+    }
+
+    // Unique prefixes work like abbreviated git SHAs
+    let prefix_matches: Vec<&SessionCandidate> = sessions
+        .iter()
+        .filter(|s| s.session_id.starts_with(session_ref))
+        .collect();
+    match prefix_matches.len() {
+        0 => {}
+        1 => return Ok(prefix_matches[0].path.clone()),
+        _ => return Err(ambiguous_reference_error(session_ref, &prefix_matches)),
+    }
+
+    // Fall back to fuzzy matching against session titles
+    let title_matches: Vec<&SessionCandidate> = sessions
+        .iter()
+        .filter(|s| session_title(&s.path, &s.session_id).is_some_and(|title| title_matches_query(&title, session_ref)))
+        .collect();
+    match title_matches.len() {
+        0 => Err(SessionFinderError::SessionNotFound { reference: session_ref.to_string(), candidates: Vec::new() }),
+        1 => Ok(title_matches[0].path.clone()),
+        _ => Err(ambiguous_reference_error(session_ref, &title_matches)),
+    }
+}
+
+/// Returns the Nth most recently modified session (1 = latest), optionally within one project.
+pub fn resolve_recent_session(nth: usize, project: Option<&ProjectMatch>) -> Result<PathBuf> {
+    if nth == 0 {
+        return Err(SessionFinderError::InvalidReference(
+            "Relative session selectors start at 1 (the latest session)".to_string(),
+        ));
+    }
+
+    let mut sessions: Vec<(SystemTime, SessionCandidate)> = list_all_session_files()?
+        .into_iter()
+        .filter(|candidate| match project {
+            Some(project) => project_matches(&candidate.path, project),
+            None => true,
+        })
+        .filter_map(|candidate| {
+            let modified = fs::metadata(&candidate.path).and_then(|m| m.modified()).ok()?;
+            Some((modified, candidate))
+        })
+        .collect();
+    sessions.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.session_id.cmp(&b.1.session_id)));
+
+    let available = sessions.len();
+    sessions
+        .into_iter()
+        .nth(nth - 1)
+        .map(|(_, candidate)| candidate.path)
+        .ok_or_else(|| {
+            SessionFinderError::InvalidReference(format!("Only {} matching session(s) found; cannot select #{}", available, nth))
+        })
+}
+
+fn project_matches(session_path: &Path, project: &ProjectMatch) -> bool {
+    match project {
+        ProjectMatch::Contains(filter) => decode_project_path(session_path)
+            .map(|decoded| decoded.contains(filter.as_str()))
+            .unwrap_or(false),
+        ProjectMatch::Directory(dir) => session_path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .is_some_and(|name| name.to_string_lossy() == encode_project_path(dir)),
+    }
+}
+
+pub fn extract_session_id_from_path(path: &Path) -> Result<String> {
+    session_file_stem(path)
+        .or_else(|| path.file_stem().and_then(|s| s.to_str()))
+        .map(|s| s.to_string())
+        .ok_or_else(|| SessionFinderError::InvalidReference(format!("Could not extract session ID from path: {:?}", path)))
+}
+
+/// A session file's name without `.jsonl`, and without the `.gz` and `.age` that `sync push`
+/// adds after it when compressing and encrypting.
+fn session_file_stem(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    let name = name.strip_suffix(&format!(".{}", ENCRYPTED_EXTENSION)).unwrap_or(name);
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    name.strip_suffix(".jsonl")
+}
+
+/// Whether the file holds a session: JSONL, possibly compressed and encrypted.
+pub fn is_session_file(path: &Path) -> bool {
+    session_file_stem(path).is_some()
+}
+
+/// Whether the session file is kept compressed or encrypted, as pulled sessions are, so its
+/// text can only be read through `read_session_text`.
+pub fn is_encoded_session_file(path: &Path) -> bool {
+    is_session_file(path) && path.extension() != Some(OsStr::new("jsonl"))
+}
+
+/// A session file's text. Encrypted sessions are decrypted with the team's key and compressed
+/// ones decompressed, in memory, so their plaintext is never written to disk.
+pub fn read_session_text(path: &Path) -> Result<String> {
+    if !is_encoded_session_file(path) {
+        return Ok(fs::read_to_string(path)?);
+    }
+    decode_session(path, fs::read(path)?)
+}
+
+/// `read_session_text` through `tokio::fs`, for callers on a tokio runtime.
+#[cfg(feature = "tokio")]
+pub async fn read_session_text_async(path: &Path) -> Result<String> {
+    let bytes = tokio::fs::read(path).await?;
+    if !is_encoded_session_file(path) {
+        return String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into());
+    }
+    decode_session(path, bytes)
+}
+
+/// The text of a compressed or encrypted session file, from its bytes.
+fn decode_session(path: &Path, mut bytes: Vec<u8>) -> Result<String> {
+    let unreadable = |message: String| SessionFinderError::ParseError { path: path.to_path_buf(), line: None, message };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let suffix = format!(".{}", ENCRYPTED_EXTENSION);
+    if name.ends_with(&suffix) {
+        bytes = decrypt(session_identity()?, &bytes).map_err(|e| unreadable(e.to_string()))?;
+    }
+    if name.strip_suffix(&suffix).unwrap_or(&name).ends_with(".gz") {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed).map_err(|e| unreadable(e.to_string()))?;
+        bytes = decompressed;
+    }
+    String::from_utf8(bytes).map_err(|e| unreadable(e.to_string()))
+}
+
+/// A reader over a session's text from the start: the file itself, or its decoded text when
+/// it is kept compressed or encrypted.
+pub fn open_session(path: &Path) -> Result<Box<dyn BufRead>> {
+    if is_encoded_session_file(path) {
+        return Ok(Box::new(Cursor::new(read_session_text(path)?.into_bytes())));
+    }
+    Ok(Box::new(BufReader::new(fs::File::open(path)?)))
+}
+
+/// Reads a session by reference, with `-` reading it from standard input. Returns the path it
+/// came from (`-` for stdin), its session ID, and its contents.
+pub fn read_session_source(session_ref: &str) -> Result<(PathBuf, String, String)> {
+    if session_ref == STDIN_REFERENCE {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        // Piped sessions have no file name, so use the ID recorded in the messages
+        let session_id = content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .find_map(|value| value.get("sessionId").and_then(|id| id.as_str()).map(|id| id.to_string()))
+            .unwrap_or_else(|| "stdin".to_string());
+        return Ok((PathBuf::from(STDIN_REFERENCE), session_id, content));
+    }
+
+    let path = resolve_session_path(session_ref)?;
+    let session_id = extract_session_id_from_path(&path)?;
+    let content = read_session_text(&path)?;
+    Ok((path, session_id, content))
+}
+
+/// `read_session_source` on a tokio runtime, reading the session through `tokio::fs`.
+#[cfg(feature = "tokio")]
+pub async fn read_session_source_async(session_ref: &str) -> Result<(PathBuf, String, String)> {
+    if session_ref == STDIN_REFERENCE {
+        return Err(SessionFinderError::Usage("A session can't be read from stdin here".to_string()));
+    }
+    let path = resolve_session_path(session_ref)?;
+    let session_id = extract_session_id_from_path(&path)?;
+    let content = read_session_text_async(&path).await?;
+    Ok((path, session_id, content))
+}
+
+/// Returns the session's summary title, if the session file has one.
+pub fn read_session_title(path: &Path) -> Option<String> {
+    let reader = open_session(path).ok()?;
+    title_in_lines(reader.lines())
+}
+
+/// `read_session_title` through `tokio::fs`.
+#[cfg(feature = "tokio")]
+pub async fn read_session_title_async(path: &Path) -> Option<String> {
+    use tokio::io::AsyncBufReadExt;
+
+    if is_encoded_session_file(path) {
+        return session_text_title(&read_session_text_async(path).await.ok()?);
+    }
+    let file = tokio::fs::File::open(path).await.ok()?;
+    let mut lines = tokio::io::BufReader::new(file).lines();
+    let mut first_lines = Vec::new();
+    while first_lines.len() < TITLE_SCAN_LINES {
+        match lines.next_line().await {
+            Ok(Some(line)) => first_lines.push(Ok(line)),
+            Ok(None) => break,
+            Err(e) => {
+                first_lines.push(Err(e));
+                break;
+            }
+        }
+    }
+    title_in_lines(first_lines.into_iter())
+}
+
+/// The session's summary title, from its text when that has already been read.
+#[cfg(feature = "tokio")]
+pub fn session_text_title(content: &str) -> Option<String> {
+    title_in_lines(content.lines().map(|line| Ok(line.to_string())))
+}
+
+fn title_in_lines(lines: impl Iterator<Item = io::Result<String>>) -> Option<String> {
+    for line in lines.take(TITLE_SCAN_LINES) {
+        let line = line.ok()?;
+        let value: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        if value.get("type").and_then(|t| t.as_str()) == Some("summary") {
+            if let Some(summary) = value.get("summary").and_then(|s| s.as_str()) {
+                return Some(summary.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+/// Every session file under the session roots.
+pub fn all_session_files() -> Result<Vec<PathBuf>> {
+    Ok(list_all_session_files()?
+        .into_iter()
+        .map(|candidate| candidate.path)
+        .collect())
+}
+
+fn list_all_session_files() -> Result<Vec<SessionCandidate>> {
+    let mut sessions = Vec::new();
+    for root in session_roots()? {
+        sessions.extend(list_session_files(&root)?);
+    }
+    Ok(without_identical_copies(sessions, |candidate| candidate.path.as_path()))
+}
+
+/// Drops copies of a session identical to one listed before it, as when a session pulled
+/// from another machine is also on this one, keeping the first in root order (Claude Code's
+/// own before imported and pulled). Only files sharing a session ID are hashed.
+pub fn without_identical_copies<T>(items: Vec<T>, path: impl Fn(&T) -> &Path) -> Vec<T> {
+    let session_id = |item: &T| extract_session_id_from_path(path(item)).unwrap_or_default();
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    for item in &items {
+        *by_id.entry(session_id(item)).or_default() += 1;
+    }
+    let shared: HashSet<String> = by_id.into_iter().filter(|(_, count)| *count > 1).map(|(id, _)| id).collect();
+    if shared.is_empty() {
+        return items;
+    }
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    items
+        .into_iter()
+        .filter(|item| {
+            let id = session_id(item);
+            if !shared.contains(&id) {
+                return true;
+            }
+            match content_hash(path(item)) {
+                Ok(hash) => seen.insert((id, hash)),
+                Err(_) => true,
+            }
+        })
+        .collect()
+}
+
+fn list_session_files(projects_dir: &Path) -> Result<Vec<SessionCandidate>> {
+    let mut sessions = Vec::new();
+
+    for entry in walkdir::WalkDir::new(projects_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || !is_session_file(entry.path()) {
+            continue;
+        }
+        if let Ok(session_id) = extract_session_id_from_path(entry.path()) {
+            sessions.push(SessionCandidate {
+                path: entry.path().to_path_buf(),
+                session_id,
+            });
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// A title matches when every word of the query appears in it, ignoring case.
+fn title_matches_query(title: &str, query: &str) -> bool {
+    let lower_title = title.to_lowercase();
+    let mut words = query.split_whitespace().peekable();
+    words.peek().is_some() && words.all(|word| lower_title.contains(&word.to_lowercase()))
+}
+
+fn ambiguous_reference_error(session_ref: &str, candidates: &[&SessionCandidate]) -> SessionFinderError {
+    let mut candidates: Vec<CandidateSession> = candidates
+        .iter()
+        .map(|candidate| CandidateSession {
+            session_id: candidate.session_id.clone(),
+            modified: fs::metadata(&candidate.path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(DateTime::<Utc>::from),
+            project: decode_project_path(&candidate.path).unwrap_or_default(),
+            title: read_session_title(&candidate.path).unwrap_or_default(),
+        })
+        .collect();
+    // List the most recently modified candidates first
+    candidates.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.session_id.cmp(&b.session_id)));
+    SessionFinderError::SessionNotFound { reference: session_ref.to_string(), candidates }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fake_home, write_session};
+    use std::time::Duration;
+
+    #[test]
+    fn titles_inferred_from_the_first_prompt_resolve_references() {
+        let (_home, home) = fake_home();
+        let messages = ["Tune the carburettor idle mixture", "Sure", "thanks", "ok", "done"];
+        let path = write_session(home, "-work-engines", "carburettor", &messages, Duration::from_secs(60));
+
+        assert_eq!(resolve_session_path("carburettor idle").unwrap(), path);
+        assert!(matches!(
+            resolve_session_path("carburettor choke"),
+            Err(SessionFinderError::SessionNotFound { .. })
+        ));
+    }
+}
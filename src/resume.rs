@@ -0,0 +1,110 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::clipboard::copy_to_clipboard;
+use crate::config::Config;
+use crate::error::{Result, SessionFinderError};
+use crate::matcher::SearchOptions;
+use crate::resolve::{extract_session_id_from_path, resolve_session_path};
+use crate::{decode_project_path, find_sessions, rank_and_limit_sessions};
+
+/// How many leading lines of a session file are checked for a `cwd` field
+const CWD_SCAN_LINES: usize = 50;
+
+/// Finds the session to resume, then prints or runs the command to resume it from its
+/// original working directory.
+pub fn run_resume(
+    query: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    exec: bool,
+    copy: bool,
+) -> Result<()> {
+    let session_path = pick_session(query, project_filter, recent_days)?;
+    let session_id = extract_session_id_from_path(&session_path)?;
+    let working_dir = session_working_dir(&session_path);
+
+    if !exec {
+        let command = resume_command(&working_dir, &session_id);
+        println!("{}", command);
+        if copy {
+            copy_to_clipboard(&command, "resume command")?;
+        }
+        return Ok(());
+    }
+
+    if !Path::new(&working_dir).is_dir() {
+        return Err(SessionFinderError::Environment(format!("Project directory no longer exists: {}", working_dir)));
+    }
+
+    let status = process::Command::new("claude")
+        .args(["--resume", &session_id])
+        .current_dir(&working_dir)
+        .status()
+        .map_err(|e| SessionFinderError::backend("claude", format!(
+            "Failed to run claude: {}. Make sure 'claude' is in your PATH",
+            e
+        )))?;
+    process::exit(status.code().unwrap_or(1));
+}
+
+/// Shell command that resumes a session from the directory it was started in.
+pub fn resume_command(working_dir: &str, session_id: &str) -> String {
+    format!("cd {} && claude --resume {}", shell_quote(working_dir), session_id)
+}
+
+/// The directory a session was run in, taken from the session's recorded `cwd` when present
+/// since the encoded project folder name cannot distinguish `-` from `/`.
+pub fn session_working_dir(session_path: &Path) -> String {
+    read_session_cwd(session_path)
+        .or_else(|| decode_project_path(session_path).ok())
+        .unwrap_or_default()
+}
+
+/// Treats the query as a session reference first, then as search terms, taking the best match.
+fn pick_session(
+    query: &[&str],
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+) -> Result<PathBuf> {
+    let joined = query.join(" ");
+    let reference_error = match resolve_session_path(&joined) {
+        Ok(path) => return Ok(path),
+        Err(e) => e,
+    };
+
+    let options = SearchOptions::from_config(&Config::load()?, true, true);
+    let sessions = find_sessions(query, project_filter, recent_days, 1, None, &options)?;
+    rank_and_limit_sessions(sessions, 1, options.sort)
+        .into_iter()
+        .next()
+        .map(|session| session.path)
+        .ok_or(reference_error)
+}
+
+fn read_session_cwd(session_path: &Path) -> Option<String> {
+    let file = fs::File::open(session_path).ok()?;
+
+    for line in BufReader::new(file).lines().take(CWD_SCAN_LINES) {
+        let line = line.ok()?;
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&line) {
+            if let Some(cwd) = value.get("cwd").and_then(|c| c.as_str()) {
+                return Some(cwd.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+pub fn shell_quote(text: &str) -> String {
+    if !text.is_empty()
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || "/._-+:@".contains(c))
+    {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
@@ -0,0 +1,439 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::error::{Result, SessionFinderError};
+use crate::grouping::ResultGrouping;
+use crate::matcher::SearchOptions;
+use crate::memory::MemoryBudget;
+use crate::ranking::SortOrder;
+use crate::resolve::{extract_session_id_from_path, read_session_text, resolve_session_path, STDIN_REFERENCE};
+use crate::time_range::TimeRange;
+use crate::timeline::{extract_content_text, extract_timeline, parse_session_messages, TimelineSearch};
+use crate::tools::ToolFilter;
+use crate::{find_sessions, rank_and_limit_sessions};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    terms: Vec<String>,
+    project: Option<String>,
+    recent_days: Option<i64>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+/// Search parameters as the command line takes them, for `find_sessions`
+#[derive(Deserialize)]
+struct FindSessionsParams {
+    terms: Vec<String>,
+    project: Option<String>,
+    recent_days: Option<i64>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+    /// `--group-by`: `project` or `day`
+    group_by: Option<String>,
+    /// `--sort`, which decides which sessions are shortlisted
+    sort: Option<String>,
+    /// `--no-ignore`: search sessions the ignore list would skip
+    #[serde(default)]
+    no_ignore: bool,
+}
+
+#[derive(Deserialize)]
+struct TimelineParams {
+    session: String,
+    #[serde(default)]
+    terms: Vec<String>,
+    #[serde(default = "default_context")]
+    context: usize,
+    top: Option<usize>,
+    /// `--tool` filters, such as `Bash` or `mcp:github`
+    #[serde(default)]
+    tools: Vec<String>,
+    /// `--after` and `--before` bounds on when entries were sent
+    after: Option<String>,
+    before: Option<String>,
+    /// `--exclude-interrupted`: leave out turns the user interrupted or rejected tool calls in
+    #[serde(default)]
+    exclude_interrupted: bool,
+}
+
+#[derive(Deserialize)]
+struct TranscriptParams {
+    session: String,
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+fn default_context() -> usize {
+    2
+}
+
+/// How a request arrived, so the response can be written back the same way
+#[derive(Clone, Copy)]
+pub enum Framing {
+    /// LSP base protocol: `Content-Length` header, blank line, body
+    ContentLength,
+    /// One JSON message per line, convenient for testing by hand
+    Line,
+}
+
+/// Serves JSON-RPC 2.0 requests on stdin/stdout until `exit` or end of input.
+///
+/// Methods: `initialize`, `search`, `find_sessions`, `timeline`, `transcript`, `shutdown`, `exit`.
+pub fn run_rpc_server() -> Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    serve(&mut stdin.lock(), &mut stdout.lock())
+}
+
+/// Answers requests read from `reader` on `writer` until `exit` or end of input.
+pub fn serve(reader: &mut impl BufRead, writer: &mut impl Write) -> Result<()> {
+    while let Some((body, framing)) = read_message(reader)? {
+        match respond(&body) {
+            Reply::Exit => break,
+            Reply::Nothing => {}
+            Reply::Response(response) => write_message(writer, &response, framing)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// What to do after one message
+pub enum Reply {
+    /// The client asked to `exit`
+    Exit,
+    /// A notification, which gets no response
+    Nothing,
+    Response(Value),
+}
+
+/// Handles one message's body, blocking until its request has been answered.
+pub fn respond(body: &str) -> Reply {
+    match serde_json::from_str::<Request>(body) {
+        Ok(request) if request.method == "exit" => Reply::Exit,
+        Ok(request) => reply(request.id, handle_request(&request.method, request.params)),
+        Err(e) => Reply::Response(error_response(None, PARSE_ERROR, &e.to_string())),
+    }
+}
+
+/// `respond` on a tokio runtime, with searches, timelines, and transcripts read through
+/// `tokio::fs` rather than on a blocked thread.
+#[cfg(feature = "tokio")]
+pub async fn respond_async(body: &str) -> Reply {
+    match serde_json::from_str::<Request>(body) {
+        Ok(request) if request.method == "exit" => Reply::Exit,
+        Ok(request) => reply(request.id, handle_request_async(&request.method, request.params).await),
+        Err(e) => Reply::Response(error_response(None, PARSE_ERROR, &e.to_string())),
+    }
+}
+
+fn reply(id: Option<Value>, result: std::result::Result<Value, (i64, String)>) -> Reply {
+    // Requests without an id are notifications and get no response
+    if id.is_none() {
+        return Reply::Nothing;
+    }
+    Reply::Response(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+pub fn handle_request(method: &str, params: Value) -> std::result::Result<Value, (i64, String)> {
+    match method {
+        "search" => {
+            let params: SearchParams = parse_params(params)?;
+            run(|| {
+                let terms: Vec<&str> = params.terms.iter().map(|t| t.as_str()).collect();
+                let options = SearchOptions::from_config(&Config::load()?, true, true);
+                let sessions = find_sessions(&terms, params.project.as_ref(), params.recent_days, params.limit, None, &options)?;
+                Ok(serde_json::to_value(rank_and_limit_sessions(sessions, params.limit, options.sort))?)
+            })
+        }
+        // Every session matching, before ranking and the command line's own filters; what the
+        // CLI asks the daemon for
+        "find_sessions" => {
+            let params: FindSessionsParams = parse_params(params)?;
+            run(|| {
+                let terms: Vec<&str> = params.terms.iter().map(|t| t.as_str()).collect();
+                let grouping = params.group_by.as_deref().map(ResultGrouping::from_name).transpose()?;
+                let options = find_sessions_options(&params)?;
+                let sessions = find_sessions(&terms, params.project.as_ref(), params.recent_days, params.limit, grouping, &options)?;
+                Ok(serde_json::to_value(sessions)?)
+            })
+        }
+        "timeline" => {
+            let params = timeline_params(params)?;
+            run(|| {
+                let terms: Vec<&str> = params.terms.iter().map(|t| t.as_str()).collect();
+                let (tool_filters, time_range, options) = timeline_filters(&params)?;
+                let search = timeline_search(&params, &terms, &tool_filters, &time_range, &options);
+                Ok(serde_json::to_value(extract_timeline(&params.session, &search)?)?)
+            })
+        }
+        "transcript" => {
+            let params: TranscriptParams = parse_params(params)?;
+            run(|| {
+                let path = resolve_session_path(&params.session)?;
+                transcript(&path, &read_session_text(&path)?)
+            })
+        }
+        // The LSP handshake, so stock clients can connect; the server offers none of LSP's
+        // own features
+        "initialize" => Ok(json!({
+            "capabilities": {},
+            "serverInfo": { "name": "session-finder", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "initialized" => Ok(Value::Null),
+        "shutdown" => Ok(Value::Null),
+        _ => Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+    }
+}
+
+/// `handle_request` on a tokio runtime, through `find_sessions_async`, `extract_timeline_async`,
+/// and `tokio::fs`.
+#[cfg(feature = "tokio")]
+pub async fn handle_request_async(method: &str, params: Value) -> std::result::Result<Value, (i64, String)> {
+    use crate::find_sessions_async;
+    use crate::resolve::read_session_text_async;
+    use crate::timeline::extract_timeline_async;
+
+    let result = match method {
+        "search" => {
+            let params: SearchParams = parse_params(params)?;
+            let terms: Vec<&str> = params.terms.iter().map(|t| t.as_str()).collect();
+            async {
+                let options = SearchOptions::from_config(&Config::load()?, true, true);
+                let sessions =
+                    find_sessions_async(&terms, params.project.as_ref(), params.recent_days, params.limit, None, &options).await?;
+                Ok(serde_json::to_value(rank_and_limit_sessions(sessions, params.limit, options.sort))?)
+            }
+            .await
+        }
+        "find_sessions" => {
+            let params: FindSessionsParams = parse_params(params)?;
+            let terms: Vec<&str> = params.terms.iter().map(|t| t.as_str()).collect();
+            async {
+                let grouping = params.group_by.as_deref().map(ResultGrouping::from_name).transpose()?;
+                let options = find_sessions_options(&params)?;
+                let sessions =
+                    find_sessions_async(&terms, params.project.as_ref(), params.recent_days, params.limit, grouping, &options)
+                        .await?;
+                Ok(serde_json::to_value(sessions)?)
+            }
+            .await
+        }
+        "timeline" => {
+            let params = timeline_params(params)?;
+            let terms: Vec<&str> = params.terms.iter().map(|t| t.as_str()).collect();
+            async {
+                let (tool_filters, time_range, options) = timeline_filters(&params)?;
+                let search = timeline_search(&params, &terms, &tool_filters, &time_range, &options);
+                Ok(serde_json::to_value(extract_timeline_async(&params.session, &search).await?)?)
+            }
+            .await
+        }
+        "transcript" => {
+            let params: TranscriptParams = parse_params(params)?;
+            async {
+                let path = resolve_session_path(&params.session)?;
+                transcript(&path, &read_session_text_async(&path).await?)
+            }
+            .await
+        }
+        _ => return handle_request(method, params),
+    };
+    result.map_err(server_error)
+}
+
+fn timeline_params(params: Value) -> std::result::Result<TimelineParams, (i64, String)> {
+    let params: TimelineParams = parse_params(params)?;
+    // stdin carries the protocol itself
+    if params.session == STDIN_REFERENCE {
+        return Err((INVALID_PARAMS, "session cannot be read from stdin here".to_string()));
+    }
+    Ok(params)
+}
+
+/// The search options a `find_sessions` request asks for.
+fn find_sessions_options(params: &FindSessionsParams) -> Result<SearchOptions> {
+    let config = Config::load()?;
+    let mut options = SearchOptions::from_config(&config, !params.no_ignore, true);
+    if let Some(sort) = &params.sort {
+        options.sort = SortOrder::from_name(sort)?;
+    }
+    options.memory_budget = config.max_memory.as_deref().map(MemoryBudget::parse).transpose()?;
+    Ok(options)
+}
+
+/// The tool filters, time range, and search options a timeline request asks for.
+fn timeline_filters(params: &TimelineParams) -> Result<(Vec<ToolFilter>, TimeRange, SearchOptions)> {
+    let tool_filters = params.tools.iter().map(|spec| ToolFilter::parse(spec)).collect::<Result<Vec<_>>>()?;
+    let time_range = TimeRange::parse(params.after.as_deref(), params.before.as_deref())?;
+    Ok((tool_filters, time_range, SearchOptions::from_config(&Config::load()?, true, true)))
+}
+
+fn timeline_search<'a>(
+    params: &TimelineParams,
+    terms: &'a [&'a str],
+    tool_filters: &'a [ToolFilter],
+    time_range: &'a TimeRange,
+    options: &'a SearchOptions,
+) -> TimelineSearch<'a> {
+    TimelineSearch {
+        search_terms: terms,
+        context_size: params.context,
+        top_entries: params.top,
+        tool_filters,
+        exclude_interrupted: params.exclude_interrupted,
+        time_range,
+        options,
+    }
+}
+
+/// Every parsed message of a session as plain text, in file order.
+fn transcript(path: &Path, content: &str) -> Result<Value> {
+    let messages: Vec<Value> = parse_session_messages(content)?
+        .iter()
+        .enumerate()
+        .filter_map(|(index, msg)| {
+            let inner = msg.message.as_ref()?;
+            Some(json!({
+                "index": index,
+                "line": msg.line_number,
+                "timestamp": msg.timestamp,
+                "role": inner.role,
+                "text": inner.content.as_ref().map(extract_content_text).unwrap_or_default(),
+            }))
+        })
+        .collect();
+
+    Ok(json!({
+        "session_id": extract_session_id_from_path(path)?,
+        "path": path,
+        "messages": messages,
+    }))
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> std::result::Result<T, (i64, String)> {
+    serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, e.to_string()))
+}
+
+fn run(f: impl FnOnce() -> Result<Value>) -> std::result::Result<Value, (i64, String)> {
+    f().map_err(server_error)
+}
+
+fn server_error(error: SessionFinderError) -> (i64, String) {
+    (SERVER_ERROR, error.to_string())
+}
+
+fn error_response(id: Option<Value>, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn read_message(reader: &mut impl BufRead) -> Result<Option<(String, Framing)>> {
+    let mut content_length: Option<usize> = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match read_header_line(&line, &mut content_length)? {
+            HeaderLine::Message(body) => return Ok(Some((body, Framing::Line))),
+            HeaderLine::BodyFollows(length) => {
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body)?;
+                return body_text(body).map(|body| Some((body, Framing::ContentLength)));
+            }
+            HeaderLine::More => {}
+        }
+    }
+}
+
+/// What a line read while waiting for a message says
+pub enum HeaderLine {
+    /// A whole message on one line
+    Message(String),
+    /// The headers are done; this many bytes of body follow
+    BodyFollows(usize),
+    /// Another header, or noise between messages
+    More,
+}
+
+/// Reads one line of a message's headers, noting `Content-Length` when it comes.
+pub fn read_header_line(line: &str, content_length: &mut Option<usize>) -> Result<HeaderLine> {
+    let trimmed = line.trim();
+    if content_length.is_none() && trimmed.starts_with('{') {
+        return Ok(HeaderLine::Message(trimmed.to_string()));
+    }
+    if trimmed.is_empty() {
+        // A blank line ends the headers; without any headers it is just noise between messages
+        return Ok(content_length.map_or(HeaderLine::More, HeaderLine::BodyFollows));
+    }
+    if let Some((name, value)) = trimmed.split_once(':') {
+        if name.eq_ignore_ascii_case("Content-Length") {
+            let length = value.trim().parse().map_err(|e| SessionFinderError::Usage(format!(
+                "Invalid Content-Length {:?}: {}",
+                value.trim(),
+                e
+            )))?;
+            *content_length = Some(length);
+        }
+    }
+    Ok(HeaderLine::More)
+}
+
+pub fn body_text(body: Vec<u8>) -> Result<String> {
+    String::from_utf8(body).map_err(|e| SessionFinderError::Usage(format!("Request body is not UTF-8: {}", e)))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value, framing: Framing) -> Result<()> {
+    writer.write_all(encode_message(message, framing)?.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// A message as written on the wire, framed like the request it answers.
+pub fn encode_message(message: &Value, framing: Framing) -> Result<String> {
+    let body = serde_json::to_string(message)?;
+    Ok(match framing {
+        Framing::ContentLength => format!("Content-Length: {}\r\n\r\n{}", body.len(), body),
+        Framing::Line => format!("{}\n", body),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_the_lsp_handshake() {
+        let initialize = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#;
+        let Reply::Response(response) = respond(initialize) else {
+            panic!("initialize is a request");
+        };
+        assert_eq!(response["id"], 1);
+        assert!(response["result"]["capabilities"].is_object());
+        assert!(matches!(respond(r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#), Reply::Nothing));
+    }
+}
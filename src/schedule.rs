@@ -0,0 +1,183 @@
+use chrono::{NaiveTime, Timelike};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use crate::digest::digest_dir;
+use crate::error::{Result, SessionFinderError};
+use crate::resume::shell_quote;
+use crate::trash::TrashOperation;
+
+/// Name of the launchd agent and the systemd user units
+const SCHEDULE_NAME: &str = "session-finder-digest";
+
+/// Parses the `--daily` time as HH:MM, 24-hour.
+pub fn parse_daily_time(value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| SessionFinderError::Usage(format!(
+            "Invalid --daily {:?}: use a 24-hour time as HH:MM, e.g. 18:00",
+            value
+        )))
+}
+
+/// Sets up a launchd agent (macOS) or systemd user timer (Linux) running `digest --save` every
+/// day at `time`, local time, replacing one installed before.
+pub fn install_schedule(time: NaiveTime, dir: Option<&str>) -> Result<()> {
+    let dir = digest_dir(dir)?;
+    let dir = std::path::absolute(&dir)?;
+    let exe = std::env::current_exe()?;
+    let args = [exe.to_string_lossy().into_owned(), "digest".into(), "--save".into(), "--dir".into(), dir.to_string_lossy().into_owned()];
+
+    if cfg!(target_os = "macos") {
+        let plist = launchd_plist_path()?;
+        fs::create_dir_all(plist.parent().expect("plist is in a directory"))?;
+        fs::write(&plist, launchd_plist(&args, time))?;
+        // Unloading fails when the agent wasn't loaded, which is fine
+        let _ = run_quietly("launchctl", &["unload", &plist.to_string_lossy()]);
+        run_quietly("launchctl", &["load", "-w", &plist.to_string_lossy()])?;
+        println!("Installed {}", plist.display());
+    } else if cfg!(target_os = "linux") {
+        let units = systemd_unit_dir()?;
+        fs::create_dir_all(&units)?;
+        fs::write(units.join(format!("{}.service", SCHEDULE_NAME)), systemd_service(&args))?;
+        fs::write(units.join(format!("{}.timer", SCHEDULE_NAME)), systemd_timer(time))?;
+        run_quietly("systemctl", &["--user", "daemon-reload"])?;
+        run_quietly("systemctl", &["--user", "enable", "--now", &format!("{}.timer", SCHEDULE_NAME)])?;
+        println!("Installed {}", units.join(format!("{}.timer", SCHEDULE_NAME)).display());
+    } else {
+        return Err(SessionFinderError::Environment(format!(
+            "schedule needs launchd or systemd; add this to your crontab instead:\n{} {} * * * {}",
+            time.minute(),
+            time.hour(),
+            args.map(|arg| shell_quote(&arg)).join(" ")
+        )));
+    }
+    println!("The digest is saved into {} daily at {}", dir.display(), time.format("%H:%M"));
+    Ok(())
+}
+
+/// Removes the launchd agent or systemd units `install_schedule` set up.
+pub fn remove_schedule() -> Result<()> {
+    let mut removed = Vec::new();
+    let mut trash = TrashOperation::begin("unschedule");
+    if cfg!(target_os = "macos") {
+        let plist = launchd_plist_path()?;
+        if plist.exists() {
+            let _ = run_quietly("launchctl", &["unload", "-w", &plist.to_string_lossy()]);
+            trash.discard_file(&plist)?;
+            removed.push(plist);
+        }
+    } else if cfg!(target_os = "linux") {
+        let units = systemd_unit_dir()?;
+        let timer = units.join(format!("{}.timer", SCHEDULE_NAME));
+        if timer.exists() {
+            let _ = run_quietly("systemctl", &["--user", "disable", "--now", &format!("{}.timer", SCHEDULE_NAME)]);
+        }
+        for unit in [timer, units.join(format!("{}.service", SCHEDULE_NAME))] {
+            if unit.exists() {
+                trash.discard_file(&unit)?;
+                removed.push(unit);
+            }
+        }
+        if !removed.is_empty() {
+            let _ = run_quietly("systemctl", &["--user", "daemon-reload"]);
+        }
+    }
+    if removed.is_empty() {
+        println!("No digest schedule is installed.");
+    }
+    for path in removed {
+        println!("Removed {}", path.display());
+    }
+    trash.finish();
+    Ok(())
+}
+
+fn home() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| SessionFinderError::Environment("HOME is not set".to_string()))
+}
+
+fn launchd_plist_path() -> Result<PathBuf> {
+    Ok(home()?.join("Library").join("LaunchAgents").join(format!("com.tekacs.{}.plist", SCHEDULE_NAME)))
+}
+
+/// `$XDG_CONFIG_HOME/systemd/user`, where user units are looked up first.
+fn systemd_unit_dir() -> Result<PathBuf> {
+    let config = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => home()?.join(".config"),
+    };
+    Ok(config.join("systemd").join("user"))
+}
+
+fn launchd_plist(args: &[String], time: NaiveTime) -> String {
+    let arguments: String = args
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.tekacs.{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{}    </array>
+    <key>StartCalendarInterval</key>
+    <dict>
+        <key>Hour</key>
+        <integer>{}</integer>
+        <key>Minute</key>
+        <integer>{}</integer>
+    </dict>
+</dict>
+</plist>
+"#,
+        SCHEDULE_NAME,
+        arguments,
+        time.hour(),
+        time.minute()
+    )
+}
+
+fn systemd_service(args: &[String]) -> String {
+    format!(
+        "[Unit]\nDescription=Save the daily session-finder digest\n\n[Service]\nType=oneshot\nExecStart={}\n",
+        args.iter().map(|arg| systemd_quote(arg)).collect::<Vec<_>>().join(" ")
+    )
+}
+
+/// `Persistent` runs a digest missed while the machine was off once it's back.
+fn systemd_timer(time: NaiveTime) -> String {
+    format!(
+        "[Unit]\nDescription=Save the daily session-finder digest\n\n[Timer]\nOnCalendar=*-*-* {}:00\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n",
+        time.format("%H:%M")
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn systemd_quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%"))
+}
+
+/// Runs a service manager command, failing with its error output.
+fn run_quietly(program: &str, args: &[&str]) -> Result<()> {
+    let output = process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| SessionFinderError::backend(program, format!("{} failed: {}", program, e)))?;
+    if !output.status.success() {
+        return Err(SessionFinderError::backend(
+            program,
+            format!("{} {} failed: {}", program, args.join(" "), String::from_utf8_lossy(&output.stderr).trim()),
+        ));
+    }
+    Ok(())
+}
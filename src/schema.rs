@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+use crate::SessionMessage;
+use crate::glyphs::Glyph;
+use crate::render::RenderOptions;
+
+/// Entry types Claude Code is known to write; anything else is reported but still kept
+const KNOWN_TYPES: &[&str] = &["user", "assistant", "system", "summary", "file-history-snapshot"];
+
+/// Why some lines of a session file could not be used as written, per reason
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ParseDiagnostics {
+    /// Non-blank lines in the file
+    pub lines: usize,
+    /// Lines that parsed into a message
+    pub parsed: usize,
+    pub problems: BTreeMap<String, ProblemCount>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ProblemCount {
+    pub count: usize,
+    /// 1-based line where the problem was first seen
+    pub first_line: usize,
+}
+
+impl ParseDiagnostics {
+    fn record(&mut self, reason: String, line_number: usize) {
+        let problem = self.problems.entry(reason).or_insert(ProblemCount { count: 0, first_line: line_number });
+        problem.count += 1;
+    }
+
+    pub fn problem_count(&self) -> usize {
+        self.problems.values().map(|problem| problem.count).sum()
+    }
+
+    /// Adds another session's counts into this one, for totals across sessions.
+    pub fn merge(&mut self, other: &ParseDiagnostics) {
+        self.lines += other.lines;
+        self.parsed += other.parsed;
+        for (reason, problem) in &other.problems {
+            self.problems.entry(reason.clone()).or_default().count += problem.count;
+        }
+    }
+
+    /// One line: parsed counts followed by each problem and where it first occurred.
+    pub fn summary(&self, render: RenderOptions) -> String {
+        let mut summary = format!("{}/{} lines parsed", self.parsed, self.lines);
+        for (reason, problem) in &self.problems {
+            summary.push_str(&format!("; {} {}{}", reason, render.glyph(Glyph::Times), problem.count));
+            if problem.first_line > 0 {
+                summary.push_str(&format!(" (first at line {})", problem.first_line));
+            }
+        }
+        summary
+    }
+}
+
+/// Parses every line of a session file, keeping the messages and recording why any line was
+/// dropped or looks off. Unknown entry types and messages without a body are kept, since
+/// newer Claude Code versions add entry types faster than this list is updated.
+pub fn parse_session_lines(content: &str) -> (Vec<SessionMessage>, ParseDiagnostics) {
+    let mut messages = Vec::new();
+    let mut diagnostics = ParseDiagnostics::default();
+
+    let mut lines = content.lines().enumerate().peekable();
+    while let Some((index, line)) = lines.next() {
+        if lines.peek().is_none() && !content.ends_with('\n') {
+            messages.extend(parse_unterminated_line(line, index + 1, &mut diagnostics));
+        } else {
+            messages.extend(parse_session_line(line, index + 1, &mut diagnostics));
+        }
+    }
+
+    (messages, diagnostics)
+}
+
+/// Parses one line of a session file, recording in `diagnostics` why it was dropped or looks
+/// off. Blank lines are skipped without being counted.
+pub fn parse_session_line(line: &str, line_number: usize, diagnostics: &mut ParseDiagnostics) -> Option<SessionMessage> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    diagnostics.lines += 1;
+
+    let mut msg = match serde_json::from_str::<SessionMessage>(line) {
+        Ok(msg) => msg,
+        Err(e) => {
+            debug!(line = line_number, error = %e, "Dropped unparseable session line");
+            diagnostics.record(failure_reason(&e), line_number);
+            return None;
+        }
+    };
+
+    if !KNOWN_TYPES.contains(&msg.msg_type.as_str()) {
+        diagnostics.record(format!("unknown type `{}`", msg.msg_type), line_number);
+    } else if matches!(msg.msg_type.as_str(), "user" | "assistant") && msg.message.is_none() {
+        diagnostics.record("missing field `message`".to_string(), line_number);
+    }
+
+    msg.line_number = line_number;
+    diagnostics.parsed += 1;
+    Some(msg)
+}
+
+/// Parses a last line without its newline. Claude Code may be midway through writing it, so
+/// one that stops short of a whole JSON value is skipped instead of counted as malformed.
+pub fn parse_unterminated_line(line: &str, line_number: usize, diagnostics: &mut ParseDiagnostics) -> Option<SessionMessage> {
+    if serde_json::from_str::<serde_json::Value>(line).is_err_and(|e| e.classify() == serde_json::error::Category::Eof) {
+        debug!(line = line_number, "Skipped a last line still being written");
+        return None;
+    }
+    parse_session_line(line, line_number, diagnostics)
+}
+
+fn failure_reason(error: &serde_json::Error) -> String {
+    use serde_json::error::Category;
+
+    match error.classify() {
+        Category::Syntax | Category::Eof => "malformed JSON".to_string(),
+        Category::Io => "unreadable line".to_string(),
+        Category::Data if error.to_string().contains("expected struct SessionMessage") => {
+            "not a JSON object".to_string()
+        }
+        Category::Data => {
+            // serde reports e.g. "missing field `type` at line 1 column 40"; the position is
+            // always within the one line, so drop it to group identical problems
+            let message = error.to_string();
+            match message.find(" at line ") {
+                Some(position) => message[..position].to_string(),
+                None => message,
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+
+use crate::code_analysis::{analyze_code_block, analyzer_for_path, LanguageAnalyzer};
+use crate::fence::code_blocks;
+use crate::timeline::extract_content_text;
+use crate::{Content, ContentType};
+
+/// Which parts of a message query terms are matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchScope {
+    /// Message text and tool input
+    #[default]
+    All,
+    /// `--in-code`: fenced code blocks and what editing tools wrote
+    Code,
+    /// `--in-prose`: message text outside code blocks, without any tool input
+    Prose,
+}
+
+/// Names `--types` accepts, one per kind of message timelines classify
+pub const CONTENT_TYPE_NAMES: &[&str] = &["tool", "error", "code", "discussion", "success"];
+
+/// Tools whose input is code written to a file, with the fields holding it
+const CODE_WRITING_TOOLS: &[(&str, &[&str])] = &[
+    ("Edit", &["new_string"]),
+    ("Write", &["content"]),
+    ("MultiEdit", &["edits"]),
+    ("NotebookEdit", &["new_source"]),
+];
+
+/// Whether code in `language`, a canonical name such as `rust`, passes `--lang`, which
+/// selected `selected` when given.
+pub fn language_selected(selected: Option<&dyn LanguageAnalyzer>, language: Option<&str>) -> bool {
+    selected.is_none_or(|selected| language == Some(selected.name()))
+}
+
+/// The `--types` name of a message's kind. Plain text is discussion, as timelines label it.
+pub fn content_type_name(content_type: &ContentType) -> &'static str {
+    match content_type {
+        ContentType::ToolCall(_) => "tool",
+        ContentType::ErrorMessage(_) => "error",
+        ContentType::CodeBlock(_) => "code",
+        ContentType::PlainText | ContentType::Discussion => "discussion",
+        ContentType::SuccessResponse(_) => "success",
+    }
+}
+
+impl MatchScope {
+    /// The part of a message's text the scope matches against, with code limited to
+    /// `language` when given. Tool input beyond code written by editing tools is left to the
+    /// caller.
+    pub(crate) fn text<'a>(self, content: &'a Content, language: Option<&dyn LanguageAnalyzer>) -> Cow<'a, str> {
+        match self {
+            MatchScope::All => extract_content_text(content),
+            MatchScope::Code => Cow::Owned(code_text(content, language)),
+            MatchScope::Prose => Cow::Owned(prose_text(&extract_content_text(content))),
+        }
+    }
+}
+
+/// The code in a message: fenced blocks in its text and the new text of `Edit`, `Write`,
+/// `MultiEdit`, and `NotebookEdit` calls, one piece per line. With a `--lang` language, only
+/// code in that language, going by fence tags and the edited file's extension.
+pub fn code_text(content: &Content, language: Option<&dyn LanguageAnalyzer>) -> String {
+    let mut pieces: Vec<&str> = Vec::new();
+    let text = extract_content_text(content);
+    pieces.extend(
+        code_blocks(&text)
+            .filter(|block| language_selected(language, analyze_code_block(block).language.as_deref()))
+            .map(|block| block.code),
+    );
+    if let Content::Array(blocks) = content {
+        for block in blocks.iter().filter(|block| block.r#type == "tool_use") {
+            let Some(fields) = CODE_WRITING_TOOLS
+                .iter()
+                .find(|(tool, _)| block.name.as_deref() == Some(tool))
+                .map(|(_, fields)| *fields)
+            else {
+                continue;
+            };
+            let Some(input) = block.input.as_ref() else {
+                continue;
+            };
+            let file_path = input.get("file_path").or_else(|| input.get("notebook_path")).and_then(|path| path.as_str());
+            if !language_selected(language, file_path.and_then(analyzer_for_path).map(|analyzer| analyzer.name())) {
+                continue;
+            }
+            for field in fields {
+                match input.get(field) {
+                    Some(serde_json::Value::String(code)) => pieces.push(code),
+                    // MultiEdit holds a list of edits, each with its own new_string
+                    Some(serde_json::Value::Array(edits)) => pieces.extend(
+                        edits.iter().filter_map(|edit| edit.get("new_string").and_then(|code| code.as_str())),
+                    ),
+                    _ => {}
+                }
+            }
+        }
+    }
+    pieces.join("\n")
+}
+
+/// `text` with its fenced code blocks cut out.
+pub fn prose_text(text: &str) -> String {
+    let mut prose = String::with_capacity(text.len());
+    let mut rest_start = 0;
+    for block in code_blocks(text) {
+        prose.push_str(&text[rest_start..block.span.start]);
+        rest_start = block.span.end;
+    }
+    prose.push_str(&text[rest_start..]);
+    prose
+}
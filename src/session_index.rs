@@ -0,0 +1,171 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::error::Result;
+
+/// Bytes before the end of what was indexed that must be unchanged for growth to count as
+/// an append rather than a rewrite
+const BOUNDARY_BYTES: u64 = 4096;
+
+/// A session file as search results last saw it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedSession {
+    size: u64,
+    modified: DateTime<Utc>,
+    /// SHA-256 chained over the content as it was appended: each append hashes the previous
+    /// value followed by the new bytes, so only the new bytes are read
+    hash: String,
+    /// SHA-256 of the last few KiB of the indexed content
+    boundary: String,
+    /// The title search results showed, for completions to offer without reading the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// How a session changed since search results last showed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionGrowth {
+    pub appended_bytes: u64,
+    /// When the session had last been modified as of then
+    pub since: DateTime<Utc>,
+}
+
+impl SessionGrowth {
+    pub fn describe(&self) -> String {
+        format!("{} bytes appended since last seen (then modified {})", self.appended_bytes, self.since.format("%Y-%m-%d %H:%M UTC"))
+    }
+}
+
+/// Sizes and rolling content hashes of the sessions search results have shown, kept in
+/// `~/.claude/session-finder/session-index.json` between runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionIndex {
+    sessions: BTreeMap<PathBuf, IndexedSession>,
+    /// Entries observed this run, compared against what was loaded so observing twice agrees
+    #[serde(skip)]
+    observed: BTreeMap<PathBuf, IndexedSession>,
+}
+
+fn index_path() -> Result<PathBuf> {
+    Ok(Path::new(&std::env::var("HOME")?)
+        .join(".claude")
+        .join("session-finder")
+        .join("session-index.json"))
+}
+
+impl SessionIndex {
+    /// The stored index; one that can't be read is started afresh, since it only records what
+    /// was seen before.
+    pub fn load() -> Result<Self> {
+        let path = index_path()?;
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Ok(SessionIndex::default());
+        };
+        Ok(serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Could not parse {}, starting a new session index: {}", path.display(), e);
+            SessionIndex::default()
+        }))
+    }
+
+    /// Records the session as it is now, with the title it was shown under, returning how it
+    /// grew since the index last saw it if it was appended to. Growth is only read from where
+    /// the indexed content ended.
+    pub fn observe(
+        &mut self,
+        path: &Path,
+        size: u64,
+        modified: DateTime<Utc>,
+        title: Option<&str>,
+    ) -> io::Result<Option<SessionGrowth>> {
+        let title = title.map(str::to_string);
+        let previous = self.sessions.get(path);
+        if let Some(previous) = previous.filter(|previous| previous.size == size && previous.modified == modified) {
+            if previous.title != title {
+                self.observed.insert(path.to_path_buf(), IndexedSession { title, ..previous.clone() });
+            }
+            return Ok(None);
+        }
+        let appended_to = match previous {
+            Some(previous) if size > previous.size => boundary_hash(path, previous.size)? == previous.boundary,
+            _ => false,
+        };
+        let (hash, growth) = match previous {
+            Some(previous) if appended_to => {
+                let mut hasher = Sha256::new();
+                hasher.update(previous.hash.as_bytes());
+                read_range(path, previous.size, size, &mut hasher)?;
+                let growth = SessionGrowth { appended_bytes: size - previous.size, since: previous.modified };
+                (format!("{:x}", hasher.finalize()), Some(growth))
+            }
+            _ => {
+                let mut hasher = Sha256::new();
+                read_range(path, 0, size, &mut hasher)?;
+                (format!("{:x}", hasher.finalize()), None)
+            }
+        };
+        let boundary = boundary_hash(path, size)?;
+        self.observed.insert(path.to_path_buf(), IndexedSession { size, modified, hash, boundary, title });
+        Ok(growth)
+    }
+
+    /// The title the session was last shown under in search results.
+    pub fn title(&self, path: &Path) -> Option<&str> {
+        self.sessions.get(path)?.title.as_deref()
+    }
+
+    /// Stores what this run observed alongside what was indexed before.
+    pub fn save(mut self) -> Result<()> {
+        if self.observed.is_empty() {
+            return Ok(());
+        }
+        let observed = std::mem::take(&mut self.observed);
+        self.sessions.extend(observed);
+        let path = index_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string(&self)?)?;
+        Ok(())
+    }
+}
+
+/// SHA-256 of the bytes just before `end`, which an append leaves as they were.
+pub fn boundary_hash(path: &Path, end: u64) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    read_range(path, end.saturating_sub(BOUNDARY_BYTES), end, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// `boundary_hash` through `tokio::fs`.
+#[cfg(feature = "tokio")]
+pub async fn boundary_hash_async(path: &Path, end: u64) -> io::Result<String> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let start = end.saturating_sub(BOUNDARY_BYTES);
+    let mut file = tokio::fs::File::open(path).await?;
+    file.seek(SeekFrom::Start(start)).await?;
+    let mut bytes = Vec::new();
+    file.take(end - start).read_to_end(&mut bytes).await?;
+    if (bytes.len() as u64) < end - start {
+        let message = format!("{} is shorter than {} bytes", path.display(), end);
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, message));
+    }
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Feeds bytes `start..end` of the file to the hasher, failing if the file is shorter.
+fn read_range(path: &Path, start: u64, end: u64, hasher: &mut Sha256) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let read = io::copy(&mut file.take(end - start), hasher)?;
+    if read < end - start {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("{} is shorter than {} bytes", path.display(), end)));
+    }
+    Ok(())
+}
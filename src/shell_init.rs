@@ -0,0 +1,168 @@
+use chrono::{DateTime, Utc};
+use clap::{Arg, Command};
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
+use clap_complete::env::{Bash, EnvCompleter, Fish, Zsh};
+use std::fs;
+use std::path::Path;
+
+use crate::claude_data::claude_data;
+use crate::error::{Result, SessionFinderError};
+use crate::resolve::{all_session_files, extract_session_id_from_path};
+use crate::session_index::SessionIndex;
+use crate::decode_project_path;
+
+/// The environment variable that asks the binary for completions instead of running
+const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Sessions offered when completing a session argument, most recent first
+const COMPLETED_SESSIONS: usize = 50;
+
+const ZSH_WIDGET: &str = r#"# session-finder integration for zsh
+# Add to ~/.zshrc:  eval "$(session-finder init zsh)"
+# Alt-S replaces the current command line (used as search terms or a session ID)
+# with the command that resumes the best matching session.
+
+_session_finder_widget() {
+  local query="$BUFFER" cmd
+  if [[ -z "$query" ]]; then
+    zle -M "session-finder: type search terms or a session ID first"
+    return 1
+  fi
+  cmd="$(session-finder resume -- ${(z)query} 2>/dev/null)"
+  if [[ -n "$cmd" ]]; then
+    BUFFER="$cmd"
+    CURSOR=${#BUFFER}
+  else
+    zle -M "session-finder: no session matches '$query'"
+  fi
+  zle reset-prompt
+}
+zle -N _session_finder_widget
+bindkey '\es' _session_finder_widget
+"#;
+
+const BASH_WIDGET: &str = r#"# session-finder integration for bash
+# Add to ~/.bashrc:  eval "$(session-finder init bash)"
+# Alt-S replaces the current command line (used as search terms or a session ID)
+# with the command that resumes the best matching session.
+
+_session_finder_widget() {
+  local query="$READLINE_LINE" cmd
+  [ -z "$query" ] && return
+  cmd="$(session-finder resume -- $query 2>/dev/null)"
+  if [ -n "$cmd" ]; then
+    READLINE_LINE="$cmd"
+    READLINE_POINT=${#READLINE_LINE}
+  fi
+}
+bind -x '"\es": _session_finder_widget'
+"#;
+
+const FISH_WIDGET: &str = r#"# session-finder integration for fish
+# Add to ~/.config/fish/config.fish:  session-finder init fish | source
+# Alt-S replaces the current command line (used as search terms or a session ID)
+# with the command that resumes the best matching session.
+
+function __session_finder_widget
+  set -l query (commandline)
+  test -z "$query"; and return
+  set -l cmd (session-finder resume -- (string split ' ' -- $query) 2>/dev/null)
+  if test -n "$cmd"
+    commandline -r -- $cmd
+  end
+  commandline -f repaint
+end
+bind \es __session_finder_widget
+"#;
+
+/// Returns the shell function, key binding, and completions for `shell`, calling the binary
+/// by the name `init` was run under.
+pub fn shell_init_script(shell: &str) -> Result<String> {
+    let mut completer = std::env::args().next().unwrap_or_else(|| "session-finder".to_string());
+    // A path such as ./target/debug/session-finder must still work from other directories
+    if Path::new(&completer).components().count() > 1 {
+        if let Ok(absolute) = std::path::absolute(&completer) {
+            completer = absolute.to_string_lossy().into_owned();
+        }
+    }
+    let (widget, completions): (&str, &dyn EnvCompleter) = match shell {
+        "zsh" => (ZSH_WIDGET, &Zsh),
+        "bash" => (BASH_WIDGET, &Bash),
+        "fish" => (FISH_WIDGET, &Fish),
+        _ => {
+            let message = format!("Unsupported shell: {} (expected bash, zsh, or fish)", shell);
+            return Err(SessionFinderError::Usage(message));
+        }
+    };
+    let name = Path::new(&completer).file_name().map_or(completer.clone(), |name| name.to_string_lossy().into_owned());
+    let mut registration = Vec::new();
+    completions.write_registration(COMPLETE_VAR, &name, &name, &completer, &mut registration)?;
+    let widget = widget.replace("session-finder resume", &format!("{} resume", completer));
+    Ok(format!("{}\n{}", widget, String::from_utf8_lossy(&registration)))
+}
+
+/// Answers the completion scripts' requests, made by running the binary with `COMPLETE` set,
+/// and exits; otherwise returns at once.
+pub fn complete_from_env(cli: fn() -> Command) {
+    clap_complete::CompleteEnv::with_factory(move || with_value_candidates(cli())).var(COMPLETE_VAR).complete();
+}
+
+/// The command with its `--project` and session arguments completed from what's on disk.
+fn with_value_candidates(cli: Command) -> Command {
+    let cli = cli.mut_args(|arg| {
+        if arg.get_id() == "project" {
+            arg.add(ArgValueCandidates::new(project_candidates))
+        } else if takes_session(&arg) {
+            arg.add(ArgValueCandidates::new(session_candidates))
+        } else {
+            arg
+        }
+    });
+    let names: Vec<String> = cli.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+    names.into_iter().fold(cli, |cli, name| cli.mut_subcommand(name, with_value_candidates))
+}
+
+/// Whether the argument's value names a session, going by its value name
+fn takes_session(arg: &Arg) -> bool {
+    arg.get_value_names()
+        .is_some_and(|names| names.iter().any(|name| name.starts_with("SESSION")))
+}
+
+/// Every project with sessions.
+fn project_candidates() -> Vec<CompletionCandidate> {
+    let mut projects: Vec<String> =
+        all_session_files().unwrap_or_default().iter().filter_map(|path| decode_project_path(path).ok()).collect();
+    projects.sort();
+    projects.dedup();
+    projects.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// The most recently modified sessions, each described by its date and title. Titles come
+/// from the session index, as search results last showed them, or Claude's history index,
+/// so no session is read.
+fn session_candidates() -> Vec<CompletionCandidate> {
+    let mut sessions: Vec<(DateTime<Utc>, _)> = all_session_files()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| Some((DateTime::from(fs::metadata(&path).ok()?.modified().ok()?), path)))
+        .collect();
+    sessions.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    let index = SessionIndex::load().unwrap_or_default();
+    let mut candidates = Vec::new();
+    for (modified, path) in sessions.into_iter().take(COMPLETED_SESSIONS) {
+        let Ok(session_id) = extract_session_id_from_path(&path) else {
+            continue;
+        };
+        let title = index
+            .title(&path)
+            .map(str::to_string)
+            .or_else(|| claude_data().title(&session_id).map(str::to_string))
+            .or_else(|| decode_project_path(&path).ok())
+            .unwrap_or_default();
+        // Descriptions are one line
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        let help = format!("{} {}", modified.format("%Y-%m-%d"), title);
+        candidates.push(CompletionCandidate::new(session_id).help(Some(help.into())));
+    }
+    candidates
+}
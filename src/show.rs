@@ -0,0 +1,104 @@
+use serde_json::Value;
+
+use crate::editor::open_in_editor;
+use crate::error::{Result, SessionFinderError};
+use crate::glyphs::Glyph;
+use crate::interrupt::interrupted_turns;
+use crate::render::RenderOptions;
+use crate::resolve::read_session_source;
+use crate::store::Store;
+use crate::timeline::{
+    classify_message_content, content_type_label, format_tool_content, interrupted_label, message_heading,
+    parse_session_messages_with_diagnostics,
+};
+use crate::{Content, SessionMessage};
+
+/// Prints message `around` with `context_size` messages on each side, in full and without any
+/// term matching, for zooming in on an index a timeline pointed to. With `edit`, opens the
+/// session file at that message instead.
+pub fn show_messages(session_ref: &str, around: usize, context_size: usize, edit: bool, render: RenderOptions) -> Result<()> {
+    let (path, session_id, content) = read_session_source(session_ref)?;
+    let (messages, _) = parse_session_messages_with_diagnostics(&content);
+    if around >= messages.len() {
+        return Err(SessionFinderError::Usage(format!(
+            "Session {} has {} message(s); there is no message {}",
+            session_id,
+            messages.len(),
+            around
+        )));
+    }
+
+    if edit {
+        return open_in_editor(&path, messages[around].line_number);
+    }
+
+    let start = around.saturating_sub(context_size);
+    let end = (around + context_size).min(messages.len() - 1);
+    let interrupted = interrupted_turns(&messages);
+    let store = Store::load()?;
+
+    println!(
+        "=== Messages {}{}{} of {} in session {} ===\n",
+        start,
+        render.glyph(Glyph::Range),
+        end,
+        messages.len(),
+        session_id
+    );
+    for (index, msg) in messages.iter().enumerate().take(end + 1).skip(start) {
+        let classified = classify_message_content(&messages, index);
+        let role = msg.message.as_ref().and_then(|m| m.role.clone()).unwrap_or_default();
+        println!(
+            "{}{} {}: {}{}",
+            if index == around { ">> " } else { "" },
+            message_heading(index, msg.line_number, msg.timestamp.as_deref()),
+            role,
+            content_type_label(&classified.content_type, render),
+            interrupted_label(interrupted[index], render)
+        );
+        if let Some(bookmark) = store.bookmark(&session_id, index) {
+            println!("  Bookmark: {}", bookmark.label.as_deref().unwrap_or("(no label)"));
+        }
+        for note in store.message_notes(&session_id, index) {
+            println!("  Note: {}", note.text);
+        }
+        println!("  {} {}", render.glyph(Glyph::Arrow), full_text(msg, render));
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Everything the message holds: its text, tool calls with their input, and tool output.
+fn full_text(msg: &SessionMessage, render: RenderOptions) -> String {
+    let Some(content) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+        return String::new();
+    };
+    let blocks = match content {
+        Content::Text(text) => return text.clone(),
+        Content::Array(blocks) => blocks,
+    };
+    let parts: Vec<String> = blocks
+        .iter()
+        .filter_map(|block| match block.r#type.as_str() {
+            "text" => block.text.clone(),
+            "tool_use" => Some(format_tool_content(block.name.as_deref().unwrap_or_default(), &block.input, render)),
+            "tool_result" => block.content.as_ref().map(result_text),
+            _ => None,
+        })
+        .collect();
+    parts.join("\n")
+}
+
+/// A tool result's output, given either as a string or as text blocks.
+pub fn result_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,218 @@
+use std::path::Path;
+
+use crate::error::Result;
+
+/// Tables `export --format sqlite` creates; messages and tool calls are numbered as timelines
+/// number them (`Message N`)
+#[cfg(feature = "sqlite")]
+const SCHEMA: &str = "
+CREATE TABLE sessions (
+    session_id TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    project TEXT NOT NULL,
+    source TEXT NOT NULL,
+    title TEXT,
+    git_branch TEXT,
+    started TEXT,
+    modified TEXT NOT NULL,
+    message_count INTEGER NOT NULL,
+    file_size INTEGER NOT NULL
+);
+CREATE TABLE messages (
+    session_id TEXT NOT NULL REFERENCES sessions (session_id),
+    message_index INTEGER NOT NULL,
+    line_number INTEGER NOT NULL,
+    uuid TEXT,
+    parent_uuid TEXT,
+    type TEXT NOT NULL,
+    role TEXT,
+    model TEXT,
+    timestamp TEXT,
+    is_sidechain INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    PRIMARY KEY (session_id, message_index)
+);
+CREATE TABLE tool_calls (
+    session_id TEXT NOT NULL REFERENCES sessions (session_id),
+    message_index INTEGER NOT NULL,
+    tool_use_id TEXT,
+    name TEXT NOT NULL,
+    input TEXT,
+    output TEXT,
+    is_error INTEGER,
+    result_message_index INTEGER
+);
+CREATE INDEX tool_calls_by_session ON tool_calls (session_id, message_index);
+CREATE INDEX tool_calls_by_name ON tool_calls (name);
+CREATE TABLE files_touched (
+    session_id TEXT NOT NULL REFERENCES sessions (session_id),
+    path TEXT NOT NULL,
+    PRIMARY KEY (session_id, path)
+);
+";
+
+/// Writes the selected sessions to a new SQLite database at `output`, replacing any file
+/// there once the database is complete.
+#[cfg(feature = "sqlite")]
+pub fn export_sqlite(session_refs: &[&str], project_filter: Option<&String>, recent_days: Option<i64>, output: &Path) -> Result<()> {
+    use rusqlite::{params, Connection};
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+
+    use crate::export::select_sessions;
+    use crate::file_ops::files_touched;
+    use crate::matcher::SearchOptions;
+    use crate::resolve::read_session_text;
+    use crate::resume::session_working_dir;
+    use crate::show::result_text;
+    use crate::timeline::{extract_content_text, parse_session_messages};
+    use crate::trash::TrashOperation;
+    use crate::Content;
+
+    let mut partial = output.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = std::path::PathBuf::from(partial);
+    if partial.exists() {
+        fs::remove_file(&partial)?;
+    }
+    let mut db = Connection::open(&partial)?;
+    db.execute_batch(SCHEMA)?;
+    let transaction = db.transaction()?;
+
+    let mut sessions = select_sessions(session_refs, &[], project_filter, recent_days, &SearchOptions::default())?;
+    // A session copied into several roots, such as a pulled copy of one's own, is written once
+    let mut exported = HashSet::new();
+    sessions.retain(|info| exported.insert(info.session_id.clone()));
+    let (mut message_rows, mut tool_call_rows) = (0, 0);
+    for info in &sessions {
+        let messages = parse_session_messages(&read_session_text(&info.path)?)?;
+        transaction.execute(
+            "INSERT INTO sessions VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                info.session_id,
+                info.path.to_string_lossy(),
+                info.project_path,
+                info.source,
+                info.title,
+                messages.iter().find_map(|msg| msg.git_branch.as_deref()),
+                messages.iter().find_map(|msg| msg.timestamp.as_deref()),
+                info.last_modified.to_rfc3339(),
+                messages.len(),
+                info.file_size_bytes,
+            ],
+        )?;
+
+        // Calls by `tool_use` ID, so results can be filled in as they turn up
+        let mut calls: Vec<ToolCallRow> = Vec::new();
+        let mut call_ids: HashMap<&str, usize> = HashMap::new();
+        for (index, msg) in messages.iter().enumerate() {
+            let inner = msg.message.as_ref();
+            let content = inner.and_then(|inner| inner.content.as_ref());
+            transaction.execute(
+                "INSERT INTO messages VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    info.session_id,
+                    index,
+                    msg.line_number,
+                    msg.uuid,
+                    msg.parent_uuid,
+                    msg.msg_type,
+                    inner.and_then(|inner| inner.role.as_deref()),
+                    inner.and_then(|inner| inner.model.as_deref()),
+                    msg.timestamp,
+                    msg.is_sidechain == Some(true),
+                    content.map(|content| extract_content_text(content).into_owned()).unwrap_or_default(),
+                ],
+            )?;
+            let Some(Content::Array(blocks)) = content else {
+                continue;
+            };
+            for block in blocks {
+                match block.r#type.as_str() {
+                    "tool_use" => {
+                        if let Some(id) = block.id.as_deref() {
+                            call_ids.insert(id, calls.len());
+                        }
+                        calls.push(ToolCallRow {
+                            message_index: index,
+                            tool_use_id: block.id.clone(),
+                            name: block.name.clone().unwrap_or_default(),
+                            input: block.input.as_ref().map(|input| input.to_string()),
+                            output: None,
+                            is_error: None,
+                            result_message_index: None,
+                        });
+                    }
+                    "tool_result" => {
+                        let Some(&call) = block.tool_use_id.as_deref().and_then(|id| call_ids.get(id)) else {
+                            continue;
+                        };
+                        let call = &mut calls[call];
+                        call.output = block.content.as_ref().map(result_text);
+                        call.is_error = Some(block.is_error == Some(true));
+                        call.result_message_index = Some(index);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for call in &calls {
+            transaction.execute(
+                "INSERT INTO tool_calls VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    info.session_id,
+                    call.message_index,
+                    call.tool_use_id,
+                    call.name,
+                    call.input,
+                    call.output,
+                    call.is_error,
+                    call.result_message_index,
+                ],
+            )?;
+        }
+        for file in files_touched(&messages, &session_working_dir(&info.path)) {
+            transaction.execute("INSERT OR IGNORE INTO files_touched VALUES (?1, ?2)", params![info.session_id, file])?;
+        }
+        message_rows += messages.len();
+        tool_call_rows += calls.len();
+    }
+    transaction.commit()?;
+    db.close().map_err(|(_, e)| e)?;
+    let mut trash = TrashOperation::begin("export");
+    if output.exists() {
+        trash.discard_file(output)?;
+    }
+    fs::rename(&partial, output)?;
+
+    println!(
+        "Exported {} session(s), {} message(s), and {} tool call(s) to {}",
+        sessions.len(),
+        message_rows,
+        tool_call_rows,
+        output.display()
+    );
+    trash.finish();
+    Ok(())
+}
+
+/// A tool call, with its result once the `tool_result` naming it is read
+#[cfg(feature = "sqlite")]
+struct ToolCallRow {
+    message_index: usize,
+    tool_use_id: Option<String>,
+    name: String,
+    input: Option<String>,
+    output: Option<String>,
+    is_error: Option<bool>,
+    result_message_index: Option<usize>,
+}
+
+/// Without SQLite compiled in there is nothing to write with.
+#[cfg(not(feature = "sqlite"))]
+pub fn export_sqlite(_session_refs: &[&str], _project_filter: Option<&String>, _recent_days: Option<i64>, _output: &Path) -> Result<()> {
+    Err(crate::error::SessionFinderError::backend(
+        "sqlite",
+        "--format sqlite needs session-finder built with `--features sqlite`",
+    ))
+}
@@ -0,0 +1,496 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, SessionFinderError};
+use crate::interrupt::interruption;
+use crate::resolve::{all_session_files, read_session_text};
+use crate::text::pad_to_width;
+use crate::timeline::parse_session_messages;
+use crate::{decode_project_path, extract_session_id, Content};
+
+/// Token counts reported by the API for one assistant message
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+impl Usage {
+    fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+}
+
+/// USD per million input and output tokens, matched against the model name
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("opus", 15.0, 75.0),
+    ("sonnet", 3.0, 15.0),
+    ("haiku", 0.8, 4.0),
+];
+
+/// Cache writes and reads are billed relative to the input price
+const CACHE_WRITE_MULTIPLIER: f64 = 1.25;
+const CACHE_READ_MULTIPLIER: f64 = 0.1;
+
+/// How usage rows are grouped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsGrouping {
+    Session,
+    Day,
+    Project,
+}
+
+impl StatsGrouping {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "session" => Ok(StatsGrouping::Session),
+            "day" => Ok(StatsGrouping::Day),
+            "project" => Ok(StatsGrouping::Project),
+            other => Err(SessionFinderError::Usage(format!(
+                "Unknown grouping: {} (expected session, day, or project)",
+                other
+            ))),
+        }
+    }
+
+    pub fn key_header(self) -> &'static str {
+        match self {
+            StatsGrouping::Session => "session",
+            StatsGrouping::Day => "date",
+            StatsGrouping::Project => "project",
+        }
+    }
+
+    pub fn key(self, session_id: &str, date: &str, project: &str) -> String {
+        match self {
+            StatsGrouping::Session => session_id.to_string(),
+            StatsGrouping::Day => date.to_string(),
+            StatsGrouping::Project => project.to_string(),
+        }
+    }
+
+    /// Rows grouped by project already name it, so the project column is left out
+    pub fn shows_project(self) -> bool {
+        self != StatsGrouping::Project
+    }
+}
+
+/// How the usage table (and other tabular reports) is written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsFormat {
+    Text,
+    Csv,
+    Tsv,
+}
+
+impl StatsFormat {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "text" => Ok(StatsFormat::Text),
+            "csv" => Ok(StatsFormat::Csv),
+            "tsv" => Ok(StatsFormat::Tsv),
+            other => Err(SessionFinderError::Usage(format!(
+                "Format '{}' is not supported for reports (use text, csv, or tsv)",
+                other
+            ))),
+        }
+    }
+}
+
+/// One row of the usage report: a session, or one project's activity on one day
+#[derive(Debug, Default)]
+struct UsageRow {
+    key: String,
+    project: String,
+    messages: usize,
+    tokens: u64,
+    cost: f64,
+    duration_secs: i64,
+    /// Turns the user interrupted or whose tool calls they rejected
+    interrupted: usize,
+}
+
+/// Activity of one session on one day
+struct SessionDay {
+    date: String,
+    messages: usize,
+    tokens: u64,
+    cost: f64,
+    interrupted: usize,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+}
+
+/// Prints message counts, token usage, estimated cost, and active duration for every session
+/// matching the project and recency filters.
+pub fn run_stats(
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    grouping: StatsGrouping,
+    format: StatsFormat,
+) -> Result<()> {
+    let mut rows: BTreeMap<(String, String), UsageRow> = BTreeMap::new();
+
+    for (path, project) in selected_session_files(project_filter, recent_days)? {
+        let session_id = extract_session_id(&path)?;
+        for day in session_days(&path)? {
+            let key = grouping.key(&session_id, &day.date, &project);
+            let row = rows.entry((key.clone(), project.clone())).or_insert_with(|| UsageRow {
+                key,
+                project: project.clone(),
+                ..Default::default()
+            });
+            row.messages += day.messages;
+            row.tokens += day.tokens;
+            row.cost += day.cost;
+            row.interrupted += day.interrupted;
+            if let (Some(first), Some(last)) = (day.first, day.last) {
+                row.duration_secs += (last - first).num_seconds();
+            }
+        }
+    }
+
+    let rows: Vec<UsageRow> = rows.into_values().collect();
+
+    match format {
+        StatsFormat::Text => print_text(grouping, &rows),
+        StatsFormat::Csv => print_delimited(grouping, &rows, ','),
+        StatsFormat::Tsv => print_delimited(grouping, &rows, '\t'),
+    }
+    Ok(())
+}
+
+/// Session files matching the project and recency filters, with their decoded project path.
+pub fn selected_session_files(project_filter: Option<&String>, recent_days: Option<i64>) -> Result<Vec<(PathBuf, String)>> {
+    let cutoff = recent_days.map(|days| Utc::now() - chrono::Duration::days(days));
+    let mut files = Vec::new();
+
+    for path in all_session_files()? {
+        let project = decode_project_path(&path)?;
+        if project_filter.is_some_and(|filter| !project.contains(filter.as_str())) {
+            continue;
+        }
+        if let Some(cutoff) = cutoff {
+            let modified: DateTime<Utc> = DateTime::from(fs::metadata(&path)?.modified()?);
+            if modified < cutoff {
+                continue;
+            }
+        }
+        files.push((path, project));
+    }
+
+    Ok(files)
+}
+
+/// Calls of one tool within one row group
+#[derive(Debug, Default)]
+struct ToolRow {
+    key: String,
+    project: String,
+    tool: String,
+    calls: usize,
+    /// Results flagged `is_error`
+    errors: usize,
+    /// Results paired with a call, and their total length in characters
+    results: usize,
+    result_chars: usize,
+}
+
+/// Prints, per tool, how often it was called, how often it failed, and how large its results
+/// were, grouped by session, day, or project.
+pub fn run_tool_stats(
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    grouping: StatsGrouping,
+    format: StatsFormat,
+) -> Result<()> {
+    let mut rows: BTreeMap<(String, String, String), ToolRow> = BTreeMap::new();
+
+    for (path, project) in selected_session_files(project_filter, recent_days)? {
+        let session_id = extract_session_id(&path)?;
+        let content = read_session_text(&path)?;
+        let messages = parse_session_messages(&content)?;
+        // Results arrive in a later message than their call, so remember which row each call went to
+        let mut calls: HashMap<&str, (String, String, String)> = HashMap::new();
+
+        for msg in &messages {
+            let Some(Content::Array(blocks)) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+                continue;
+            };
+            let date = msg.timestamp.as_deref()
+                .filter(|ts| ts.as_bytes().get(4) == Some(&b'-'))
+                .and_then(|ts| ts.get(..10))
+                .unwrap_or("unknown");
+
+            for block in blocks {
+                match block.r#type.as_str() {
+                    "tool_use" => {
+                        let tool = block.name.clone().unwrap_or_else(|| "unknown".to_string());
+                        let key = (grouping.key(&session_id, date, &project), project.clone(), tool.clone());
+                        let row = rows.entry(key.clone()).or_insert_with(|| ToolRow {
+                            key: key.0.clone(),
+                            project: project.clone(),
+                            tool,
+                            ..Default::default()
+                        });
+                        row.calls += 1;
+                        if let Some(id) = block.id.as_deref() {
+                            calls.insert(id, key);
+                        }
+                    }
+                    "tool_result" => {
+                        let Some(row) = block.tool_use_id.as_deref()
+                            .and_then(|id| calls.get(id))
+                            .and_then(|key| rows.get_mut(key))
+                        else {
+                            continue;
+                        };
+                        row.results += 1;
+                        row.result_chars += block.content.as_ref().map(result_chars).unwrap_or(0);
+                        if block.is_error == Some(true) {
+                            row.errors += 1;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let mut rows: Vec<ToolRow> = rows.into_values().collect();
+    rows.sort_by(|a, b| (&a.key, &a.project).cmp(&(&b.key, &b.project)).then_with(|| b.calls.cmp(&a.calls)));
+
+    match format {
+        StatsFormat::Text => print_tool_text(grouping, &rows),
+        StatsFormat::Csv => print_tool_delimited(grouping, &rows, ','),
+        StatsFormat::Tsv => print_tool_delimited(grouping, &rows, '\t'),
+    }
+    Ok(())
+}
+
+/// Length of a tool result's text; results given as blocks count their text blocks.
+fn result_chars(content: &serde_json::Value) -> usize {
+    match content {
+        serde_json::Value::String(text) => text.chars().count(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.get("text").and_then(|text| text.as_str()))
+            .map(|text| text.chars().count())
+            .sum(),
+        other => other.to_string().len(),
+    }
+}
+
+impl ToolRow {
+    fn error_rate(&self) -> f64 {
+        if self.results == 0 { 0.0 } else { self.errors as f64 / self.results as f64 }
+    }
+
+    fn average_result_chars(&self) -> usize {
+        self.result_chars.checked_div(self.results).unwrap_or(0)
+    }
+}
+
+fn print_tool_text(grouping: StatsGrouping, rows: &[ToolRow]) {
+    if rows.is_empty() {
+        println!("No tool calls found");
+        return;
+    }
+
+    let project_header = if grouping.shows_project() { "  project" } else { "" };
+    println!(
+        "{:<36}  {:<24}  {:>7}  {:>7}  {:>6}  {:>10}{}",
+        grouping.key_header(), "tool", "calls", "errors", "failed", "avg result", project_header
+    );
+    for row in rows {
+        let project = if grouping.shows_project() { format!("  {}", row.project) } else { String::new() };
+        println!(
+            "{}  {}  {:>7}  {:>7}  {:>5.1}%  {:>10}{}",
+            pad_to_width(&row.key, 36),
+            pad_to_width(&row.tool, 24),
+            row.calls,
+            row.errors,
+            row.error_rate() * 100.0,
+            row.average_result_chars(),
+            project
+        );
+    }
+
+    let calls: usize = rows.iter().map(|row| row.calls).sum();
+    let errors: usize = rows.iter().map(|row| row.errors).sum();
+    println!("\nTotal: {} call(s), {} failed", calls, errors);
+}
+
+fn print_tool_delimited(grouping: StatsGrouping, rows: &[ToolRow], delimiter: char) {
+    let mut header = vec![grouping.key_header()];
+    if grouping.shows_project() {
+        header.push("project");
+    }
+    header.extend(["tool", "calls", "errors", "error_rate", "avg_result_chars"]);
+    println!("{}", header.join(&delimiter.to_string()));
+
+    for row in rows {
+        let mut fields = vec![delimited_field(&row.key, delimiter)];
+        if grouping.shows_project() {
+            fields.push(delimited_field(&row.project, delimiter));
+        }
+        fields.extend([
+            delimited_field(&row.tool, delimiter),
+            row.calls.to_string(),
+            row.errors.to_string(),
+            format!("{:.4}", row.error_rate()),
+            row.average_result_chars().to_string(),
+        ]);
+        println!("{}", fields.join(&delimiter.to_string()));
+    }
+}
+
+/// Splits a session's messages by the UTC day they were sent on.
+fn session_days(path: &Path) -> Result<Vec<SessionDay>> {
+    let content = read_session_text(path)?;
+    let messages = parse_session_messages(&content)?;
+    let mut days: Vec<SessionDay> = Vec::new();
+    // A rejected tool call is usually followed by an interrupt marker; count the turn once
+    let mut previous_interrupted = false;
+
+    for msg in &messages {
+        let Some(inner) = msg.message.as_ref() else {
+            continue;
+        };
+        let sent = msg.timestamp.as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc));
+        let date = sent.map(|ts| ts.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "unknown".to_string());
+
+        let index = match days.iter().position(|day| day.date == date) {
+            Some(index) => index,
+            None => {
+                days.push(SessionDay { date, messages: 0, tokens: 0, cost: 0.0, interrupted: 0, first: None, last: None });
+                days.len() - 1
+            }
+        };
+        let day = &mut days[index];
+
+        day.messages += 1;
+        let interrupted = interruption(msg).is_some();
+        if interrupted && !previous_interrupted {
+            day.interrupted += 1;
+        }
+        previous_interrupted = interrupted;
+        if let Some(usage) = &inner.usage {
+            day.tokens += usage.total();
+            day.cost += estimate_cost(inner.model.as_deref(), usage);
+        }
+        if let Some(sent) = sent {
+            day.first = Some(day.first.map_or(sent, |first| first.min(sent)));
+            day.last = Some(day.last.map_or(sent, |last| last.max(sent)));
+        }
+    }
+
+    Ok(days)
+}
+
+/// Estimated USD cost of one message; unknown models are not priced.
+pub fn estimate_cost(model: Option<&str>, usage: &Usage) -> f64 {
+    let Some(model) = model else {
+        return 0.0;
+    };
+    let Some(&(_, input, output)) = MODEL_PRICES.iter().find(|(family, _, _)| model.contains(family)) else {
+        return 0.0;
+    };
+
+    let input_cost = usage.input_tokens as f64 * input
+        + usage.cache_creation_input_tokens as f64 * input * CACHE_WRITE_MULTIPLIER
+        + usage.cache_read_input_tokens as f64 * input * CACHE_READ_MULTIPLIER;
+    (input_cost + usage.output_tokens as f64 * output) / 1_000_000.0
+}
+
+fn print_text(grouping: StatsGrouping, rows: &[UsageRow]) {
+    if rows.is_empty() {
+        println!("No sessions found");
+        return;
+    }
+
+    let project_header = if grouping.shows_project() { "  project" } else { "" };
+    println!(
+        "{:<36}  {:>8}  {:>12}  {:>9}  {:>9}  {:>11}{}",
+        grouping.key_header(), "messages", "tokens", "cost", "duration", "interrupted", project_header
+    );
+    for row in rows {
+        let project = if grouping.shows_project() { format!("  {}", row.project) } else { String::new() };
+        println!(
+            "{}  {:>8}  {:>12}  {:>9}  {:>9}  {:>11}{}",
+            pad_to_width(&row.key, 36),
+            row.messages,
+            row.tokens,
+            format!("${:.2}", row.cost),
+            format_duration(row.duration_secs),
+            row.interrupted,
+            project
+        );
+    }
+
+    let total_cost: f64 = rows.iter().map(|row| row.cost).sum();
+    let total_tokens: u64 = rows.iter().map(|row| row.tokens).sum();
+    let total_secs: i64 = rows.iter().map(|row| row.duration_secs).sum();
+    let total_interrupted: usize = rows.iter().map(|row| row.interrupted).sum();
+    println!(
+        "\nTotal: {} row(s), {} tokens, ${:.2} estimated, {} active, {} interrupted turn(s)",
+        rows.len(),
+        total_tokens,
+        total_cost,
+        format_duration(total_secs),
+        total_interrupted
+    );
+}
+
+fn print_delimited(grouping: StatsGrouping, rows: &[UsageRow], delimiter: char) {
+    let mut header = vec![grouping.key_header()];
+    if grouping.shows_project() {
+        header.push("project");
+    }
+    header.extend(["messages", "tokens", "cost_usd", "duration_seconds", "interrupted"]);
+    println!("{}", header.join(&delimiter.to_string()));
+
+    for row in rows {
+        let mut fields = vec![delimited_field(&row.key, delimiter)];
+        if grouping.shows_project() {
+            fields.push(delimited_field(&row.project, delimiter));
+        }
+        fields.extend([
+            row.messages.to_string(),
+            row.tokens.to_string(),
+            format!("{:.4}", row.cost),
+            row.duration_secs.to_string(),
+            row.interrupted.to_string(),
+        ]);
+        println!("{}", fields.join(&delimiter.to_string()));
+    }
+}
+
+/// Quotes CSV fields that need it; TSV has no quoting, so tabs and newlines become spaces.
+pub fn delimited_field(text: &str, delimiter: char) -> String {
+    if delimiter == '\t' {
+        return text.replace(['\t', '\n', '\r'], " ");
+    }
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn format_duration(secs: i64) -> String {
+    let minutes = secs / 60;
+    if minutes >= 60 {
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
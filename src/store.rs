@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, SessionFinderError};
+
+/// User-assigned metadata for sessions, kept next to Claude's own files in
+/// `~/.claude/session-finder/store.toml` so it survives reinstalls of this tool.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Store {
+    #[serde(default)]
+    sessions: BTreeMap<String, SessionAnnotations>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionAnnotations {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Note>,
+}
+
+impl SessionAnnotations {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.bookmarks.is_empty() && self.notes.is_empty()
+    }
+}
+
+/// A message within a session worth coming back to, by timeline message index
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub message: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Free text attached to a whole session, or to one message by timeline message index
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Note {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<usize>,
+    pub text: String,
+}
+
+pub fn store_path() -> Result<PathBuf> {
+    Ok(Path::new(&std::env::var("HOME")?)
+        .join(".claude")
+        .join("session-finder")
+        .join("store.toml"))
+}
+
+impl Store {
+    /// Reads the store, or an empty one if nothing has been saved yet.
+    pub fn load() -> Result<Self> {
+        Self::load_from(&store_path()?)
+    }
+
+    /// Reads a store file elsewhere, such as one in a backup.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Store::default());
+        }
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| SessionFinderError::toml(path.to_path_buf(), &content, e))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = store_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write then rename, so an interrupted save never leaves a truncated store behind
+        let temp_path = path.with_extension("toml.tmp");
+        fs::write(&temp_path, toml::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    pub fn session(&self, session_id: &str) -> Option<&SessionAnnotations> {
+        self.sessions.get(session_id)
+    }
+
+    /// IDs of every session with annotations.
+    pub fn session_ids(&self) -> impl Iterator<Item = &String> {
+        self.sessions.keys()
+    }
+
+    fn session_mut(&mut self, session_id: &str) -> &mut SessionAnnotations {
+        self.sessions.entry(session_id.to_string()).or_default()
+    }
+
+    /// Drops sessions left without any annotations, keeping the file tidy.
+    fn prune(&mut self) {
+        self.sessions.retain(|_, annotations| !annotations.is_empty());
+    }
+
+    pub fn tags(&self, session_id: &str) -> &[String] {
+        self.session(session_id).map(|s| s.tags.as_slice()).unwrap_or_default()
+    }
+
+    pub fn has_tags(&self, session_id: &str, tags: &[&str]) -> bool {
+        let session_tags = self.tags(session_id);
+        tags.iter().all(|tag| session_tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+
+    /// IDs of every session carrying all of `tags`.
+    pub fn sessions_with_tags(&self, tags: &[&str]) -> Vec<String> {
+        self.sessions
+            .keys()
+            .filter(|session_id| self.has_tags(session_id, tags))
+            .cloned()
+            .collect()
+    }
+
+    pub fn add_tag(&mut self, session_id: &str, tag: &str) {
+        let tags = &mut self.session_mut(session_id).tags;
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+            tags.sort();
+        }
+    }
+
+    /// Removes the tag, returning it as it was spelled if the session had it.
+    pub fn remove_tag(&mut self, session_id: &str, tag: &str) -> Option<String> {
+        let tags = &mut self.session_mut(session_id).tags;
+        let removed = tags.iter().position(|t| t.eq_ignore_ascii_case(tag)).map(|index| tags.remove(index));
+        self.prune();
+        removed
+    }
+
+    pub fn bookmarks(&self, session_id: &str) -> &[Bookmark] {
+        self.session(session_id).map(|s| s.bookmarks.as_slice()).unwrap_or_default()
+    }
+
+    pub fn bookmark(&self, session_id: &str, message: usize) -> Option<&Bookmark> {
+        self.bookmarks(session_id).iter().find(|b| b.message == message)
+    }
+
+    /// Adds a bookmark, replacing the label of an existing one on the same message.
+    pub fn add_bookmark(&mut self, session_id: &str, message: usize, label: Option<String>) {
+        let bookmarks = &mut self.session_mut(session_id).bookmarks;
+        match bookmarks.iter_mut().find(|b| b.message == message) {
+            Some(existing) => existing.label = label,
+            None => {
+                bookmarks.push(Bookmark { message, label });
+                bookmarks.sort_by_key(|b| b.message);
+            }
+        }
+    }
+
+    pub fn remove_bookmark(&mut self, session_id: &str, message: usize) -> Option<Bookmark> {
+        let bookmarks = &mut self.session_mut(session_id).bookmarks;
+        let removed = bookmarks.iter().position(|b| b.message == message).map(|index| bookmarks.remove(index));
+        self.prune();
+        removed
+    }
+
+    pub fn notes(&self, session_id: &str) -> &[Note] {
+        self.session(session_id).map(|s| s.notes.as_slice()).unwrap_or_default()
+    }
+
+    /// Notes attached to one message of a session.
+    pub fn message_notes<'a>(&'a self, session_id: &str, message: usize) -> impl Iterator<Item = &'a Note> {
+        self.notes(session_id).iter().filter(move |note| note.message == Some(message))
+    }
+
+    pub fn add_note(&mut self, session_id: &str, message: Option<usize>, text: String) {
+        self.session_mut(session_id).notes.push(Note { message, text });
+    }
+
+    /// Puts a note back at `position` (1-based), or last if there are fewer notes now.
+    pub fn insert_note(&mut self, session_id: &str, position: usize, note: Note) {
+        let notes = &mut self.session_mut(session_id).notes;
+        let index = position.saturating_sub(1).min(notes.len());
+        notes.insert(index, note);
+    }
+
+    /// Removes the note at `position` (1-based, in listing order).
+    pub fn remove_note(&mut self, session_id: &str, position: usize) -> Option<Note> {
+        let notes = &mut self.session_mut(session_id).notes;
+        let removed = (position >= 1 && position <= notes.len()).then(|| notes.remove(position - 1));
+        self.prune();
+        removed
+    }
+
+    /// IDs of sessions with a note mentioning any of `terms`, case-insensitively.
+    pub fn sessions_with_notes_matching(&self, terms: &[&str]) -> Vec<String> {
+        let terms: Vec<String> = terms.iter().map(|term| term.to_lowercase()).collect();
+        self.sessions
+            .iter()
+            .filter(|(_, annotations)| {
+                annotations.notes.iter().any(|note| {
+                    let text = note.text.to_lowercase();
+                    terms.iter().any(|term| text.contains(term))
+                })
+            })
+            .map(|(session_id, _)| session_id.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,105 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::timeline::{extract_content_text, has_error};
+use crate::{Content, SessionMessage};
+
+/// Messages scoring at least this are classified as success responses
+pub const SUCCESS_THRESHOLD: f64 = 0.5;
+
+/// How many earlier messages are checked for the error a success may have resolved
+const FIX_WINDOW: usize = 6;
+
+/// Phrases that mostly mean something now works, with their weight
+const STRONG_PHRASES: &[&str] = &[
+    "that works", "it works", "works now", "working now", "works perfectly", "that fixed it",
+    "fixed it", "that did it", "that's it", "problem solved", "all tests pass", "tests pass now",
+    "lgtm", "looks good to me", "perfect", "excellent",
+];
+const STRONG_WEIGHT: f64 = 0.5;
+
+/// Words that often, but far from always, signal success
+const WEAK_PHRASES: &[&str] = &[
+    "works", "working", "great", "success", "successful", "solved", "fixed", "thanks", "thank you",
+    "nice", "good", "done", "completed",
+];
+const WEAK_WEIGHT: f64 = 0.2;
+
+/// Words that turn a following success phrase around, as in "still doesn't work"
+const NEGATIONS: &[&str] = &[
+    "not", "never", "still", "doesn't", "didn't", "isn't", "wasn't", "don't", "won't",
+    "can't", "cannot", "hasn't", "haven't", "nothing", "without",
+];
+
+/// How many words before a phrase a negation applies to
+const NEGATION_REACH: usize = 3;
+
+/// Beyond this many characters a success word is more likely incidental
+const LONG_MESSAGE_CHARS: usize = 400;
+
+fn phrase_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        let mut phrases: Vec<&str> = STRONG_PHRASES.iter().chain(WEAK_PHRASES).copied().collect();
+        // Longest first, so "that works" wins over "works"
+        phrases.sort_by_key(|phrase| std::cmp::Reverse(phrase.len()));
+        let alternation = phrases.iter().map(|phrase| regex::escape(phrase)).collect::<Vec<_>>().join("|");
+        Regex::new(&format!(r"\b(?:{})\b", alternation)).expect("valid regex")
+    })
+}
+
+/// How likely the message at `index` is to report that something worked, from 0 to 1.
+/// Confirmations from the user weigh more than the assistant's own claims, a success right
+/// after an error (the end of a fix) weighs more, and negated phrases count against it.
+pub fn success_confidence(messages: &[SessionMessage], index: usize, text: &str) -> f64 {
+    let lower = text.to_lowercase();
+    let mut score: f64 = 0.0;
+    for found in phrase_regex().find_iter(&lower) {
+        let weight = if STRONG_PHRASES.contains(&found.as_str()) { STRONG_WEIGHT } else { WEAK_WEIGHT };
+        if is_negated(&lower[..found.start()]) {
+            score -= weight;
+        } else {
+            score += weight;
+        }
+    }
+    if score <= 0.0 {
+        return 0.0;
+    }
+
+    let role = messages[index].message.as_ref().and_then(|m| m.role.as_deref());
+    score *= if role == Some("user") { 1.2 } else { 0.6 };
+    if follows_error(messages, index) {
+        score += 0.25;
+    }
+    if text.chars().count() > LONG_MESSAGE_CHARS {
+        score *= 0.7;
+    }
+    score.min(1.0)
+}
+
+/// Whether one of the few words before a phrase negates it.
+fn is_negated(before: &str) -> bool {
+    before
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':'))
+        .rev()
+        .filter(|word| !word.is_empty())
+        .take(NEGATION_REACH)
+        // A sentence boundary ends the negation's reach
+        .take_while(|word| !word.ends_with(['.', '!', '?']))
+        .any(|word| NEGATIONS.contains(&word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')))
+}
+
+/// Whether an error turned up in the messages just before `index`: an error in the text or
+/// a tool result flagged as one.
+fn follows_error(messages: &[SessionMessage], index: usize) -> bool {
+    messages[index.saturating_sub(FIX_WINDOW)..index].iter().any(|msg| {
+        let Some(content) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+            return false;
+        };
+        let flagged = match content {
+            Content::Array(blocks) => blocks.iter().any(|block| block.is_error == Some(true)),
+            Content::Text(_) => false,
+        };
+        flagged || has_error(&extract_content_text(content))
+    })
+}
@@ -0,0 +1,290 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use regex::Regex;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+use tracing::{debug, info};
+
+use crate::crypto::{encrypt, load_identity, ENCRYPTED_EXTENSION};
+use crate::decode_project_path;
+use crate::error::{Result, SessionFinderError};
+use crate::machines::is_own_dir;
+use crate::resolve::{claude_projects_dir, is_session_file, team_dir};
+
+/// Remotes with this prefix are synced through the AWS CLI rather than copied directly
+const S3_PREFIX: &str = "s3://";
+
+/// What `push` does to each session file before sharing it
+pub struct PushOptions {
+    pub redact: bool,
+    pub compress: bool,
+    /// Encrypt with the team's age key, so the remote never holds plaintext
+    pub encrypt: bool,
+}
+
+/// Copies this user's Claude Code sessions to `<remote>/<owner>/<project>/`, where the owner
+/// directory is the user's name, with the machine's after an `@` when one is given, skipping
+/// files the remote already has an up-to-date copy of.
+pub fn sync_push(remote: &str, owner: &str, project_filter: Option<&str>, options: &PushOptions) -> Result<()> {
+    let projects_dir = claude_projects_dir()?;
+    if !projects_dir.exists() {
+        return Err(SessionFinderError::RootNotFound { path: projects_dir });
+    }
+    let target = local_remote_dir(remote)?.join(owner);
+    let redactor = Redactor::new();
+    let identity = if options.encrypt { Some(load_identity()?) } else { None };
+    let mut pushed = 0;
+    let mut unchanged = 0;
+
+    for entry in walkdir::WalkDir::new(&projects_dir) {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension() != Some(OsStr::new("jsonl")) {
+            continue;
+        }
+        if let Some(filter) = project_filter {
+            if !decode_project_path(path).is_ok_and(|project| project.contains(filter)) {
+                continue;
+            }
+        }
+
+        let Ok(relative) = path.strip_prefix(&projects_dir) else {
+            continue;
+        };
+        let mut file_name = relative.display().to_string();
+        if options.compress {
+            file_name.push_str(".gz");
+        }
+        if options.encrypt {
+            file_name.push('.');
+            file_name.push_str(ENCRYPTED_EXTENSION);
+        }
+        let destination = target.join(file_name);
+        if is_up_to_date(path, &destination) {
+            unchanged += 1;
+            continue;
+        }
+
+        let mut content = fs::read_to_string(path)?;
+        if options.redact {
+            content = redactor.redact(&content);
+        }
+        let mut bytes = content.into_bytes();
+        if options.compress {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&bytes)?;
+            bytes = encoder.finish()?;
+        }
+        if let Some(identity) = &identity {
+            bytes = encrypt(identity, &bytes)?;
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&destination, bytes)?;
+        debug!(file = %destination.display(), "Pushed session");
+        pushed += 1;
+    }
+
+    if remote.starts_with(S3_PREFIX) {
+        aws_s3_sync(&local_remote_dir(remote)?.join(owner), &format!("{}/{}", remote.trim_end_matches('/'), owner))?;
+    }
+    eprintln!("Pushed {} session(s) to {} ({} already up to date)", pushed, remote, unchanged);
+    Ok(())
+}
+
+/// Copies other users' sessions from the remote into the team directory, which is searched
+/// alongside local sessions. Each user's sessions keep their own subdirectory. With a machine
+/// name, the user's own sessions from their other machines are copied too. Encrypted files
+/// are copied as they are and only decrypted in memory when read, so plaintext never reaches
+/// the disk; compressed ones are restored to plain JSONL.
+pub fn sync_pull(remote: &str, user: &str, machine: Option<&str>) -> Result<()> {
+    let source = local_remote_dir(remote)?;
+    if remote.starts_with(S3_PREFIX) {
+        aws_s3_sync_down(remote, &source)?;
+    }
+    if !source.is_dir() {
+        return Err(SessionFinderError::Usage(format!("Remote not found: {}", remote)));
+    }
+    let team = team_dir()?;
+    let mut pulled = 0;
+
+    for entry in walkdir::WalkDir::new(&source) {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file() || !is_session_file(path) {
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(&source) else {
+            continue;
+        };
+        // Our own sessions are already searched where Claude Code wrote them
+        if relative.components().next().is_some_and(|owner| is_own_dir(&owner.as_os_str().to_string_lossy(), user, machine)) {
+            continue;
+        }
+
+        let mut name = relative.to_string_lossy().into_owned();
+        let encrypted = name.ends_with(&format!(".{}", ENCRYPTED_EXTENSION));
+        let compressed = !encrypted && strip_suffix_in_place(&mut name, ".gz");
+        let destination = team.join(name);
+        if is_up_to_date(path, &destination) {
+            continue;
+        }
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if compressed {
+            let mut content = String::new();
+            GzDecoder::new(fs::File::open(path)?).read_to_string(&mut content)?;
+            fs::write(&destination, content)?;
+        } else {
+            fs::copy(path, &destination)?;
+        }
+        debug!(file = %destination.display(), "Pulled session");
+        pulled += 1;
+    }
+
+    info!(dir = %team.display(), "Team sessions updated");
+    eprintln!("Pulled {} new or updated session(s) from {}", pulled, remote);
+    Ok(())
+}
+
+fn strip_suffix_in_place(name: &mut String, suffix: &str) -> bool {
+    let stripped = name.ends_with(suffix);
+    if stripped {
+        name.truncate(name.len() - suffix.len());
+    }
+    stripped
+}
+
+/// The directory that mirrors the remote: the remote itself for paths, a local staging copy for S3.
+fn local_remote_dir(remote: &str) -> Result<PathBuf> {
+    match remote.strip_prefix(S3_PREFIX) {
+        Some(bucket_path) => {
+            let name: String = bucket_path
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            Ok(team_dir()?.with_file_name("s3-staging").join(name))
+        }
+        None => Ok(PathBuf::from(remote)),
+    }
+}
+
+/// The copy is current when it was written after the source was last modified.
+fn is_up_to_date(source: &Path, copy: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (modified(source), modified(copy)) {
+        (Some(source), Some(copy)) => copy >= source,
+        _ => false,
+    }
+}
+
+fn aws_s3_sync(local: &Path, remote: &str) -> Result<()> {
+    run_aws(&["s3", "sync", &local.to_string_lossy(), remote])
+}
+
+fn aws_s3_sync_down(remote: &str, local: &Path) -> Result<()> {
+    fs::create_dir_all(local)?;
+    run_aws(&["s3", "sync", remote, &local.to_string_lossy()])
+}
+
+fn run_aws(args: &[&str]) -> Result<()> {
+    debug!("Running aws {}", args.join(" "));
+    let started = SystemTime::now();
+    let status = process::Command::new("aws")
+        .args(args)
+        .status()
+        .map_err(|e| SessionFinderError::backend("aws", format!("aws failed: {}. S3 remotes need the AWS CLI in your PATH", e)))?;
+    debug!(elapsed_ms = started.elapsed().map(|e| e.as_millis() as u64).unwrap_or_default(), "aws finished");
+    if !status.success() {
+        return Err(SessionFinderError::backend("aws", format!(
+            "aws {} failed with status: {}",
+            args.join(" "),
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Replaces credentials that commonly end up in transcripts (API keys, tokens, private keys,
+/// `password=...` assignments) with `[REDACTED]`. Matches never span a JSON quote or escape,
+/// so redacted lines stay valid JSON.
+struct Redactor {
+    /// Each pattern with its replacement; assignments keep their key
+    patterns: Vec<(Regex, &'static str)>,
+}
+
+impl Redactor {
+    fn new() -> Self {
+        let patterns = [
+            (r"sk-[A-Za-z0-9_-]{20,}", "[REDACTED]"),
+            (r"AKIA[0-9A-Z]{16}", "[REDACTED]"),
+            (r"gh[pousr]_[A-Za-z0-9]{36,}", "[REDACTED]"),
+            (r"github_pat_[A-Za-z0-9_]{22,}", "[REDACTED]"),
+            (r"xox[abprs]-[A-Za-z0-9-]{10,}", "[REDACTED]"),
+            (r"(?i)(bearer )[A-Za-z0-9._~+/-]{20,}=*", "${1}[REDACTED]"),
+            (r"-----BEGIN [A-Z ]*PRIVATE KEY-----[^\x22]*?-----END [A-Z ]*PRIVATE KEY-----", "[REDACTED]"),
+            (
+                r"(?i)((?:password|passwd|secret|api_key|apikey|access_token|auth_token)\s*[=:]\s*)[^\s\x22\\,}]+",
+                "${1}[REDACTED]",
+            ),
+        ];
+        Redactor {
+            patterns: patterns
+                .iter()
+                .map(|(pattern, replacement)| (Regex::new(pattern).expect("valid redaction pattern"), *replacement))
+                .collect(),
+        }
+    }
+
+    fn redact(&self, content: &str) -> String {
+        let mut redacted = content.to_string();
+        for (pattern, replacement) in &self.patterns {
+            redacted = pattern.replace_all(&redacted, *replacement).into_owned();
+        }
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{default_key_file, generate_key_file};
+    use crate::resolve::read_session_text;
+    use crate::test_support::{fake_home, write_session};
+    use std::time::Duration;
+
+    #[test]
+    fn encrypted_sessions_are_pulled_as_they_are_and_read_back() {
+        let (_home, home) = fake_home();
+        let messages = ["ship the flux capacitor, password=hunter2", "done"];
+        let session = write_session(home, "-work-sync", "synced", &messages, Duration::ZERO);
+        generate_key_file().unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(default_key_file().unwrap()).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        assert!(generate_key_file().is_err(), "an existing key is never replaced");
+
+        let remote = tempfile::tempdir().unwrap();
+        let remote_path = remote.path().to_str().unwrap();
+        let options = PushOptions { redact: true, compress: true, encrypt: true };
+        sync_push(remote_path, "alice", Some("work/sync"), &options).unwrap();
+        let pushed = remote.path().join("alice").join("-work-sync").join("synced.jsonl.gz.age");
+        assert!(!String::from_utf8_lossy(&fs::read(&pushed).unwrap()).contains("flux capacitor"));
+
+        sync_pull(remote_path, "bob", None).unwrap();
+        let pulled = team_dir().unwrap().join("alice").join("-work-sync").join("synced.jsonl.gz.age");
+        assert_eq!(fs::read(&pulled).unwrap(), fs::read(&pushed).unwrap(), "kept encrypted on disk");
+        let redacted = fs::read_to_string(&session).unwrap().replace("hunter2", "[REDACTED]");
+        assert_eq!(read_session_text(&pulled).unwrap(), redacted);
+    }
+}
@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::error::Result;
+use crate::extract_session_id;
+use crate::is_common_term_word;
+use crate::resolve::read_session_text;
+use crate::stats::{delimited_field, selected_session_files, StatsFormat, StatsGrouping};
+use crate::text::words;
+use crate::time_range::carried_message_times;
+use crate::timeline::{extract_content_text, parse_session_messages};
+
+/// Which words `stats --terms` counts, and which it reports
+#[derive(Debug, Clone)]
+pub struct TermOptions {
+    /// Skip boilerplate, stopwords, and words under three letters, as common terms do
+    pub stopped: bool,
+    /// Stopwords from `.session-finder.toml`, lowercase
+    pub stopwords: HashSet<String>,
+    /// Leave out words counted fewer times than this in a row
+    pub min_count: usize,
+}
+
+/// Word frequencies for one session, day, or project
+#[derive(Debug, Serialize)]
+struct TermRow {
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    /// Every word counted, including those below `min_count`
+    words: usize,
+    terms: BTreeMap<String, usize>,
+}
+
+/// Prints the full frequency table of words in message text for every session matching the
+/// project and recency filters, per session, day, or project, for analysis elsewhere: JSON
+/// with one object per row, CSV or TSV with one line per row and word, or the top words of
+/// each row as text.
+pub fn run_term_stats(
+    project_filter: Option<&String>,
+    recent_days: Option<i64>,
+    grouping: StatsGrouping,
+    options: TermOptions,
+    format: Option<&str>,
+) -> Result<()> {
+    let format = match format {
+        Some("json") => None,
+        other => Some(StatsFormat::from_name(other.unwrap_or("text"))?),
+    };
+    let mut rows: BTreeMap<(String, String), TermRow> = BTreeMap::new();
+    for (path, project) in selected_session_files(project_filter, recent_days)? {
+        let session_id = extract_session_id(&path)?;
+        let messages = parse_session_messages(&read_session_text(&path)?)?;
+        let times = carried_message_times(&messages);
+        for (msg, time) in messages.iter().zip(times) {
+            let Some(content) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+                continue;
+            };
+            let date = time.map(|time| time.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "unknown".to_string());
+            let key = grouping.key(&session_id, &date, &project);
+            let row = rows.entry((key.clone(), project.clone())).or_insert_with(|| TermRow {
+                key,
+                project: grouping.shows_project().then(|| project.clone()),
+                words: 0,
+                terms: BTreeMap::new(),
+            });
+            for word in words(&extract_content_text(content)) {
+                if options.stopped && !is_common_term_word(&word, &options.stopwords) {
+                    continue;
+                }
+                row.words += 1;
+                *row.terms.entry(word).or_default() += 1;
+            }
+        }
+    }
+    let mut rows: Vec<TermRow> = rows.into_values().collect();
+    for row in &mut rows {
+        row.terms.retain(|_, count| *count >= options.min_count);
+    }
+
+    match format {
+        None => println!("{}", serde_json::to_string_pretty(&rows)?),
+        Some(StatsFormat::Text) => print_text(&rows),
+        Some(StatsFormat::Csv) => print_delimited(grouping, &rows, ','),
+        Some(StatsFormat::Tsv) => print_delimited(grouping, &rows, '\t'),
+    }
+    Ok(())
+}
+
+/// Words shown per row in the text report; the other formats have them all
+const TEXT_TERMS: usize = 15;
+
+/// A row's words, most frequent first, ties alphabetically.
+fn by_count(row: &TermRow) -> Vec<(&String, usize)> {
+    let mut terms: Vec<(&String, usize)> = row.terms.iter().map(|(term, count)| (term, *count)).collect();
+    terms.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    terms
+}
+
+fn print_text(rows: &[TermRow]) {
+    if rows.is_empty() {
+        println!("No sessions found");
+        return;
+    }
+    for row in rows {
+        let project = row.project.as_ref().map(|project| format!(" ({})", project)).unwrap_or_default();
+        println!("{}{}: {} words, {} distinct", row.key, project, row.words, row.terms.len());
+        let top: Vec<String> = by_count(row)
+            .into_iter()
+            .take(TEXT_TERMS)
+            .map(|(term, count)| format!("{}({})", term, count))
+            .collect();
+        println!("  {}\n", top.join(", "));
+    }
+}
+
+fn print_delimited(grouping: StatsGrouping, rows: &[TermRow], delimiter: char) {
+    let mut header = vec![grouping.key_header()];
+    if grouping.shows_project() {
+        header.push("project");
+    }
+    header.extend(["term", "count"]);
+    println!("{}", header.join(&delimiter.to_string()));
+
+    for row in rows {
+        for (term, count) in by_count(row) {
+            let mut fields = vec![delimited_field(&row.key, delimiter)];
+            if let Some(project) = &row.project {
+                fields.push(delimited_field(project, delimiter));
+            }
+            fields.extend([delimited_field(term, delimiter), count.to_string()]);
+            println!("{}", fields.join(&delimiter.to_string()));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Session files in a scratch home directory, for tests that search, resolve or store
+//! things under `~/.claude`
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, SystemTime};
+
+static HOME: OnceLock<tempfile::TempDir> = OnceLock::new();
+static IN_USE: Mutex<()> = Mutex::new(());
+
+/// Points `HOME` at a scratch directory shared by every test in the process, and holds it
+/// until the guard is dropped, so tests see only their own files. Each test should keep to
+/// its own project folders.
+pub fn fake_home() -> (MutexGuard<'static, ()>, &'static Path) {
+    let guard = IN_USE.lock().unwrap_or_else(|e| e.into_inner());
+    let home = HOME.get_or_init(|| {
+        let home = tempfile::tempdir().expect("scratch home");
+        std::env::set_var("HOME", home.path());
+        home
+    });
+    (guard, home.path())
+}
+
+/// Writes a session to `project` (an encoded project folder name such as `-work-app`), the
+/// messages alternating between user and assistant, last modified `age` ago.
+pub fn write_session(home: &Path, project: &str, session_id: &str, messages: &[&str], age: Duration) -> PathBuf {
+    let folder = home.join(".claude").join("projects").join(project);
+    fs::create_dir_all(&folder).expect("project folder");
+    let mut lines = Vec::new();
+    for (index, text) in messages.iter().enumerate() {
+        let (role, content) = if index % 2 == 0 {
+            ("user", serde_json::json!(text))
+        } else {
+            ("assistant", serde_json::json!([{ "type": "text", "text": text }]))
+        };
+        lines.push(serde_json::json!({
+            "type": role,
+            "uuid": format!("{}-{}", session_id, index),
+            "parentUuid": index.checked_sub(1).map(|previous| format!("{}-{}", session_id, previous)),
+            "sessionId": session_id,
+            "timestamp": format!("2026-09-01T10:{:02}:00.000Z", index),
+            "message": { "role": role, "content": content },
+        }).to_string());
+    }
+    let path = folder.join(format!("{}.jsonl", session_id));
+    fs::write(&path, lines.join("\n") + "\n").expect("session file");
+    let modified = SystemTime::now() - age;
+    fs::File::options().write(true).open(&path).and_then(|file| file.set_modified(modified)).expect("mtime");
+    path
+}
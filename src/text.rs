@@ -0,0 +1,144 @@
+/// Characters that combine with the one before them and take no column of their own
+const ZERO_WIDTH: &[(u32, u32)] = &[
+    (0x0300, 0x036F), // combining diacritical marks
+    (0x0483, 0x0489),
+    (0x0591, 0x05BD),
+    (0x0610, 0x061A),
+    (0x064B, 0x065F),
+    (0x0E31, 0x0E31),
+    (0x0E34, 0x0E3A),
+    (0x0E47, 0x0E4E),
+    (0x1AB0, 0x1AFF),
+    (0x1DC0, 0x1DFF),
+    (0x200B, 0x200F), // zero-width space and joiners
+    (0x20D0, 0x20FF),
+    (0x3099, 0x309A), // kana voicing marks
+    (0xFE00, 0xFE0F), // variation selectors
+    (0xFE20, 0xFE2F),
+    (0x1F3FB, 0x1F3FF), // skin tone modifiers
+    (0xE0100, 0xE01EF),
+];
+
+/// East Asian wide and fullwidth characters and emoji, which take two columns
+const DOUBLE_WIDTH: &[(u32, u32)] = &[
+    (0x1100, 0x115F), // Hangul jamo
+    (0x231A, 0x231B),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F5),
+    (0x26FA, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B55),
+    (0x2E80, 0x303E), // CJK radicals, punctuation
+    (0x3041, 0x3098), // Hiragana
+    (0x309B, 0x33FF), // Katakana, CJK compatibility
+    (0x3400, 0x4DBF), // CJK extension A
+    (0x4E00, 0x9FFF), // CJK unified ideographs
+    (0xA000, 0xA4CF), // Yi
+    (0xA960, 0xA97F),
+    (0xAC00, 0xD7A3), // Hangul syllables
+    (0xF900, 0xFAFF), // CJK compatibility ideographs
+    (0xFE10, 0xFE19),
+    (0xFE30, 0xFE6F),
+    (0xFF00, 0xFF60), // fullwidth forms
+    (0xFFE0, 0xFFE6),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F200, 0x1F251),
+    (0x1F300, 0x1F3FA), // pictographs and emoji
+    (0x1F400, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F7E0, 0x1F7EB),
+    (0x1F900, 0x1F9FF),
+    (0x1FA70, 0x1FAFF),
+    (0x20000, 0x2FFFD), // CJK extensions B and later
+    (0x30000, 0x3FFFD),
+];
+
+fn in_ranges(c: char, ranges: &[(u32, u32)]) -> bool {
+    let code = c as u32;
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < code {
+                std::cmp::Ordering::Less
+            } else if start > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Columns a character takes in a terminal: none for combining marks and control characters,
+/// two for wide CJK characters and emoji, one otherwise.
+pub fn char_width(c: char) -> usize {
+    if c.is_control() || in_ranges(c, ZERO_WIDTH) {
+        0
+    } else if in_ranges(c, DOUBLE_WIDTH) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Columns `text` takes in a terminal.
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// The longest prefix of `text` that fits in `width` columns. Combining marks stay with the
+/// character before them, so the cut never separates them.
+pub fn take_width(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (index, c) in text.char_indices() {
+        let columns = char_width(c);
+        if columns > 0 && used + columns > width {
+            return &text[..index];
+        }
+        used += columns;
+    }
+    text
+}
+
+/// Lowercase words of `text` with surrounding punctuation trimmed, as common terms count them.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|word| !word.is_empty())
+}
+
+/// Cuts `text` to at most `max_width` columns, ending with `...` when anything was cut.
+pub fn truncate_text(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    format!("{}...", take_width(text, max_width.saturating_sub(3)))
+}
+
+/// Pads `text` with spaces to `width` columns, for aligning table columns that may hold wide
+/// characters. Text already that wide is returned as is.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{}{}", text, " ".repeat(padding))
+}
@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, SessionFinderError};
+
+/// `--budget`: how long a search may take. Past half of it, the scan stops and sessions are
+/// analysed without counting word frequencies; once it is used up, shortlisted sessions not
+/// yet analysed are skipped, the oldest being queued last.
+#[derive(Debug, Clone)]
+pub struct TimeBudget {
+    limit: Duration,
+    started: Instant,
+    /// What the budget cost, for the report at the end; shared by every clone
+    costs: Arc<BudgetCosts>,
+}
+
+#[derive(Debug, Default)]
+struct BudgetCosts {
+    scan_stopped: AtomicBool,
+    without_common_terms: AtomicUsize,
+    skipped_sessions: AtomicUsize,
+}
+
+impl TimeBudget {
+    /// Accepts a number with an optional `ms`, `s`, or `m` suffix (seconds by default), e.g. `5s`.
+    pub fn parse(value: &str) -> Result<Duration> {
+        let trimmed = value.trim();
+        let digits = trimmed.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let millis_per_unit = match trimmed[digits.len()..].to_ascii_lowercase().as_str() {
+            "ms" => 1,
+            "" | "s" => 1_000,
+            "m" => 60_000,
+            _ => return Err(SessionFinderError::Usage(format!(
+                "Invalid time budget {:?}: use a number with ms, s, or m, e.g. 5s",
+                value
+            ))),
+        };
+        digits
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .and_then(|count| count.checked_mul(millis_per_unit))
+            .filter(|millis| *millis > 0)
+            .map(Duration::from_millis)
+            .ok_or_else(|| SessionFinderError::Usage(format!(
+                "Invalid time budget {:?}: use a number with ms, s, or m, e.g. 5s",
+                value
+            )))
+    }
+
+    /// A budget of `limit`, starting now.
+    pub fn starting_now(limit: Duration) -> Self {
+        TimeBudget { limit, started: Instant::now(), costs: Arc::default() }
+    }
+
+    pub fn limit(&self) -> Duration {
+        self.limit
+    }
+
+    fn used(&self, fraction: f64) -> bool {
+        self.started.elapsed() >= self.limit.mul_f64(fraction)
+    }
+
+    /// Whether the scan should stop, half the budget being gone to leave the rest for
+    /// analysis; records that it did.
+    pub fn stops_scan(&self) -> bool {
+        let stop = self.used(0.5);
+        if stop {
+            self.costs.scan_stopped.store(true, Ordering::Relaxed);
+        }
+        stop
+    }
+
+    /// Whether a shortlisted session should be left unanalysed, the budget being used up;
+    /// records that it was.
+    pub fn skips_session(&self) -> bool {
+        let skip = self.used(1.0);
+        if skip {
+            self.costs.skipped_sessions.fetch_add(1, Ordering::Relaxed);
+        }
+        skip
+    }
+
+    /// Whether a session should be analysed without counting word frequencies, the costliest
+    /// part, half the budget being gone; records that it was.
+    pub fn skips_common_terms(&self) -> bool {
+        let skip = self.used(0.5);
+        if skip {
+            self.costs.without_common_terms.fetch_add(1, Ordering::Relaxed);
+        }
+        skip
+    }
+
+    /// Tells on stderr what was left out to stay within the budget, if anything was.
+    pub fn report(&self) {
+        let mut skipped = Vec::new();
+        if self.costs.scan_stopped.load(Ordering::Relaxed) {
+            skipped.push("stopped scanning before every session file was searched".to_string());
+        }
+        let skipped_sessions = self.costs.skipped_sessions.load(Ordering::Relaxed);
+        if skipped_sessions > 0 {
+            skipped.push(format!("left out {} older shortlisted session(s) without analysing them", skipped_sessions));
+        }
+        let without_terms = self.costs.without_common_terms.load(Ordering::Relaxed);
+        if without_terms > 0 {
+            skipped.push(format!("skipped common terms for {} session(s)", without_terms));
+        }
+        if !skipped.is_empty() {
+            eprintln!("To stay within the {:?} budget, the search {}.", self.limit, skipped.join("; "));
+        }
+    }
+}
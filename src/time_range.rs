@@ -0,0 +1,110 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use crate::error::{Result, SessionFinderError};
+use crate::glyphs::Glyph;
+use crate::render::RenderOptions;
+use crate::timeline::message_time;
+use crate::SessionMessage;
+
+/// The part of a session `--after` and `--before` keep
+#[derive(Default)]
+pub struct TimeRange {
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+}
+
+impl TimeRange {
+    pub fn parse(after: Option<&str>, before: Option<&str>) -> Result<Self> {
+        Ok(TimeRange {
+            after: after.map(|value| parse_time_bound("--after", value)).transpose()?,
+            before: before.map(|value| parse_time_bound("--before", value)).transpose()?,
+        })
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.after.is_none() && self.before.is_none()
+    }
+
+    /// Whether a message sent at `time` falls in the range. With a bound set, messages whose
+    /// time is unknown are left out.
+    pub fn contains(&self, time: Option<DateTime<Utc>>) -> bool {
+        if self.is_unbounded() {
+            return true;
+        }
+        let Some(time) = time else {
+            return false;
+        };
+        self.after.is_none_or(|after| time >= after) && self.before.is_none_or(|before| time < before)
+    }
+}
+
+/// Accepts RFC 3339 (`2026-10-01T10:00:00Z`), `2026-10-01 10:00[:00]`, or a bare date, which
+/// means midnight. Times without an offset are UTC, like the timestamps timelines print.
+pub fn parse_time_bound(flag: &str, value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(time.and_utc());
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc());
+    }
+    Err(SessionFinderError::Usage(format!(
+        "Invalid {} time {:?}: use YYYY-MM-DD, YYYY-MM-DD HH:MM[:SS], or RFC 3339",
+        flag,
+        value
+    )))
+}
+
+/// When each message was sent. Messages without a timestamp take the time of the message
+/// before them, so they keep their place when entries are ordered by time.
+pub fn carried_message_times(messages: &[SessionMessage]) -> Vec<Option<DateTime<Utc>>> {
+    let mut last = None;
+    messages
+        .iter()
+        .map(|msg| {
+            last = message_time(msg).or(last);
+            last
+        })
+        .collect()
+}
+
+/// Pauses at least this long are called out in timelines
+const LONG_GAP_SECS: i64 = 10 * 60;
+
+/// Time from one message to another, as `+14s`, `+2m14s`, `+1h05m`, or `+2d03h`, or with a
+/// minus sign when `to` came first.
+pub fn format_delta(from: DateTime<Utc>, to: DateTime<Utc>) -> String {
+    let secs = (to - from).num_seconds();
+    let sign = if secs < 0 { '-' } else { '+' };
+    let secs = secs.abs();
+    if secs < 60 {
+        format!("{}{}s", sign, secs)
+    } else if secs < 3600 {
+        format!("{}{}m{:02}s", sign, secs / 60, secs % 60)
+    } else if secs < 86400 {
+        format!("{}{}h{:02}m", sign, secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}{}d{:02}h", sign, secs / 86400, secs % 86400 / 3600)
+    }
+}
+
+/// `⏳ 42 minutes later` when a long pause separates two consecutive messages.
+pub fn gap_line(previous: Option<DateTime<Utc>>, current: Option<DateTime<Utc>>, render: RenderOptions) -> Option<String> {
+    let secs = (current? - previous?).num_seconds();
+    if secs < LONG_GAP_SECS {
+        return None;
+    }
+    let (count, unit) = if secs < 2 * 3600 {
+        (secs / 60, "minute")
+    } else if secs < 2 * 86400 {
+        (secs / 3600, "hour")
+    } else {
+        (secs / 86400, "day")
+    };
+    Some(format!("{} {} {}{} later", render.glyph(Glyph::Later), count, unit, if count == 1 { "" } else { "s" }))
+}
@@ -1,31 +1,83 @@
-use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use regex::Regex;
-use serde_json;
-use std::fs;
-use std::path::{Path, PathBuf};
-use walkdir;
+use std::borrow::Cow;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{debug, info};
 
+use crate::code_analysis::{analyze_code_block, analyzer_for_path};
+use crate::command_status::{bash_outcomes, message_command_outcome, CommandOutcome};
+use crate::error::Result;
+use crate::error_rules::error_rules;
+use crate::fence::first_code_block;
+use crate::glyphs::Glyph;
+use crate::highlight::{file_language, highlight_code, highlight_fenced, highlight_tool_content};
+use crate::interrupt::interrupted_turns;
+use crate::matcher::{query_matcher, SearchOptions, TermMatcher};
+use crate::render::RenderOptions;
+use crate::resolve::read_session_source;
+use crate::schema::{parse_session_lines, ParseDiagnostics};
+use crate::scope::MatchScope;
+use crate::store::Store;
+use crate::success::{success_confidence, SUCCESS_THRESHOLD};
+use crate::text::truncate_text;
+use crate::time_range::{carried_message_times, format_delta, gap_line, TimeRange};
+use crate::tools::{is_read_tool, matching_tool_calls, parse_mcp_tool, ToolFilter};
 use crate::{
-    ClassifiedContent, CodeInfo, ContentType, ErrorInfo, SessionMessage, TimelineEntry,
-    TimelineExtraction, ToolInfo, Content,
+    ClassifiedContent, SuccessInfo, ContextMessage, MatchSummary, CodeInfo, ContentType, ErrorInfo, MatchScore, SessionMessage,
+    TimelineEntry, TimelineExtraction, ToolInfo, Content,
 };
 
+/// Characters of surrounding text shown on each side of a match in timeline excerpts
+const EXCERPT_RADIUS: usize = 80;
+
+/// Matched terms closer together than this many bytes earn a proximity bonus
+const PROXIMITY_WINDOW: usize = 200;
+
+/// Longest excerpt printed on a quickfix line
+const QUICKFIX_EXCERPT_LEN: usize = 160;
+
+/// Maximum number of excerpt windows shown for a single timeline entry
+const MAX_EXCERPT_WINDOWS: usize = 5;
+
+/// How matched terms are marked in excerpts
+#[derive(Debug, Clone, Copy)]
+enum HighlightStyle {
+    /// Bold yellow, for terminals
+    Ansi,
+    /// `**term**`, for pipes and files
+    Markers,
+    /// No marking, for copying text elsewhere
+    Plain,
+}
+
 #[derive(Debug)]
 pub struct CodeDiffTimeline {
     pub session_id: String,
+    pub session_path: PathBuf,
     pub code_changes: Vec<CodeDiffEntry>,
+    pub parse_diagnostics: ParseDiagnostics,
 }
 
 #[derive(Debug)]
 pub struct CodeDiffEntry {
     pub message_index: usize,
-    pub timestamp: String,
+    pub line_number: usize,
+    pub timestamp: Option<String>,
     pub role: String,
     pub code_content: String,
     pub language: Option<String>,
     pub change_type: CodeChangeType,
-    pub context_before: Vec<String>,
-    pub context_after: Vec<String>,
+    /// False for code blocks that look cut off or partial; tool changes are always complete
+    pub complete: bool,
+    /// Part of a turn the user interrupted or whose tool call they rejected
+    pub interrupted: bool,
+    /// How a Bash command ended
+    pub command_outcome: Option<CommandOutcome>,
+    pub context_before: Vec<ContextMessage>,
+    pub context_after: Vec<ContextMessage>,
 }
 
 #[derive(Debug)]
@@ -36,151 +88,417 @@ pub enum CodeChangeType {
     BashCommand, // Executable commands
 }
 
-pub fn extract_timeline(
-    session_path: &str,
-    search_terms: &[&str],
-    context_size: usize,
-) -> Result<TimelineExtraction> {
-    let full_path = resolve_session_path(session_path)?;
-    let session_id = extract_session_id_from_path(&full_path)?;
-    let content = fs::read_to_string(&full_path)?;
+/// What `extract_timeline` looks for in a session
+pub struct TimelineSearch<'a> {
+    pub search_terms: &'a [&'a str],
+    /// Messages shown before and after each match
+    pub context_size: usize,
+    pub top_entries: Option<usize>,
+    pub tool_filters: &'a [ToolFilter],
+    pub exclude_interrupted: bool,
+    pub time_range: &'a TimeRange,
+    pub options: &'a SearchOptions,
+}
+
+pub fn extract_timeline(session_path: &str, search: &TimelineSearch) -> Result<TimelineExtraction> {
+    let started = Instant::now();
+    let (full_path, session_id, content) = read_session_source(session_path)?;
+    debug!(reference = session_path, file = %full_path.display(), "Resolved session");
+    Ok(timeline_in(full_path, session_id, &content, search, started))
+}
+
+/// `extract_timeline` on a tokio runtime, reading the session through `tokio::fs`. Sessions
+/// can't be read from stdin this way.
+#[cfg(feature = "tokio")]
+pub async fn extract_timeline_async(session_path: &str, search: &TimelineSearch<'_>) -> Result<TimelineExtraction> {
+    let started = Instant::now();
+    let (full_path, session_id, content) = crate::resolve::read_session_source_async(session_path).await?;
+    debug!(reference = session_path, file = %full_path.display(), "Resolved session");
+    Ok(timeline_in(full_path, session_id, &content, search, started))
+}
+
+/// The timeline of a session already read, timed from `started`.
+fn timeline_in(
+    full_path: PathBuf,
+    session_id: String,
+    content: &str,
+    search: &TimelineSearch,
+    started: Instant,
+) -> TimelineExtraction {
+    let TimelineSearch { search_terms, context_size, top_entries, tool_filters, exclude_interrupted, time_range, options } =
+        *search;
+    let (all_messages, parse_diagnostics) = parse_session_messages_with_diagnostics(content);
+    let interrupted = interrupted_turns(&all_messages);
+    let command_outcomes = bash_outcomes(&all_messages);
+    let mut matching_indices = if tool_filters.is_empty() {
+        find_matching_messages(&all_messages, search_terms, options)
+    } else {
+        find_tool_calls(&all_messages, search_terms, tool_filters)
+    };
+    if exclude_interrupted {
+        matching_indices.retain(|&index| !interrupted[index]);
+    }
+    let times = carried_message_times(&all_messages);
+    matching_indices.retain(|&index| time_range.contains(times[index]));
+    info!(
+        messages = all_messages.len(),
+        matches = matching_indices.len(),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "Parsed and matched session"
+    );
     
-    let all_messages = parse_session_messages(&content)?;
-    let matching_indices = find_matching_messages(&all_messages, search_terms);
+    let total_matches = matching_indices.len();
     
-    let timeline: Vec<TimelineEntry> = matching_indices
+    let mut timeline: Vec<TimelineEntry> = matching_indices
         .into_iter()
         .map(|index| {
             let msg = &all_messages[index];
             let context_before = extract_context_messages(&all_messages, index, context_size, true);
             let context_after = extract_context_messages(&all_messages, index, context_size, false);
+            let mut classified_content = classify_message_content(&all_messages, index);
+            // Tool calls carry no text of their own, so show the selected calls themselves
+            for call in matching_tool_calls(msg, tool_filters) {
+                let call_text = format_tool_content(call.name.as_deref().unwrap_or_default(), &call.input, options.render);
+                let raw_content = &mut classified_content.raw_content;
+                if !raw_content.is_empty() {
+                    raw_content.push('\n');
+                }
+                raw_content.push_str(&call_text);
+            }
+            let match_score = match msg.message.as_ref().and_then(|m| m.content.as_ref()) {
+                Some(content) if options.scope != MatchScope::All => {
+                    // Only the scope's text is scored; a bare tool call shows the code it wrote
+                    let scoped_text = options.match_text(content);
+                    let match_score = score_match(&scoped_text, search_terms);
+                    if classified_content.raw_content.is_empty() {
+                        classified_content.raw_content = scoped_text.into_owned();
+                    }
+                    match_score
+                }
+                _ => score_match(&classified_content.raw_content, search_terms),
+            };
             
             TimelineEntry {
                 message_index: index,
-                timestamp: msg.timestamp.clone().unwrap_or_default(),
+                line_number: msg.line_number,
+                timestamp: msg.timestamp.clone(),
                 role: msg.message.as_ref()
                     .and_then(|m| m.role.clone())
                     .unwrap_or_default(),
-                classified_content: classify_message_content(msg),
+                classified_content,
+                match_score,
+                interrupted: interrupted[index],
+                command_outcome: message_command_outcome(msg, &command_outcomes),
                 context_before,
                 context_after,
             }
         })
         .collect();
+    
+    // With --top, keep the strongest matches and present them best-first; otherwise order by
+    // when messages were sent, since merged sidechains can leave the file out of order
+    if let Some(top) = top_entries {
+        timeline.sort_by(|a, b| {
+            b.match_score.score
+                .total_cmp(&a.match_score.score)
+                .then_with(|| a.message_index.cmp(&b.message_index))
+        });
+        timeline.truncate(top);
+    } else {
+        timeline.sort_by_key(|entry| (times[entry.message_index], entry.message_index));
+    }
 
-    Ok(TimelineExtraction {
+    TimelineExtraction {
         session_id,
+        session_path: full_path,
         query_term: search_terms.join(" "),
+        search_terms: search_terms.iter().map(|term| term.to_string()).collect(),
+        total_matches,
         timeline,
-    })
+        parse_diagnostics,
+        message_times: all_messages.iter().map(message_time).collect(),
+        ranked: top_entries.is_some(),
+    }
 }
 
-fn resolve_session_path(session_path: &str) -> Result<PathBuf> {
-    let path = Path::new(session_path);
-    
-    // If it's already a full path, use it
-    if path.is_absolute() && path.exists() {
-        return Ok(path.to_path_buf());
+/// When the message was sent, if it recorded a valid timestamp.
+pub fn message_time(msg: &SessionMessage) -> Option<DateTime<Utc>> {
+    parse_timestamp(msg.timestamp.as_deref()?)
+}
+
+pub fn parse_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|ts| ts.with_timezone(&Utc))
+}
+
+/// Prints messages around an entry, each with its time relative to the entry (`-2m14s`), and
+/// a `⏳` line wherever a long pause separates two of them.
+fn print_context(heading: &str, context: &[ContextMessage], entry_time: Option<DateTime<Utc>>, render: RenderOptions) {
+    if context.is_empty() {
+        return;
     }
-    
-    // If it's just a session ID, try to find it in ~/.claude/projects
-    let projects_dir = Path::new(&std::env::var("HOME")?)
-        .join(".claude")
-        .join("projects");
-    
-    if path.extension().is_none() {
-        // It's probably just a session ID, search for it
-        for entry in walkdir::WalkDir::new(&projects_dir) {
-            let entry = entry?;
-            if entry.file_type().is_file() {
-                if let Some(stem) = entry.path().file_stem() {
-                    if stem == session_path {
-                        return Ok(entry.path().to_path_buf());
-                    }
-                }
+    println!("  {}", heading);
+    let mut previous_time = None;
+    for ctx in context {
+        let time = context_time(ctx);
+        if let Some(gap) = gap_line(previous_time, time, render) {
+            println!("    {}", gap);
+        }
+        previous_time = time.or(previous_time);
+        match (entry_time, time) {
+            (Some(entry_time), Some(time)) => {
+                println!("{}", render.wrap_indented(&ctx.summary, &format!("    ({}) ", format_delta(entry_time, time)), "      "))
             }
+            _ => println!("{}", render.wrap_indented(&ctx.summary, "    ", "      ")),
         }
     }
-    
-    // Try as relative to projects dir
-    let candidate = projects_dir.join(session_path);
-    if candidate.exists() {
-        return Ok(candidate);
-    }
-    
-    Err(anyhow!("Could not resolve session path: {}", session_path))
 }
 
-fn extract_session_id_from_path(path: &Path) -> Result<String> {
-    path.file_stem()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_string())
-        .ok_or_else(|| anyhow!("Could not extract session ID from path: {:?}", path))
+fn context_time(ctx: &ContextMessage) -> Option<DateTime<Utc>> {
+    ctx.timestamp.as_deref().and_then(parse_timestamp)
 }
 
-fn parse_session_messages(content: &str) -> Result<Vec<SessionMessage>> {
-    let mut messages = Vec::new();
-    
-    for (index, line) in content.lines().enumerate() {
-        if let Ok(mut msg) = serde_json::from_str::<SessionMessage>(line) {
-            // Store the line index for reference
-            if msg.timestamp.is_none() {
-                msg.timestamp = Some(format!("line_{}", index));
-            }
-            messages.push(msg);
-        }
+/// ` (+2m14s)` since the previous entry shown, when both were timestamped.
+fn delta_label(previous: Option<DateTime<Utc>>, current: Option<DateTime<Utc>>) -> String {
+    match (previous, current) {
+        (Some(previous), Some(current)) => format!(" ({})", format_delta(previous, current)),
+        _ => String::new(),
     }
-    
-    Ok(messages)
 }
 
-fn find_matching_messages(messages: &[SessionMessage], search_terms: &[&str]) -> Vec<usize> {
+pub fn parse_session_messages(content: &str) -> Result<Vec<SessionMessage>> {
+    Ok(parse_session_messages_with_diagnostics(content).0)
+}
+
+/// Like `parse_session_messages`, also returning why any lines were dropped or looked off.
+pub fn parse_session_messages_with_diagnostics(content: &str) -> (Vec<SessionMessage>, ParseDiagnostics) {
+    parse_session_lines(content)
+}
+
+pub fn find_matching_messages(messages: &[SessionMessage], search_terms: &[&str], options: &SearchOptions) -> Vec<usize> {
+    let matcher = query_matcher(search_terms);
     messages
         .iter()
         .enumerate()
         .filter_map(|(index, msg)| {
-            if let Some(inner_msg) = &msg.message {
-                if let Some(content) = &inner_msg.content {
-                    let content_text = extract_content_text(content);
-                    
-                    // Skip lines that mention session-finder to avoid false positives
-                    if content_text.to_lowercase().contains("session-finder") || 
-                       content_text.to_lowercase().contains("session_finder") {
-                        return None;
-                    }
-                    
-                    for term in search_terms {
-                        if content_text.to_lowercase().contains(&term.to_lowercase()) {
-                            return Some(index);
+            let content = msg.message.as_ref()?.content.as_ref()?;
+            let content_text = extract_content_text(content).to_lowercase();
+
+            if options.exclusions.is_excluded(&content_text) {
+                return None;
+            }
+            if options.restricts_content_types()
+                && !options.content_type_selected(&classify_message_content(messages, index).content_type)
+            {
+                return None;
+            }
+
+            let matched = match options.scope {
+                MatchScope::All => matcher.is_match(&content_text),
+                _ => matcher.is_match(&options.match_text(content).to_lowercase()),
+            };
+            matched.then_some(index)
+        })
+        .collect()
+}
+
+/// How often and where the search terms occur in the session. Message text and the input of
+/// tools that change things count as matches; hits inside file reads (`Read`, `Grep`, `Glob`
+/// calls and their results) are counted apart, since they are often incidental. With
+/// `--in-code`, only code counts, and with `--in-prose` only text outside code blocks.
+pub fn match_summary(messages: &[SessionMessage], search_terms: &[&str], options: &SearchOptions) -> MatchSummary {
+    let mut counter = MatchCounter::new(search_terms, options);
+    for index in 0..messages.len() {
+        counter.add(index, messages, index);
+    }
+    counter.summary
+}
+
+/// Builds a `MatchSummary` one message at a time, so a session can be counted in chunks.
+#[derive(Clone)]
+pub struct MatchCounter {
+    matcher: Arc<TermMatcher>,
+    options: SearchOptions,
+    /// IDs of read tool calls, whose results count as read occurrences
+    read_calls: Vec<String>,
+    pub summary: MatchSummary,
+}
+
+impl MatchCounter {
+    pub fn new(search_terms: &[&str], options: &SearchOptions) -> Self {
+        MatchCounter {
+            matcher: query_matcher(search_terms),
+            options: options.clone(),
+            read_calls: Vec::new(),
+            summary: MatchSummary::default(),
+        }
+    }
+
+    fn count(&self, text: &str) -> usize {
+        self.matcher.count(&text.to_lowercase())
+    }
+
+    /// Counts `messages[position]`, the message at `index` in the session; `messages` may be
+    /// just the chunk of the session holding it.
+    pub fn add(&mut self, index: usize, messages: &[SessionMessage], position: usize) {
+        let msg = &messages[position];
+        let Some(content) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+            return;
+        };
+        if self.options.restricts_content_types()
+            && !self.options.content_type_selected(&classify_message_content(messages, position).content_type)
+        {
+            return;
+        }
+        let lowercase_text = extract_content_text(content).to_lowercase();
+        if self.options.exclusions.is_excluded(&lowercase_text) {
+            return;
+        }
+        let scope = self.options.scope;
+        let mut hits = match scope {
+            MatchScope::All => self.matcher.count(&lowercase_text),
+            _ => self.count(&self.options.match_text(content)),
+        };
+        // Outside the default scope, tool input only counts where the scope's text includes it
+        if let (MatchScope::All, Content::Array(blocks)) = (scope, content) {
+            for block in blocks {
+                match block.r#type.as_str() {
+                    "tool_use" => {
+                        let input = block.input.as_ref().map(|input| input.to_string()).unwrap_or_default();
+                        if is_read_tool(block.name.as_deref().unwrap_or_default()) {
+                            self.summary.read_occurrences += self.count(&input);
+                            self.read_calls.extend(block.id.clone());
+                        } else {
+                            hits += self.count(&input);
                         }
                     }
+                    "tool_result" if block.tool_use_id.as_ref().is_some_and(|id| self.read_calls.contains(id)) => {
+                        self.summary.read_occurrences +=
+                            block.content.as_ref().map(|c| self.count(&c.to_string())).unwrap_or(0);
+                    }
+                    _ => {}
                 }
             }
-            None
+        }
+        if hits > 0 {
+            self.summary.occurrences += hits;
+            self.summary.messages += 1;
+            self.summary.first_message.get_or_insert(index);
+            self.summary.last_message = Some(index);
+        }
+    }
+}
+
+/// Messages calling a tool the filters select. With search terms, the terms must also appear
+/// in the message text or the call's input.
+fn find_tool_calls(messages: &[SessionMessage], search_terms: &[&str], tool_filters: &[ToolFilter]) -> Vec<usize> {
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(_, msg)| {
+            let calls = matching_tool_calls(msg, tool_filters);
+            if calls.is_empty() || search_terms.is_empty() {
+                return !calls.is_empty();
+            }
+            let mut searchable = msg.message.as_ref()
+                .and_then(|m| m.content.as_ref())
+                .map(extract_content_text)
+                .unwrap_or_default();
+            for call in calls {
+                if let Some(input) = &call.input {
+                    let searchable = searchable.to_mut();
+                    searchable.push(' ');
+                    searchable.push_str(&input.to_string());
+                }
+            }
+            query_matcher(search_terms).is_match(&searchable.to_lowercase())
         })
+        .map(|(index, _)| index)
         .collect()
 }
 
+/// Scores how strongly a message matches the query: more distinct terms, more hits, and
+/// terms appearing close together all rank higher.
+fn score_match(content_text: &str, search_terms: &[&str]) -> MatchScore {
+    let mut positions = query_matcher(search_terms).find_all(&content_text.to_lowercase());
+    let term_hits = positions.len();
+    let mut matched: Vec<usize> = positions.iter().map(|(_, term)| *term).collect();
+    matched.sort_unstable();
+    matched.dedup();
+    let terms_matched = matched.len();
+    
+    let proximity_span = if terms_matched > 1 {
+        shortest_covering_span(&mut positions, terms_matched)
+    } else {
+        None
+    };
+    
+    let terms_total = search_terms.len();
+    let mut score = terms_matched as f64 * 2.0 + (1.0 + term_hits as f64).ln();
+    if terms_total > 1 && terms_matched == terms_total {
+        score += 2.0;
+    }
+    if let Some(span) = proximity_span {
+        if span < PROXIMITY_WINDOW {
+            score += 1.0 - span as f64 / PROXIMITY_WINDOW as f64;
+        }
+    }
+    
+    MatchScore {
+        score,
+        term_hits,
+        terms_matched,
+        terms_total,
+        proximity_span,
+    }
+}
+
+/// Finds the shortest byte span containing at least one hit of each of `distinct_terms` terms.
+fn shortest_covering_span(positions: &mut [(usize, usize)], distinct_terms: usize) -> Option<usize> {
+    positions.sort_unstable();
+    
+    let mut counts: std::collections::HashMap<usize, usize> = std::collections::HashMap::new();
+    let mut best: Option<usize> = None;
+    let mut left = 0;
+    
+    for right in 0..positions.len() {
+        *counts.entry(positions[right].1).or_insert(0) += 1;
+        while counts.len() == distinct_terms {
+            let span = positions[right].0 - positions[left].0;
+            best = Some(best.map_or(span, |current| current.min(span)));
+            let left_term = positions[left].1;
+            if let Some(count) = counts.get_mut(&left_term) {
+                *count -= 1;
+                if *count == 0 {
+                    counts.remove(&left_term);
+                }
+            }
+            left += 1;
+        }
+    }
+    
+    best
+}
+
 fn extract_context_messages(
     messages: &[SessionMessage],
     center_index: usize,
     context_size: usize,
     before: bool,
-) -> Vec<String> {
+) -> Vec<ContextMessage> {
     let mut context = Vec::new();
     
     if before {
         let start = center_index.saturating_sub(context_size);
         for i in start..center_index {
             if let Some(msg) = messages.get(i) {
-                context.push(format_message_summary(msg));
+                context.push(context_message(i, msg));
             }
         }
     } else {
         let end = std::cmp::min(center_index + context_size + 1, messages.len());
         for i in (center_index + 1)..end {
             if let Some(msg) = messages.get(i) {
-                context.push(format_message_summary(msg));
+                context.push(context_message(i, msg));
             }
         }
     }
@@ -188,14 +506,17 @@ fn extract_context_messages(
     context
 }
 
-fn classify_message_content(msg: &SessionMessage) -> ClassifiedContent {
-    if let Some(inner_msg) = &msg.message {
+/// Classifies the message at `index`; the messages before it help judge whether it reports
+/// a success.
+pub fn classify_message_content(messages: &[SessionMessage], index: usize) -> ClassifiedContent {
+    if let Some(inner_msg) = &messages[index].message {
         if let Some(content) = &inner_msg.content {
             let content_text = extract_content_text(content);
-            let content_type = determine_content_type(content, &content_text);
+            let content_type = determine_content_type(content, &content_text)
+                .unwrap_or_else(|| classify_prose(messages, index, &content_text));
             
             return ClassifiedContent {
-                raw_content: content_text,
+                raw_content: content_text.into_owned(),
                 content_type,
             };
         }
@@ -207,93 +528,72 @@ fn classify_message_content(msg: &SessionMessage) -> ClassifiedContent {
     }
 }
 
-fn determine_content_type(content: &Content, content_text: &str) -> ContentType {
-    match content {
-        Content::Array(blocks) => {
-            // Check for tool calls first
-            for block in blocks {
-                if block.r#type == "tool_use" {
-                    return ContentType::ToolCall(ToolInfo {
-                        tool_name: block.name.clone().unwrap_or_default(),
-                        action_type: classify_tool_action(&block.name.as_deref().unwrap_or_default()),
-                        target_files: extract_target_files(&block.input),
-                    });
-                }
+/// The type of a message with a tool call, code, or an error in it.
+fn determine_content_type(content: &Content, content_text: &str) -> Option<ContentType> {
+    if let Content::Array(blocks) = content {
+        // Check for tool calls first
+        for block in blocks {
+            if block.r#type == "tool_use" {
+                let tool_name = block.name.as_deref().unwrap_or_default();
+                return Some(ContentType::ToolCall(ToolInfo {
+                    tool_name: tool_name.to_string(),
+                    action_type: classify_tool_action(tool_name),
+                    target_files: extract_target_files(&block.input),
+                    mcp_server: parse_mcp_tool(tool_name).map(|mcp| mcp.server.to_string()),
+                }));
             }
         }
-        _ => {}
     }
     
     // Check for code blocks
     if let Some(code_info) = extract_code_block_info(content_text) {
-        return ContentType::CodeBlock(code_info);
+        return Some(ContentType::CodeBlock(code_info));
     }
     
     // Check for error messages
-    if let Some(error_info) = detect_error_patterns(content_text) {
-        return ContentType::ErrorMessage(error_info);
-    }
-    
-    // Check for success responses
-    if is_success_response(content_text) {
-        return ContentType::SuccessResponse;
+    detect_error_patterns(content_text).map(ContentType::ErrorMessage)
+}
+
+/// A success response if the message confidently reports one, otherwise discussion.
+fn classify_prose(messages: &[SessionMessage], index: usize, content_text: &str) -> ContentType {
+    let confidence = success_confidence(messages, index, content_text);
+    if confidence >= SUCCESS_THRESHOLD {
+        ContentType::SuccessResponse(SuccessInfo { confidence })
+    } else {
+        ContentType::Discussion
     }
-    
-    ContentType::Discussion
 }
 
-fn extract_content_text(content: &Content) -> String {
+/// The text blocks of a message, joined with spaces. Borrows when there is only one block, as
+/// for most messages.
+pub fn extract_content_text(content: &Content) -> Cow<'_, str> {
     match content {
-        Content::Text(text) => text.clone(),
+        Content::Text(text) => Cow::Borrowed(text),
         Content::Array(blocks) => {
-            blocks
+            let mut texts = blocks
                 .iter()
-                .filter_map(|block| {
-                    if block.r#type == "text" {
-                        block.text.as_ref()
-                    } else {
-                        None
+                .filter(|block| block.r#type == "text")
+                .filter_map(|block| block.text.as_deref());
+            let Some(first) = texts.next() else {
+                return Cow::Borrowed("");
+            };
+            match texts.next() {
+                None => Cow::Borrowed(first),
+                Some(second) => {
+                    let mut joined = format!("{} {}", first, second);
+                    for text in texts {
+                        joined.push(' ');
+                        joined.push_str(text);
                     }
-                })
-                .cloned()
-                .collect::<Vec<String>>()
-                .join(" ")
+                    Cow::Owned(joined)
+                }
+            }
         }
     }
 }
 
 fn extract_code_block_info(content: &str) -> Option<CodeInfo> {
-    let fence_regex = Regex::new(r"```(\w+)?\n(.*?)\n```").ok()?;
-    
-    if let Some(captures) = fence_regex.captures(content) {
-        let language = captures.get(1).map(|m| m.as_str().to_string());
-        let code = captures.get(2).map(|m| m.as_str()).unwrap_or("");
-        let line_count = code.lines().count();
-        let is_complete = is_complete_code_block(code, language.as_deref());
-        
-        return Some(CodeInfo {
-            language,
-            is_complete,
-            line_count,
-        });
-    }
-    
-    None
-}
-
-fn is_complete_code_block(code: &str, language: Option<&str>) -> bool {
-    match language {
-        Some("rust") => {
-            code.contains("fn ") && (code.contains("{") && code.contains("}"))
-        }
-        Some("javascript") | Some("typescript") => {
-            code.contains("function ") || code.contains("=>") || code.contains("{")
-        }
-        Some("python") => {
-            code.contains("def ") || code.contains("class ")
-        }
-        _ => code.lines().count() > 3 // Simple heuristic for other languages
-    }
+    first_code_block(content).map(|block| analyze_code_block(&block))
 }
 
 fn classify_tool_action(tool_name: &str) -> String {
@@ -302,6 +602,7 @@ fn classify_tool_action(tool_name: &str) -> String {
         "Edit" | "Write" | "MultiEdit" => "write",
         "Bash" => "execute",
         "LS" => "list",
+        name if parse_mcp_tool(name).is_some() => "mcp",
         _ => "other",
     }
     .to_string()
@@ -327,43 +628,20 @@ fn extract_target_files(input: &Option<serde_json::Value>) -> Vec<String> {
 }
 
 fn detect_error_patterns(content: &str) -> Option<ErrorInfo> {
-    if content.contains("error[E") || content.contains("cannot find") {
-        Some(ErrorInfo {
-            error_type: "compilation".to_string(),
-            severity: "error".to_string(),
-            source: Some("rustc".to_string()),
-        })
-    } else if content.contains("warning:") {
-        Some(ErrorInfo {
-            error_type: "compilation".to_string(),
-            severity: "warning".to_string(),
-            source: Some("rustc".to_string()),
-        })
-    } else if content.contains("Permission denied") || content.contains("No such file") {
-        Some(ErrorInfo {
-            error_type: "tool_error".to_string(),
-            severity: "error".to_string(),
-            source: Some("system".to_string()),
-        })
-    } else if content.contains("panicked at") || content.contains("thread 'main' panicked") {
-        Some(ErrorInfo {
-            error_type: "runtime".to_string(),
-            severity: "error".to_string(),
-            source: Some("rust".to_string()),
-        })
-    } else {
-        None
-    }
+    error_rules().detect(content)
 }
 
-fn is_success_response(content: &str) -> bool {
-    let success_indicators = [
-        "works", "perfect", "great", "excellent", "success", "completed",
-        "fixed", "solved", "done", "good", "that's it"
-    ];
-    
-    let lower_content = content.to_lowercase();
-    success_indicators.iter().any(|&indicator| lower_content.contains(indicator))
+/// Whether the text reports an error (not just a warning).
+pub fn has_error(content: &str) -> bool {
+    detect_error_patterns(content).is_some_and(|error| error.severity == "error")
+}
+
+fn context_message(index: usize, msg: &SessionMessage) -> ContextMessage {
+    ContextMessage {
+        message_index: index,
+        timestamp: msg.timestamp.clone(),
+        summary: format_message_summary(msg),
+    }
 }
 
 fn format_message_summary(msg: &SessionMessage) -> String {
@@ -371,63 +649,71 @@ fn format_message_summary(msg: &SessionMessage) -> String {
         if let Some(role) = &inner_msg.role {
             if let Some(content) = &inner_msg.content {
                 let content_text = extract_content_text(content);
-                let truncated = if content_text.len() > 100 {
-                    format!("{}...", &content_text[..97])
-                } else {
-                    content_text
-                };
-                return format!("{}: {}", role, truncated);
+                return format!("{}: {}", role, truncate_text(&content_text, 100));
             }
         }
     }
     "Unknown message".to_string()
 }
 
-pub fn display_timeline(timeline: &TimelineExtraction) -> Result<()> {
+pub fn display_timeline(timeline: &TimelineExtraction, full_content: bool, render: RenderOptions) -> Result<()> {
     println!("=== Timeline for \"{}\" in session {} ===\n", 
              timeline.query_term, timeline.session_id);
     
+    if timeline.timeline.len() < timeline.total_matches {
+        println!("Showing top {} of {} matching messages by score\n",
+                 timeline.timeline.len(), timeline.total_matches);
+    }
+    
+    let store = Store::load()?;
+    let term_regex = build_term_regex(&timeline.search_terms);
+    let style = if std::io::stdout().is_terminal() {
+        HighlightStyle::Ansi
+    } else {
+        HighlightStyle::Markers
+    };
+    
+    let mut previous_time = None;
     for entry in &timeline.timeline {
-        let content_type_label = match &entry.classified_content.content_type {
-            ContentType::PlainText => "Discussion".to_string(),
-            ContentType::CodeBlock(info) => {
-                format!("Code Block ({}, {} lines)", 
-                       info.language.as_deref().unwrap_or("unknown"), 
-                       info.line_count)
-            }
-            ContentType::ToolCall(info) => {
-                format!("Tool Call ({} → {})", 
-                       info.tool_name, 
-                       info.target_files.join(", "))
-            }
-            ContentType::ErrorMessage(info) => {
-                format!("Error ({})", info.error_type)
-            }
-            ContentType::SuccessResponse => "Success Response".to_string(),
-            ContentType::Discussion => "Discussion".to_string(),
-        };
+        let content_type_label = content_type_label(&entry.classified_content.content_type, render);
+        // Gaps only mean something when entries are in time order
+        let entry_time = entry.timestamp.as_deref().and_then(parse_timestamp);
+        let delta = if timeline.ranked { String::new() } else { delta_label(previous_time, entry_time) };
+        if let Some(gap) = gap_line(previous_time, entry_time, render).filter(|_| !timeline.ranked) {
+            println!("{}\n", gap);
+        }
+        previous_time = entry_time.or(previous_time);
         
-        println!("[Message {} - {}] {}: {}", 
-                 entry.message_index, 
-                 entry.timestamp, 
+        println!("{}{} {}: {}{}", 
+                 message_heading(entry.message_index, entry.line_number, entry.timestamp.as_deref()),
+                 delta,
                  entry.role, 
-                 content_type_label);
+                 content_type_label,
+                 interrupted_label(entry.interrupted, render));
+        println!("  Score: {}", format_match_score(&entry.match_score));
+        if let Some(outcome) = entry.command_outcome {
+            println!("  Command: {}", outcome.label(render));
+        }
+        if let Some(bookmark) = store.bookmark(&timeline.session_id, entry.message_index) {
+            println!("  Bookmark: {}", bookmark.label.as_deref().unwrap_or("(no label)"));
+        }
+        for note in store.message_notes(&timeline.session_id, entry.message_index) {
+            println!("{}", render.wrap_indented(&note.text, "  Note: ", "    "));
+        }
         
-        if !entry.context_before.is_empty() {
-            println!("  Context before:");
-            for ctx in &entry.context_before {
-                println!("    {}", ctx);
-            }
+        print_context("Context before:", &entry.context_before, entry_time, render);
+        if let Some(gap) = entry.context_before.last().and_then(|ctx| gap_line(context_time(ctx), entry_time, render)) {
+            println!("  {}", gap);
         }
         
-        println!("  → {}", entry.classified_content.raw_content);
+        let raw_content = &entry.classified_content.raw_content;
+        let content = match &term_regex {
+            Some(regex) if !full_content => Cow::Owned(excerpt_matches(raw_content, regex, style)),
+            _ => highlight_content(&entry.classified_content.content_type, raw_content),
+        };
+        println!("{}", render.wrap_indented(&content, &format!("  {} ", render.glyph(Glyph::Arrow)), "    "));
         
-        if !entry.context_after.is_empty() {
-            println!("  Context after:");
-            for ctx in &entry.context_after {
-                println!("    {}", ctx);
-            }
-        }
+        print_context("Context after:", &entry.context_after, entry_time, render);
         
         println!();
     }
@@ -435,17 +721,288 @@ pub fn display_timeline(timeline: &TimelineExtraction) -> Result<()> {
     Ok(())
 }
 
+/// A whole message's content with its code coloured: what Write and Edit calls write, and
+/// fenced code blocks.
+fn highlight_content<'a>(content_type: &ContentType, raw_content: &'a str) -> Cow<'a, str> {
+    match content_type {
+        ContentType::ToolCall(info) if matches!(info.tool_name.as_str(), "Write" | "Edit" | "MultiEdit") => {
+            match info.target_files.first().and_then(|file| file_language(file)) {
+                Some(language) => highlight_tool_content(raw_content, language),
+                None => Cow::Borrowed(raw_content),
+            }
+        }
+        _ => highlight_fenced(raw_content),
+    }
+}
+
+/// How a message's content is labeled in timelines, e.g. `Tool Call (Edit → src/main.rs)`.
+pub fn content_type_label(content_type: &ContentType, render: RenderOptions) -> String {
+    match content_type {
+        ContentType::PlainText => "Discussion".to_string(),
+        ContentType::CodeBlock(info) => {
+            format!("Code Block ({}, {} line{}{})", 
+                   info.language.as_deref().unwrap_or("unknown"), 
+                   info.line_count,
+                   if info.line_count == 1 { "" } else { "s" },
+                   if info.is_complete { "" } else { ", incomplete" })
+        }
+        ContentType::ToolCall(info) => {
+            let tool = match parse_mcp_tool(&info.tool_name) {
+                Some(mcp) => format!("MCP {}: {}", mcp.server, mcp.tool),
+                None => info.tool_name.clone(),
+            };
+            if info.target_files.is_empty() {
+                format!("Tool Call ({})", tool)
+            } else {
+                format!("Tool Call ({} {} {})", tool, render.glyph(Glyph::Arrow), info.target_files.join(", "))
+            }
+        }
+        ContentType::ErrorMessage(info) => {
+            let severity = if info.severity == "warning" { "Warning" } else { "Error" };
+            match info.source.as_ref().filter(|source| **source != info.error_type) {
+                Some(source) => format!("{} ({}, {})", severity, info.error_type, source),
+                None => format!("{} ({})", severity, info.error_type),
+            }
+        }
+        ContentType::SuccessResponse(info) => {
+            format!("Success Response ({:.0}% confidence)", info.confidence * 100.0)
+        }
+        ContentType::Discussion => "Discussion".to_string(),
+    }
+}
+
+/// `[Message 12, line 15 - 2026-10-01T10:00:00Z]`: the message's position among parsed messages
+/// (what `show --around` and bookmarks take), the session file line it came from, and when it
+/// was sent, if recorded.
+pub fn message_heading(index: usize, line_number: usize, timestamp: Option<&str>) -> String {
+    match timestamp {
+        Some(timestamp) => format!("[Message {}, line {} - {}]", index, line_number, timestamp),
+        None => format!("[Message {}, line {}]", index, line_number),
+    }
+}
+
+pub fn interrupted_label(interrupted: bool, render: RenderOptions) -> String {
+    if interrupted { format!(" {} interrupted", render.glyph(Glyph::Interrupted)) } else { String::new() }
+}
+
+pub fn format_match_score(match_score: &MatchScore) -> String {
+    let mut details = format!("{} hits, {}/{} terms",
+                              match_score.term_hits,
+                              match_score.terms_matched,
+                              match_score.terms_total);
+    if let Some(span) = match_score.proximity_span {
+        details.push_str(&format!(", within {} chars", span));
+    }
+    format!("{:.1} ({})", match_score.score, details)
+}
+
+/// Prints one `path:line: excerpt` line per entry, the format editors' quickfix lists read.
+pub fn display_timeline_quickfix(timeline: &TimelineExtraction) {
+    let term_regex = build_term_regex(&timeline.search_terms);
+    
+    for entry in &timeline.timeline {
+        let raw_content = &entry.classified_content.raw_content;
+        let excerpt = match &term_regex {
+            Some(regex) => excerpt_matches(raw_content, regex, HighlightStyle::Plain),
+            None => flatten_whitespace(raw_content),
+        };
+        println!("{}:{}: {}: {}",
+                 timeline.session_path.display(),
+                 entry.line_number,
+                 entry.role,
+                 truncate_text(&excerpt, QUICKFIX_EXCERPT_LEN));
+    }
+}
+
+/// The content of the first timeline entry as it would be displayed, without highlighting,
+/// for copying elsewhere.
+pub fn first_entry_excerpt(timeline: &TimelineExtraction, full_content: bool) -> Option<String> {
+    let entry = timeline.timeline.first()?;
+    Some(entry_excerpt(timeline, entry, full_content, false))
+}
+
+/// An entry's content as written to files: the matched excerpts, or everything with
+/// `full_content`, with terms in `**bold**` when `marked`.
+pub fn entry_excerpt(timeline: &TimelineExtraction, entry: &TimelineEntry, full_content: bool, marked: bool) -> String {
+    let raw_content = &entry.classified_content.raw_content;
+    let style = if marked { HighlightStyle::Markers } else { HighlightStyle::Plain };
+    match build_term_regex(&timeline.search_terms) {
+        Some(regex) if !full_content => excerpt_matches(raw_content, &regex, style),
+        _ => raw_content.clone(),
+    }
+}
+
+/// The parts of `text` around the search terms, as timelines excerpt a matched message.
+pub fn term_excerpt(text: &str, search_terms: &[&str]) -> String {
+    let search_terms: Vec<String> = search_terms.iter().map(|term| term.to_string()).collect();
+    match build_term_regex(&search_terms) {
+        Some(regex) => excerpt_matches(text, &regex, HighlightStyle::Plain),
+        None => flatten_whitespace(text),
+    }
+}
+
+/// Builds a case-insensitive regex matching any of the search terms, preferring longer terms
+/// so overlapping terms always highlight the same way.
+fn build_term_regex(search_terms: &[String]) -> Option<Regex> {
+    let mut terms: Vec<&str> = search_terms
+        .iter()
+        .map(|term| term.as_str())
+        .filter(|term| !term.is_empty())
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    
+    terms.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    terms.dedup();
+    
+    let alternation = terms
+        .iter()
+        .map(|term| regex::escape(term))
+        .collect::<Vec<_>>()
+        .join("|");
+    Regex::new(&format!("(?i){}", alternation)).ok()
+}
+
+/// Renders the parts of `text` surrounding each match, with the matches highlighted.
+/// Falls back to the full text when nothing matches.
+fn excerpt_matches(text: &str, term_regex: &Regex, style: HighlightStyle) -> String {
+    let matches: Vec<(usize, usize)> = term_regex
+        .find_iter(text)
+        .map(|m| (m.start(), m.end()))
+        .collect();
+    if matches.is_empty() {
+        return text.to_string();
+    }
+    
+    // Merge the windows around each match so overlapping excerpts are shown once
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    for &(start, end) in &matches {
+        let window_start = floor_char_boundary(text, start.saturating_sub(EXCERPT_RADIUS));
+        let window_end = ceil_char_boundary(text, end + EXCERPT_RADIUS);
+        match windows.last_mut() {
+            Some(last) if window_start <= last.1 => last.1 = window_end,
+            _ => windows.push((window_start, window_end)),
+        }
+    }
+    
+    let hidden_windows = windows.len().saturating_sub(MAX_EXCERPT_WINDOWS);
+    let mut excerpt = String::new();
+    
+    for &(window_start, window_end) in windows.iter().take(MAX_EXCERPT_WINDOWS) {
+        if window_start > 0 || !excerpt.is_empty() {
+            excerpt.push_str("...");
+        }
+        
+        let mut cursor = window_start;
+        for &(start, end) in matches.iter().filter(|(start, _)| *start >= window_start && *start < window_end) {
+            excerpt.push_str(&flatten_whitespace(&text[cursor..start]));
+            excerpt.push_str(&highlight(&text[start..end], style));
+            cursor = end;
+        }
+        excerpt.push_str(&flatten_whitespace(&text[cursor..window_end]));
+    }
+    
+    let last_shown_end = windows[windows.len() - hidden_windows - 1].1;
+    if last_shown_end < text.len() {
+        excerpt.push_str("...");
+    }
+    if hidden_windows > 0 {
+        excerpt.push_str(&format!(" (+{} more matching excerpts)", hidden_windows));
+    }
+    
+    excerpt
+}
+
+fn highlight(matched: &str, style: HighlightStyle) -> String {
+    match style {
+        HighlightStyle::Ansi => format!("\x1b[1;33m{}\x1b[0m", matched),
+        HighlightStyle::Markers => format!("**{}**", matched),
+        HighlightStyle::Plain => matched.to_string(),
+    }
+}
+
+fn flatten_whitespace(text: &str) -> String {
+    text.replace(['\n', '\r', '\t'], " ")
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut boundary = index.min(text.len());
+    while boundary > 0 && !text.is_char_boundary(boundary) {
+        boundary -= 1;
+    }
+    boundary
+}
+
+fn ceil_char_boundary(text: &str, index: usize) -> usize {
+    let mut boundary = index.min(text.len());
+    while boundary < text.len() && !text.is_char_boundary(boundary) {
+        boundary += 1;
+    }
+    boundary
+}
+
+/// Tool calls the code-diff timeline shows by default
+pub const CODE_DIFF_TOOLS: &[&str] = &["Edit", "Write", "MultiEdit", "Bash"];
+
+/// What the code-diff timeline includes: calls to these tools, and optionally code blocks
+/// from message text
+pub struct CodeDiffSelection {
+    pub tools: Vec<String>,
+    pub code_blocks: bool,
+}
+
+impl CodeDiffSelection {
+    /// Everything: all code-changing tools and code blocks
+    pub fn all() -> Self {
+        CodeDiffSelection {
+            tools: CODE_DIFF_TOOLS.iter().map(|tool| tool.to_string()).collect(),
+            code_blocks: true,
+        }
+    }
+
+    /// Only the named tools, without code blocks; `Edit` also selects `MultiEdit`.
+    pub fn only(tools: &[&str]) -> Self {
+        let mut selected: Vec<String> = tools.iter().map(|tool| tool.to_string()).collect();
+        if tools.iter().any(|tool| tool.eq_ignore_ascii_case("Edit")) {
+            selected.push("MultiEdit".to_string());
+        }
+        CodeDiffSelection { tools: selected, code_blocks: false }
+    }
+
+    pub fn without(mut self, tool: &str) -> Self {
+        self.tools.retain(|selected| !selected.eq_ignore_ascii_case(tool));
+        self
+    }
+
+    fn includes(&self, tool_name: &str) -> bool {
+        self.tools.iter().any(|tool| tool.eq_ignore_ascii_case(tool_name))
+    }
+}
+
 pub fn extract_code_diff_timeline(
     session_path: &str,
     search_terms: &[&str],
     context_size: usize,
+    selection: &CodeDiffSelection,
+    exclude_interrupted: bool,
+    time_range: &TimeRange,
+    render: RenderOptions,
 ) -> Result<CodeDiffTimeline> {
-    let full_path = resolve_session_path(session_path)?;
-    let session_id = extract_session_id_from_path(&full_path)?;
-    let content = fs::read_to_string(&full_path)?;
+    let (full_path, session_id, content) = read_session_source(session_path)?;
+    debug!(reference = session_path, file = %full_path.display(), "Resolved session");
     
-    let all_messages = parse_session_messages(&content)?;
-    let code_change_indices = find_code_change_messages(&all_messages);
+    let (all_messages, parse_diagnostics) = parse_session_messages_with_diagnostics(&content);
+    let interrupted = interrupted_turns(&all_messages);
+    let command_outcomes = bash_outcomes(&all_messages);
+    let mut code_change_indices = find_code_change_messages(&all_messages, selection);
+    if exclude_interrupted {
+        code_change_indices.retain(|&index| !interrupted[index]);
+    }
+    let times = carried_message_times(&all_messages);
+    code_change_indices.retain(|&index| time_range.contains(times[index]));
+    code_change_indices.sort_by_key(|&index| (times[index], index));
+    info!(messages = all_messages.len(), code_changes = code_change_indices.len(), "Parsed session");
     
     let code_changes: Vec<CodeDiffEntry> = code_change_indices
         .into_iter()
@@ -453,17 +1010,25 @@ pub fn extract_code_diff_timeline(
             let msg = &all_messages[index];
             let context_before = extract_context_messages(&all_messages, index, context_size, true);
             let context_after = extract_context_messages(&all_messages, index, context_size, false);
-            let (code_content, language, change_type) = extract_code_from_message(msg);
+            let (code_content, language, change_type, complete) = extract_code_from_message(msg, selection, render);
+            let command_outcome = match change_type {
+                CodeChangeType::BashCommand => message_command_outcome(msg, &command_outcomes),
+                _ => None,
+            };
             
             CodeDiffEntry {
                 message_index: index,
-                timestamp: msg.timestamp.clone().unwrap_or_default(),
+                line_number: msg.line_number,
+                timestamp: msg.timestamp.clone(),
                 role: msg.message.as_ref()
                     .and_then(|m| m.role.clone())
                     .unwrap_or_default(),
                 code_content,
                 language,
                 change_type,
+                complete,
+                interrupted: interrupted[index],
+                command_outcome,
                 context_before,
                 context_after,
             }
@@ -475,45 +1040,29 @@ pub fn extract_code_diff_timeline(
             }
             
             // Check if any search term matches the code content or context
-            search_terms.iter().any(|term| {
-                let term_lower = term.to_lowercase();
-                
-                // Check code content
-                if entry.code_content.to_lowercase().contains(&term_lower) {
-                    return true;
-                }
-                
-                // Check context before
-                if entry.context_before.iter().any(|ctx| 
-                    ctx.to_lowercase().contains(&term_lower)) {
-                    return true;
-                }
-                
-                // Check context after
-                if entry.context_after.iter().any(|ctx| 
-                    ctx.to_lowercase().contains(&term_lower)) {
-                    return true;
-                }
-                
-                false
-            })
+            let matcher = query_matcher(search_terms);
+            matcher.is_match(&entry.code_content.to_lowercase())
+                || entry.context_before.iter().chain(&entry.context_after)
+                    .any(|ctx| matcher.is_match(&ctx.summary.to_lowercase()))
         })
         .collect();
 
     Ok(CodeDiffTimeline {
         session_id,
+        session_path: full_path,
         code_changes,
+        parse_diagnostics,
     })
 }
 
-fn find_code_change_messages(messages: &[SessionMessage]) -> Vec<usize> {
+fn find_code_change_messages(messages: &[SessionMessage], selection: &CodeDiffSelection) -> Vec<usize> {
     messages
         .iter()
         .enumerate()
         .filter_map(|(index, msg)| {
             if let Some(inner_msg) = &msg.message {
                 if let Some(content) = &inner_msg.content {
-                    if has_code_content(content) {
+                    if has_code_content(content, selection) {
                         return Some(index);
                     }
                 }
@@ -523,27 +1072,35 @@ fn find_code_change_messages(messages: &[SessionMessage]) -> Vec<usize> {
         .collect()
 }
 
-fn has_code_content(content: &Content) -> bool {
+fn has_code_content(content: &Content, selection: &CodeDiffSelection) -> bool {
     match content {
         Content::Array(blocks) => {
             blocks.iter().any(|block| {
                 // Check for tool calls that modify code
                 if block.r#type == "tool_use" {
                     if let Some(name) = &block.name {
-                        return matches!(name.as_str(), "Edit" | "Write" | "MultiEdit" | "Bash");
+                        return selection.includes(name);
                     }
                 }
-                false
+                // ...or code blocks in text
+                selection.code_blocks
+                    && block.r#type == "text"
+                    && block.text.as_deref().and_then(first_code_block).is_some()
             })
         }
         Content::Text(text) => {
             // Check for code blocks in markdown
-            text.contains("```")
+            selection.code_blocks && first_code_block(text).is_some()
         }
     }
 }
 
-fn extract_code_from_message(msg: &SessionMessage) -> (String, Option<String>, CodeChangeType) {
+/// The code a message changed or showed: its content, language, kind, and whether it is complete.
+fn extract_code_from_message(
+    msg: &SessionMessage,
+    selection: &CodeDiffSelection,
+    render: RenderOptions,
+) -> (String, Option<String>, CodeChangeType, bool) {
     if let Some(inner_msg) = &msg.message {
         if let Some(content) = &inner_msg.content {
             match content {
@@ -551,7 +1108,7 @@ fn extract_code_from_message(msg: &SessionMessage) -> (String, Option<String>, C
                     // Look for tool calls first
                     for block in blocks {
                         if block.r#type == "tool_use" {
-                            if let Some(name) = &block.name {
+                            if let Some(name) = block.name.as_ref().filter(|name| selection.includes(name)) {
                                 let change_type = match name.as_str() {
                                     "Edit" | "MultiEdit" => CodeChangeType::Edit,
                                     "Write" => CodeChangeType::Write,
@@ -559,74 +1116,50 @@ fn extract_code_from_message(msg: &SessionMessage) -> (String, Option<String>, C
                                     _ => continue,
                                 };
                                 
-                                let code_content = format_tool_content(name, &block.input);
-                                return (code_content, None, change_type);
+                                let code_content = format_tool_content(name, &block.input, render);
+                                return (code_content, tool_language(name, &block.input), change_type, true);
                             }
                         }
                     }
                     
                     // Look for code blocks in text blocks
-                    for block in blocks {
+                    for block in blocks.iter().filter(|_| selection.code_blocks) {
                         if block.r#type == "text" {
                             if let Some(text) = &block.text {
-                                if let Some((code, lang)) = extract_code_block_from_text(text) {
-                                    return (code, lang, CodeChangeType::CodeBlock);
+                                if let Some((code, info)) = extract_code_block_from_text(text) {
+                                    return (code, info.language, CodeChangeType::CodeBlock, info.is_complete);
                                 }
                             }
                         }
                     }
                 }
                 Content::Text(text) => {
-                    if let Some((code, lang)) = extract_code_block_from_text(text) {
-                        return (code, lang, CodeChangeType::CodeBlock);
+                    if let Some((code, info)) = extract_code_block_from_text(text) {
+                        return (code, info.language, CodeChangeType::CodeBlock, info.is_complete);
                     }
                 }
             }
         }
     }
     
-    ("".to_string(), None, CodeChangeType::CodeBlock)
+    ("".to_string(), None, CodeChangeType::CodeBlock, false)
 }
 
-fn extract_code_block_from_text(text: &str) -> Option<(String, Option<String>)> {
-    // Find code blocks manually to handle multiline content
-    let lines: Vec<&str> = text.lines().collect();
-    let mut i = 0;
-    
-    while i < lines.len() {
-        let line = lines[i];
-        if line.starts_with("```") {
-            // Extract language if present
-            let language = if line.len() > 3 {
-                let lang_part = &line[3..].trim();
-                if lang_part.is_empty() {
-                    None
-                } else {
-                    Some(lang_part.to_string())
-                }
-            } else {
-                None
-            };
-            
-            // Find the closing fence
-            let mut code_lines = Vec::new();
-            i += 1;
-            while i < lines.len() && !lines[i].starts_with("```") {
-                code_lines.push(lines[i]);
-                i += 1;
-            }
-            
-            if !code_lines.is_empty() {
-                return Some((code_lines.join("\n"), language));
-            }
-        }
-        i += 1;
+/// Language of the code a tool call runs or writes: shell for Bash, otherwise the edited
+/// file's, from its extension.
+fn tool_language(tool_name: &str, input: &Option<serde_json::Value>) -> Option<String> {
+    if tool_name == "Bash" {
+        return Some("shell".to_string());
     }
-    
-    None
+    let file_path = input.as_ref()?.get("file_path")?.as_str()?;
+    analyzer_for_path(file_path).map(|analyzer| analyzer.name().to_string())
+}
+
+fn extract_code_block_from_text(text: &str) -> Option<(String, CodeInfo)> {
+    first_code_block(text).map(|block| (block.code.to_string(), analyze_code_block(&block)))
 }
 
-fn format_tool_content(tool_name: &str, input: &Option<serde_json::Value>) -> String {
+pub fn format_tool_content(tool_name: &str, input: &Option<serde_json::Value>, render: RenderOptions) -> String {
     if let Some(input_val) = input {
         match tool_name {
             "Write" => {
@@ -637,7 +1170,7 @@ fn format_tool_content(tool_name: &str, input: &Option<serde_json::Value>) -> St
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
                 
-                format!("📝 Write to {}\n{}", file_path, content)
+                format!("{} Write to {}\n{}", render.glyph(Glyph::Write), file_path, content)
             },
             "Edit" | "MultiEdit" => {
                 let file_path = input_val.get("file_path")
@@ -650,8 +1183,8 @@ fn format_tool_content(tool_name: &str, input: &Option<serde_json::Value>) -> St
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
                 
-                format!("✏️ Edit {}\n--- Replace:\n{}\n+++ With:\n{}", 
-                       file_path, old_string, new_string)
+                format!("{} Edit {}\n--- Replace:\n{}\n+++ With:\n{}", 
+                       render.glyph(Glyph::Edit), file_path, old_string, new_string)
             },
             "Bash" => {
                 let command = input_val.get("command")
@@ -661,21 +1194,39 @@ fn format_tool_content(tool_name: &str, input: &Option<serde_json::Value>) -> St
                     .and_then(|v| v.as_str());
                 
                 if let Some(desc) = description {
-                    format!("🔧 {} ({})", command, desc)
+                    format!("{} {} ({})", render.glyph(Glyph::Tool), command, desc)
                 } else {
-                    format!("🔧 {}", command)
+                    format!("{} {}", render.glyph(Glyph::Tool), command)
                 }
             },
-            _ => format!("🔧 {} with input: {}", tool_name, input_val)
+            name => match parse_mcp_tool(name) {
+                Some(mcp) => format!("{} {} {} {} with input: {}", render.glyph(Glyph::Mcp), mcp.server, render.glyph(Glyph::Within), mcp.tool, input_val),
+                None => format!("{} {} with input: {}", render.glyph(Glyph::Tool), tool_name, input_val),
+            },
         }
     } else {
-        format!("🔧 {}", tool_name)
+        match parse_mcp_tool(tool_name) {
+            Some(mcp) => format!("{} {} {} {}", render.glyph(Glyph::Mcp), mcp.server, render.glyph(Glyph::Within), mcp.tool),
+            None => format!("{} {}", render.glyph(Glyph::Tool), tool_name),
+        }
+    }
+}
+
+/// Prints one `path:line: summary` line per code change, for editors' quickfix lists.
+pub fn display_code_diff_quickfix(timeline: &CodeDiffTimeline) {
+    for entry in &timeline.code_changes {
+        println!("{}:{}: {}: {}",
+                 timeline.session_path.display(),
+                 entry.line_number,
+                 entry.role,
+                 truncate_text(&flatten_whitespace(&entry.code_content), QUICKFIX_EXCERPT_LEN));
     }
 }
 
-pub fn display_code_diff_timeline(timeline: &CodeDiffTimeline) -> Result<()> {
+pub fn display_code_diff_timeline(timeline: &CodeDiffTimeline, render: RenderOptions) -> Result<()> {
     println!("=== Code Diff Timeline for session {} ===\n", timeline.session_id);
     
+    let mut previous_time = None;
     for entry in &timeline.code_changes {
         let change_type_label = match entry.change_type {
             CodeChangeType::Edit => "Edit",
@@ -684,33 +1235,43 @@ pub fn display_code_diff_timeline(timeline: &CodeDiffTimeline) -> Result<()> {
             CodeChangeType::BashCommand => "Bash",
         };
         
-        let language_info = entry.language.as_deref().unwrap_or("unknown");
+        let mut language_info = entry.language.as_deref().unwrap_or("unknown").to_string();
+        if !entry.complete {
+            language_info.push_str(", incomplete");
+        }
+        
+        let entry_time = entry.timestamp.as_deref().and_then(parse_timestamp);
+        let delta = delta_label(previous_time, entry_time);
+        if let Some(gap) = gap_line(previous_time, entry_time, render) {
+            println!("{}\n", gap);
+        }
+        previous_time = entry_time.or(previous_time);
         
-        println!("[Message {} - {}] {}: {} ({})", 
-                 entry.message_index, 
-                 entry.timestamp, 
+        println!("{}{} {}: {} ({}){}{}", 
+                 message_heading(entry.message_index, entry.line_number, entry.timestamp.as_deref()),
+                 delta,
                  entry.role, 
                  change_type_label,
-                 language_info);
+                 language_info,
+                 entry.command_outcome.map(|outcome| format!(" {}", outcome.label(render))).unwrap_or_default(),
+                 interrupted_label(entry.interrupted, render));
         
-        if !entry.context_before.is_empty() {
-            println!("  Context before:");
-            for ctx in &entry.context_before {
-                println!("    {}", ctx);
-            }
+        print_context("Context before:", &entry.context_before, entry_time, render);
+        if let Some(gap) = entry.context_before.last().and_then(|ctx| gap_line(context_time(ctx), entry_time, render)) {
+            println!("  {}", gap);
         }
         
         println!("  Code:");
-        for line in entry.code_content.lines() {
-            println!("    {}", line);
+        if !entry.code_content.is_empty() {
+            let code = match (&entry.change_type, entry.language.as_deref()) {
+                (CodeChangeType::Edit | CodeChangeType::Write, Some(language)) => highlight_tool_content(&entry.code_content, language),
+                (CodeChangeType::CodeBlock, language) => highlight_code(&entry.code_content, language),
+                _ => Cow::Borrowed(entry.code_content.as_str()),
+            };
+            println!("{}", render.wrap_indented(&code, "    ", "    "));
         }
         
-        if !entry.context_after.is_empty() {
-            println!("  Context after:");
-            for ctx in &entry.context_after {
-                println!("    {}", ctx);
-            }
-        }
+        print_context("Context after:", &entry.context_after, entry_time, render);
         
         println!();
     }
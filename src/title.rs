@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::claude_data::claude_data;
+use crate::glyphs::Glyph;
+use crate::interrupt::{interruption, is_user_prompt};
+use crate::render::RenderOptions;
+use crate::resolve::{open_session, read_session_title};
+use crate::schema::{parse_session_line, ParseDiagnostics};
+use crate::text::{display_width, take_width};
+use crate::timeline::extract_content_text;
+use crate::SessionMessage;
+
+/// Widest inferred title, in terminal columns
+const TITLE_LEN: usize = 72;
+
+/// Claude Code wraps slash commands, local command output, and its own notices in these tags
+/// or prefixes; they say nothing about what the session was for.
+const WRAPPER_PREFIXES: &[&str] = &[
+    "<command-name>",
+    "<command-message>",
+    "<command-args>",
+    "<local-command-stdout>",
+    "<local-command-stderr>",
+    "<system-reminder>",
+    "Caveat: The messages below were generated by the user",
+];
+
+/// A title for sessions without a summary entry: the first line of the first prompt the user
+/// typed, cut at a word boundary.
+pub fn infer_title(messages: &[SessionMessage], render: RenderOptions) -> Option<String> {
+    typed_prompts(messages).find_map(|text| title_from_prompt(&text, render))
+}
+
+/// A session's title as search results show it: its summary, else its title in Claude's
+/// history index, else one inferred from the first prompt. Only the file's lines up to that
+/// prompt are read.
+pub fn session_title(path: &Path, session_id: &str) -> Option<String> {
+    read_session_title(path)
+        .or_else(|| claude_data().title(session_id).map(str::to_string))
+        .or_else(|| {
+            let mut diagnostics = ParseDiagnostics::default();
+            open_session(path)
+                .ok()?
+                .lines()
+                .map_while(Result::ok)
+                .enumerate()
+                .filter_map(|(index, line)| parse_session_line(&line, index + 1, &mut diagnostics))
+                .find_map(|msg| infer_title(std::slice::from_ref(&msg), RenderOptions::default()))
+        })
+}
+
+/// The whole of the first prompt the user typed.
+pub fn first_prompt(messages: &[SessionMessage]) -> Option<String> {
+    typed_prompts(messages)
+        .map(|text| text.trim().to_string())
+        .find(|text| !text.is_empty() && !is_wrapper(text))
+}
+
+/// Whether a user message's text is Claude Code's wrapping of a command or notice rather
+/// than something typed.
+pub fn is_wrapper(text: &str) -> bool {
+    let text = text.trim_start();
+    WRAPPER_PREFIXES.iter().any(|prefix| text.starts_with(prefix))
+}
+
+/// Text of the user's prompts, leaving out tool results and interruptions
+fn typed_prompts(messages: &[SessionMessage]) -> impl Iterator<Item = Cow<'_, str>> {
+    messages
+        .iter()
+        .filter(|msg| is_user_prompt(msg) && interruption(msg).is_none())
+        .filter_map(|msg| msg.message.as_ref()?.content.as_ref().map(extract_content_text))
+}
+
+fn title_from_prompt(text: &str, render: RenderOptions) -> Option<String> {
+    let text = text.trim();
+    if is_wrapper(text) {
+        return None;
+    }
+    let line = text
+        .lines()
+        .map(|line| line.trim().trim_start_matches(['#', '>', '-', '*', ' ']).trim())
+        .find(|line| !line.is_empty() && !line.starts_with("```"))?;
+    let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(shorten(&line, render))
+}
+
+/// Cuts to `TITLE_LEN` columns at the last word boundary, marking the cut with `…`.
+fn shorten(text: &str, render: RenderOptions) -> String {
+    if display_width(text) <= TITLE_LEN {
+        return text.to_string();
+    }
+    let cut = take_width(text, TITLE_LEN - 1);
+    let kept = match cut.rfind(' ') {
+        // A very long first word would leave almost nothing, so cut mid-word instead
+        Some(space) if display_width(&cut[..space]) > TITLE_LEN / 2 => &cut[..space],
+        _ => cut,
+    };
+    format!("{}{}", kept.trim_end_matches([',', ';', ':', ' ']), render.glyph(Glyph::Ellipsis))
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Content, SessionMessage};
+
+/// Context window assumed when the config file doesn't set `context_window`
+pub const DEFAULT_CONTEXT_WINDOW: u64 = 200_000;
+
+/// Rough characters per token for English prose and code
+const CHARS_PER_TOKEN: u64 = 4;
+
+/// How many tokens a session would take up in context if resumed
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct TokenEstimate {
+    pub tokens: u64,
+    /// Taken from the API's usage report for the last assistant message rather than estimated
+    /// from the length of the text alone
+    pub measured: bool,
+}
+
+impl TokenEstimate {
+    /// A line for search results saying whether resuming would fit in `context_window` tokens.
+    pub fn describe(&self, context_window: u64) -> String {
+        let tokens = format!("{}{}", if self.measured { "" } else { "~" }, abbreviate(self.tokens));
+        let window = abbreviate(context_window);
+        if self.tokens > context_window {
+            format!("{} tokens, over the {} context window: start fresh with a summary instead of resuming", tokens, window)
+        } else {
+            let percent = self.tokens * 100 / context_window.max(1);
+            format!("{} tokens, {}% of the {} context window", tokens, percent, window)
+        }
+    }
+}
+
+/// `48000` as `48k`, `1200000` as `1.2M`
+fn abbreviate(tokens: u64) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{}k", tokens / 1_000),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// Adds up a session's size in tokens one message at a time. The last usage report gives the
+/// context size exactly as of that reply; messages after it are estimated from their length.
+#[derive(Default, Clone)]
+pub struct TokenCounter {
+    /// Context size as of the last assistant message with usage
+    reported: Option<u64>,
+    /// Characters since that message, or since the start without one
+    chars_since_report: u64,
+}
+
+impl TokenCounter {
+    pub fn add(&mut self, msg: &SessionMessage) {
+        // Subagents run in a context of their own
+        if msg.is_sidechain == Some(true) {
+            return;
+        }
+        let Some(inner) = msg.message.as_ref() else {
+            return;
+        };
+        if let Some(usage) = inner.usage {
+            self.reported = Some(
+                usage.input_tokens
+                    + usage.cache_creation_input_tokens
+                    + usage.cache_read_input_tokens
+                    + usage.output_tokens,
+            );
+            self.chars_since_report = 0;
+        } else if let Some(content) = inner.content.as_ref() {
+            self.chars_since_report += content_chars(content);
+        }
+    }
+
+    pub fn finish(&self) -> TokenEstimate {
+        let estimated = self.chars_since_report.div_ceil(CHARS_PER_TOKEN);
+        TokenEstimate {
+            tokens: self.reported.unwrap_or(0) + estimated,
+            measured: self.reported.is_some() && estimated == 0,
+        }
+    }
+}
+
+/// Characters of everything a message puts in context: text, tool calls, and tool results.
+fn content_chars(content: &Content) -> u64 {
+    let chars = match content {
+        Content::Text(text) => text.chars().count(),
+        Content::Array(blocks) => blocks
+            .iter()
+            .map(|block| {
+                block.text.as_ref().map_or(0, |text| text.chars().count())
+                    + block.input.as_ref().map_or(0, |input| input.to_string().chars().count())
+                    + block.content.as_ref().map_or(0, |content| match content {
+                        serde_json::Value::String(text) => text.chars().count(),
+                        other => other.to_string().chars().count(),
+                    })
+            })
+            .sum(),
+    };
+    chars as u64
+}
+
+/// Token estimate for a whole session.
+pub fn estimate_tokens(messages: &[SessionMessage]) -> TokenEstimate {
+    let mut counter = TokenCounter::default();
+    for msg in messages {
+        counter.add(msg);
+    }
+    counter.finish()
+}
@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use crate::error::{Result, SessionFinderError};
+use crate::resolve::read_session_text;
+use crate::timeline::parse_session_messages;
+use crate::{Content, ContentBlock, SessionMessage};
+
+/// Claude Code names MCP tools `mcp__<server>__<tool>`
+const MCP_PREFIX: &str = "mcp__";
+
+/// Tools that only look at files; a term showing up in their input or output usually just
+/// means the file was opened, not that the session was about it
+const READ_TOOLS: &[&str] = &["Read", "Glob", "Grep", "LS", "NotebookRead"];
+
+pub fn is_read_tool(name: &str) -> bool {
+    READ_TOOLS.contains(&name)
+}
+
+/// A tool provided by an MCP server, split out of its qualified name
+pub struct McpTool<'a> {
+    pub server: &'a str,
+    pub tool: &'a str,
+}
+
+/// Splits `mcp__github__create_pr` into server `github` and tool `create_pr`. Server names
+/// can't contain `__`, so the first separator after the prefix ends the server name.
+pub fn parse_mcp_tool(name: &str) -> Option<McpTool<'_>> {
+    let (server, tool) = name.strip_prefix(MCP_PREFIX)?.split_once("__")?;
+    if server.is_empty() || tool.is_empty() {
+        return None;
+    }
+    Some(McpTool { server, tool })
+}
+
+/// Which tool calls `--tool` selects
+pub enum ToolFilter {
+    /// A built-in or MCP tool by its full name, ignoring case (`Bash`, `mcp__github__create_pr`)
+    Name(String),
+    /// `mcp`, `mcp:SERVER`, or `mcp:SERVER/TOOL`
+    Mcp { server: Option<String>, tool: Option<String> },
+}
+
+impl ToolFilter {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("mcp") {
+            return Ok(ToolFilter::Mcp { server: None, tool: None });
+        }
+        if let Some(rest) = spec.strip_prefix("mcp:") {
+            let (server, tool) = match rest.split_once('/') {
+                Some((server, tool)) => (server, Some(tool.to_string())),
+                None => (rest, None),
+            };
+            if server.is_empty() {
+                return Err(SessionFinderError::Usage(format!("Missing MCP server name in --tool {}", spec)));
+            }
+            return Ok(ToolFilter::Mcp { server: Some(server.to_string()), tool });
+        }
+        if spec.is_empty() {
+            return Err(SessionFinderError::Usage("--tool needs a tool name".to_string()));
+        }
+        Ok(ToolFilter::Name(spec.to_string()))
+    }
+
+    pub fn matches(&self, tool_name: &str) -> bool {
+        match self {
+            ToolFilter::Name(name) => tool_name.eq_ignore_ascii_case(name),
+            ToolFilter::Mcp { server, tool } => parse_mcp_tool(tool_name)
+                .is_some_and(|mcp| part_matches(server, mcp.server) && part_matches(tool, mcp.tool)),
+        }
+    }
+}
+
+/// An omitted server or tool name matches any.
+fn part_matches(expected: &Option<String>, actual: &str) -> bool {
+    match expected {
+        Some(expected) => expected.eq_ignore_ascii_case(actual),
+        None => true,
+    }
+}
+
+/// `tool_use` blocks in the message whose tool any of the filters selects.
+pub fn matching_tool_calls<'a>(msg: &'a SessionMessage, filters: &[ToolFilter]) -> Vec<&'a ContentBlock> {
+    let Some(Content::Array(blocks)) = msg.message.as_ref().and_then(|m| m.content.as_ref()) else {
+        return Vec::new();
+    };
+    blocks
+        .iter()
+        .filter(|block| block.r#type == "tool_use")
+        .filter(|block| {
+            let name = block.name.as_deref().unwrap_or_default();
+            filters.iter().any(|filter| filter.matches(name))
+        })
+        .collect()
+}
+
+/// Whether the session made any call the filters select.
+pub fn session_uses_tool(path: &Path, filters: &[ToolFilter]) -> Result<bool> {
+    let content = read_session_text(path)?;
+    let messages = parse_session_messages(&content)?;
+    Ok(messages.iter().any(|msg| !matching_tool_calls(msg, filters).is_empty()))
+}
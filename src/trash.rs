@@ -0,0 +1,331 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+use crate::config::Config;
+use crate::error::{Result, SessionFinderError};
+use crate::memory::parse_byte_size;
+use crate::store::{Note, Store};
+
+/// Days an operation can be undone without `[trash] keep_days`
+const DEFAULT_KEEP_DAYS: i64 = 30;
+
+/// Most the trash holds without `[trash] max_size`
+const DEFAULT_MAX_SIZE: u64 = 1 << 30;
+
+/// How much the trash keeps, from `[trash]` in the config file
+#[derive(Debug, Default, Deserialize)]
+pub struct TrashConfig {
+    /// Days an operation stays in the trash
+    pub keep_days: Option<i64>,
+    /// Most the trash may hold, e.g. `500M`; the oldest operations are emptied first
+    pub max_size: Option<String>,
+}
+
+/// Where removed files and annotations are kept, one directory per operation, until undone.
+pub fn trash_dir() -> Result<PathBuf> {
+    Ok(Path::new(&std::env::var("HOME")?)
+        .join(".claude")
+        .join("session-finder")
+        .join("trash"))
+}
+
+/// Something an operation removed, with what it takes to put it back
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TrashedItem {
+    /// A file moved into the operation's directory under the name `stored`
+    File { original: PathBuf, stored: String },
+    Tag { session: String, tag: String },
+    Bookmark {
+        session: String,
+        message: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+    },
+    Note {
+        session: String,
+        /// 1-based place in the session's notes
+        position: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<usize>,
+        text: String,
+    },
+}
+
+impl TrashedItem {
+    fn describe(&self) -> String {
+        match self {
+            TrashedItem::File { original, .. } => format!("file {}", original.display()),
+            TrashedItem::Tag { session, tag } => format!("tag {} on {}", tag, session),
+            TrashedItem::Bookmark { session, message, .. } => format!("bookmark on message {} of {}", message, session),
+            TrashedItem::Note { session, position, .. } => format!("note {} on {}", position, session),
+        }
+    }
+}
+
+/// `manifest.toml` in an operation's trash directory
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    operation: String,
+    created: DateTime<Utc>,
+    #[serde(default)]
+    items: Vec<TrashedItem>,
+}
+
+/// Collects what one run of a command removes. Nothing is written until the first item, and
+/// the manifest is rewritten with each one, so an operation that fails halfway can still be
+/// undone as far as it got.
+pub struct TrashOperation {
+    id: Option<String>,
+    manifest: Manifest,
+}
+
+impl TrashOperation {
+    pub fn begin(operation: &str) -> Self {
+        TrashOperation { id: None, manifest: Manifest { operation: operation.to_string(), created: Utc::now(), items: Vec::new() } }
+    }
+
+    /// The operation's directory, created on first use under an ID from its time and command.
+    fn dir(&mut self) -> Result<PathBuf> {
+        let trash = trash_dir()?;
+        if let Some(id) = &self.id {
+            return Ok(trash.join(id));
+        }
+        let base = format!("{}-{}", self.manifest.created.format("%Y%m%d-%H%M%S"), self.manifest.operation);
+        let mut id = base.clone();
+        let mut attempt = 1;
+        while trash.join(&id).exists() {
+            attempt += 1;
+            id = format!("{}-{}", base, attempt);
+        }
+        fs::create_dir_all(trash.join(&id))?;
+        self.id = Some(id.clone());
+        Ok(trash.join(id))
+    }
+
+    /// Moves a file into the trash instead of deleting or overwriting it.
+    pub fn discard_file(&mut self, path: &Path) -> Result<()> {
+        let dir = self.dir()?;
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let stored = format!("{}-{}", self.manifest.items.len() + 1, name);
+        move_file(path, &dir.join(&stored))?;
+        self.record(TrashedItem::File { original: std::path::absolute(path)?, stored })
+    }
+
+    /// Records an annotation about to be removed from the store.
+    pub fn record(&mut self, item: TrashedItem) -> Result<()> {
+        let dir = self.dir()?;
+        self.manifest.items.push(item);
+        fs::write(dir.join("manifest.toml"), toml::to_string_pretty(&self.manifest)?)?;
+        Ok(())
+    }
+
+    /// Says how to undo the operation, if it removed anything, and empties older operations
+    /// the trash no longer has room or time for.
+    pub fn finish(self) {
+        let Some(id) = self.id else {
+            return;
+        };
+        eprintln!(
+            "Moved {} item(s) to the trash; `session-finder undo {}` restores them",
+            self.manifest.items.len(),
+            id
+        );
+        let expired = Config::load().and_then(|config| expire_trash(&config.trash, Utc::now(), Some(&id)));
+        match expired {
+            Ok(expired) if !expired.is_empty() => {
+                eprintln!("Emptied {} older operation(s) from the trash", expired.len())
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Could not empty old operations from the trash: {}", e),
+        }
+    }
+}
+
+/// Empties operations older than the configured number of days, then the oldest of the rest
+/// until the trash fits in its size limit, sparing `keep`. Returns the IDs emptied.
+pub fn expire_trash(config: &TrashConfig, now: DateTime<Utc>, keep: Option<&str>) -> Result<Vec<String>> {
+    let trash = trash_dir()?;
+    let keep_for = Duration::days(config.keep_days.unwrap_or(DEFAULT_KEEP_DAYS));
+    let max_size = config.max_size.as_deref().map(parse_byte_size).transpose()?.unwrap_or(DEFAULT_MAX_SIZE);
+    let mut operations = Vec::new();
+    for (id, manifest) in trashed_operations(&trash)? {
+        operations.push((manifest.created, directory_size(&trash.join(&id)), id));
+    }
+    operations.sort();
+    let mut size: u64 = operations.iter().map(|(_, bytes, _)| bytes).sum();
+    let mut expired = Vec::new();
+    for (created, bytes, id) in operations {
+        if Some(id.as_str()) == keep || (now - created < keep_for && size <= max_size) {
+            continue;
+        }
+        fs::remove_dir_all(trash.join(&id))?;
+        size -= bytes;
+        expired.push(id);
+    }
+    Ok(expired)
+}
+
+fn directory_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Moves a file, copying it when the trash is on another filesystem.
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+fn load_manifest(dir: &Path) -> Result<Manifest> {
+    let path = dir.join("manifest.toml");
+    let content = fs::read_to_string(&path)?;
+    toml::from_str(&content).map_err(|e| SessionFinderError::toml(path.clone(), &content, e))
+}
+
+/// Restores everything the operation removed, then empties its trash directory. Files that have
+/// been written again since are themselves moved to the trash first, so undoing loses nothing.
+/// Without an ID, lists the operations that can be undone, newest first.
+pub fn run_undo(id: Option<&str>) -> Result<()> {
+    let trash = trash_dir()?;
+    let Some(id) = id else {
+        return list_operations(&trash);
+    };
+    let dir = trash.join(id);
+    if !is_plain_name(id) || !dir.join("manifest.toml").exists() {
+        return Err(SessionFinderError::Usage(format!(
+            "No operation {} in the trash; run `session-finder undo` to list them",
+            id
+        )));
+    }
+    let manifest = load_manifest(&dir)?;
+
+    let mut displaced = TrashOperation::begin("undo");
+    let mut store: Option<Store> = None;
+    // Last removed first, so notes go back to the places they were taken from
+    for item in manifest.items.iter().rev() {
+        match item {
+            TrashedItem::File { original, stored } => {
+                if !is_plain_name(stored) {
+                    return Err(SessionFinderError::Usage(format!(
+                        "Operation {} names a stored file outside its directory: {}",
+                        id,
+                        stored
+                    )));
+                }
+                if original.exists() {
+                    displaced.discard_file(original)?;
+                }
+                move_file(&dir.join(stored), original)?;
+            }
+            TrashedItem::Tag { session, tag } => store_for(&mut store)?.add_tag(session, tag),
+            TrashedItem::Bookmark { session, message, label } => {
+                store_for(&mut store)?.add_bookmark(session, *message, label.clone())
+            }
+            TrashedItem::Note { session, position, message, text } => {
+                store_for(&mut store)?.insert_note(session, *position, Note { message: *message, text: text.clone() })
+            }
+        }
+        println!("Restored {}", item.describe());
+    }
+    if let Some(store) = store {
+        store.save()?;
+    }
+    fs::remove_dir_all(&dir)?;
+    displaced.finish();
+    Ok(())
+}
+
+/// Whether `name` is a single file name, so joining it to a directory stays inside it: no
+/// separators, and not `.` or `..`.
+fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) && !name.contains('\\')
+}
+
+fn store_for(store: &mut Option<Store>) -> Result<&mut Store> {
+    if store.is_none() {
+        *store = Some(Store::load()?);
+    }
+    Ok(store.as_mut().expect("store was just loaded"))
+}
+
+/// Every operation in the trash with its manifest, in no particular order.
+fn trashed_operations(trash: &Path) -> Result<Vec<(String, Manifest)>> {
+    let mut operations = Vec::new();
+    if trash.exists() {
+        for entry in fs::read_dir(trash)? {
+            let dir = entry?.path();
+            if dir.join("manifest.toml").exists() {
+                operations.push((dir.file_name().unwrap_or_default().to_string_lossy().into_owned(), load_manifest(&dir)?));
+            }
+        }
+    }
+    Ok(operations)
+}
+
+fn list_operations(trash: &Path) -> Result<()> {
+    let mut operations = trashed_operations(trash)?;
+    if operations.is_empty() {
+        println!("The trash is empty.");
+        return Ok(());
+    }
+    operations.sort_by(|(a_id, a), (b_id, b)| b.created.cmp(&a.created).then_with(|| b_id.cmp(a_id)));
+    for (id, manifest) in operations {
+        println!("{}  {}  {} item(s)", id, manifest.created.format("%Y-%m-%d %H:%M UTC"), manifest.items.len());
+        for item in &manifest.items {
+            println!("    {}", item.describe());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fake_home, write_session};
+
+    #[test]
+    fn trash_empties_the_oldest_operations_first() {
+        let (_home, home) = fake_home();
+        let text = "x".repeat(1000);
+        let mut ids = Vec::new();
+        for name in ["first", "second", "third"] {
+            let path = write_session(home, "-work-expire", name, &[text.as_str()], std::time::Duration::ZERO);
+            let mut trash = TrashOperation::begin(&format!("expire-{}", name));
+            trash.discard_file(&path).unwrap();
+            ids.push(trash.id.expect("something was trashed"));
+        }
+        let trash = trash_dir().unwrap();
+        let kept = || ids.iter().filter(|id| trash.join(id).exists()).count();
+
+        let roomy = TrashConfig { keep_days: Some(30), max_size: Some("1G".to_string()) };
+        expire_trash(&roomy, Utc::now(), None).unwrap();
+        assert_eq!(kept(), 3);
+
+        // Room for the two newest only
+        let room = directory_size(&trash.join(&ids[1])) + directory_size(&trash.join(&ids[2]));
+        let tight = TrashConfig { keep_days: Some(30), max_size: Some(room.to_string()) };
+        expire_trash(&tight, Utc::now(), None).unwrap();
+        assert!(!trash.join(&ids[0]).exists());
+        assert_eq!(kept(), 2);
+
+        // Past keep_days everything goes but the operation being made
+        expire_trash(&roomy, Utc::now() + Duration::days(31), Some(&ids[2])).unwrap();
+        assert_eq!(kept(), 1);
+        assert!(trash.join(&ids[2]).exists());
+    }
+}
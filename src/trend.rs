@@ -0,0 +1,187 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::density::sparkline;
+use crate::error::{Result, SessionFinderError};
+use crate::glyphs::Glyph;
+use crate::render::RenderOptions;
+use crate::matcher::SearchOptions;
+use crate::prefilter::for_each_matching_file;
+use crate::resolve::{read_session_text, session_roots};
+use crate::stats::StatsFormat;
+use crate::time_range::{carried_message_times, parse_time_bound};
+use crate::timeline::{find_matching_messages, parse_session_messages};
+use crate::{decode_project_path, extract_session_id};
+
+/// How matches are counted up over time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrendPeriod {
+    Day,
+    /// Weeks starting on Monday
+    Week,
+}
+
+impl TrendPeriod {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "day" => Ok(TrendPeriod::Day),
+            "week" => Ok(TrendPeriod::Week),
+            other => Err(SessionFinderError::Usage(format!("Unknown period: {} (expected day or week)", other))),
+        }
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            TrendPeriod::Day => "date",
+            TrendPeriod::Week => "week",
+        }
+    }
+
+    /// The first day of the period `date` falls in.
+    fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            TrendPeriod::Day => date,
+            TrendPeriod::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        }
+    }
+
+    fn next(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            TrendPeriod::Day => start + Duration::days(1),
+            TrendPeriod::Week => start + Duration::days(7),
+        }
+    }
+}
+
+/// Accepts a number of days or weeks back (`90d`, `12w`) or a date or time as `--after` does.
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    let trimmed = value.trim();
+    let days_per_unit = match trimmed.chars().last() {
+        Some('d') => Some(1),
+        Some('w') => Some(7),
+        _ => None,
+    };
+    match days_per_unit.and_then(|days| Some(days * trimmed[..trimmed.len() - 1].parse::<i64>().ok()?)) {
+        Some(days) => Ok(Utc::now() - Duration::days(days)),
+        None => parse_time_bound("--since", trimmed)
+            .map_err(|_| SessionFinderError::Usage(format!(
+                "Invalid --since {:?}: use days or weeks back (90d, 12w) or a date (YYYY-MM-DD)",
+                value
+            ))),
+    }
+}
+
+/// Matching messages and the sessions they came from in one period
+#[derive(Default)]
+struct TrendBucket {
+    messages: usize,
+    sessions: HashSet<String>,
+}
+
+/// Counts messages matching any of the terms per day or week since `since`, across every
+/// session, to show whether something keeps coming up more or less often. Messages are
+/// matched as timelines match them, so `--types` and `--in-code` apply.
+pub fn run_trend(
+    search_terms: &[&str],
+    project_filter: Option<&String>,
+    since: DateTime<Utc>,
+    period: TrendPeriod,
+    as_sparkline: bool,
+    format: StatsFormat,
+    options: &SearchOptions,
+) -> Result<()> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for root in session_roots()? {
+        for_each_matching_file(&root, search_terms, false, |path, _| {
+            let path = root.join(path);
+            let in_project = project_filter.is_none_or(|filter| decode_project_path(&path).is_ok_and(|project| project.contains(filter.as_str())));
+            // A file last written before `since` holds no messages after it
+            let modified: DateTime<Utc> = DateTime::from(fs::metadata(&path)?.modified()?);
+            if in_project && !options.ignore.ignores_file(&path) && modified >= since {
+                files.push(path);
+            }
+            Ok(true)
+        })?;
+    }
+
+    let mut buckets: BTreeMap<NaiveDate, TrendBucket> = BTreeMap::new();
+    let first = period.start(since.date_naive());
+    let last = period.start(Utc::now().date_naive());
+    let mut start = first;
+    while start <= last {
+        buckets.insert(start, TrendBucket::default());
+        start = period.next(start);
+    }
+    for path in files {
+        let session_id = extract_session_id(&path)?;
+        let messages = parse_session_messages(&read_session_text(&path)?)?;
+        let times = carried_message_times(&messages);
+        for index in find_matching_messages(&messages, search_terms, options) {
+            let Some(time) = times[index].filter(|time| *time >= since) else {
+                continue;
+            };
+            if let Some(bucket) = buckets.get_mut(&period.start(time.date_naive())) {
+                bucket.messages += 1;
+                bucket.sessions.insert(session_id.clone());
+            }
+        }
+    }
+
+    match format {
+        StatsFormat::Text if as_sparkline => print_sparkline(&buckets, options.render),
+        StatsFormat::Text => print_table(period, &buckets),
+        StatsFormat::Csv => print_delimited(period, &buckets, ','),
+        StatsFormat::Tsv => print_delimited(period, &buckets, '\t'),
+    }
+    Ok(())
+}
+
+fn print_sparkline(buckets: &BTreeMap<NaiveDate, TrendBucket>, render: RenderOptions) {
+    let counts: Vec<usize> = buckets.values().map(|bucket| bucket.messages).collect();
+    let (Some(first), Some(last)) = (buckets.keys().next(), buckets.keys().next_back()) else {
+        return;
+    };
+    let rule = render.glyph(Glyph::Rule);
+    println!("{} {}{}{} {}", first, rule, sparkline(&counts, render), rule, last);
+    print_summary(buckets);
+}
+
+fn print_table(period: TrendPeriod, buckets: &BTreeMap<NaiveDate, TrendBucket>) {
+    println!("{:<10}  {:>8}  {:>8}", period.header(), "messages", "sessions");
+    for (start, bucket) in buckets {
+        println!("{:<10}  {:>8}  {:>8}", start, bucket.messages, bucket.sessions.len());
+    }
+    println!();
+    print_summary(buckets);
+}
+
+/// The total, the busiest period, and how the later half compares with the earlier one.
+fn print_summary(buckets: &BTreeMap<NaiveDate, TrendBucket>) {
+    let total: usize = buckets.values().map(|bucket| bucket.messages).sum();
+    let Some((peak_start, peak)) = buckets.iter().max_by_key(|(start, bucket)| (bucket.messages, std::cmp::Reverse(**start))) else {
+        return;
+    };
+    if total == 0 {
+        println!("No matching messages in this range.");
+        return;
+    }
+    let half = buckets.len() / 2;
+    let earlier: usize = buckets.values().take(half).map(|bucket| bucket.messages).sum();
+    let later: usize = buckets.values().skip(buckets.len() - half).map(|bucket| bucket.messages).sum();
+    let direction = match later.cmp(&earlier) {
+        _ if half == 0 => String::new(),
+        std::cmp::Ordering::Greater => format!("; up from {} to {} between the first and last {} periods", earlier, later, half),
+        std::cmp::Ordering::Less => format!("; down from {} to {} between the first and last {} periods", earlier, later, half),
+        std::cmp::Ordering::Equal => format!("; level at {} in the first and last {} periods", earlier, half),
+    };
+    println!("{} matching message(s), peak {} starting {}{}", total, peak.messages, peak_start, direction);
+}
+
+fn print_delimited(period: TrendPeriod, buckets: &BTreeMap<NaiveDate, TrendBucket>, delimiter: char) {
+    println!("{}{}messages{}sessions", period.header(), delimiter, delimiter);
+    for (start, bucket) in buckets {
+        println!("{}{}{}{}{}", start, delimiter, bucket.messages, delimiter, bucket.sessions.len());
+    }
+}
@@ -0,0 +1,82 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::{debug, warn};
+
+use crate::error::{Result, SessionFinderError};
+use crate::events::SessionEvent;
+
+/// Seconds a webhook gets to answer before the post is given up
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// How event payloads are shaped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The event's fields as a JSON object
+    #[default]
+    Json,
+    /// A `text` message for Slack incoming webhooks (and the many services that accept them)
+    Slack,
+}
+
+/// The body posted for an event.
+pub fn webhook_payload(event: &SessionEvent, format: WebhookFormat) -> Value {
+    match format {
+        WebhookFormat::Json => json!({
+            "event": event.kind.name(),
+            "summary": event.summary(),
+            "detail": event.detail,
+            "session_id": event.session_id,
+            "title": event.title,
+            "project": event.project,
+            "path": event.path,
+        }),
+        WebhookFormat::Slack => json!({
+            "text": format!(
+                "*{}*\n{}\n`{}` in {}",
+                slack_escape(&event.summary()),
+                slack_escape(&event.detail),
+                event.session_id,
+                slack_escape(&event.project)
+            ),
+        }),
+    }
+}
+
+/// Text with the characters Slack treats as markup escaped.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Posts the event to the webhook on a thread of its own, so a slow endpoint doesn't hold up
+/// the daemon; failures are logged.
+pub fn post_event(url: &str, event: &SessionEvent, format: WebhookFormat) {
+    let url = url.to_string();
+    let payload = webhook_payload(event, format);
+    std::thread::spawn(move || {
+        if let Err(e) = post_json(&url, &payload) {
+            warn!("Could not post to the webhook: {:#}", e);
+        }
+    });
+}
+
+/// POSTs JSON with curl, the way sync relies on the AWS CLI for S3.
+fn post_json(url: &str, payload: &Value) -> Result<()> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", &WEBHOOK_TIMEOUT_SECS.to_string()])
+        .args(["--header", "Content-Type: application/json", "--data-binary", "@-", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| SessionFinderError::backend("curl", format!("curl failed: {}. Webhooks need curl in your PATH", e)))?;
+    child.stdin.take().expect("stdin is piped").write_all(payload.to_string().as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(SessionFinderError::backend("curl", String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    debug!("Posted to the webhook");
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use std::path::{Path, PathBuf};
+
+use crate::machines::remap_project_root;
+use crate::{decode_project_dir, encode_project_path};
+use crate::resume::session_working_dir;
+
+/// The root of the git repository `dir` is in: the nearest directory up from it holding `.git`
+/// (a directory, or a file in worktrees and submodules).
+pub fn repository_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().find(|ancestor| ancestor.join(".git").exists()).map(Path::to_path_buf)
+}
+
+/// Whether the session was run in the repository at `root` or a directory inside it. Project
+/// folder names can't tell `/repo-old` from `/repo/old`, so sessions that might be either are
+/// checked against their recorded `cwd`.
+/// Sessions from another machine whose project root is mapped in `[project_roots]` count by
+/// their mapped project path.
+pub fn in_workspace(session_path: &Path, root: &Path) -> bool {
+    let Some(project_dir) = session_path.parent().and_then(|parent| parent.file_name()) else {
+        return false;
+    };
+    let project_dir = project_dir.to_string_lossy();
+    if let Some(project) = remap_project_root(&decode_project_dir(&project_dir)) {
+        return Path::new(&project).starts_with(root);
+    }
+    let encoded_root = encode_project_path(root);
+    if project_dir == encoded_root {
+        return true;
+    }
+    project_dir.strip_prefix(&encoded_root).is_some_and(|rest| rest.starts_with('-'))
+        && Path::new(&session_working_dir(session_path)).starts_with(root)
+}
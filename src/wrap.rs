@@ -0,0 +1,40 @@
+use std::io::IsTerminal;
+
+/// Columns wrapped lines are kept to even in a very narrow terminal
+const MIN_WIDTH: usize = 20;
+
+/// Extra indentation of a wrapped line's continuations, past the line's own indentation
+const HANGING_INDENT: &str = "  ";
+
+/// Columns output is wrapped to: `--width`, or the terminal's width. Output piped elsewhere
+/// without `--width` isn't wrapped.
+pub fn output_width(requested: Option<usize>) -> Option<usize> {
+    match requested {
+        Some(width) => Some(width.max(MIN_WIDTH)),
+        None => std::io::stdout().is_terminal().then(|| textwrap::termwidth().max(MIN_WIDTH)),
+    }
+}
+
+/// `text` with its first line after `prefix` and the lines after it after `indent`, each wrapped
+/// to `width` columns when given. A wrapped line continues under its own indentation and a
+/// little further in, so code keeps its shape.
+pub fn wrap_indented(text: &str, prefix: &str, indent: &str, width: Option<usize>) -> String {
+    let mut out = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let lead = if index == 0 { prefix } else { indent };
+        let (Some(width), false) = (width, line.trim().is_empty()) else {
+            out.push(format!("{}{}", lead, line).trim_end().to_string());
+            continue;
+        };
+        let body = line.trim_start();
+        let own_indent = &line[..line.len() - body.len()];
+        let first = format!("{}{}", lead, own_indent);
+        let rest = format!("{}{}{}", indent, own_indent, HANGING_INDENT);
+        let options = textwrap::Options::new(width).initial_indent(&first).subsequent_indent(&rest);
+        out.extend(textwrap::wrap(body, options).into_iter().map(|wrapped| wrapped.into_owned()));
+    }
+    if out.is_empty() {
+        out.push(prefix.trim_end().to_string());
+    }
+    out.join("\n")
+}